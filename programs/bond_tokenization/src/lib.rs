@@ -1,5 +1,7 @@
 use anchor_lang::prelude::*;
-use anchor_spl::token::{Mint, Token, TokenAccount, MintTo};
+use anchor_spl::token::{
+    burn, mint_to, transfer, Burn, Mint, MintTo, Token, TokenAccount, Transfer,
+};
 
 declare_id!("BondToKEN11111111111111111111111111111111111");
 
@@ -7,12 +9,29 @@ declare_id!("BondToKEN11111111111111111111111111111111111");
 pub mod bond_tokenization {
     use super::*;
 
-    pub fn mint_bond(ctx: Context<MintBond>, isin: String) -> Result<()> {
+    pub fn mint_bond(
+        ctx: Context<MintBond>,
+        isin: String,
+        maturity: i64,
+        coupon_rate_bps: u64,
+        units: u64,
+        face_value: u64,
+    ) -> Result<()> {
         require!(isin.len() <= 12, ErrorCode::InvalidISINLength);
+        require!(units > 0 && face_value > 0, ErrorCode::InvalidAmount);
+        require!(
+            maturity > Clock::get()?.unix_timestamp,
+            ErrorCode::InvalidMaturity
+        );
         let bond = &mut ctx.accounts.bond_metadata;
         bond.isin = isin;
         bond.mint = ctx.accounts.mint.key();
         bond.authority = ctx.accounts.authority.key();
+        bond.maturity = maturity;
+        bond.coupon_rate_bps = coupon_rate_bps;
+        bond.face_value = face_value;
+        bond.status = BondStatus::Active;
+        bond.bump = ctx.bumps.bond_metadata;
 
         mint_to(
             CpiContext::new(
@@ -23,7 +42,69 @@ pub mod bond_tokenization {
                     authority: ctx.accounts.mint_authority.to_account_info(),
                 },
             ),
-            1,
+            units,
+        )?;
+
+        Ok(())
+    }
+
+    // A default is terminal: holders and lenders price on it, so the issuer cannot undo it.
+    pub fn set_bond_status(ctx: Context<UpdateBond>, status: BondStatus) -> Result<()> {
+        let bond = &mut ctx.accounts.bond_metadata;
+        require!(
+            bond.status != BondStatus::Defaulted,
+            ErrorCode::BondDefaulted
+        );
+        bond.status = status;
+        Ok(())
+    }
+
+    pub fn open_redemption_vault(_ctx: Context<OpenRedemptionVault>) -> Result<()> {
+        Ok(())
+    }
+
+    pub fn redeem_matured(ctx: Context<RedeemMatured>, amount: u64) -> Result<()> {
+        let bond = &ctx.accounts.bond_metadata;
+        require!(amount > 0, ErrorCode::InvalidAmount);
+        require!(
+            Clock::get()?.unix_timestamp >= bond.maturity,
+            ErrorCode::BondNotMatured
+        );
+        require!(bond.status == BondStatus::Active, ErrorCode::BondDefaulted);
+        let payout = amount
+            .checked_mul(bond.face_value)
+            .ok_or(ErrorCode::MathOverflow)?;
+        require!(
+            ctx.accounts.redemption_vault.amount >= payout,
+            ErrorCode::InsufficientRedemptionFunds
+        );
+
+        burn(
+            CpiContext::new(
+                ctx.accounts.token_program.to_account_info(),
+                Burn {
+                    mint: ctx.accounts.mint.to_account_info(),
+                    from: ctx.accounts.holder_bond_account.to_account_info(),
+                    authority: ctx.accounts.holder.to_account_info(),
+                },
+            ),
+            amount,
+        )?;
+
+        let mint = bond.mint;
+        let bump = [bond.bump];
+        let signer_seeds: &[&[&[u8]]] = &[&[b"bond_metadata", mint.as_ref(), &bump]];
+        transfer(
+            CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                Transfer {
+                    from: ctx.accounts.redemption_vault.to_account_info(),
+                    to: ctx.accounts.holder_payment_account.to_account_info(),
+                    authority: ctx.accounts.bond_metadata.to_account_info(),
+                },
+                signer_seeds,
+            ),
+            payout,
         )?;
 
         Ok(())
@@ -33,7 +114,13 @@ pub mod bond_tokenization {
 #[derive(Accounts)]
 #[instruction(isin: String)]
 pub struct MintBond<'info> {
-    #[account(init, payer = authority, space = 8 + 32 + 32 + 12)]
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + 32 + 32 + 4 + 12 + 8 + 8 + 8 + 1 + 1,
+        seeds = [b"bond_metadata", mint.key().as_ref()],
+        bump
+    )]
     pub bond_metadata: Account<'info, BondMetadata>,
     #[account(mut)]
     pub mint: Account<'info, Mint>,
@@ -47,15 +134,85 @@ pub struct MintBond<'info> {
     pub system_program: Program<'info, System>,
 }
 
+#[derive(Accounts)]
+pub struct UpdateBond<'info> {
+    pub authority: Signer<'info>,
+    #[account(mut, has_one = authority)]
+    pub bond_metadata: Account<'info, BondMetadata>,
+}
+
+#[derive(Accounts)]
+pub struct OpenRedemptionVault<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    #[account(has_one = authority)]
+    pub bond_metadata: Account<'info, BondMetadata>,
+    pub payment_mint: Account<'info, Mint>,
+    #[account(
+        init,
+        payer = authority,
+        seeds = [b"redemption_vault", bond_metadata.mint.as_ref()],
+        bump,
+        token::mint = payment_mint,
+        token::authority = bond_metadata
+    )]
+    pub redemption_vault: Account<'info, TokenAccount>,
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct RedeemMatured<'info> {
+    pub holder: Signer<'info>,
+    #[account(
+        has_one = mint,
+        seeds = [b"bond_metadata", mint.key().as_ref()],
+        bump = bond_metadata.bump,
+    )]
+    pub bond_metadata: Account<'info, BondMetadata>,
+    #[account(mut)]
+    pub mint: Account<'info, Mint>,
+    #[account(mut, token::mint = mint, token::authority = holder)]
+    pub holder_bond_account: Account<'info, TokenAccount>,
+    #[account(mut, seeds = [b"redemption_vault", mint.key().as_ref()], bump)]
+    pub redemption_vault: Account<'info, TokenAccount>,
+    #[account(mut, token::mint = redemption_vault.mint)]
+    pub holder_payment_account: Account<'info, TokenAccount>,
+    pub token_program: Program<'info, Token>,
+}
+
 #[account]
 pub struct BondMetadata {
     pub mint: Pubkey,
     pub authority: Pubkey,
     pub isin: String,
+    pub maturity: i64,
+    pub coupon_rate_bps: u64,
+    pub face_value: u64,
+    pub status: BondStatus,
+    pub bump: u8,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq)]
+pub enum BondStatus {
+    Active,
+    Defaulted,
 }
 
 #[error_code]
 pub enum ErrorCode {
     #[msg("ISIN number must be 12 characters or less.")]
     InvalidISINLength,
+    #[msg("Bond maturity must be in the future.")]
+    InvalidMaturity,
+    #[msg("Amount must be greater than zero.")]
+    InvalidAmount,
+    #[msg("Bond has not reached maturity.")]
+    BondNotMatured,
+    #[msg("Bond has defaulted and cannot be redeemed.")]
+    BondDefaulted,
+    #[msg("Redemption vault does not hold enough funds.")]
+    InsufficientRedemptionFunds,
+    #[msg("Math overflow.")]
+    MathOverflow,
 }
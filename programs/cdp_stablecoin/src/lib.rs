@@ -1,131 +1,14750 @@
 use anchor_lang::prelude::*;
-use anchor_spl::token::{Mint, TokenAccount, Token, MintTo};
+use anchor_lang::solana_program::ed25519_program;
+use anchor_lang::solana_program::instruction::{AccountMeta, Instruction};
+use anchor_lang::solana_program::program::{invoke, invoke_signed, set_return_data};
+use anchor_lang::solana_program::program_option::COption;
+use anchor_lang::solana_program::sysvar::instructions::{
+    load_current_index_checked, load_instruction_at_checked,
+};
+use anchor_spl::associated_token::AssociatedToken;
+use anchor_spl::metadata::mpl_token_metadata::types::DataV2;
+use anchor_spl::metadata::{
+    create_metadata_accounts_v3, update_metadata_accounts_v2, CreateMetadataAccountsV3, Metadata,
+    UpdateMetadataAccountsV2,
+};
+use anchor_spl::token::spl_token::instruction::AuthorityType;
+use anchor_spl::token::{Burn, CloseAccount, MintTo, SetAuthority, Token, Transfer};
+use anchor_spl::token_2022::spl_token_2022::extension::confidential_transfer;
+use anchor_spl::token_2022::spl_token_2022::onchain::invoke_transfer_checked;
+use anchor_spl::token_2022::spl_token_2022::solana_zk_token_sdk::zk_token_elgamal::pod;
+use anchor_spl::token_2022::Token2022;
+use anchor_spl::token_interface::{self, Mint, TokenAccount, TokenInterface, TransferChecked};
+use bond_tokenization::program::BondTokenization;
+use bond_tokenization::{BondMetadata, BondStatus};
+use chainlink_solana::v2::read_feed_v2;
+use pyth_solana_receiver_sdk::price_update::{PriceUpdateV2, VerificationLevel};
+use stablecoin_transfer_hook::Compliance;
+use switchboard_solana::AggregatorAccountData;
 
 declare_id!("CDPStab1111111111111111111111111111111111111");
 
-pub const BOND_UNIT_VALUE: u64 = 1000;
-pub const MARGIN_PERCENT: u64 = 5;
+pub const SECONDS_PER_YEAR: i64 = 365 * 24 * 3600;
+pub const MAX_COUPON_PERIODS: u32 = 50;
+pub const P_SCALE: u128 = 1_000_000_000_000_000_000;
+pub const NICR_PRECISION: u128 = 1_000_000_000_000_000_000;
+pub const PEG_PRICE: u64 = 1_000_000;
+pub const FEE_PRECISION: u128 = 1_000_000_000_000_000_000;
+pub const REDEMPTION_BETA: u128 = 2;
+pub const MAX_ORACLE_FEEDS: usize = 5;
+pub const YIELD_CURVE_POINTS: usize = 8;
+pub const MAX_REGISTRY_ENTRIES: usize = 32;
+pub const MAX_PENDING_CHANGES: usize = 16;
+pub const SCALE_FACTOR: u128 = 1_000_000_000;
+pub const MAX_SCALE_SUMS: usize = 16;
+pub const SAVINGS_VIRTUAL_AMOUNT: u128 = 1_000_000;
+pub const PAUSE_BORROW: u64 = 1 << 0;
+pub const PAUSE_WITHDRAW: u64 = 1 << 1;
+pub const PAUSE_LIQUIDATION: u64 = 1 << 2;
+pub const PAUSE_ACCRUAL: u64 = 1 << 3;
+pub const PAUSE_PSM: u64 = 1 << 4;
+pub const PAUSE_REDEMPTION: u64 = 1 << 5;
+pub const PAUSE_FLASH_MINT: u64 = 1 << 6;
+pub const PAUSE_DEPOSIT: u64 = 1 << 7;
+pub const PAUSE_DELEVERAGE: u64 = 1 << 8;
+pub const PAUSE_REPAY: u64 = 1 << 9;
+pub const PAUSE_SAVINGS: u64 = 1 << 10;
+pub const PAUSE_BRIDGE: u64 = 1 << 11;
+pub const PAUSE_POL: u64 = 1 << 12;
 
 #[program]
 pub mod cdp_stablecoin {
     use super::*;
 
-    pub fn set_borrow_rate(ctx: Context<SetBorrowRate>, new_rate_bps: u64) -> Result<()> {
-        ctx.accounts.config.borrow_rate_bps = new_rate_bps;
+    pub fn set_rate_model<'info>(
+        ctx: Context<'_, '_, 'info, 'info, SetRateModel<'info>>,
+        base_rate_bps: u64,
+        slope1_bps: u64,
+        slope2_bps: u64,
+        kink_bps: u64,
+    ) -> Result<()> {
+        require!(
+            base_rate_bps <= 10000 && slope1_bps <= 10000 && slope2_bps <= 10000,
+            ErrorCode::InvalidParameter
+        );
+        require!(
+            kink_bps > 0 && kink_bps <= 10000,
+            ErrorCode::InvalidParameter
+        );
+        if ctx.accounts.rate_model.bump != 0 {
+            require_no_timelock(&ctx.accounts.config)?;
+            accrue_all_collateral(
+                &ctx.accounts.config,
+                &ctx.accounts.rate_model,
+                ctx.remaining_accounts,
+                Clock::get()?.unix_timestamp,
+            )?;
+        }
+        let rate_model = &mut ctx.accounts.rate_model;
+        rate_model.base_rate_bps = base_rate_bps;
+        rate_model.slope1_bps = slope1_bps;
+        rate_model.slope2_bps = slope2_bps;
+        rate_model.kink_bps = kink_bps;
+        rate_model.bump = ctx.bumps.rate_model;
         Ok(())
     }
 
-    pub fn deposit_bond_and_mint(ctx: Context<DepositBondAndMint>, nft_count: u64) -> Result<()> {
-        let vault = &mut ctx.accounts.vault;
+    pub fn set_peg_params<'info>(
+        ctx: Context<'_, '_, 'info, 'info, UpdateRateModel<'info>>,
+        peg_feeder: Pubkey,
+        peg_sensitivity_bps: u64,
+        max_peg_adjustment_bps: u64,
+        twap_window_secs: i64,
+    ) -> Result<()> {
+        require!(
+            max_peg_adjustment_bps <= 10000 && twap_window_secs > 0,
+            ErrorCode::InvalidParameter
+        );
+        accrue_all_collateral(
+            &ctx.accounts.config,
+            &ctx.accounts.rate_model,
+            ctx.remaining_accounts,
+            Clock::get()?.unix_timestamp,
+        )?;
+        let rate_model = &mut ctx.accounts.rate_model;
+        rate_model.peg_feeder = peg_feeder;
+        rate_model.peg_sensitivity_bps = peg_sensitivity_bps;
+        rate_model.max_peg_adjustment_bps = max_peg_adjustment_bps;
+        rate_model.twap_window_secs = twap_window_secs;
+        Ok(())
+    }
+
+    pub fn update_peg_price<'info>(
+        ctx: Context<'_, '_, 'info, 'info, UpdatePegPrice<'info>>,
+        price: u64,
+    ) -> Result<()> {
+        require!(price > 0, ErrorCode::InvalidAmount);
+        let now = Clock::get()?.unix_timestamp;
+        accrue_all_collateral(
+            &ctx.accounts.config,
+            &ctx.accounts.rate_model,
+            ctx.remaining_accounts,
+            now,
+        )?;
+        let rate_model = &mut ctx.accounts.rate_model;
+
+        // The previous observation held since it was posted, so it is folded into the
+        // average with a weight of its age, capped at the full window.
+        if rate_model.twap_price == 0 {
+            rate_model.twap_price = price;
+        } else {
+            let window = rate_model.twap_window_secs as u128;
+            let weight = ((now - rate_model.last_price_at).max(0) as u128).min(window);
+            rate_model.twap_price = (((rate_model.twap_price as u128) * (window - weight)
+                + (rate_model.last_price as u128) * weight)
+                / window) as u64;
+        }
+        rate_model.last_price = price;
+        rate_model.last_price_at = now;
+
+        emit!(PegPriceUpdatedEvent {
+            price,
+            twap_price: rate_model.twap_price,
+        });
+
+        Ok(())
+    }
+
+    pub fn set_late_rate(ctx: Context<UpdateConfig>, late_rate_bps: u64) -> Result<()> {
+        require!(late_rate_bps <= 10000, ErrorCode::InvalidParameter);
+        ctx.accounts.config.late_rate_bps = late_rate_bps;
+        Ok(())
+    }
+
+    // A crank never pays more than the smallest vault it can act on is worth.
+    pub fn set_keeper_bounty(ctx: Context<UpdateConfig>, keeper_bounty: u64) -> Result<()> {
+        require!(
+            keeper_bounty <= ctx.accounts.config.min_debt,
+            ErrorCode::InvalidParameter
+        );
+        ctx.accounts.config.keeper_bounty = keeper_bounty;
+        Ok(())
+    }
+
+    pub fn set_surplus_distribution(
+        ctx: Context<UpdateConfig>,
+        reserve_target: u64,
+        min_surplus_distribution: u64,
+    ) -> Result<()> {
+        let config = &mut ctx.accounts.config;
+        config.reserve_target = reserve_target;
+        config.min_surplus_distribution = min_surplus_distribution;
+        Ok(())
+    }
+
+    pub fn set_debt_auction_params(
+        ctx: Context<UpdateConfig>,
+        governance_mint: Pubkey,
+        duration_secs: i64,
+        min_bid_decrement_bps: u64,
+    ) -> Result<()> {
+        require!(
+            duration_secs > 0 && min_bid_decrement_bps < 10000,
+            ErrorCode::InvalidParameter
+        );
+        let config = &mut ctx.accounts.config;
+        config.governance_mint = governance_mint;
+        config.debt_auction_duration_secs = duration_secs;
+        config.min_bid_decrement_bps = min_bid_decrement_bps;
+        Ok(())
+    }
+
+    pub fn set_surplus_auction_params(
+        ctx: Context<UpdateConfig>,
+        surplus_buffer_cap: u64,
+        duration_secs: i64,
+        min_bid_increment_bps: u64,
+    ) -> Result<()> {
+        require!(duration_secs > 0, ErrorCode::InvalidParameter);
+        let config = &mut ctx.accounts.config;
+        config.surplus_buffer_cap = surplus_buffer_cap;
+        config.surplus_auction_duration_secs = duration_secs;
+        config.min_bid_increment_bps = min_bid_increment_bps;
+        Ok(())
+    }
+
+    pub fn set_flash_fee(ctx: Context<UpdateConfig>, flash_fee_bps: u64) -> Result<()> {
+        require!(flash_fee_bps <= 10000, ErrorCode::InvalidParameter);
+        ctx.accounts.config.flash_fee_bps = flash_fee_bps;
+        Ok(())
+    }
+
+    pub fn set_arb_threshold(ctx: Context<UpdateConfig>, threshold_bps: u64) -> Result<()> {
+        require!(threshold_bps <= 10000, ErrorCode::InvalidParameter);
+        ctx.accounts.config.arb_threshold_bps = threshold_bps;
+        Ok(())
+    }
+
+    pub fn set_min_accrual_interval(ctx: Context<UpdateConfig>, interval_secs: i64) -> Result<()> {
+        require!(interval_secs >= 0, ErrorCode::InvalidParameter);
+        ctx.accounts.config.min_accrual_interval_secs = interval_secs;
+        Ok(())
+    }
+
+    pub fn set_risk_authority(ctx: Context<UpdateConfig>, risk_authority: Pubkey) -> Result<()> {
+        ctx.accounts.config.risk_authority = risk_authority;
+        Ok(())
+    }
+
+    pub fn set_oracle_manager(ctx: Context<UpdateConfig>, oracle_manager: Pubkey) -> Result<()> {
+        ctx.accounts.config.oracle_manager = oracle_manager;
+        Ok(())
+    }
+
+    // Admin handover takes two steps so a mistyped key can't take over governance: the
+    // proposed admin has to sign to accept, and the current admin can withdraw the offer.
+    pub fn propose_admin(ctx: Context<UpdateConfig>, new_admin: Pubkey) -> Result<()> {
+        require!(new_admin != Pubkey::default(), ErrorCode::InvalidParameter);
+        let config = &mut ctx.accounts.config;
+        config.pending_admin = new_admin;
+
+        emit!(AdminProposedEvent {
+            admin: config.admin,
+            pending_admin: new_admin,
+        });
+
+        Ok(())
+    }
+
+    pub fn accept_admin(ctx: Context<AcceptAdmin>) -> Result<()> {
+        let config = &mut ctx.accounts.config;
+        let previous_admin = config.admin;
+        config.admin = config.pending_admin;
+        config.pending_admin = Pubkey::default();
+
+        emit!(AdminTransferredEvent {
+            previous_admin,
+            admin: config.admin,
+        });
+
+        Ok(())
+    }
+
+    pub fn cancel_admin_transfer(ctx: Context<UpdateConfig>) -> Result<()> {
+        let config = &mut ctx.accounts.config;
+        require!(
+            config.pending_admin != Pubkey::default(),
+            ErrorCode::NoPendingAdmin
+        );
+        let cancelled = config.pending_admin;
+        config.pending_admin = Pubkey::default();
+
+        emit!(AdminTransferCancelledEvent {
+            admin: config.admin,
+            cancelled_admin: cancelled,
+        });
+
+        Ok(())
+    }
+
+    // The guardian can only add pause flags; lifting a pause takes the admin.
+    pub fn set_pause_flags(ctx: Context<SetPauseFlags>, paused_flags: u64) -> Result<()> {
+        let config = &mut ctx.accounts.config;
+        let previous_flags = config.paused_flags;
+        require!(
+            ctx.accounts.authority.key() == config.admin
+                || paused_flags & previous_flags == previous_flags,
+            ErrorCode::Unauthorized
+        );
+        config.paused_flags = paused_flags;
+
+        emit!(PauseFlagsUpdatedEvent {
+            authority: ctx.accounts.authority.key(),
+            previous_flags,
+            paused_flags,
+        });
+
+        Ok(())
+    }
+
+    pub fn set_guardian(ctx: Context<UpdateConfig>, guardian: Pubkey) -> Result<()> {
+        ctx.accounts.config.guardian = guardian;
+        Ok(())
+    }
+
+    pub fn set_settlement_wait(ctx: Context<UpdateConfig>, wait_secs: i64) -> Result<()> {
+        require!(wait_secs >= 0, ErrorCode::InvalidParameter);
+        ctx.accounts.config.settlement_wait_secs = wait_secs;
+        Ok(())
+    }
+
+    pub fn set_min_debt(ctx: Context<UpdateConfig>, min_debt: u64) -> Result<()> {
+        ctx.accounts.config.min_debt = min_debt;
+        Ok(())
+    }
+
+    pub fn set_close_factor(ctx: Context<UpdateConfig>, close_factor_bps: u64) -> Result<()> {
+        require!(
+            close_factor_bps > 0 && close_factor_bps <= 10000,
+            ErrorCode::InvalidParameter
+        );
+        ctx.accounts.config.close_factor_bps = close_factor_bps;
+        Ok(())
+    }
+
+    pub fn set_borrow_fee(ctx: Context<UpdateConfig>, fee_bps: u64) -> Result<()> {
+        require!(fee_bps <= 10000, ErrorCode::InvalidParameter);
+        ctx.accounts.config.borrow_fee_bps = fee_bps;
+        Ok(())
+    }
+
+    pub fn set_treasury_share(ctx: Context<UpdateConfig>, share_bps: u64) -> Result<()> {
+        let config = &ctx.accounts.config;
+        let total_bps = share_bps
+            .checked_add(config.reserve_factor_bps)
+            .and_then(|total| total.checked_add(config.insurance_share_bps))
+            .ok_or(ErrorCode::MathOverflow)?;
+        require!(total_bps <= 10000, ErrorCode::InvalidParameter);
+        ctx.accounts.config.treasury_share_bps = share_bps;
+        Ok(())
+    }
+
+    pub fn set_reserve_factor(ctx: Context<UpdateConfig>, reserve_factor_bps: u64) -> Result<()> {
+        let config = &ctx.accounts.config;
+        let total_bps = reserve_factor_bps
+            .checked_add(config.treasury_share_bps)
+            .and_then(|total| total.checked_add(config.insurance_share_bps))
+            .ok_or(ErrorCode::MathOverflow)?;
+        require!(total_bps <= 10000, ErrorCode::InvalidParameter);
+        ctx.accounts.config.reserve_factor_bps = reserve_factor_bps;
+        Ok(())
+    }
+
+    pub fn set_insurance_share(ctx: Context<UpdateConfig>, share_bps: u64) -> Result<()> {
         let config = &ctx.accounts.config;
+        let total_bps = share_bps
+            .checked_add(config.treasury_share_bps)
+            .and_then(|total| total.checked_add(config.reserve_factor_bps))
+            .ok_or(ErrorCode::MathOverflow)?;
+        require!(total_bps <= 10000, ErrorCode::InvalidParameter);
+        ctx.accounts.config.insurance_share_bps = share_bps;
+        Ok(())
+    }
+
+    pub fn set_redemption_fee(ctx: Context<UpdateConfig>, fee_bps: u64) -> Result<()> {
+        require!(fee_bps <= 10000, ErrorCode::InvalidParameter);
+        ctx.accounts.config.redemption_fee_bps = fee_bps;
+        Ok(())
+    }
+
+    pub fn set_redemption_decay(
+        ctx: Context<SetRedemptionDecay>,
+        half_life_secs: i64,
+    ) -> Result<()> {
+        require!(half_life_secs > 0, ErrorCode::InvalidParameter);
+        let fee_state = &mut ctx.accounts.fee_state;
+        fee_state.half_life_secs = half_life_secs;
+        fee_state.bump = ctx.bumps.fee_state;
+        Ok(())
+    }
+
+    pub fn set_critical_cr(ctx: Context<UpdateConfig>, critical_cr_bps: u64) -> Result<()> {
+        require!(
+            critical_cr_bps == 0 || critical_cr_bps > 10000,
+            ErrorCode::InvalidParameter
+        );
+        ctx.accounts.config.critical_cr_bps = critical_cr_bps;
+        Ok(())
+    }
+
+    pub fn set_grace_period(ctx: Context<UpdateConfig>, grace_period_secs: i64) -> Result<()> {
+        require!(grace_period_secs >= 0, ErrorCode::InvalidParameter);
+        ctx.accounts.config.grace_period_secs = grace_period_secs;
+        Ok(())
+    }
+
+    pub fn set_max_total_debt(ctx: Context<UpdateConfig>, max_total_debt: u64) -> Result<()> {
+        require_no_timelock(&ctx.accounts.config)?;
+        ctx.accounts.config.max_total_debt = max_total_debt;
+        Ok(())
+    }
+
+    pub fn set_max_vault_debt(ctx: Context<UpdateConfig>, max_vault_debt: u64) -> Result<()> {
+        require_no_timelock(&ctx.accounts.config)?;
+        ctx.accounts.config.max_vault_debt = max_vault_debt;
+        Ok(())
+    }
+
+    // The delay can be lengthened at once; shortening it is itself a queued change.
+    pub fn set_param_change_delay(ctx: Context<UpdateConfig>, delay_secs: i64) -> Result<()> {
+        require!(
+            delay_secs >= ctx.accounts.config.param_change_delay_secs,
+            ErrorCode::TimelockActive
+        );
+        ctx.accounts.config.param_change_delay_secs = delay_secs;
+        Ok(())
+    }
+
+    // While a delay is set, rates, LTVs and ceilings only change through the queue, so
+    // borrowers and integrators see every change param_change_delay_secs before it lands.
+    pub fn queue_param_change(
+        ctx: Context<QueueParamChange>,
+        param: RiskParam,
+        target: Pubkey,
+        value: u64,
+    ) -> Result<()> {
+        validate_param_value(param, value)?;
+        let eta = Clock::get()?
+            .unix_timestamp
+            .checked_add(ctx.accounts.config.param_change_delay_secs)
+            .ok_or(ErrorCode::MathOverflow)?;
+        let pending = &mut ctx.accounts.pending_changes;
+        require!(
+            pending.changes.len() < MAX_PENDING_CHANGES,
+            ErrorCode::PendingChangesFull
+        );
+        let id = pending.next_id;
+        pending.next_id += 1;
+        pending.changes.push(ParamChange {
+            id,
+            param,
+            target,
+            value,
+            eta,
+        });
+        pending.bump = ctx.bumps.pending_changes;
+
+        emit!(ParamChangeQueuedEvent {
+            id,
+            param,
+            target,
+            value,
+            eta,
+        });
+
+        Ok(())
+    }
+
+    pub fn cancel_param_change(ctx: Context<CancelParamChange>, id: u64) -> Result<()> {
+        let pending = &mut ctx.accounts.pending_changes;
+        let index = pending
+            .changes
+            .iter()
+            .position(|change| change.id == id)
+            .ok_or(ErrorCode::ParamChangeNotFound)?;
+        pending.changes.remove(index);
+
+        emit!(ParamChangeCancelledEvent { id });
+
+        Ok(())
+    }
+
+    // Anyone can apply a change once its delay has elapsed. Cross-field rules such as
+    // max LTV staying under liquidation LTV are checked against the live values here.
+    pub fn execute_param_change<'info>(
+        ctx: Context<'_, '_, 'info, 'info, ExecuteParamChange<'info>>,
+        id: u64,
+    ) -> Result<()> {
+        let now = Clock::get()?.unix_timestamp;
+        let pending = &mut ctx.accounts.pending_changes;
+        let index = pending
+            .changes
+            .iter()
+            .position(|change| change.id == id)
+            .ok_or(ErrorCode::ParamChangeNotFound)?;
+        let change = pending.changes[index];
+        require!(now >= change.eta, ErrorCode::ParamChangeNotReady);
+        pending.changes.remove(index);
+
+        if matches!(
+            change.param,
+            RiskParam::BaseRate | RiskParam::Slope1 | RiskParam::Slope2 | RiskParam::Kink
+        ) {
+            // A named collateral config would be written back over its accrued copy.
+            require!(
+                ctx.accounts.collateral_config.is_none(),
+                ErrorCode::ParamTargetMismatch
+            );
+            accrue_all_collateral(
+                &ctx.accounts.config,
+                &ctx.accounts.rate_model,
+                ctx.remaining_accounts,
+                now,
+            )?;
+        }
+
+        let value = change.value;
+        let config = &mut ctx.accounts.config;
+        let rate_model = &mut ctx.accounts.rate_model;
+        let previous = match change.param {
+            RiskParam::MaxTotalDebt => std::mem::replace(&mut config.max_total_debt, value),
+            RiskParam::MaxVaultDebt => std::mem::replace(&mut config.max_vault_debt, value),
+            RiskParam::ChangeDelay => {
+                std::mem::replace(&mut config.param_change_delay_secs, value as i64) as u64
+            }
+            RiskParam::BaseRate => std::mem::replace(&mut rate_model.base_rate_bps, value),
+            RiskParam::Slope1 => std::mem::replace(&mut rate_model.slope1_bps, value),
+            RiskParam::Slope2 => std::mem::replace(&mut rate_model.slope2_bps, value),
+            RiskParam::Kink => std::mem::replace(&mut rate_model.kink_bps, value),
+            RiskParam::MarketDebtCeiling => {
+                let market = ctx
+                    .accounts
+                    .stablecoin_market
+                    .as_mut()
+                    .ok_or(ErrorCode::ParamTargetMismatch)?;
+                require!(market.mint == change.target, ErrorCode::ParamTargetMismatch);
+                std::mem::replace(&mut market.debt_ceiling, value)
+            }
+            RiskParam::BorrowRate
+            | RiskParam::MaxLtv
+            | RiskParam::LiquidationLtv
+            | RiskParam::DebtCeiling => {
+                let collateral = ctx
+                    .accounts
+                    .collateral_config
+                    .as_mut()
+                    .ok_or(ErrorCode::ParamTargetMismatch)?;
+                require!(
+                    collateral.mint == change.target,
+                    ErrorCode::ParamTargetMismatch
+                );
+                match change.param {
+                    RiskParam::BorrowRate => {
+                        validate_borrow_rate(config, collateral.borrow_rate_bps, value)?;
+                        accrue_borrow_index(collateral, rate_model, now)?;
+                        std::mem::replace(&mut collateral.borrow_rate_bps, value)
+                    }
+                    RiskParam::MaxLtv => {
+                        require!(
+                            value < collateral.liquidation_ltv_bps,
+                            ErrorCode::InvalidParameter
+                        );
+                        std::mem::replace(&mut collateral.max_ltv_bps, value)
+                    }
+                    RiskParam::LiquidationLtv => {
+                        require!(value > collateral.max_ltv_bps, ErrorCode::InvalidParameter);
+                        std::mem::replace(&mut collateral.liquidation_ltv_bps, value)
+                    }
+                    _ => std::mem::replace(&mut collateral.debt_ceiling, value),
+                }
+            }
+        };
+
+        emit!(ParamChangeExecutedEvent {
+            id,
+            param: change.param,
+            target: change.target,
+            previous,
+            value,
+        });
+
+        Ok(())
+    }
+
+    pub fn set_max_mint_per_tx(ctx: Context<UpdateConfig>, max_mint_per_tx: u64) -> Result<()> {
+        ctx.accounts.config.max_mint_per_tx = max_mint_per_tx;
+        Ok(())
+    }
+
+    pub fn set_rate_limit(
+        ctx: Context<SetRateLimit>,
+        window_slots: u64,
+        max_mint_per_window: u64,
+    ) -> Result<()> {
+        require!(window_slots > 0, ErrorCode::InvalidParameter);
+        let rate_limit = &mut ctx.accounts.rate_limit;
+        rate_limit.window_slots = window_slots;
+        rate_limit.max_mint_per_window = max_mint_per_window;
+        rate_limit.bump = ctx.bumps.rate_limit;
+        Ok(())
+    }
+
+    pub fn set_withdrawal_cooldown(
+        ctx: Context<UpdateConfig>,
+        withdraw_threshold: u64,
+        withdraw_delay_secs: i64,
+    ) -> Result<()> {
+        require!(withdraw_delay_secs >= 0, ErrorCode::InvalidParameter);
+        ctx.accounts.config.withdraw_threshold = withdraw_threshold;
+        ctx.accounts.config.withdraw_delay_secs = withdraw_delay_secs;
+        Ok(())
+    }
+
+    pub fn set_discount_rate(ctx: Context<UpdateConfig>, discount_rate_bps: u64) -> Result<()> {
+        require!(discount_rate_bps <= 10000, ErrorCode::InvalidParameter);
+        ctx.accounts.config.discount_rate_bps = discount_rate_bps;
+        Ok(())
+    }
+
+    pub fn set_rating_haircuts(ctx: Context<UpdateConfig>, haircuts_bps: [u64; 5]) -> Result<()> {
+        require!(
+            haircuts_bps.iter().all(|haircut| *haircut < 10000),
+            ErrorCode::InvalidParameter
+        );
+        ctx.accounts.config.rating_haircuts_bps = haircuts_bps;
+        Ok(())
+    }
+
+    pub fn set_rating_spreads(ctx: Context<UpdateConfig>, spreads_bps: [u64; 5]) -> Result<()> {
+        require!(
+            spreads_bps.iter().all(|spread| *spread <= 10000),
+            ErrorCode::InvalidParameter
+        );
+        ctx.accounts.config.rating_spreads_bps = spreads_bps;
+        Ok(())
+    }
+
+    pub fn set_curve_publisher(ctx: Context<SetCurvePublisher>, publisher: Pubkey) -> Result<()> {
+        let curve = &mut ctx.accounts.yield_curve;
+        curve.publisher = publisher;
+        curve.bump = ctx.bumps.yield_curve;
+        Ok(())
+    }
+
+    pub fn publish_yield_curve(
+        ctx: Context<PublishYieldCurve>,
+        tenors_secs: Vec<i64>,
+        rates_bps: Vec<u64>,
+    ) -> Result<()> {
+        let points = tenors_secs.len();
+        require!(
+            points > 0 && points <= YIELD_CURVE_POINTS && rates_bps.len() == points,
+            ErrorCode::InvalidParameter
+        );
+        require!(
+            tenors_secs[0] > 0 && tenors_secs.windows(2).all(|pair| pair[0] < pair[1]),
+            ErrorCode::InvalidParameter
+        );
+        require!(
+            rates_bps.iter().all(|rate| *rate <= 10000),
+            ErrorCode::InvalidParameter
+        );
+
+        let curve = &mut ctx.accounts.yield_curve;
+        curve.tenors_secs = [0; YIELD_CURVE_POINTS];
+        curve.rates_bps = [0; YIELD_CURVE_POINTS];
+        curve.tenors_secs[..points].copy_from_slice(&tenors_secs);
+        curve.rates_bps[..points].copy_from_slice(&rates_bps);
+        curve.point_count = points as u8;
+        curve.updated_at = Clock::get()?.unix_timestamp;
+
+        emit!(YieldCurvePublishedEvent {
+            publisher: curve.publisher,
+            tenors_secs,
+            rates_bps,
+            updated_at: curve.updated_at,
+        });
+
+        Ok(())
+    }
+
+    pub fn set_custodian(ctx: Context<SetCustodian>, custodian: Pubkey) -> Result<()> {
+        let attestation = &mut ctx.accounts.nav_attestation;
+        attestation.mint = ctx.accounts.collateral_config.mint;
+        attestation.custodian = custodian;
+        attestation.bump = ctx.bumps.nav_attestation;
+        Ok(())
+    }
+
+    pub fn submit_nav_attestation(
+        ctx: Context<SubmitNavAttestation>,
+        nav: u64,
+        attested_at: i64,
+    ) -> Result<()> {
+        let attestation = &mut ctx.accounts.nav_attestation;
+        require!(nav > 0, ErrorCode::InvalidPrice);
+        require!(
+            attested_at > attestation.attested_at && attested_at <= Clock::get()?.unix_timestamp,
+            ErrorCode::InvalidAttestation
+        );
+
+        let mut message = Vec::with_capacity(48);
+        message.extend_from_slice(attestation.mint.as_ref());
+        message.extend_from_slice(&nav.to_le_bytes());
+        message.extend_from_slice(&attested_at.to_le_bytes());
+        verify_ed25519_signature(&ctx.accounts.instructions, &attestation.custodian, &message)?;
+
+        attestation.nav = nav;
+        attestation.attested_at = attested_at;
+
+        emit!(NavAttestedEvent {
+            mint: attestation.mint,
+            custodian: attestation.custodian,
+            nav,
+            attested_at,
+        });
+
+        Ok(())
+    }
+
+    pub fn initialize_feed_registry(ctx: Context<InitializeFeedRegistry>) -> Result<()> {
+        ctx.accounts.feed_registry.bump = ctx.bumps.feed_registry;
+        Ok(())
+    }
+
+    pub fn register_feeds(
+        ctx: Context<ManageFeedRegistry>,
+        feeds: Vec<OracleFeed>,
+        quorum: u8,
+        max_price_age_secs: u64,
+        max_confidence_bps: u64,
+    ) -> Result<()> {
+        let mint = ctx.accounts.collateral_config.mint;
+        let entry = feed_entry(mint, &feeds, quorum, max_price_age_secs, max_confidence_bps)?;
+        let registry = &mut ctx.accounts.feed_registry;
+        require!(
+            !registry.entries.iter().any(|known| known.mint == mint),
+            ErrorCode::FeedAlreadyRegistered
+        );
+        require!(
+            registry.entries.len() < MAX_REGISTRY_ENTRIES,
+            ErrorCode::FeedRegistryFull
+        );
+        registry.entries.push(entry);
+        apply_feed_entry(&mut ctx.accounts.collateral_config, &entry);
+
+        emit!(FeedRegistryUpdatedEvent {
+            mint,
+            action: RegistryAction::Added,
+            feed_count: entry.feed_count,
+            quorum,
+        });
+
+        Ok(())
+    }
+
+    pub fn update_feeds(
+        ctx: Context<ManageFeedRegistry>,
+        feeds: Vec<OracleFeed>,
+        quorum: u8,
+        max_price_age_secs: u64,
+        max_confidence_bps: u64,
+    ) -> Result<()> {
+        let mint = ctx.accounts.collateral_config.mint;
+        let entry = feed_entry(mint, &feeds, quorum, max_price_age_secs, max_confidence_bps)?;
+        let registry = &mut ctx.accounts.feed_registry;
+        let existing = registry
+            .entries
+            .iter_mut()
+            .find(|existing| existing.mint == mint)
+            .ok_or(ErrorCode::FeedNotRegistered)?;
+        *existing = entry;
+        apply_feed_entry(&mut ctx.accounts.collateral_config, &entry);
+
+        emit!(FeedRegistryUpdatedEvent {
+            mint,
+            action: RegistryAction::Updated,
+            feed_count: entry.feed_count,
+            quorum,
+        });
+
+        Ok(())
+    }
+
+    pub fn remove_feeds(ctx: Context<ManageFeedRegistry>) -> Result<()> {
+        let mint = ctx.accounts.collateral_config.mint;
+        let registry = &mut ctx.accounts.feed_registry;
+        let index = registry
+            .entries
+            .iter()
+            .position(|existing| existing.mint == mint)
+            .ok_or(ErrorCode::FeedNotRegistered)?;
+        registry.entries.swap_remove(index);
+        // With no feeds left the collateral goes back to model pricing.
+        let cleared = feed_entry(mint, &[], 0, 0, 0)?;
+        apply_feed_entry(&mut ctx.accounts.collateral_config, &cleared);
+
+        emit!(FeedRegistryUpdatedEvent {
+            mint,
+            action: RegistryAction::Removed,
+            feed_count: 0,
+            quorum: 0,
+        });
+
+        Ok(())
+    }
+
+    pub fn set_fx_feed(
+        ctx: Context<SetFxFeed>,
+        currency: Currency,
+        feed_id: [u8; 32],
+        max_price_age_secs: u64,
+    ) -> Result<()> {
+        require!(currency != Currency::Usd, ErrorCode::InvalidParameter);
+        require!(max_price_age_secs > 0, ErrorCode::InvalidParameter);
+        let fx_feed = &mut ctx.accounts.fx_feed;
+        fx_feed.currency = currency;
+        fx_feed.feed_id = feed_id;
+        fx_feed.max_price_age_secs = max_price_age_secs;
+        fx_feed.bump = ctx.bumps.fx_feed;
+
+        emit!(FxFeedSetEvent {
+            currency,
+            feed_id,
+            max_price_age_secs,
+        });
+
+        Ok(())
+    }
+
+    pub fn set_collateral_currency(
+        ctx: Context<SetCollateralCurrency>,
+        currency: Currency,
+    ) -> Result<()> {
+        let collateral = &mut ctx.accounts.collateral_config;
+        collateral.currency = currency;
+
+        emit!(CollateralCurrencySetEvent {
+            mint: collateral.mint,
+            currency,
+        });
+
+        Ok(())
+    }
+
+    pub fn register_stablecoin_market(
+        ctx: Context<RegisterStablecoinMarket>,
+        currency: Currency,
+        debt_ceiling: u64,
+    ) -> Result<()> {
+        let market = &mut ctx.accounts.stablecoin_market;
+        market.mint = ctx.accounts.stablecoin_mint.key();
+        market.currency = currency;
+        market.debt_ceiling = debt_ceiling;
+        market.bump = ctx.bumps.stablecoin_market;
+
+        emit!(StablecoinMarketRegisteredEvent {
+            mint: market.mint,
+            currency,
+            debt_ceiling,
+        });
+
+        Ok(())
+    }
+
+    pub fn set_market_debt_ceiling(
+        ctx: Context<SetMarketDebtCeiling>,
+        debt_ceiling: u64,
+    ) -> Result<()> {
+        require_no_timelock(&ctx.accounts.config)?;
+        ctx.accounts.stablecoin_market.debt_ceiling = debt_ceiling;
+        Ok(())
+    }
+
+    // A collateral borrows a single stablecoin and is valued in that market's currency.
+    // Moving it to another market is only allowed while it backs no debt.
+    pub fn set_collateral_market(ctx: Context<SetCollateralMarket>) -> Result<()> {
+        let collateral = &mut ctx.accounts.collateral_config;
+        require!(collateral.total_debt == 0, ErrorCode::OutstandingDebt);
+        let market = &ctx.accounts.stablecoin_market;
+        collateral.stablecoin_mint = market.mint;
+        collateral.market_currency = market.currency;
+
+        emit!(CollateralMarketSetEvent {
+            mint: collateral.mint,
+            stablecoin_mint: market.mint,
+            currency: market.currency,
+        });
+
+        Ok(())
+    }
+
+    pub fn set_price_band(
+        ctx: Context<SetPriceBand>,
+        min_unit_value: u64,
+        max_unit_value: u64,
+        max_price_change_bps: u64,
+    ) -> Result<()> {
+        require!(
+            max_unit_value == 0 || min_unit_value <= max_unit_value,
+            ErrorCode::InvalidParameter
+        );
+        let collateral = &mut ctx.accounts.collateral_config;
+        collateral.min_unit_value = min_unit_value;
+        collateral.max_unit_value = max_unit_value;
+        collateral.max_price_change_bps = max_price_change_bps;
+        Ok(())
+    }
+
+    pub fn set_emergency_price(
+        ctx: Context<SetEmergencyPrice>,
+        unit_value: u64,
+        expires_at: i64,
+    ) -> Result<()> {
+        require!(
+            unit_value == 0 || expires_at > Clock::get()?.unix_timestamp,
+            ErrorCode::InvalidParameter
+        );
+        let cache = &mut ctx.accounts.price_cache;
+        cache.emergency_unit_value = unit_value;
+        cache.emergency_expires_at = expires_at;
+
+        emit!(EmergencyPriceSetEvent {
+            mint: cache.mint,
+            authority: ctx.accounts.authority.key(),
+            unit_value,
+            expires_at,
+        });
+
+        Ok(())
+    }
+
+    pub fn set_manual_mark(
+        ctx: Context<SetManualMark>,
+        unit_value: u64,
+        expires_at: i64,
+    ) -> Result<()> {
+        require!(
+            unit_value == 0 || expires_at > Clock::get()?.unix_timestamp,
+            ErrorCode::InvalidParameter
+        );
+        let cache = &mut ctx.accounts.price_cache;
+        let previous_unit_value = cache.manual_unit_value;
+        cache.manual_unit_value = unit_value;
+        cache.manual_expires_at = expires_at;
+
+        emit!(ManualMarkSetEvent {
+            mint: cache.mint,
+            risk_authority: ctx.accounts.risk_authority.key(),
+            unit_value,
+            previous_unit_value,
+            expires_at,
+        });
+
+        Ok(())
+    }
+
+    pub fn acknowledge_price_band(ctx: Context<AcknowledgePriceBand>) -> Result<()> {
+        let collateral = &mut ctx.accounts.collateral_config;
+        require!(collateral.borrowing_paused, ErrorCode::InvalidParameter);
+        if collateral.pending_unit_value > 0 {
+            let now = Clock::get()?.unix_timestamp;
+            collateral.unit_value = collateral.pending_unit_value;
+            collateral.value_updated_at = now;
+            let cache = &mut ctx.accounts.price_cache;
+            cache.unit_value = collateral.unit_value;
+            cache.updated_at = now;
+        }
+        collateral.pending_unit_value = 0;
+        collateral.borrowing_paused = false;
+
+        emit!(PriceBandAcknowledgedEvent {
+            mint: collateral.mint,
+            risk_authority: ctx.accounts.risk_authority.key(),
+            unit_value: collateral.unit_value,
+        });
+
+        Ok(())
+    }
+
+    pub fn set_rating_rates(ctx: Context<SetRatingRates>, rates_bps: [u64; 5]) -> Result<()> {
+        require!(
+            rates_bps.iter().all(|rate| *rate <= 10000),
+            ErrorCode::InvalidParameter
+        );
+        ctx.accounts.collateral_config.rating_rates_bps = rates_bps;
+        Ok(())
+    }
+
+    pub fn set_collateral_config(
+        ctx: Context<SetCollateralConfig>,
+        max_ltv_bps: u64,
+        liquidation_ltv_bps: u64,
+        liquidation_penalty_bps: u64,
+        borrow_rate_bps: u64,
+        debt_ceiling: u64,
+        rating: CreditRating,
+    ) -> Result<()> {
+        require!(
+            max_ltv_bps > 0
+                && max_ltv_bps < liquidation_ltv_bps
+                && liquidation_ltv_bps <= 10000,
+            ErrorCode::InvalidParameter
+        );
+        require!(liquidation_penalty_bps <= 10000, ErrorCode::InvalidParameter);
+        require!(borrow_rate_bps <= 10000, ErrorCode::InvalidParameter);
+        let face_value = ctx.accounts.bond_metadata.face_value;
+        require!(face_value > 0, ErrorCode::InvalidParameter);
+        let existing = ctx.accounts.collateral_config.mint != Pubkey::default();
+        if existing {
+            require_no_timelock(&ctx.accounts.config)?;
+        }
+        let previous_rate_bps = if existing {
+            ctx.accounts.collateral_config.borrow_rate_bps
+        } else {
+            borrow_rate_bps
+        };
+        validate_borrow_rate(&ctx.accounts.config, previous_rate_bps, borrow_rate_bps)?;
+        if !existing {
+            ctx.accounts.config.collateral_config_count += 1;
+        }
+
+        let collateral = &mut ctx.accounts.collateral_config;
+        collateral.mint = ctx.accounts.collateral_mint.key();
+        collateral.max_ltv_bps = max_ltv_bps;
+        collateral.liquidation_ltv_bps = liquidation_ltv_bps;
+        collateral.liquidation_penalty_bps = liquidation_penalty_bps;
+        collateral.borrow_rate_bps = borrow_rate_bps;
+        collateral.debt_ceiling = debt_ceiling;
+        collateral.rating = rating;
+        collateral.face_value = face_value;
+        collateral.issuer = ctx.accounts.bond_metadata.authority;
+        if collateral.unit_value == 0 {
+            collateral.unit_value = face_value;
+        }
+        if collateral.borrow_index == 0 {
+            collateral.borrow_index = P_SCALE;
+            collateral.index_updated_at = Clock::get()?.unix_timestamp;
+        }
+        collateral.bump = ctx.bumps.collateral_config;
+
+        let price_twap = &mut ctx.accounts.price_twap;
+        price_twap.mint = collateral.mint;
+        price_twap.bump = ctx.bumps.price_twap;
+
+        let price_cache = &mut ctx.accounts.price_cache;
+        price_cache.mint = collateral.mint;
+        price_cache.bump = ctx.bumps.price_cache;
+        Ok(())
+    }
+
+    pub fn set_borrow_rate_limits(
+        ctx: Context<UpdateConfig>,
+        max_borrow_rate_bps: u64,
+        max_rate_step_bps: u64,
+    ) -> Result<()> {
+        require!(
+            max_borrow_rate_bps <= 10000 && max_rate_step_bps <= 10000,
+            ErrorCode::InvalidParameter
+        );
+        let config = &mut ctx.accounts.config;
+        config.max_borrow_rate_bps = max_borrow_rate_bps;
+        config.max_rate_step_bps = max_rate_step_bps;
+        Ok(())
+    }
+
+    pub fn set_borrow_rate(ctx: Context<SetBorrowRate>, borrow_rate_bps: u64) -> Result<()> {
+        require_no_timelock(&ctx.accounts.config)?;
+        let collateral = &mut ctx.accounts.collateral_config;
+        let previous_rate_bps = collateral.borrow_rate_bps;
+        validate_borrow_rate(&ctx.accounts.config, previous_rate_bps, borrow_rate_bps)?;
+        accrue_borrow_index(
+            collateral,
+            &ctx.accounts.rate_model,
+            Clock::get()?.unix_timestamp,
+        )?;
+        collateral.borrow_rate_bps = borrow_rate_bps;
+
+        emit!(BorrowRateUpdatedEvent {
+            mint: collateral.mint,
+            previous_rate_bps,
+            borrow_rate_bps,
+        });
+
+        Ok(())
+    }
+
+    // The new rate applies from effective_at onwards; the borrow index compounds the old
+    // rate up to that moment and the new one after it, whenever it is next accrued.
+    pub fn schedule_borrow_rate(
+        ctx: Context<ScheduleBorrowRate>,
+        borrow_rate_bps: u64,
+        effective_at: i64,
+    ) -> Result<()> {
+        require!(borrow_rate_bps <= 10000, ErrorCode::InvalidParameter);
+        let now = Clock::get()?.unix_timestamp;
+        require!(effective_at > now, ErrorCode::InvalidParameter);
+        require!(
+            effective_at - now >= ctx.accounts.config.param_change_delay_secs,
+            ErrorCode::TimelockActive
+        );
+        let collateral = &mut ctx.accounts.collateral_config;
+        accrue_borrow_index(collateral, &ctx.accounts.rate_model, now)?;
+        validate_borrow_rate(
+            &ctx.accounts.config,
+            collateral.borrow_rate_bps,
+            borrow_rate_bps,
+        )?;
+        collateral.scheduled_borrow_rate_bps = borrow_rate_bps;
+        collateral.rate_effective_at = effective_at;
+
+        emit!(BorrowRateScheduledEvent {
+            mint: collateral.mint,
+            current_rate_bps: collateral.borrow_rate_bps,
+            scheduled_rate_bps: borrow_rate_bps,
+            effective_at,
+        });
+
+        Ok(())
+    }
+
+    pub fn cancel_scheduled_borrow_rate(ctx: Context<ScheduleBorrowRate>) -> Result<()> {
+        let now = Clock::get()?.unix_timestamp;
+        let collateral = &mut ctx.accounts.collateral_config;
+        accrue_borrow_index(collateral, &ctx.accounts.rate_model, now)?;
+        require!(
+            collateral.rate_effective_at != 0,
+            ErrorCode::NoScheduledRate
+        );
+        collateral.scheduled_borrow_rate_bps = 0;
+        collateral.rate_effective_at = 0;
+
+        emit!(BorrowRateScheduledEvent {
+            mint: collateral.mint,
+            current_rate_bps: collateral.borrow_rate_bps,
+            scheduled_rate_bps: collateral.borrow_rate_bps,
+            effective_at: 0,
+        });
+
+        Ok(())
+    }
+
+    pub fn set_twap_window(ctx: Context<SetTwapWindow>, window_secs: i64) -> Result<()> {
+        require!(window_secs > 0, ErrorCode::InvalidParameter);
+        ctx.accounts.price_twap.window_secs = window_secs;
+        Ok(())
+    }
+
+    pub fn update_price_twap(ctx: Context<UpdatePriceTwap>) -> Result<()> {
+        let spot = ctx.accounts.collateral_config.unit_value;
+        let price_twap = &mut ctx.accounts.price_twap;
+        require!(price_twap.window_secs > 0, ErrorCode::InvalidParameter);
+        require!(spot > 0, ErrorCode::InvalidPrice);
+
+        let now = Clock::get()?.unix_timestamp;
+        if price_twap.twap_value == 0 {
+            price_twap.twap_value = spot;
+        } else {
+            let window = price_twap.window_secs as u128;
+            let weight = ((now - price_twap.last_update).max(0) as u128).min(window);
+            price_twap.twap_value = (((price_twap.twap_value as u128) * (window - weight)
+                + (price_twap.last_value as u128) * weight)
+                / window) as u64;
+        }
+        price_twap.last_value = spot;
+        price_twap.last_update = now;
+
+        emit!(PriceTwapUpdatedEvent {
+            mint: price_twap.mint,
+            spot_value: spot,
+            twap_value: price_twap.twap_value,
+        });
+
+        Ok(())
+    }
+
+    pub fn refresh_price_cache<'info>(
+        ctx: Context<'_, '_, 'info, 'info, RefreshPriceCache<'info>>,
+    ) -> Result<()> {
+        require!(
+            ctx.accounts.collateral_config.oracle_feed_count > 0,
+            ErrorCode::InvalidParameter
+        );
+        let fx = fx_conversion(
+            &ctx.accounts.collateral_config,
+            ctx.accounts.fx_feed.as_deref(),
+            ctx.accounts.fx_price_update.as_deref(),
+            Clock::get()?.unix_timestamp,
+        )?;
+        apply_oracle_price(
+            &mut ctx.accounts.collateral_config,
+            &mut ctx.accounts.price_cache,
+            ctx.remaining_accounts,
+            fx,
+        )?;
+        Ok(())
+    }
+
+    pub fn refresh_collateral_value(ctx: Context<RefreshCollateralValue>) -> Result<()> {
+        require!(
+            ctx.accounts.collateral_config.oracle_feed_count == 0,
+            ErrorCode::PriceFeedRequired
+        );
+        let now = Clock::get()?.unix_timestamp;
+        let config = &ctx.accounts.config;
+        let unit_value = match &ctx.accounts.yield_curve {
+            Some(curve) => {
+                require!(curve.point_count > 0, ErrorCode::InvalidParameter);
+                let spread =
+                    config.rating_spreads_bps[ctx.accounts.collateral_config.rating as usize];
+                present_value(&ctx.accounts.bond_metadata, now, |secs| {
+                    curve_rate_bps(curve, secs) + spread
+                })
+            }
+            None => present_value(&ctx.accounts.bond_metadata, now, |_| {
+                config.discount_rate_bps
+            }),
+        };
+        let (rate, scale) = fx_conversion(
+            &ctx.accounts.collateral_config,
+            ctx.accounts.fx_feed.as_deref(),
+            ctx.accounts.fx_price_update.as_deref(),
+            now,
+        )?;
+        let unit_value = ((unit_value as u128) * rate / scale).min(u64::MAX as u128) as u64;
+        let collateral = &mut ctx.accounts.collateral_config;
+        collateral.unit_value = unit_value;
+        collateral.value_updated_at = now;
+
+        emit!(CollateralRevaluedEvent {
+            mint: collateral.mint,
+            unit_value,
+            updated_at: now,
+        });
+
+        Ok(())
+    }
+
+    pub fn set_issuer_cap(ctx: Context<SetIssuerCap>, issuer: Pubkey, debt_cap: u64) -> Result<()> {
+        let exposure = &mut ctx.accounts.issuer_exposure;
+        exposure.issuer = issuer;
+        exposure.debt_cap = debt_cap;
+        exposure.bump = ctx.bumps.issuer_exposure;
+
+        emit!(IssuerCapSetEvent { issuer, debt_cap });
+
+        Ok(())
+    }
+
+    pub fn approve_collateral(ctx: Context<ApproveCollateral>, issuer: Pubkey) -> Result<()> {
+        let approval = &mut ctx.accounts.approval;
+        approval.mint = ctx.accounts.collateral_mint.key();
+        approval.issuer = issuer;
+        approval.bump = ctx.bumps.approval;
+
+        emit!(CollateralApprovedEvent {
+            mint: approval.mint,
+            issuer,
+        });
+
+        Ok(())
+    }
+
+    pub fn revoke_collateral(ctx: Context<RevokeCollateral>) -> Result<()> {
+        emit!(CollateralRevokedEvent {
+            mint: ctx.accounts.approval.mint,
+            issuer: ctx.accounts.approval.issuer,
+        });
+
+        Ok(())
+    }
+
+    pub fn initialize_config(
+        ctx: Context<InitializeConfig>,
+        min_debt: u64,
+        close_factor_bps: u64,
+        critical_cr_bps: u64,
+        max_total_debt: u64,
+        max_vault_debt: u64,
+    ) -> Result<()> {
+        require!(
+            close_factor_bps > 0 && close_factor_bps <= 10000,
+            ErrorCode::InvalidParameter
+        );
+        require!(
+            critical_cr_bps == 0 || critical_cr_bps > 10000,
+            ErrorCode::InvalidParameter
+        );
+        let config = &mut ctx.accounts.config;
+        config.admin = ctx.accounts.admin.key();
+        config.stablecoin_mint = ctx.accounts.stablecoin_mint.key();
+        config.mint_authority_bump = ctx.bumps.mint_authority;
+        config.min_debt = min_debt;
+        config.close_factor_bps = close_factor_bps;
+        config.critical_cr_bps = critical_cr_bps;
+        config.max_total_debt = max_total_debt;
+        config.max_vault_debt = max_vault_debt;
+        config.bump = ctx.bumps.config;
+
+        emit!(ConfigInitializedEvent {
+            config: config.key(),
+            admin: config.admin,
+            stablecoin_mint: config.stablecoin_mint,
+        });
+
+        Ok(())
+    }
+
+    pub fn initialize_global_state(ctx: Context<InitializeGlobalState>) -> Result<()> {
+        let global = &mut ctx.accounts.global_state;
+        global.total_face_value = 0;
+        global.total_debt = 0;
+        global.total_collateral_value = 0;
+        global.bump = ctx.bumps.global_state;
+        Ok(())
+    }
+
+    // The global state PDA holds the update authority, so the first call creates the
+    // metadata and later calls update it in place.
+    pub fn set_stablecoin_metadata(
+        ctx: Context<SetStablecoinMetadata>,
+        name: String,
+        symbol: String,
+        uri: String,
+    ) -> Result<()> {
+        require!(
+            name.len() <= 32 && symbol.len() <= 10 && uri.len() <= 200,
+            ErrorCode::InvalidParameter
+        );
+        let data = DataV2 {
+            name: name.clone(),
+            symbol: symbol.clone(),
+            uri: uri.clone(),
+            seller_fee_basis_points: 0,
+            creators: None,
+            collection: None,
+            uses: None,
+        };
+        // Creating the metadata needs the mint authority's signature as well.
+        let bump = [ctx.accounts.global_state.bump];
+        let mint_bump = [ctx.accounts.config.mint_authority_bump];
+        let signer_seeds: &[&[&[u8]]] =
+            &[&[b"global_state", &bump], &[b"mint_authority", &mint_bump]];
+        if ctx.accounts.metadata.data_is_empty() {
+            create_metadata_accounts_v3(
+                CpiContext::new_with_signer(
+                    ctx.accounts.token_metadata_program.to_account_info(),
+                    CreateMetadataAccountsV3 {
+                        metadata: ctx.accounts.metadata.to_account_info(),
+                        mint: ctx.accounts.stablecoin_mint.to_account_info(),
+                        mint_authority: ctx.accounts.mint_authority.to_account_info(),
+                        payer: ctx.accounts.admin.to_account_info(),
+                        update_authority: ctx.accounts.global_state.to_account_info(),
+                        system_program: ctx.accounts.system_program.to_account_info(),
+                        rent: ctx.accounts.rent.to_account_info(),
+                    },
+                    signer_seeds,
+                ),
+                data,
+                true,
+                true,
+                None,
+            )?;
+        } else {
+            update_metadata_accounts_v2(
+                CpiContext::new_with_signer(
+                    ctx.accounts.token_metadata_program.to_account_info(),
+                    UpdateMetadataAccountsV2 {
+                        metadata: ctx.accounts.metadata.to_account_info(),
+                        update_authority: ctx.accounts.global_state.to_account_info(),
+                    },
+                    signer_seeds,
+                ),
+                None,
+                Some(data),
+                None,
+                None,
+            )?;
+        }
+
+        emit!(StablecoinMetadataSetEvent { name, symbol, uri });
+
+        Ok(())
+    }
+
+    pub fn initialize_treasury(_ctx: Context<InitializeTreasury>) -> Result<()> {
+        Ok(())
+    }
+
+    pub fn initialize_reserve(_ctx: Context<InitializeReserve>) -> Result<()> {
+        Ok(())
+    }
+
+    pub fn initialize_insurance_fund(_ctx: Context<InitializeInsuranceFund>) -> Result<()> {
+        Ok(())
+    }
+
+    pub fn initialize_pol(
+        ctx: Context<InitializePol>,
+        amm_program: Pubkey,
+        pool: Pubkey,
+    ) -> Result<()> {
+        let pol = &mut ctx.accounts.pol;
+        pol.amm_program = amm_program;
+        pol.pool = pool;
+        pol.lp_mint = ctx.accounts.lp_mint.key();
+        pol.stablecoin_deployed = 0;
+        pol.usdc_deployed = 0;
+        pol.bump = ctx.bumps.pol;
+        Ok(())
+    }
+
+    pub fn initialize_flash_lock(ctx: Context<InitializeFlashLock>) -> Result<()> {
+        ctx.accounts.flash_lock.bump = ctx.bumps.flash_lock;
+        Ok(())
+    }
+
+    // Supply minted before the ledger existed has no recorded origin and is attributed to
+    // CDP debt.
+    pub fn initialize_supply_ledger(ctx: Context<InitializeSupplyLedger>) -> Result<()> {
+        let ledger = &mut ctx.accounts.supply_ledger;
+        ledger.cdp_debt = ctx.accounts.stablecoin_mint.supply;
+        ledger.bump = ctx.bumps.supply_ledger;
+        Ok(())
+    }
+
+    pub fn initialize_sorted_vaults(ctx: Context<InitializeSortedVaults>) -> Result<()> {
+        let list = &mut ctx.accounts.sorted_vaults;
+        list.head = Pubkey::default();
+        list.tail = Pubkey::default();
+        list.size = 0;
+        list.bump = ctx.bumps.sorted_vaults;
+        Ok(())
+    }
+
+    pub fn deposit_collateral(ctx: Context<DepositCollateral>, bond_amount: u64) -> Result<()> {
+        require_not_paused(&ctx.accounts.config, PAUSE_DEPOSIT)?;
+        require!(bond_amount > 0, ErrorCode::InvalidAmount);
+        require_eligible_bond(&ctx.accounts.bond_metadata, Clock::get()?.unix_timestamp)?;
+        require!(ctx.accounts.user_bond_account.amount >= bond_amount, ErrorCode::NotEnoughBonds);
+
+        anchor_spl::token::transfer(
+            CpiContext::new(
+                ctx.accounts.token_program.to_account_info(),
+                Transfer {
+                    from: ctx.accounts.user_bond_account.to_account_info(),
+                    to: ctx.accounts.escrow.to_account_info(),
+                    authority: ctx.accounts.user.to_account_info(),
+                },
+            ),
+            bond_amount,
+        )?;
+
+        let user_key = ctx.accounts.user.key();
+        let collateral_mint_key = ctx.accounts.collateral_mint.key();
+        let bump = [ctx.bumps.vault];
+        let signer_seeds: &[&[&[u8]]] =
+            &[&[b"vault", user_key.as_ref(), collateral_mint_key.as_ref(), &bump]];
+
+        anchor_spl::token::mint_to(
+            CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                MintTo {
+                    mint: ctx.accounts.receipt_mint.to_account_info(),
+                    to: ctx.accounts.user_receipt_account.to_account_info(),
+                    authority: ctx.accounts.vault.to_account_info(),
+                },
+                signer_seeds,
+            ),
+            bond_amount,
+        )?;
+
+        anchor_spl::token::mint_to(
+            CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                MintTo {
+                    mint: ctx.accounts.position_mint.to_account_info(),
+                    to: ctx.accounts.user_position_account.to_account_info(),
+                    authority: ctx.accounts.vault.to_account_info(),
+                },
+                signer_seeds,
+            ),
+            1,
+        )?;
+        anchor_spl::token::set_authority(
+            CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                SetAuthority {
+                    account_or_mint: ctx.accounts.position_mint.to_account_info(),
+                    current_authority: ctx.accounts.vault.to_account_info(),
+                },
+                signer_seeds,
+            ),
+            AuthorityType::MintTokens,
+            None,
+        )?;
+
+        let vault = &mut ctx.accounts.vault;
+        vault.owner = user_key;
+        vault.creator = user_key;
+        vault.position_mint = ctx.accounts.position_mint.key();
+        vault.pending_owner = Pubkey::default();
+        vault.delegate = Pubkey::default();
+        vault.collateral_mint = collateral_mint_key;
+        vault.receipt_mint = ctx.accounts.receipt_mint.key();
+        vault.bond_amount = bond_amount;
+        vault.leg_amount = 0;
+        vault.leg_values = CollateralValues::default();
+        vault.collateral_face_value = 0;
+        vault.leg_collateral_value = 0;
+        vault.borrowed = 0;
+        vault.settlement_due = false;
+        vault.last_borrow_timestamp = Clock::get()?.unix_timestamp;
+        vault.bump = ctx.bumps.vault;
+        track_vault(
+            &mut ctx.accounts.global_state,
+            &mut ctx.accounts.collateral_config,
+            &ctx.accounts.config,
+            0,
+            0,
+            vault,
+        )?;
+
+        emit!(DepositCollateralEvent {
+            vault: vault.key(),
+            owner: vault.owner,
+            bond_amount,
+            total_amount: vault.bond_amount,
+        });
+
+        Ok(())
+    }
+
+    pub fn add_collateral<'info>(
+        ctx: Context<'_, '_, 'info, 'info, AddCollateral<'info>>,
+        bond_amount: u64,
+        hint: ListHint,
+    ) -> Result<()> {
+        require_not_paused(&ctx.accounts.config, PAUSE_DEPOSIT)?;
+        require!(bond_amount > 0, ErrorCode::InvalidAmount);
+        require_eligible_bond(&ctx.accounts.bond_metadata, Clock::get()?.unix_timestamp)?;
+        load_cached_price(
+            &mut ctx.accounts.collateral_config,
+            &ctx.accounts.price_cache,
+            true,
+        )?;
+        require!(
+            ctx.accounts.authority_bond_account.amount >= bond_amount,
+            ErrorCode::NotEnoughBonds
+        );
+
+        anchor_spl::token::transfer(
+            CpiContext::new(
+                ctx.accounts.token_program.to_account_info(),
+                Transfer {
+                    from: ctx.accounts.authority_bond_account.to_account_info(),
+                    to: ctx.accounts.escrow.to_account_info(),
+                    authority: ctx.accounts.authority.to_account_info(),
+                },
+            ),
+            bond_amount,
+        )?;
+
+        let creator = ctx.accounts.vault.creator;
+        let collateral_mint = ctx.accounts.vault.collateral_mint;
+        let bump = [ctx.accounts.vault.bump];
+        let signer_seeds: &[&[&[u8]]] =
+            &[&[b"vault", creator.as_ref(), collateral_mint.as_ref(), &bump]];
+
+        anchor_spl::token::mint_to(
+            CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                MintTo {
+                    mint: ctx.accounts.receipt_mint.to_account_info(),
+                    to: ctx.accounts.owner_receipt_account.to_account_info(),
+                    authority: ctx.accounts.vault.to_account_info(),
+                },
+                signer_seeds,
+            ),
+            bond_amount,
+        )?;
+
+        let vault = &mut ctx.accounts.vault;
+        let (collateral_before, debt_before) = (primary_amount(vault), vault.borrowed);
+        vault.bond_amount = vault
+            .bond_amount
+            .checked_add(bond_amount)
+            .ok_or(ErrorCode::MathOverflow)?;
+        let now = Clock::get()?.unix_timestamp;
+        let recovery = is_recovery_mode(&ctx.accounts.global_state, &ctx.accounts.config)?;
+        refresh_unhealthy_flag(
+            vault,
+            &ctx.accounts.collateral_config,
+            &ctx.accounts.rate_model,
+            &ctx.accounts.config,
+            recovery,
+            now,
+        )?;
+
+        track_vault(
+            &mut ctx.accounts.global_state,
+            &mut ctx.accounts.collateral_config,
+            &ctx.accounts.config,
+            collateral_before,
+            debt_before,
+            vault,
+        )?;
+
+        let vault_key = vault.key();
+        update_sorted_position(
+            &mut ctx.accounts.sorted_vaults,
+            vault_key,
+            vault,
+            hint,
+            ctx.remaining_accounts,
+        )?;
+
+        emit!(DepositCollateralEvent {
+            vault: vault.key(),
+            owner: vault.owner,
+            bond_amount,
+            total_amount: vault.bond_amount,
+        });
+
+        Ok(())
+    }
+
+    pub fn borrow<'info>(
+        ctx: Context<'_, '_, 'info, 'info, Borrow<'info>>,
+        amount: u64,
+        hint: ListHint,
+    ) -> Result<()> {
+        require_not_paused(&ctx.accounts.config, PAUSE_BORROW)?;
+        require!(
+            ctx.accounts.deny_entry.data_is_empty(),
+            ErrorCode::AccountDenied
+        );
+        sync_position_owner(&mut ctx.accounts.vault, ctx.accounts.owner.key());
+        load_cached_price(
+            &mut ctx.accounts.collateral_config,
+            &ctx.accounts.price_cache,
+            false,
+        )?;
+        require!(
+            !ctx.accounts.collateral_config.borrowing_paused,
+            ErrorCode::BorrowingPaused
+        );
+        require!(
+            ctx.accounts.settlement.data_is_empty(),
+            ErrorCode::SettlementTriggered
+        );
+        let vault = &mut ctx.accounts.vault;
+        accrue_borrow_index(
+            &mut ctx.accounts.collateral_config,
+            &ctx.accounts.rate_model,
+            Clock::get()?.unix_timestamp,
+        )?;
+        let config = &ctx.accounts.config;
+        let rate_model = &ctx.accounts.rate_model;
+        let collateral = &ctx.accounts.collateral_config;
+        let risk_collateral = risk_view(collateral, &ctx.accounts.price_twap);
+        let recovery = is_recovery_mode(&ctx.accounts.global_state, config)?;
+        let (collateral_before, debt_before) = (primary_amount(vault), vault.borrowed);
+
+        require!(amount > 0, ErrorCode::InvalidAmount);
+        require!(!vault.settlement_due, ErrorCode::SettlementDue);
+
+        let now = Clock::get()?.unix_timestamp;
+        require!(
+            vault.term_maturity == 0 || now < vault.term_maturity,
+            ErrorCode::TermExpired
+        );
+        require_eligible_bond(&ctx.accounts.bond_metadata, now)?;
+        let interest = pending_interest(vault, collateral, rate_model, config, now)?;
+        let fee = borrow_fee(amount, config);
+        let minted = amount.checked_add(fee).ok_or(ErrorCode::MathOverflow)?;
+        let new_debt = vault
+            .borrowed
+            .checked_add(interest)
+            .and_then(|debt| debt.checked_add(minted))
+            .ok_or(ErrorCode::MathOverflow)?;
+        let values = vault_values(vault, &risk_collateral, config)?;
+        require!(
+            new_debt <= borrow_limit(&values, config, recovery)?,
+            ErrorCode::UndercollateralizedVault
+        );
+        require!(new_debt >= config.min_debt, ErrorCode::DebtBelowMinimum);
+        require_within_debt_ceiling(collateral, debt_before, new_debt)?;
+        require_within_global_ceiling(&ctx.accounts.global_state, config, debt_before, new_debt)?;
+        require_within_vault_cap(config, new_debt)?;
+        require_within_issuer_cap(&ctx.accounts.issuer_exposure, debt_before, new_debt)?;
+        require_within_market_ceiling(
+            &ctx.accounts.stablecoin_market,
+            ctx.accounts.stablecoin_mint.supply,
+            minted,
+        )?;
+        limit_mint(config, &mut ctx.accounts.rate_limit, minted)?;
+
+        mint_stablecoin(
+            ctx.accounts.stablecoin_token_program.to_account_info(),
+            token_interface::MintTo {
+                mint: ctx.accounts.stablecoin_mint.to_account_info(),
+                to: ctx.accounts.owner_stablecoin_account.to_account_info(),
+                authority: ctx.accounts.mint_authority.to_account_info(),
+            },
+            ctx.accounts.config.mint_authority_bump,
+            amount,
+        )?;
+        record_mint(
+            &mut ctx.accounts.supply_ledger,
+            SupplySource::CdpDebt,
+            amount,
+        )?;
+
+        mint_stablecoin(
+            ctx.accounts.stablecoin_token_program.to_account_info(),
+            token_interface::MintTo {
+                mint: ctx.accounts.stablecoin_mint.to_account_info(),
+                to: ctx.accounts.treasury.to_account_info(),
+                authority: ctx.accounts.mint_authority.to_account_info(),
+            },
+            ctx.accounts.config.mint_authority_bump,
+            fee,
+        )?;
+        record_mint(&mut ctx.accounts.supply_ledger, SupplySource::CdpDebt, fee)?;
+
+        lock_fixed_rate(
+            vault,
+            collateral,
+            rate_model,
+            debt_before + interest,
+            new_debt,
+        )?;
+        vault.borrowed = new_debt;
+        vault.unpaid_interest += interest;
+        checkpoint_debt(vault, collateral, now);
+
+        track_vault(
+            &mut ctx.accounts.global_state,
+            &mut ctx.accounts.collateral_config,
+            &ctx.accounts.config,
+            collateral_before,
+            debt_before,
+            vault,
+        )?;
+        track_issuer_debt(&mut ctx.accounts.issuer_exposure, debt_before, vault.borrowed)?;
+
+        let vault_key = vault.key();
+        update_sorted_position(
+            &mut ctx.accounts.sorted_vaults,
+            vault_key,
+            vault,
+            hint,
+            ctx.remaining_accounts,
+        )?;
+
+        emit!(BorrowEvent {
+            vault: vault.key(),
+            owner: vault.owner,
+            amount,
+            fee,
+            interest,
+            total_debt: new_debt,
+        });
+
+        Ok(())
+    }
+
+    pub fn loop_borrow<'info>(
+        ctx: Context<'_, '_, 'info, 'info, LoopBorrow<'info>>,
+        target_amount: u64,
+        swap_data: Vec<Vec<u8>>,
+        hint: ListHint,
+        list_nodes: u8,
+    ) -> Result<()> {
+        require_not_paused(&ctx.accounts.config, PAUSE_BORROW)?;
+        require!(
+            ctx.accounts.deny_entry.data_is_empty(),
+            ErrorCode::AccountDenied
+        );
+        sync_position_owner(&mut ctx.accounts.vault, ctx.accounts.owner.key());
+        require!(
+            !ctx.accounts.collateral_config.borrowing_paused,
+            ErrorCode::BorrowingPaused
+        );
+        require!(
+            ctx.accounts.settlement.data_is_empty(),
+            ErrorCode::SettlementTriggered
+        );
+        require!(
+            ctx.accounts.swap_program.key() != crate::ID,
+            ErrorCode::InvalidSwapProgram
+        );
+        require!(
+            (list_nodes as usize) <= ctx.remaining_accounts.len(),
+            ErrorCode::InvalidRemainingAccounts
+        );
+        let (node_accounts, swap_accounts) = ctx.remaining_accounts.split_at(list_nodes as usize);
+        load_cached_price(
+            &mut ctx.accounts.collateral_config,
+            &ctx.accounts.price_cache,
+            false,
+        )?;
+
+        let vault = &ctx.accounts.vault;
+        accrue_borrow_index(
+            &mut ctx.accounts.collateral_config,
+            &ctx.accounts.rate_model,
+            Clock::get()?.unix_timestamp,
+        )?;
+        let config = &ctx.accounts.config;
+        let rate_model = &ctx.accounts.rate_model;
+        let collateral = &ctx.accounts.collateral_config;
+        let risk_collateral = risk_view(collateral, &ctx.accounts.price_twap);
+        let recovery = is_recovery_mode(&ctx.accounts.global_state, config)?;
+        let (collateral_before, debt_before) = (primary_amount(vault), vault.borrowed);
+        require!(target_amount > vault.bond_amount, ErrorCode::InvalidAmount);
+        require!(!vault.settlement_due, ErrorCode::SettlementDue);
+
+        let now = Clock::get()?.unix_timestamp;
+        require!(
+            vault.term_maturity == 0 || now < vault.term_maturity,
+            ErrorCode::TermExpired
+        );
+        require_eligible_bond(&ctx.accounts.bond_metadata, now)?;
+        let interest = pending_interest(vault, collateral, rate_model, config, now)?;
+        let mut total_debt = vault
+            .borrowed
+            .checked_add(interest)
+            .ok_or(ErrorCode::MathOverflow)?;
+        let mut bond_amount = vault.bond_amount;
+        let (leg_amount, leg_values) = (vault.leg_amount, vault.leg_values);
+
+        let creator = vault.creator;
+        let collateral_mint = vault.collateral_mint;
+        let bump = [vault.bump];
+        let signer_seeds: &[&[&[u8]]] =
+            &[&[b"vault", creator.as_ref(), collateral_mint.as_ref(), &bump]];
+
+        let mut rounds: u8 = 0;
+        let mut total_borrowed: u64 = 0;
+        let mut total_fee: u64 = 0;
+        for data in swap_data {
+            if bond_amount >= target_amount {
+                break;
+            }
+            let values = projected_values(
+                bond_amount - leg_amount,
+                &leg_values,
+                &risk_collateral,
+                config,
+            )?;
+            let headroom = borrow_limit(&values, config, recovery)?.saturating_sub(total_debt);
+            let available =
+                ((headroom as u128) * 10000 / (10000 + config.borrow_fee_bps as u128)) as u64;
+            if available == 0 {
+                break;
+            }
+            let fee = borrow_fee(available, config);
+
+            mint_stablecoin(
+                ctx.accounts.stablecoin_token_program.to_account_info(),
+                token_interface::MintTo {
+                    mint: ctx.accounts.stablecoin_mint.to_account_info(),
+                    to: ctx.accounts.owner_stablecoin_account.to_account_info(),
+                    authority: ctx.accounts.mint_authority.to_account_info(),
+                },
+                ctx.accounts.config.mint_authority_bump,
+                available,
+            )?;
+            record_mint(
+                &mut ctx.accounts.supply_ledger,
+                SupplySource::CdpDebt,
+                available,
+            )?;
+            total_debt = total_debt
+                .checked_add(available + fee)
+                .ok_or(ErrorCode::MathOverflow)?;
+            total_borrowed = total_borrowed
+                .checked_add(available)
+                .ok_or(ErrorCode::MathOverflow)?;
+            total_fee += fee;
+
+            let bond_balance_before = ctx.accounts.owner_bond_account.amount;
+            invoke_swap(&ctx.accounts.swap_program, swap_accounts, data)?;
+            ctx.accounts.owner_bond_account.reload()?;
+            let acquired = ctx
+                .accounts
+                .owner_bond_account
+                .amount
+                .saturating_sub(bond_balance_before)
+                .min(target_amount - bond_amount);
+            require!(acquired > 0, ErrorCode::NotEnoughBonds);
+
+            anchor_spl::token::transfer(
+                CpiContext::new(
+                    ctx.accounts.token_program.to_account_info(),
+                    Transfer {
+                        from: ctx.accounts.owner_bond_account.to_account_info(),
+                        to: ctx.accounts.escrow.to_account_info(),
+                        authority: ctx.accounts.owner.to_account_info(),
+                    },
+                ),
+                acquired,
+            )?;
+            anchor_spl::token::mint_to(
+                CpiContext::new_with_signer(
+                    ctx.accounts.token_program.to_account_info(),
+                    MintTo {
+                        mint: ctx.accounts.receipt_mint.to_account_info(),
+                        to: ctx.accounts.owner_receipt_account.to_account_info(),
+                        authority: ctx.accounts.vault.to_account_info(),
+                    },
+                    signer_seeds,
+                ),
+                acquired,
+            )?;
+            bond_amount += acquired;
+            rounds += 1;
+        }
+        require!(rounds > 0, ErrorCode::UndercollateralizedVault);
+        require!(total_debt >= config.min_debt, ErrorCode::DebtBelowMinimum);
+        require_within_debt_ceiling(collateral, debt_before, total_debt)?;
+        require_within_global_ceiling(&ctx.accounts.global_state, config, debt_before, total_debt)?;
+        require_within_vault_cap(config, total_debt)?;
+        require_within_issuer_cap(&ctx.accounts.issuer_exposure, debt_before, total_debt)?;
+        let minted = total_borrowed
+            .checked_add(total_fee)
+            .ok_or(ErrorCode::MathOverflow)?;
+        require_within_market_ceiling(
+            &ctx.accounts.stablecoin_market,
+            ctx.accounts.stablecoin_mint.supply,
+            minted,
+        )?;
+        limit_mint(config, &mut ctx.accounts.rate_limit, minted)?;
+
+        mint_stablecoin(
+            ctx.accounts.stablecoin_token_program.to_account_info(),
+            token_interface::MintTo {
+                mint: ctx.accounts.stablecoin_mint.to_account_info(),
+                to: ctx.accounts.treasury.to_account_info(),
+                authority: ctx.accounts.mint_authority.to_account_info(),
+            },
+            ctx.accounts.config.mint_authority_bump,
+            total_fee,
+        )?;
+        record_mint(
+            &mut ctx.accounts.supply_ledger,
+            SupplySource::CdpDebt,
+            total_fee,
+        )?;
+
+        let vault = &mut ctx.accounts.vault;
+        lock_fixed_rate(
+            vault,
+            collateral,
+            rate_model,
+            debt_before + interest,
+            total_debt,
+        )?;
+        vault.bond_amount = bond_amount;
+        vault.borrowed = total_debt;
+        vault.unpaid_interest += interest;
+        checkpoint_debt(vault, collateral, now);
+        refresh_unhealthy_flag(vault, collateral, rate_model, config, recovery, now)?;
+        track_vault(
+            &mut ctx.accounts.global_state,
+            &mut ctx.accounts.collateral_config,
+            &ctx.accounts.config,
+            collateral_before,
+            debt_before,
+            vault,
+        )?;
+        track_issuer_debt(&mut ctx.accounts.issuer_exposure, debt_before, vault.borrowed)?;
+
+        let vault_key = vault.key();
+        update_sorted_position(
+            &mut ctx.accounts.sorted_vaults,
+            vault_key,
+            vault,
+            hint,
+            node_accounts,
+        )?;
+
+        emit!(LoopBorrowEvent {
+            vault: vault_key,
+            owner: vault.owner,
+            rounds,
+            borrowed: total_borrowed,
+            fee: total_fee,
+            collateral_acquired: primary_amount(vault) - collateral_before,
+            total_amount: bond_amount,
+            total_debt,
+        });
+
+        Ok(())
+    }
+
+    pub fn get_vault_health(ctx: Context<GetVaultHealth>) -> Result<()> {
+        let vault = &ctx.accounts.vault;
+        let config = &ctx.accounts.config;
+        let mut collateral = (*ctx.accounts.collateral_config).clone();
+        load_cached_price(&mut collateral, &ctx.accounts.price_cache, true)?;
+        let now = Clock::get()?.unix_timestamp;
+        let interest = pending_interest(vault, &collateral, &ctx.accounts.rate_model, config, now)?;
+        let debt = vault
+            .borrowed
+            .checked_add(interest)
+            .ok_or(ErrorCode::MathOverflow)?;
+        let values = vault_values(vault, &collateral, config)?;
+        let recovery = is_recovery_mode(&ctx.accounts.global_state, config)?;
+
+        let health = VaultHealth {
+            collateral_value: values.market_value,
+            debt,
+            health_factor_bps: health_factor_bps(&values, debt)?,
+            liquidatable: debt > 0 && is_liquidatable(&values, debt, config, recovery)?,
+        };
+        set_return_data(&health.try_to_vec()?);
+        Ok(())
+    }
+
+    pub fn accrue_interest<'info>(
+        ctx: Context<'_, '_, 'info, 'info, AccrueInterest<'info>>,
+        hint: ListHint,
+        hook_accounts: u8,
+    ) -> Result<()> {
+        let (remaining, hook_accounts) =
+            split_hook_accounts(ctx.remaining_accounts, hook_accounts)?;
+        require_not_paused(&ctx.accounts.config, PAUSE_ACCRUAL)?;
+        let interest = accrue_vault(
+            &mut ctx.accounts.vault,
+            &mut ctx.accounts.collateral_config,
+            &mut ctx.accounts.issuer_exposure,
+            &mut ctx.accounts.global_state,
+            &ctx.accounts.rate_model,
+            &ctx.accounts.config,
+            Clock::get()?.unix_timestamp,
+        )?;
+        let vault_key = ctx.accounts.vault.key();
+        update_sorted_position(
+            &mut ctx.accounts.sorted_vaults,
+            vault_key,
+            &mut ctx.accounts.vault,
+            hint,
+            remaining,
+        )?;
+
+        if interest > 0 {
+            pay_keeper_bounty(
+                &ctx.accounts.stablecoin_token_program,
+                &ctx.accounts.stablecoin_mint,
+                &ctx.accounts.treasury,
+                &ctx.accounts.keeper_stablecoin_account,
+                &ctx.accounts.global_state,
+                hook_accounts,
+                accrual_bounty(&ctx.accounts.config, interest),
+            )?;
+        }
+
+        Ok(())
+    }
+
+    pub fn accrue_many<'info>(
+        ctx: Context<'_, '_, 'info, 'info, AccrueMany<'info>>,
+        hints: Vec<ListHint>,
+        list_nodes: u8,
+        hook_accounts: u8,
+    ) -> Result<()> {
+        let (remaining, hook_accounts) =
+            split_hook_accounts(ctx.remaining_accounts, hook_accounts)?;
+        require_not_paused(&ctx.accounts.config, PAUSE_ACCRUAL)?;
+        require!(
+            (list_nodes as usize) <= remaining.len(),
+            ErrorCode::InvalidRemainingAccounts
+        );
+        let (vault_accounts, node_accounts) =
+            remaining.split_at(remaining.len() - list_nodes as usize);
+        require!(
+            vault_accounts.len() % 3 == 0 && vault_accounts.len() / 3 == hints.len(),
+            ErrorCode::InvalidRemainingAccounts
+        );
+
+        let now = Clock::get()?.unix_timestamp;
+        let mut accrued: u64 = 0;
+        let mut total_interest: u64 = 0;
+        let mut total_bounty: u64 = 0;
+
+        for (accounts, hint) in vault_accounts.chunks(3).zip(hints) {
+            let mut vault = Account::<Vault>::try_from(&accounts[0])?;
+            let mut collateral_config = Account::<CollateralConfig>::try_from(&accounts[1])?;
+            require!(
+                collateral_config.mint == vault.collateral_mint,
+                ErrorCode::InvalidRemainingAccounts
+            );
+            let mut issuer_exposure = Account::<IssuerExposure>::try_from(&accounts[2])?;
+            require!(
+                issuer_exposure.issuer == collateral_config.issuer,
+                ErrorCode::InvalidRemainingAccounts
+            );
+
+            // A vault that would breach the global ceiling is skipped rather than failing
+            // the whole batch; accrue_vault checks the ceiling before touching any state.
+            let interest = match accrue_vault(
+                &mut vault,
+                &mut collateral_config,
+                &mut issuer_exposure,
+                &mut ctx.accounts.global_state,
+                &ctx.accounts.rate_model,
+                &ctx.accounts.config,
+                now,
+            ) {
+                Ok(interest) => interest,
+                Err(err) if err == ErrorCode::GlobalDebtCeilingExceeded.into() => continue,
+                Err(err) => return Err(err),
+            };
+            let vault_key = vault.key();
+            update_sorted_position(
+                &mut ctx.accounts.sorted_vaults,
+                vault_key,
+                &mut vault,
+                hint,
+                node_accounts,
+            )?;
+            issuer_exposure.exit(&crate::ID)?;
+            vault.exit(&crate::ID)?;
+            collateral_config.exit(&crate::ID)?;
+
+            if interest > 0 {
+                total_interest += interest;
+                let bounty = accrual_bounty(&ctx.accounts.config, interest);
+                total_bounty = total_bounty.saturating_add(bounty);
+                accrued += 1;
+            }
+        }
+
+        let bounty = pay_keeper_bounty(
+            &ctx.accounts.stablecoin_token_program,
+            &ctx.accounts.stablecoin_mint,
+            &ctx.accounts.treasury,
+            &ctx.accounts.keeper_stablecoin_account,
+            &ctx.accounts.global_state,
+            hook_accounts,
+            total_bounty,
+        )?;
+
+        emit!(AccrueManyEvent {
+            caller: ctx.accounts.caller.key(),
+            vaults: accrued,
+            total_interest,
+            bounty,
+        });
+
+        Ok(())
+    }
+
+    pub fn repay<'info>(
+        ctx: Context<'_, '_, 'info, 'info, Repay<'info>>,
+        amount: u64,
+        hint: ListHint,
+    ) -> Result<()> {
+        require_not_paused(&ctx.accounts.config, PAUSE_REPAY)?;
+        require!(
+            ctx.accounts.deny_entry.data_is_empty(),
+            ErrorCode::AccountDenied
+        );
+        let vault = &mut ctx.accounts.vault;
+        accrue_borrow_index(
+            &mut ctx.accounts.collateral_config,
+            &ctx.accounts.rate_model,
+            Clock::get()?.unix_timestamp,
+        )?;
+        let config = &ctx.accounts.config;
+        let rate_model = &ctx.accounts.rate_model;
+        let collateral = &ctx.accounts.collateral_config;
+        let (collateral_before, debt_before) = (primary_amount(vault), vault.borrowed);
+
+        let now = Clock::get()?.unix_timestamp;
+        let interest = pending_interest(vault, collateral, rate_model, config, now)?;
+        let total_debt = vault
+            .borrowed
+            .checked_add(interest)
+            .ok_or(ErrorCode::MathOverflow)?;
+
+        require!(amount > 0, ErrorCode::InvalidAmount);
+        require!(amount <= total_debt, ErrorCode::RepayExceedsDebt);
+        let remaining_debt = total_debt - amount;
+        require!(
+            remaining_debt == 0 || remaining_debt >= config.min_debt,
+            ErrorCode::DebtBelowMinimum
+        );
+        require!(
+            ctx.accounts.authority_stablecoin_account.amount >= amount,
+            ErrorCode::InsufficientStablecoin
+        );
+
+        token_interface::burn(
+            CpiContext::new(
+                ctx.accounts.stablecoin_token_program.to_account_info(),
+                token_interface::Burn {
+                    mint: ctx.accounts.stablecoin_mint.to_account_info(),
+                    from: ctx.accounts.authority_stablecoin_account.to_account_info(),
+                    authority: ctx.accounts.authority.to_account_info(),
+                },
+            ),
+            amount,
+        )?;
+
+        vault.borrowed = remaining_debt;
+        settle_repayment(
+            vault,
+            RepaymentAccounts {
+                global_state: &mut ctx.accounts.global_state,
+                collateral_config: &mut ctx.accounts.collateral_config,
+                issuer_exposure: &mut ctx.accounts.issuer_exposure,
+                sorted_vaults: &mut ctx.accounts.sorted_vaults,
+                config: &ctx.accounts.config,
+                rate_model: &ctx.accounts.rate_model,
+            },
+            Some(InterestMint {
+                ledger: &mut ctx.accounts.supply_ledger,
+                token_program: &ctx.accounts.stablecoin_token_program,
+                stablecoin_mint: &ctx.accounts.stablecoin_mint,
+                mint_authority: &ctx.accounts.mint_authority,
+                recipients: [
+                    &ctx.accounts.staking_reward_vault,
+                    &ctx.accounts.treasury,
+                    &ctx.accounts.reserve,
+                    &ctx.accounts.insurance_fund,
+                ],
+            }),
+            Repayment {
+                collateral_before,
+                debt_before,
+                interest,
+                repaid: amount,
+                now,
+            },
+            hint,
+            ctx.remaining_accounts,
+        )?;
+
+        emit!(RepayEvent {
+            vault: vault.key(),
+            owner: vault.owner,
+            amount,
+            interest,
+            remaining_debt,
+        });
+
+        Ok(())
+    }
+
+    pub fn repay_for<'info>(
+        ctx: Context<'_, '_, 'info, 'info, RepayFor<'info>>,
+        amount: u64,
+        hint: ListHint,
+    ) -> Result<()> {
+        require_not_paused(&ctx.accounts.config, PAUSE_REPAY)?;
+        require!(
+            ctx.accounts.deny_entry.data_is_empty(),
+            ErrorCode::AccountDenied
+        );
+        let vault = &mut ctx.accounts.vault;
+        accrue_borrow_index(
+            &mut ctx.accounts.collateral_config,
+            &ctx.accounts.rate_model,
+            Clock::get()?.unix_timestamp,
+        )?;
+        let config = &ctx.accounts.config;
+        let rate_model = &ctx.accounts.rate_model;
+        let collateral = &ctx.accounts.collateral_config;
+        let (collateral_before, debt_before) = (primary_amount(vault), vault.borrowed);
+
+        let now = Clock::get()?.unix_timestamp;
+        let interest = pending_interest(vault, collateral, rate_model, config, now)?;
+        let total_debt = vault
+            .borrowed
+            .checked_add(interest)
+            .ok_or(ErrorCode::MathOverflow)?;
+
+        require!(amount > 0, ErrorCode::InvalidAmount);
+        require!(amount <= total_debt, ErrorCode::RepayExceedsDebt);
+        let remaining_debt = total_debt - amount;
+        require!(
+            remaining_debt == 0 || remaining_debt >= config.min_debt,
+            ErrorCode::DebtBelowMinimum
+        );
+        // A delegate can only burn what the account owner approved it for.
+        let source = &ctx.accounts.payer_stablecoin_account;
+        let spendable = if source.owner == ctx.accounts.payer.key() {
+            source.amount
+        } else {
+            source.delegated_amount.min(source.amount)
+        };
+        require!(spendable >= amount, ErrorCode::InsufficientStablecoin);
+
+        token_interface::burn(
+            CpiContext::new(
+                ctx.accounts.stablecoin_token_program.to_account_info(),
+                token_interface::Burn {
+                    mint: ctx.accounts.stablecoin_mint.to_account_info(),
+                    from: ctx.accounts.payer_stablecoin_account.to_account_info(),
+                    authority: ctx.accounts.payer.to_account_info(),
+                },
+            ),
+            amount,
+        )?;
+
+        vault.borrowed = remaining_debt;
+        settle_repayment(
+            vault,
+            RepaymentAccounts {
+                global_state: &mut ctx.accounts.global_state,
+                collateral_config: &mut ctx.accounts.collateral_config,
+                issuer_exposure: &mut ctx.accounts.issuer_exposure,
+                sorted_vaults: &mut ctx.accounts.sorted_vaults,
+                config: &ctx.accounts.config,
+                rate_model: &ctx.accounts.rate_model,
+            },
+            Some(InterestMint {
+                ledger: &mut ctx.accounts.supply_ledger,
+                token_program: &ctx.accounts.stablecoin_token_program,
+                stablecoin_mint: &ctx.accounts.stablecoin_mint,
+                mint_authority: &ctx.accounts.mint_authority,
+                recipients: [
+                    &ctx.accounts.staking_reward_vault,
+                    &ctx.accounts.treasury,
+                    &ctx.accounts.reserve,
+                    &ctx.accounts.insurance_fund,
+                ],
+            }),
+            Repayment {
+                collateral_before,
+                debt_before,
+                interest,
+                repaid: amount,
+                now,
+            },
+            hint,
+            ctx.remaining_accounts,
+        )?;
+
+        emit!(RepayForEvent {
+            vault: vault.key(),
+            owner: vault.owner,
+            payer: ctx.accounts.payer.key(),
+            funded_by: ctx.accounts.payer_stablecoin_account.owner,
+            amount,
+            interest,
+            remaining_debt,
+        });
+
+        Ok(())
+    }
+
+    pub fn withdraw_collateral<'info>(
+        ctx: Context<'_, '_, 'info, 'info, WithdrawCollateral<'info>>,
+        bond_amount: u64,
+        hint: ListHint,
+    ) -> Result<()> {
+        require_not_paused(&ctx.accounts.config, PAUSE_WITHDRAW)?;
+        sync_position_owner(&mut ctx.accounts.vault, ctx.accounts.owner.key());
+        load_cached_price(
+            &mut ctx.accounts.collateral_config,
+            &ctx.accounts.price_cache,
+            false,
+        )?;
+        consume_pending_withdrawal(
+            &mut ctx.accounts.vault,
+            &ctx.accounts.config,
+            bond_amount,
+            Clock::get()?.unix_timestamp,
+        )?;
+        let vault = &ctx.accounts.vault;
+        let config = &ctx.accounts.config;
+        let rate_model = &ctx.accounts.rate_model;
+        let collateral = &ctx.accounts.collateral_config;
+        let risk_collateral = risk_view(collateral, &ctx.accounts.price_twap);
+        let recovery = is_recovery_mode(&ctx.accounts.global_state, config)?;
+
+        require!(bond_amount > 0, ErrorCode::InvalidAmount);
+        require!(primary_amount(vault) >= bond_amount, ErrorCode::NotEnoughCollateral);
+
+        let now = Clock::get()?.unix_timestamp;
+        let total_debt = vault
+            .borrowed
+            .checked_add(pending_interest(
+                vault, collateral, rate_model, config, now,
+            )?)
+            .ok_or(ErrorCode::MathOverflow)?;
+        let remaining_bonds = vault.bond_amount - bond_amount;
+        let values = projected_values(
+            primary_amount(vault) - bond_amount,
+            &vault.leg_values,
+            &risk_collateral,
+            config,
+        )?;
+        require!(
+            total_debt <= borrow_limit(&values, config, recovery)?,
+            ErrorCode::UndercollateralizedVault
+        );
+
+        let creator = vault.creator;
+        let collateral_mint = vault.collateral_mint;
+        let bump = [vault.bump];
+        let signer_seeds: &[&[&[u8]]] =
+            &[&[b"vault", creator.as_ref(), collateral_mint.as_ref(), &bump]];
+
+        anchor_spl::token::burn(
+            CpiContext::new(
+                ctx.accounts.token_program.to_account_info(),
+                Burn {
+                    mint: ctx.accounts.receipt_mint.to_account_info(),
+                    from: ctx.accounts.owner_receipt_account.to_account_info(),
+                    authority: ctx.accounts.owner.to_account_info(),
+                },
+            ),
+            bond_amount,
+        )?;
+
+        anchor_spl::token::transfer(
+            CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                Transfer {
+                    from: ctx.accounts.escrow.to_account_info(),
+                    to: ctx.accounts.owner_bond_account.to_account_info(),
+                    authority: ctx.accounts.vault.to_account_info(),
+                },
+                signer_seeds,
+            ),
+            bond_amount,
+        )?;
+
+        let vault = &mut ctx.accounts.vault;
+        let (collateral_before, debt_before) = (primary_amount(vault), vault.borrowed);
+        vault.bond_amount = remaining_bonds;
+        track_vault(
+            &mut ctx.accounts.global_state,
+            &mut ctx.accounts.collateral_config,
+            &ctx.accounts.config,
+            collateral_before,
+            debt_before,
+            vault,
+        )?;
+
+        let vault_key = vault.key();
+        update_sorted_position(
+            &mut ctx.accounts.sorted_vaults,
+            vault_key,
+            vault,
+            hint,
+            ctx.remaining_accounts,
+        )?;
+
+        emit!(WithdrawCollateralEvent {
+            vault: vault.key(),
+            owner: vault.owner,
+            bond_amount,
+            remaining_amount: remaining_bonds,
+        });
+
+        Ok(())
+    }
+
+    pub fn deposit_collateral_leg<'info>(
+        ctx: Context<'_, '_, 'info, 'info, DepositCollateralLeg<'info>>,
+        bond_amount: u64,
+        hint: ListHint,
+    ) -> Result<()> {
+        require_not_paused(&ctx.accounts.config, PAUSE_DEPOSIT)?;
+        require!(bond_amount > 0, ErrorCode::InvalidAmount);
+        require_eligible_bond(&ctx.accounts.bond_metadata, Clock::get()?.unix_timestamp)?;
+        require!(
+            ctx.accounts.authority_bond_account.amount >= bond_amount,
+            ErrorCode::NotEnoughBonds
+        );
+
+        anchor_spl::token::transfer(
+            CpiContext::new(
+                ctx.accounts.token_program.to_account_info(),
+                Transfer {
+                    from: ctx.accounts.authority_bond_account.to_account_info(),
+                    to: ctx.accounts.leg_escrow.to_account_info(),
+                    authority: ctx.accounts.authority.to_account_info(),
+                },
+            ),
+            bond_amount,
+        )?;
+
+        let creator = ctx.accounts.vault.creator;
+        let collateral_mint = ctx.accounts.vault.collateral_mint;
+        let bump = [ctx.accounts.vault.bump];
+        let signer_seeds: &[&[&[u8]]] =
+            &[&[b"vault", creator.as_ref(), collateral_mint.as_ref(), &bump]];
+
+        anchor_spl::token::mint_to(
+            CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                MintTo {
+                    mint: ctx.accounts.receipt_mint.to_account_info(),
+                    to: ctx.accounts.owner_receipt_account.to_account_info(),
+                    authority: ctx.accounts.vault.to_account_info(),
+                },
+                signer_seeds,
+            ),
+            bond_amount,
+        )?;
+
+        let leg = &mut ctx.accounts.leg;
+        leg.vault = ctx.accounts.vault.key();
+        leg.mint = ctx.accounts.leg_mint.key();
+        leg.escrow = ctx.accounts.leg_escrow.key();
+        leg.bond_amount = leg
+            .bond_amount
+            .checked_add(bond_amount)
+            .ok_or(ErrorCode::MathOverflow)?;
+        leg.bump = ctx.bumps.leg;
+        let leg_amount = leg.bond_amount;
+
+        let vault = &mut ctx.accounts.vault;
+        revalue_leg(
+            vault,
+            leg,
+            ctx.accounts.leg_collateral_config.rating,
+            &ctx.accounts.leg_collateral_config,
+            &ctx.accounts.config,
+        )?;
+        let (collateral_before, debt_before) = (primary_amount(vault), vault.borrowed);
+        vault.bond_amount = vault
+            .bond_amount
+            .checked_add(bond_amount)
+            .ok_or(ErrorCode::MathOverflow)?;
+        vault.leg_amount += bond_amount;
+        let now = Clock::get()?.unix_timestamp;
+        let recovery = is_recovery_mode(&ctx.accounts.global_state, &ctx.accounts.config)?;
+        refresh_unhealthy_flag(
+            vault,
+            &ctx.accounts.collateral_config,
+            &ctx.accounts.rate_model,
+            &ctx.accounts.config,
+            recovery,
+            now,
+        )?;
+
+        track_vault(
+            &mut ctx.accounts.global_state,
+            &mut ctx.accounts.collateral_config,
+            &ctx.accounts.config,
+            collateral_before,
+            debt_before,
+            vault,
+        )?;
+
+        let vault_key = vault.key();
+        update_sorted_position(
+            &mut ctx.accounts.sorted_vaults,
+            vault_key,
+            vault,
+            hint,
+            ctx.remaining_accounts,
+        )?;
+
+        emit!(CollateralLegEvent {
+            vault: vault_key,
+            mint: ctx.accounts.leg_mint.key(),
+            amount_delta: bond_amount as i64,
+            leg_amount,
+            total_face_value: vault.collateral_face_value,
+        });
+
+        Ok(())
+    }
+
+    pub fn withdraw_collateral_leg<'info>(
+        ctx: Context<'_, '_, 'info, 'info, WithdrawCollateralLeg<'info>>,
+        bond_amount: u64,
+        hint: ListHint,
+    ) -> Result<()> {
+        require_not_paused(&ctx.accounts.config, PAUSE_WITHDRAW)?;
+        sync_position_owner(&mut ctx.accounts.vault, ctx.accounts.owner.key());
+        consume_pending_withdrawal(
+            &mut ctx.accounts.vault,
+            &ctx.accounts.config,
+            bond_amount,
+            Clock::get()?.unix_timestamp,
+        )?;
+        load_cached_price(
+            &mut ctx.accounts.collateral_config,
+            &ctx.accounts.price_cache,
+            false,
+        )?;
+        load_cached_price(
+            &mut ctx.accounts.leg_collateral_config,
+            &ctx.accounts.leg_price_cache,
+            false,
+        )?;
+        let vault = &ctx.accounts.vault;
+        let config = &ctx.accounts.config;
+        let rate_model = &ctx.accounts.rate_model;
+        let collateral = &ctx.accounts.collateral_config;
+        let recovery = is_recovery_mode(&ctx.accounts.global_state, config)?;
+
+        require!(bond_amount > 0, ErrorCode::InvalidAmount);
+        require!(ctx.accounts.leg.bond_amount >= bond_amount, ErrorCode::NotEnoughCollateral);
+
+        let now = Clock::get()?.unix_timestamp;
+        let total_debt = vault
+            .borrowed
+            .checked_add(pending_interest(
+                vault, collateral, rate_model, config, now,
+            )?)
+            .ok_or(ErrorCode::MathOverflow)?;
+        let remaining_bonds = vault.bond_amount - bond_amount;
+        let leg = &ctx.accounts.leg;
+        let leg_risk = risk_view(
+            &ctx.accounts.leg_collateral_config,
+            &ctx.accounts.leg_price_twap,
+        );
+        let leg_values =
+            replace_leg_values(vault, leg, leg.bond_amount - bond_amount, &leg_risk, config)?;
+        let values = projected_values(
+            primary_amount(vault),
+            &leg_values,
+            &risk_view(collateral, &ctx.accounts.price_twap),
+            config,
+        )?;
+        require!(
+            total_debt <= borrow_limit(&values, config, recovery)?,
+            ErrorCode::UndercollateralizedVault
+        );
+
+        let creator = vault.creator;
+        let collateral_mint = vault.collateral_mint;
+        let bump = [vault.bump];
+        let signer_seeds: &[&[&[u8]]] =
+            &[&[b"vault", creator.as_ref(), collateral_mint.as_ref(), &bump]];
+
+        anchor_spl::token::burn(
+            CpiContext::new(
+                ctx.accounts.token_program.to_account_info(),
+                Burn {
+                    mint: ctx.accounts.receipt_mint.to_account_info(),
+                    from: ctx.accounts.owner_receipt_account.to_account_info(),
+                    authority: ctx.accounts.owner.to_account_info(),
+                },
+            ),
+            bond_amount,
+        )?;
+
+        anchor_spl::token::transfer(
+            CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                Transfer {
+                    from: ctx.accounts.leg_escrow.to_account_info(),
+                    to: ctx.accounts.owner_bond_account.to_account_info(),
+                    authority: ctx.accounts.vault.to_account_info(),
+                },
+                signer_seeds,
+            ),
+            bond_amount,
+        )?;
+
+        let leg = &mut ctx.accounts.leg;
+        leg.bond_amount -= bond_amount;
+        let (leg_mint, leg_amount, leg_rating) = (leg.mint, leg.bond_amount, leg.rating);
+
+        let vault = &mut ctx.accounts.vault;
+        revalue_leg(
+            vault,
+            leg,
+            leg_rating,
+            &ctx.accounts.leg_collateral_config,
+            &ctx.accounts.config,
+        )?;
+        let (collateral_before, debt_before) = (primary_amount(vault), vault.borrowed);
+        vault.bond_amount = remaining_bonds;
+        vault.leg_amount -= bond_amount;
+        track_vault(
+            &mut ctx.accounts.global_state,
+            &mut ctx.accounts.collateral_config,
+            &ctx.accounts.config,
+            collateral_before,
+            debt_before,
+            vault,
+        )?;
+
+        let vault_key = vault.key();
+        update_sorted_position(
+            &mut ctx.accounts.sorted_vaults,
+            vault_key,
+            vault,
+            hint,
+            ctx.remaining_accounts,
+        )?;
+
+        emit!(CollateralLegEvent {
+            vault: vault_key,
+            mint: leg_mint,
+            amount_delta: -(bond_amount as i64),
+            leg_amount,
+            total_face_value: vault.collateral_face_value,
+        });
+
+        Ok(())
+    }
+
+    pub fn substitute_collateral<'info>(
+        ctx: Context<'_, '_, 'info, 'info, SubstituteCollateral<'info>>,
+        withdraw_amount: u64,
+        deposit_amount: u64,
+        hint: ListHint,
+    ) -> Result<()> {
+        require_not_paused(&ctx.accounts.config, PAUSE_DEPOSIT | PAUSE_WITHDRAW)?;
+        sync_position_owner(&mut ctx.accounts.vault, ctx.accounts.owner.key());
+        load_cached_price(
+            &mut ctx.accounts.collateral_config,
+            &ctx.accounts.price_cache,
+            false,
+        )?;
+        load_cached_price(
+            &mut ctx.accounts.source_collateral_config,
+            &ctx.accounts.source_price_cache,
+            false,
+        )?;
+        load_cached_price(
+            &mut ctx.accounts.target_collateral_config,
+            &ctx.accounts.target_price_cache,
+            false,
+        )?;
+        let vault = &ctx.accounts.vault;
+        let config = &ctx.accounts.config;
+        let rate_model = &ctx.accounts.rate_model;
+        let collateral = &ctx.accounts.collateral_config;
+        let now = Clock::get()?.unix_timestamp;
+        require_eligible_bond(&ctx.accounts.bond_metadata, now)?;
+        require!(withdraw_amount > 0 && deposit_amount > 0, ErrorCode::InvalidAmount);
+
+        let (source_mint, source_escrow) = match &ctx.accounts.source_leg {
+            Some(leg) => {
+                require!(leg.vault == vault.key(), ErrorCode::InvalidParameter);
+                require!(leg.bond_amount >= withdraw_amount, ErrorCode::NotEnoughCollateral);
+                (leg.mint, leg.escrow)
+            }
+            None => {
+                require!(
+                    primary_amount(vault) >= withdraw_amount,
+                    ErrorCode::NotEnoughCollateral
+                );
+                (vault.collateral_mint, ctx.accounts.escrow.key())
+            }
+        };
+        require!(
+            ctx.accounts.source_escrow.key() == source_escrow,
+            ErrorCode::InvalidParameter
+        );
+        require!(
+            ctx.accounts.source_collateral_config.mint == source_mint
+                && ctx.accounts.owner_source_account.mint == source_mint,
+            ErrorCode::InvalidParameter
+        );
+        require!(
+            ctx.accounts.target_mint.key() != source_mint,
+            ErrorCode::InvalidParameter
+        );
+        require!(
+            ctx.accounts.owner_target_account.amount >= deposit_amount,
+            ErrorCode::NotEnoughBonds
+        );
+        // The substitute is valued at the lower of spot and TWAP, the collateral it replaces
+        // at spot.
+        let source_risk = risk_view(
+            &ctx.accounts.source_collateral_config,
+            &ctx.accounts.source_price_twap,
+        );
+        let target_risk = risk_view(
+            &ctx.accounts.target_collateral_config,
+            &ctx.accounts.target_price_twap,
+        );
+        require!(
+            haircut_value(deposit_amount, &target_risk, config)?
+                >= haircut_value(withdraw_amount, &ctx.accounts.source_collateral_config, config)?,
+            ErrorCode::SubstituteValueTooLow
+        );
+
+        let total_debt = vault
+            .borrowed
+            .checked_add(pending_interest(
+                vault, collateral, rate_model, config, now,
+            )?)
+            .ok_or(ErrorCode::MathOverflow)?;
+        let new_amount = (vault.bond_amount - withdraw_amount)
+            .checked_add(deposit_amount)
+            .ok_or(ErrorCode::MathOverflow)?;
+        let (primary, legs) = match &ctx.accounts.source_leg {
+            Some(leg) => (
+                primary_amount(vault),
+                replace_leg_values(
+                    vault,
+                    leg,
+                    leg.bond_amount - withdraw_amount,
+                    &source_risk,
+                    config,
+                )?,
+            ),
+            None => (primary_amount(vault) - withdraw_amount, vault.leg_values),
+        };
+        let target_amount = ctx
+            .accounts
+            .leg
+            .bond_amount
+            .checked_add(deposit_amount)
+            .ok_or(ErrorCode::MathOverflow)?;
+        let target_values = collateral_values(target_amount, &target_risk, config)?;
+        let legs = legs
+            .checked_sub(&ctx.accounts.leg.values)?
+            .checked_add(&target_values)?;
+        let values = projected_values(
+            primary,
+            &legs,
+            &risk_view(collateral, &ctx.accounts.price_twap),
+            config,
+        )?;
+        let recovery = is_recovery_mode(&ctx.accounts.global_state, config)?;
+        require!(
+            total_debt <= borrow_limit(&values, config, recovery)?,
+            ErrorCode::UndercollateralizedVault
+        );
+
+        anchor_spl::token::transfer(
+            CpiContext::new(
+                ctx.accounts.token_program.to_account_info(),
+                Transfer {
+                    from: ctx.accounts.owner_target_account.to_account_info(),
+                    to: ctx.accounts.leg_escrow.to_account_info(),
+                    authority: ctx.accounts.owner.to_account_info(),
+                },
+            ),
+            deposit_amount,
+        )?;
+
+        let owner = vault.owner;
+        let creator = vault.creator;
+        let collateral_mint = vault.collateral_mint;
+        let bump = [vault.bump];
+        let signer_seeds: &[&[&[u8]]] =
+            &[&[b"vault", creator.as_ref(), collateral_mint.as_ref(), &bump]];
+
+        anchor_spl::token::transfer(
+            CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                Transfer {
+                    from: ctx.accounts.source_escrow.to_account_info(),
+                    to: ctx.accounts.owner_source_account.to_account_info(),
+                    authority: ctx.accounts.vault.to_account_info(),
+                },
+                signer_seeds,
+            ),
+            withdraw_amount,
+        )?;
+
+        if deposit_amount > withdraw_amount {
+            anchor_spl::token::mint_to(
+                CpiContext::new_with_signer(
+                    ctx.accounts.token_program.to_account_info(),
+                    MintTo {
+                        mint: ctx.accounts.receipt_mint.to_account_info(),
+                        to: ctx.accounts.owner_receipt_account.to_account_info(),
+                        authority: ctx.accounts.vault.to_account_info(),
+                    },
+                    signer_seeds,
+                ),
+                deposit_amount - withdraw_amount,
+            )?;
+        } else if withdraw_amount > deposit_amount {
+            anchor_spl::token::burn(
+                CpiContext::new(
+                    ctx.accounts.token_program.to_account_info(),
+                    Burn {
+                        mint: ctx.accounts.receipt_mint.to_account_info(),
+                        from: ctx.accounts.owner_receipt_account.to_account_info(),
+                        authority: ctx.accounts.owner.to_account_info(),
+                    },
+                ),
+                withdraw_amount - deposit_amount,
+            )?;
+        }
+
+        if let Some(source_leg) = &mut ctx.accounts.source_leg {
+            source_leg.bond_amount -= withdraw_amount;
+            let rating = source_leg.rating;
+            revalue_leg(
+                &mut ctx.accounts.vault,
+                source_leg,
+                rating,
+                &ctx.accounts.source_collateral_config,
+                &ctx.accounts.config,
+            )?;
+        }
+        let leg = &mut ctx.accounts.leg;
+        leg.vault = ctx.accounts.vault.key();
+        leg.mint = ctx.accounts.target_mint.key();
+        leg.escrow = ctx.accounts.leg_escrow.key();
+        leg.bond_amount = leg
+            .bond_amount
+            .checked_add(deposit_amount)
+            .ok_or(ErrorCode::MathOverflow)?;
+        leg.bump = ctx.bumps.leg;
+        revalue_leg(
+            &mut ctx.accounts.vault,
+            leg,
+            ctx.accounts.target_collateral_config.rating,
+            &ctx.accounts.target_collateral_config,
+            &ctx.accounts.config,
+        )?;
+
+        let leg_withdrawn = if ctx.accounts.source_leg.is_some() {
+            withdraw_amount
+        } else {
+            0
+        };
+        let vault = &mut ctx.accounts.vault;
+        let (collateral_before, debt_before) = (primary_amount(vault), vault.borrowed);
+        vault.bond_amount = new_amount;
+        vault.leg_amount = (vault.leg_amount - leg_withdrawn)
+            .checked_add(deposit_amount)
+            .ok_or(ErrorCode::MathOverflow)?;
+        refresh_unhealthy_flag(vault, collateral, rate_model, config, recovery, now)?;
+        track_vault(
+            &mut ctx.accounts.global_state,
+            &mut ctx.accounts.collateral_config,
+            &ctx.accounts.config,
+            collateral_before,
+            debt_before,
+            vault,
+        )?;
+
+        let vault_key = vault.key();
+        update_sorted_position(
+            &mut ctx.accounts.sorted_vaults,
+            vault_key,
+            vault,
+            hint,
+            ctx.remaining_accounts,
+        )?;
+
+        emit!(CollateralSubstitutedEvent {
+            vault: vault_key,
+            owner,
+            from_mint: source_mint,
+            to_mint: ctx.accounts.target_mint.key(),
+            withdrawn: withdraw_amount,
+            deposited: deposit_amount,
+            total_face_value: vault.collateral_face_value,
+        });
+
+        Ok(())
+    }
+
+    pub fn close_collateral_leg(ctx: Context<CloseCollateralLeg>) -> Result<()> {
+        sync_position_owner(&mut ctx.accounts.vault, ctx.accounts.owner.key());
+        require!(ctx.accounts.leg.bond_amount == 0, ErrorCode::CollateralNotWithdrawn);
+
+        let vault = &ctx.accounts.vault;
+        let creator = vault.creator;
+        let collateral_mint = vault.collateral_mint;
+        let bump = [vault.bump];
+        let signer_seeds: &[&[&[u8]]] =
+            &[&[b"vault", creator.as_ref(), collateral_mint.as_ref(), &bump]];
+
+        anchor_spl::token::close_account(CpiContext::new_with_signer(
+            ctx.accounts.token_program.to_account_info(),
+            CloseAccount {
+                account: ctx.accounts.leg_escrow.to_account_info(),
+                destination: ctx.accounts.owner.to_account_info(),
+                authority: ctx.accounts.vault.to_account_info(),
+            },
+            signer_seeds,
+        ))?;
+
+        Ok(())
+    }
+
+    // Leg values only change on the vault when the leg is re-priced, so anyone can refresh
+    // a leg at its cached price, typically ahead of a liquidation.
+    pub fn revalue_collateral_leg(ctx: Context<RevalueCollateralLeg>) -> Result<()> {
+        load_cached_price(
+            &mut ctx.accounts.leg_collateral_config,
+            &ctx.accounts.price_cache,
+            false,
+        )?;
+        let rating = ctx.accounts.leg.rating;
+        revalue_leg(
+            &mut ctx.accounts.vault,
+            &mut ctx.accounts.leg,
+            rating,
+            &ctx.accounts.leg_collateral_config,
+            &ctx.accounts.config,
+        )?;
+
+        emit!(CollateralLegRevaluedEvent {
+            vault: ctx.accounts.vault.key(),
+            mint: ctx.accounts.leg.mint,
+            market_value: ctx.accounts.leg.values.market_value,
+            vault_leg_value: ctx.accounts.vault.leg_values.market_value,
+        });
+
+        Ok(())
+    }
+
+    pub fn reinsert_vault<'info>(
+        ctx: Context<'_, '_, 'info, 'info, ReinsertVault<'info>>,
+        hint: ListHint,
+    ) -> Result<()> {
+        let vault = &mut ctx.accounts.vault;
+        let vault_key = vault.key();
+        update_sorted_position(
+            &mut ctx.accounts.sorted_vaults,
+            vault_key,
+            vault,
+            hint,
+            ctx.remaining_accounts,
+        )
+    }
+
+    pub fn close_vault<'info>(ctx: Context<'_, '_, 'info, 'info, CloseVault<'info>>) -> Result<()> {
+        sync_position_owner(&mut ctx.accounts.vault, ctx.accounts.owner.key());
+        require!(ctx.accounts.vault.borrowed == 0, ErrorCode::OutstandingDebt);
+        require!(ctx.accounts.vault.bond_amount == 0, ErrorCode::CollateralNotWithdrawn);
+        require!(ctx.accounts.vault.surplus == 0, ErrorCode::UnclaimedSurplus);
+
+        let vault_key = ctx.accounts.vault.key();
+        update_sorted_position(
+            &mut ctx.accounts.sorted_vaults,
+            vault_key,
+            &mut ctx.accounts.vault,
+            ListHint::default(),
+            ctx.remaining_accounts,
+        )?;
+
+        let vault = &ctx.accounts.vault;
+        let owner = vault.owner;
+        let creator = vault.creator;
+        let collateral_mint = vault.collateral_mint;
+        let bump = [vault.bump];
+        let signer_seeds: &[&[&[u8]]] =
+            &[&[b"vault", creator.as_ref(), collateral_mint.as_ref(), &bump]];
+
+        anchor_spl::token::burn(
+            CpiContext::new(
+                ctx.accounts.token_program.to_account_info(),
+                Burn {
+                    mint: ctx.accounts.position_mint.to_account_info(),
+                    from: ctx.accounts.position_account.to_account_info(),
+                    authority: ctx.accounts.owner.to_account_info(),
+                },
+            ),
+            1,
+        )?;
+
+        anchor_spl::token::close_account(CpiContext::new_with_signer(
+            ctx.accounts.token_program.to_account_info(),
+            CloseAccount {
+                account: ctx.accounts.escrow.to_account_info(),
+                destination: ctx.accounts.owner.to_account_info(),
+                authority: ctx.accounts.vault.to_account_info(),
+            },
+            signer_seeds,
+        ))?;
+
+        emit!(CloseVaultEvent {
+            vault: ctx.accounts.vault.key(),
+            owner,
+        });
+
+        Ok(())
+    }
+
+    // The position NFT is escrowed under the vault until the recipient claims it, so neither
+    // side can operate the vault while a handover is pending.
+    pub fn propose_vault_owner(ctx: Context<ProposeVaultOwner>, new_owner: Pubkey) -> Result<()> {
+        sync_position_owner(&mut ctx.accounts.vault, ctx.accounts.owner.key());
+        let vault = &ctx.accounts.vault;
+        require!(
+            new_owner != Pubkey::default() && new_owner != vault.owner,
+            ErrorCode::InvalidParameter
+        );
+
+        anchor_spl::token::transfer(
+            CpiContext::new(
+                ctx.accounts.token_program.to_account_info(),
+                Transfer {
+                    from: ctx.accounts.position_account.to_account_info(),
+                    to: ctx.accounts.position_escrow.to_account_info(),
+                    authority: ctx.accounts.owner.to_account_info(),
+                },
+            ),
+            1,
+        )?;
+
+        let vault = &mut ctx.accounts.vault;
+        vault.pending_owner = new_owner;
+
+        emit!(VaultOwnerProposedEvent {
+            vault: vault.key(),
+            owner: vault.owner,
+            pending_owner: new_owner,
+        });
+
+        Ok(())
+    }
+
+    pub fn accept_vault_owner(ctx: Context<AcceptVaultOwner>) -> Result<()> {
+        release_position(
+            &ctx.accounts.vault,
+            &ctx.accounts.position_escrow,
+            &ctx.accounts.new_owner_position_account,
+            &ctx.accounts.token_program,
+        )?;
+
+        let vault = &mut ctx.accounts.vault;
+        let previous_owner = vault.owner;
+        sync_position_owner(vault, ctx.accounts.new_owner.key());
+        vault.pending_owner = Pubkey::default();
+
+        emit!(VaultOwnerTransferredEvent {
+            vault: vault.key(),
+            previous_owner,
+            new_owner: vault.owner,
+        });
+
+        Ok(())
+    }
+
+    pub fn cancel_vault_owner_transfer(ctx: Context<CancelVaultOwnerTransfer>) -> Result<()> {
+        release_position(
+            &ctx.accounts.vault,
+            &ctx.accounts.position_escrow,
+            &ctx.accounts.position_account,
+            &ctx.accounts.token_program,
+        )?;
+
+        let vault = &mut ctx.accounts.vault;
+        let cancelled = vault.pending_owner;
+        vault.pending_owner = Pubkey::default();
+
+        emit!(VaultOwnerTransferCancelledEvent {
+            vault: vault.key(),
+            owner: vault.owner,
+            cancelled_owner: cancelled,
+        });
+
+        Ok(())
+    }
+
+    pub fn start_fixed_term(ctx: Context<StartFixedTerm>, term_secs: i64) -> Result<()> {
+        sync_position_owner(&mut ctx.accounts.vault, ctx.accounts.owner.key());
+        require!(term_secs > 0, ErrorCode::InvalidParameter);
+        let rate_bps = variable_rate_bps(
+            &ctx.accounts.vault,
+            &ctx.accounts.collateral_config,
+            &ctx.accounts.rate_model,
+        )?;
+        let vault = &mut ctx.accounts.vault;
+        require!(vault.borrowed == 0, ErrorCode::OutstandingDebt);
+        require!(vault.term_maturity == 0, ErrorCode::TermActive);
+        let maturity = Clock::get()?
+            .unix_timestamp
+            .checked_add(term_secs)
+            .ok_or(ErrorCode::MathOverflow)?;
+        vault.term_maturity = maturity;
+        vault.term_rate_bps = rate_bps;
+
+        emit!(FixedTermStartedEvent {
+            vault: vault.key(),
+            owner: vault.owner,
+            maturity,
+            rate_bps,
+        });
+
+        Ok(())
+    }
+
+    pub fn set_rate_mode(ctx: Context<SetRateMode>, mode: RateMode) -> Result<()> {
+        sync_position_owner(&mut ctx.accounts.vault, ctx.accounts.owner.key());
+        let vault = &mut ctx.accounts.vault;
+        require!(vault.borrowed == 0, ErrorCode::OutstandingDebt);
+        vault.rate_mode = mode;
+        vault.fixed_rate_bps = 0;
+
+        emit!(RateModeSetEvent {
+            vault: vault.key(),
+            owner: vault.owner,
+            mode,
+        });
+
+        Ok(())
+    }
+
+    pub fn request_withdrawal(ctx: Context<ManageWithdrawal>, bond_amount: u64) -> Result<()> {
+        sync_position_owner(&mut ctx.accounts.vault, ctx.accounts.owner.key());
+        require!(bond_amount > 0, ErrorCode::InvalidAmount);
+        let vault = &mut ctx.accounts.vault;
+        require!(
+            primary_amount(vault) >= bond_amount,
+            ErrorCode::NotEnoughCollateral
+        );
+        let ready_at = Clock::get()?.unix_timestamp + ctx.accounts.config.withdraw_delay_secs;
+        vault.pending_withdrawal = bond_amount;
+        vault.withdrawal_ready_at = ready_at;
+
+        emit!(WithdrawalRequestedEvent {
+            vault: vault.key(),
+            owner: vault.owner,
+            amount: bond_amount,
+            ready_at,
+        });
+
+        Ok(())
+    }
+
+    pub fn cancel_withdrawal(ctx: Context<ManageWithdrawal>) -> Result<()> {
+        sync_position_owner(&mut ctx.accounts.vault, ctx.accounts.owner.key());
+        let vault = &mut ctx.accounts.vault;
+        let amount = vault.pending_withdrawal;
+        require!(amount > 0, ErrorCode::NoPendingWithdrawal);
+        vault.pending_withdrawal = 0;
+        vault.withdrawal_ready_at = 0;
+
+        emit!(WithdrawalCancelledEvent {
+            vault: vault.key(),
+            owner: vault.owner,
+            amount,
+        });
+
+        Ok(())
+    }
+
+    pub fn set_vault_delegate(ctx: Context<SetVaultDelegate>, delegate: Pubkey) -> Result<()> {
+        sync_position_owner(&mut ctx.accounts.vault, ctx.accounts.owner.key());
+        let vault = &mut ctx.accounts.vault;
+        vault.delegate = delegate;
+
+        emit!(VaultDelegateSetEvent {
+            vault: vault.key(),
+            owner: vault.owner,
+            delegate,
+        });
+
+        Ok(())
+    }
+
+    pub fn flag_unhealthy(ctx: Context<FlagUnhealthy>) -> Result<()> {
+        load_cached_price(
+            &mut ctx.accounts.collateral_config,
+            &ctx.accounts.price_cache,
+            true,
+        )?;
+        let config = &ctx.accounts.config;
+        let rate_model = &ctx.accounts.rate_model;
+        let collateral = &ctx.accounts.collateral_config;
+        let recovery = is_recovery_mode(&ctx.accounts.global_state, config)?;
+        let vault = &mut ctx.accounts.vault;
+
+        let now = Clock::get()?.unix_timestamp;
+        let total_debt = vault
+            .borrowed
+            .checked_add(pending_interest(
+                vault, collateral, rate_model, config, now,
+            )?)
+            .ok_or(ErrorCode::MathOverflow)?;
+        require!(
+            total_debt > 0
+                && (is_past_due(vault, now)
+                    || is_liquidatable(
+                        &vault_values(vault, collateral, config)?,
+                        total_debt,
+                        config,
+                        recovery,
+                    )?),
+            ErrorCode::VaultHealthy
+        );
+        require!(vault.unhealthy_since == 0, ErrorCode::AlreadyFlagged);
+        vault.unhealthy_since = now;
+
+        emit!(FlagUnhealthyEvent {
+            vault: vault.key(),
+            owner: vault.owner,
+            flagged_at: now,
+        });
+
+        Ok(())
+    }
+
+    pub fn flag_for_settlement(ctx: Context<FlagForSettlement>) -> Result<()> {
+        let bond = &ctx.accounts.bond_metadata;
+        let now = Clock::get()?.unix_timestamp;
+        require!(!is_eligible_bond(bond, now), ErrorCode::BondEligible);
+
+        let vault = &mut ctx.accounts.vault;
+        match &ctx.accounts.leg {
+            Some(leg) => require!(
+                leg.vault == vault.key() && leg.mint == bond.mint && leg.bond_amount > 0,
+                ErrorCode::InvalidParameter
+            ),
+            None => require!(bond.mint == vault.collateral_mint, ErrorCode::InvalidParameter),
+        }
+        require!(vault.bond_amount > 0, ErrorCode::NotEnoughCollateral);
+        require!(!vault.settlement_due, ErrorCode::AlreadyFlagged);
+        vault.settlement_due = true;
+        if vault.unhealthy_since == 0 {
+            vault.unhealthy_since = now;
+        }
+
+        emit!(SettlementFlaggedEvent {
+            vault: vault.key(),
+            owner: vault.owner,
+            bond_mint: bond.mint,
+            flagged_at: now,
+        });
+
+        Ok(())
+    }
+
+    pub fn settle_matured<'info>(
+        ctx: Context<'_, '_, 'info, 'info, SettleMatured<'info>>,
+        hint: ListHint,
+    ) -> Result<()> {
+        require_not_paused(&ctx.accounts.config, PAUSE_REPAY)?;
+        let bond = &ctx.accounts.bond_metadata;
+        let now = Clock::get()?.unix_timestamp;
+        require!(now >= bond.maturity, ErrorCode::BondNotMatured);
+
+        let vault = &ctx.accounts.vault;
+        accrue_borrow_index(
+            &mut ctx.accounts.collateral_config,
+            &ctx.accounts.rate_model,
+            Clock::get()?.unix_timestamp,
+        )?;
+        let config = &ctx.accounts.config;
+        let rate_model = &ctx.accounts.rate_model;
+        let collateral = &ctx.accounts.collateral_config;
+        let bond_amount = primary_amount(vault);
+        require!(bond_amount > 0, ErrorCode::NotEnoughCollateral);
+
+        let interest = pending_interest(vault, collateral, rate_model, config, now)?;
+        let total_debt = vault
+            .borrowed
+            .checked_add(interest)
+            .ok_or(ErrorCode::MathOverflow)?;
+
+        let owner = vault.owner;
+        let creator = vault.creator;
+        let collateral_mint = vault.collateral_mint;
+        let bump = [vault.bump];
+        let signer_seeds: &[&[&[u8]]] =
+            &[&[b"vault", creator.as_ref(), collateral_mint.as_ref(), &bump]];
+
+        let balance_before = ctx.accounts.psm_reserve.amount;
+        bond_tokenization::cpi::redeem_matured(
+            CpiContext::new_with_signer(
+                ctx.accounts.bond_program.to_account_info(),
+                bond_tokenization::cpi::accounts::RedeemMatured {
+                    holder: ctx.accounts.vault.to_account_info(),
+                    bond_metadata: ctx.accounts.bond_metadata.to_account_info(),
+                    mint: ctx.accounts.collateral_mint.to_account_info(),
+                    holder_bond_account: ctx.accounts.escrow.to_account_info(),
+                    redemption_vault: ctx.accounts.redemption_vault.to_account_info(),
+                    holder_payment_account: ctx.accounts.psm_reserve.to_account_info(),
+                    token_program: ctx.accounts.token_program.to_account_info(),
+                },
+                signer_seeds,
+            ),
+            bond_amount,
+        )?;
+        ctx.accounts.psm_reserve.reload()?;
+        let proceeds = ctx
+            .accounts
+            .psm_reserve
+            .amount
+            .saturating_sub(balance_before);
+
+        let value = scale_decimals(
+            proceeds,
+            ctx.accounts.payment_mint.decimals,
+            ctx.accounts.stablecoin_mint.decimals,
+        )?;
+        let repay_amount = value.min(total_debt);
+        let remainder = value - repay_amount;
+
+        // Proceeds are paid in the bond's payment mint into the PSM reserve. The repaid debt is
+        // burned out of the CDP buckets and its supply, which stays in circulation, is re-sourced
+        // to the PSM; the remainder is minted to the surplus vault for the owner. The PSM is only
+        // credited with the stablecoin the reserve now backs, not any rounding dust.
+        record_mint(
+            &mut ctx.accounts.supply_ledger,
+            SupplySource::Psm,
+            repay_amount,
+        )?;
+        if remainder > 0 {
+            mint_stablecoin(
+                ctx.accounts.stablecoin_token_program.to_account_info(),
+                token_interface::MintTo {
+                    mint: ctx.accounts.stablecoin_mint.to_account_info(),
+                    to: ctx.accounts.surplus_vault.to_account_info(),
+                    authority: ctx.accounts.mint_authority.to_account_info(),
+                },
+                ctx.accounts.config.mint_authority_bump,
+                remainder,
+            )?;
+            record_mint(
+                &mut ctx.accounts.supply_ledger,
+                SupplySource::Psm,
+                remainder,
+            )?;
+        }
+        let psm = &mut ctx.accounts.psm_state;
+        psm.psm_supply = psm
+            .psm_supply
+            .checked_add(value)
+            .ok_or(ErrorCode::MathOverflow)?;
+
+        let vault = &mut ctx.accounts.vault;
+        let (collateral_before, debt_before) = (primary_amount(vault), vault.borrowed);
+        vault.bond_amount -= bond_amount;
+        vault.borrowed = total_debt - repay_amount;
+        vault.surplus = vault
+            .surplus
+            .checked_add(remainder)
+            .ok_or(ErrorCode::MathOverflow)?;
+        if vault.borrowed == 0 && vault.leg_amount == 0 {
+            vault.settlement_due = false;
+        }
+        settle_repayment(
+            vault,
+            RepaymentAccounts {
+                global_state: &mut ctx.accounts.global_state,
+                collateral_config: &mut ctx.accounts.collateral_config,
+                issuer_exposure: &mut ctx.accounts.issuer_exposure,
+                sorted_vaults: &mut ctx.accounts.sorted_vaults,
+                config: &ctx.accounts.config,
+                rate_model: &ctx.accounts.rate_model,
+            },
+            Some(InterestMint {
+                ledger: &mut ctx.accounts.supply_ledger,
+                token_program: &ctx.accounts.stablecoin_token_program,
+                stablecoin_mint: &ctx.accounts.stablecoin_mint,
+                mint_authority: &ctx.accounts.mint_authority,
+                recipients: [
+                    &ctx.accounts.staking_reward_vault,
+                    &ctx.accounts.treasury,
+                    &ctx.accounts.reserve,
+                    &ctx.accounts.insurance_fund,
+                ],
+            }),
+            Repayment {
+                collateral_before,
+                debt_before,
+                interest,
+                repaid: repay_amount,
+                now,
+            },
+            hint,
+            ctx.remaining_accounts,
+        )?;
+
+        emit!(SettleMaturedEvent {
+            vault: vault.key(),
+            owner,
+            bond_mint: collateral_mint,
+            collateral_redeemed: bond_amount,
+            proceeds,
+            debt_repaid: repay_amount,
+            surplus: remainder,
+        });
+
+        Ok(())
+    }
+
+    pub fn liquidate<'info>(
+        ctx: Context<'_, '_, 'info, 'info, Liquidate<'info>>,
+        repay_amount: u64,
+        hint: ListHint,
+        hook_accounts: u8,
+    ) -> Result<()> {
+        let (remaining, hook_accounts) =
+            split_hook_accounts(ctx.remaining_accounts, hook_accounts)?;
+        require_not_paused(&ctx.accounts.config, PAUSE_LIQUIDATION)?;
+        require!(
+            ctx.accounts.deny_entry.data_is_empty(),
+            ErrorCode::AccountDenied
+        );
+        load_cached_price(
+            &mut ctx.accounts.collateral_config,
+            &ctx.accounts.price_cache,
+            true,
+        )?;
+        let vault = &ctx.accounts.vault;
+        accrue_borrow_index(
+            &mut ctx.accounts.collateral_config,
+            &ctx.accounts.rate_model,
+            Clock::get()?.unix_timestamp,
+        )?;
+        let config = &ctx.accounts.config;
+        let rate_model = &ctx.accounts.rate_model;
+        let collateral = &ctx.accounts.collateral_config;
+        let recovery = is_recovery_mode(&ctx.accounts.global_state, config)?;
+
+        let now = Clock::get()?.unix_timestamp;
+        let LiquidationPlan {
+            interest,
+            total_debt,
+            repay_amount,
+            seize_amount,
+            surplus,
+        } = plan_liquidation(
+            vault,
+            collateral,
+            collateral,
+            rate_model,
+            config,
+            recovery,
+            now,
+            repay_amount,
+            primary_amount(vault),
+        )?;
+        require!(
+            ctx.accounts.liquidator_stablecoin_account.amount >= repay_amount + surplus,
+            ErrorCode::InsufficientStablecoin
+        );
+
+        token_interface::burn(
+            CpiContext::new(
+                ctx.accounts.stablecoin_token_program.to_account_info(),
+                token_interface::Burn {
+                    mint: ctx.accounts.stablecoin_mint.to_account_info(),
+                    from: ctx.accounts.liquidator_stablecoin_account.to_account_info(),
+                    authority: ctx.accounts.liquidator.to_account_info(),
+                },
+            ),
+            repay_amount,
+        )?;
+
+        if surplus > 0 {
+            transfer_stablecoin(
+                ctx.accounts.stablecoin_token_program.to_account_info(),
+                TransferChecked {
+                    from: ctx.accounts.liquidator_stablecoin_account.to_account_info(),
+                    mint: ctx.accounts.stablecoin_mint.to_account_info(),
+                    to: ctx.accounts.surplus_vault.to_account_info(),
+                    authority: ctx.accounts.liquidator.to_account_info(),
+                },
+                hook_accounts,
+                surplus,
+                ctx.accounts.stablecoin_mint.decimals,
+                &[],
+            )?;
+        }
+
+        let owner = vault.owner;
+        let creator = vault.creator;
+        let collateral_mint = vault.collateral_mint;
+        let bump = [vault.bump];
+        let signer_seeds: &[&[&[u8]]] =
+            &[&[b"vault", creator.as_ref(), collateral_mint.as_ref(), &bump]];
+
+        anchor_spl::token::transfer(
+            CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                Transfer {
+                    from: ctx.accounts.escrow.to_account_info(),
+                    to: ctx.accounts.liquidator_bond_account.to_account_info(),
+                    authority: ctx.accounts.vault.to_account_info(),
+                },
+                signer_seeds,
+            ),
+            seize_amount,
+        )?;
+
+        let vault = &mut ctx.accounts.vault;
+        let (collateral_before, debt_before) = (primary_amount(vault), vault.borrowed);
+        vault.bond_amount -= seize_amount;
+        vault.borrowed = total_debt - repay_amount;
+        vault.surplus = vault
+            .surplus
+            .checked_add(surplus)
+            .ok_or(ErrorCode::MathOverflow)?;
+        settle_repayment(
+            vault,
+            RepaymentAccounts {
+                global_state: &mut ctx.accounts.global_state,
+                collateral_config: &mut ctx.accounts.collateral_config,
+                issuer_exposure: &mut ctx.accounts.issuer_exposure,
+                sorted_vaults: &mut ctx.accounts.sorted_vaults,
+                config: &ctx.accounts.config,
+                rate_model: &ctx.accounts.rate_model,
+            },
+            Some(InterestMint {
+                ledger: &mut ctx.accounts.supply_ledger,
+                token_program: &ctx.accounts.stablecoin_token_program,
+                stablecoin_mint: &ctx.accounts.stablecoin_mint,
+                mint_authority: &ctx.accounts.mint_authority,
+                recipients: [
+                    &ctx.accounts.staking_reward_vault,
+                    &ctx.accounts.treasury,
+                    &ctx.accounts.reserve,
+                    &ctx.accounts.insurance_fund,
+                ],
+            }),
+            Repayment {
+                collateral_before,
+                debt_before,
+                interest,
+                repaid: repay_amount,
+                now,
+            },
+            hint,
+            remaining,
+        )?;
+
+        emit!(LiquidateEvent {
+            vault: vault.key(),
+            owner,
+            liquidator: ctx.accounts.liquidator.key(),
+            debt_repaid: repay_amount,
+            collateral_seized: seize_amount,
+            remaining_debt: total_debt - repay_amount,
+            surplus,
+        });
+
+        Ok(())
+    }
+
+    pub fn liquidate_leg<'info>(
+        ctx: Context<'_, '_, 'info, 'info, LiquidateLeg<'info>>,
+        repay_amount: u64,
+        hint: ListHint,
+        hook_accounts: u8,
+    ) -> Result<()> {
+        let (remaining, hook_accounts) =
+            split_hook_accounts(ctx.remaining_accounts, hook_accounts)?;
+        require_not_paused(&ctx.accounts.config, PAUSE_LIQUIDATION)?;
+        require!(
+            ctx.accounts.deny_entry.data_is_empty(),
+            ErrorCode::AccountDenied
+        );
+        load_cached_price(
+            &mut ctx.accounts.collateral_config,
+            &ctx.accounts.price_cache,
+            true,
+        )?;
+        load_cached_price(
+            &mut ctx.accounts.leg_collateral_config,
+            &ctx.accounts.leg_price_cache,
+            true,
+        )?;
+        // The leg is re-priced before the plan so the health check sees its current value.
+        let leg_rating = ctx.accounts.leg.rating;
+        revalue_leg(
+            &mut ctx.accounts.vault,
+            &mut ctx.accounts.leg,
+            leg_rating,
+            &ctx.accounts.leg_collateral_config,
+            &ctx.accounts.config,
+        )?;
+        let vault = &ctx.accounts.vault;
+        accrue_borrow_index(
+            &mut ctx.accounts.collateral_config,
+            &ctx.accounts.rate_model,
+            Clock::get()?.unix_timestamp,
+        )?;
+        let config = &ctx.accounts.config;
+        let rate_model = &ctx.accounts.rate_model;
+        let collateral = &ctx.accounts.collateral_config;
+        let recovery = is_recovery_mode(&ctx.accounts.global_state, config)?;
+
+        let now = Clock::get()?.unix_timestamp;
+        let LiquidationPlan {
+            interest,
+            total_debt,
+            repay_amount,
+            seize_amount,
+            surplus,
+        } = plan_liquidation(
+            vault,
+            collateral,
+            &ctx.accounts.leg_collateral_config,
+            rate_model,
+            config,
+            recovery,
+            now,
+            repay_amount,
+            ctx.accounts.leg.bond_amount,
+        )?;
+        require!(
+            ctx.accounts.liquidator_stablecoin_account.amount >= repay_amount + surplus,
+            ErrorCode::InsufficientStablecoin
+        );
+
+        token_interface::burn(
+            CpiContext::new(
+                ctx.accounts.stablecoin_token_program.to_account_info(),
+                token_interface::Burn {
+                    mint: ctx.accounts.stablecoin_mint.to_account_info(),
+                    from: ctx.accounts.liquidator_stablecoin_account.to_account_info(),
+                    authority: ctx.accounts.liquidator.to_account_info(),
+                },
+            ),
+            repay_amount,
+        )?;
+
+        if surplus > 0 {
+            transfer_stablecoin(
+                ctx.accounts.stablecoin_token_program.to_account_info(),
+                TransferChecked {
+                    from: ctx.accounts.liquidator_stablecoin_account.to_account_info(),
+                    mint: ctx.accounts.stablecoin_mint.to_account_info(),
+                    to: ctx.accounts.surplus_vault.to_account_info(),
+                    authority: ctx.accounts.liquidator.to_account_info(),
+                },
+                hook_accounts,
+                surplus,
+                ctx.accounts.stablecoin_mint.decimals,
+                &[],
+            )?;
+        }
+
+        let owner = vault.owner;
+        let creator = vault.creator;
+        let collateral_mint = vault.collateral_mint;
+        let bump = [vault.bump];
+        let signer_seeds: &[&[&[u8]]] =
+            &[&[b"vault", creator.as_ref(), collateral_mint.as_ref(), &bump]];
+
+        anchor_spl::token::transfer(
+            CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                Transfer {
+                    from: ctx.accounts.leg_escrow.to_account_info(),
+                    to: ctx.accounts.liquidator_bond_account.to_account_info(),
+                    authority: ctx.accounts.vault.to_account_info(),
+                },
+                signer_seeds,
+            ),
+            seize_amount,
+        )?;
+
+        ctx.accounts.leg.bond_amount -= seize_amount;
+
+        let vault = &mut ctx.accounts.vault;
+        let leg = &mut ctx.accounts.leg;
+        let leg_rating = leg.rating;
+        revalue_leg(
+            vault,
+            leg,
+            leg_rating,
+            &ctx.accounts.leg_collateral_config,
+            &ctx.accounts.config,
+        )?;
+        let (collateral_before, debt_before) = (primary_amount(vault), vault.borrowed);
+        vault.bond_amount -= seize_amount;
+        vault.leg_amount -= seize_amount;
+        vault.borrowed = total_debt - repay_amount;
+        vault.surplus = vault
+            .surplus
+            .checked_add(surplus)
+            .ok_or(ErrorCode::MathOverflow)?;
+        settle_repayment(
+            vault,
+            RepaymentAccounts {
+                global_state: &mut ctx.accounts.global_state,
+                collateral_config: &mut ctx.accounts.collateral_config,
+                issuer_exposure: &mut ctx.accounts.issuer_exposure,
+                sorted_vaults: &mut ctx.accounts.sorted_vaults,
+                config: &ctx.accounts.config,
+                rate_model: &ctx.accounts.rate_model,
+            },
+            Some(InterestMint {
+                ledger: &mut ctx.accounts.supply_ledger,
+                token_program: &ctx.accounts.stablecoin_token_program,
+                stablecoin_mint: &ctx.accounts.stablecoin_mint,
+                mint_authority: &ctx.accounts.mint_authority,
+                recipients: [
+                    &ctx.accounts.staking_reward_vault,
+                    &ctx.accounts.treasury,
+                    &ctx.accounts.reserve,
+                    &ctx.accounts.insurance_fund,
+                ],
+            }),
+            Repayment {
+                collateral_before,
+                debt_before,
+                interest,
+                repaid: repay_amount,
+                now,
+            },
+            hint,
+            remaining,
+        )?;
+
+        emit!(LiquidateEvent {
+            vault: vault.key(),
+            owner,
+            liquidator: ctx.accounts.liquidator.key(),
+            debt_repaid: repay_amount,
+            collateral_seized: seize_amount,
+            remaining_debt: total_debt - repay_amount,
+            surplus,
+        });
+
+        Ok(())
+    }
+
+    pub fn flash_liquidate<'info>(
+        ctx: Context<'_, '_, 'info, 'info, FlashLiquidate<'info>>,
+        repay_amount: u64,
+        swap_data: Vec<u8>,
+        hint: ListHint,
+        list_nodes: u8,
+        hook_accounts: u8,
+    ) -> Result<()> {
+        let (remaining, hook_accounts) =
+            split_hook_accounts(ctx.remaining_accounts, hook_accounts)?;
+        require_not_paused(&ctx.accounts.config, PAUSE_LIQUIDATION)?;
+        require!(
+            ctx.accounts.deny_entry.data_is_empty(),
+            ErrorCode::AccountDenied
+        );
+        require!(
+            ctx.accounts.swap_program.key() != crate::ID,
+            ErrorCode::InvalidSwapProgram
+        );
+        require!(
+            (list_nodes as usize) <= remaining.len(),
+            ErrorCode::InvalidRemainingAccounts
+        );
+        let (node_accounts, swap_accounts) = remaining.split_at(list_nodes as usize);
+        load_cached_price(
+            &mut ctx.accounts.collateral_config,
+            &ctx.accounts.price_cache,
+            true,
+        )?;
+
+        let vault = &ctx.accounts.vault;
+        accrue_borrow_index(
+            &mut ctx.accounts.collateral_config,
+            &ctx.accounts.rate_model,
+            Clock::get()?.unix_timestamp,
+        )?;
+        let config = &ctx.accounts.config;
+        let rate_model = &ctx.accounts.rate_model;
+        let collateral = &ctx.accounts.collateral_config;
+        let recovery = is_recovery_mode(&ctx.accounts.global_state, config)?;
+
+        let now = Clock::get()?.unix_timestamp;
+        let LiquidationPlan {
+            interest,
+            total_debt,
+            repay_amount,
+            seize_amount,
+            surplus,
+        } = plan_liquidation(
+            vault,
+            collateral,
+            collateral,
+            rate_model,
+            config,
+            recovery,
+            now,
+            repay_amount,
+            primary_amount(vault),
+        )?;
+
+        let owner = vault.owner;
+        let creator = vault.creator;
+        let collateral_mint = vault.collateral_mint;
+        let bump = [vault.bump];
+        let signer_seeds: &[&[&[u8]]] =
+            &[&[b"vault", creator.as_ref(), collateral_mint.as_ref(), &bump]];
+
+        anchor_spl::token::transfer(
+            CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                Transfer {
+                    from: ctx.accounts.escrow.to_account_info(),
+                    to: ctx.accounts.liquidator_bond_account.to_account_info(),
+                    authority: ctx.accounts.vault.to_account_info(),
+                },
+                signer_seeds,
+            ),
+            seize_amount,
+        )?;
+
+        // The debt is repaid with stablecoin the liquidator only obtains by selling the
+        // seized collateral through the provided route; the burn fails if it falls short.
+        invoke_swap(&ctx.accounts.swap_program, swap_accounts, swap_data)?;
+
+        ctx.accounts.liquidator_stablecoin_account.reload()?;
+        require!(
+            ctx.accounts.liquidator_stablecoin_account.amount >= repay_amount + surplus,
+            ErrorCode::FlashRepayShortfall
+        );
+
+        token_interface::burn(
+            CpiContext::new(
+                ctx.accounts.stablecoin_token_program.to_account_info(),
+                token_interface::Burn {
+                    mint: ctx.accounts.stablecoin_mint.to_account_info(),
+                    from: ctx.accounts.liquidator_stablecoin_account.to_account_info(),
+                    authority: ctx.accounts.liquidator.to_account_info(),
+                },
+            ),
+            repay_amount,
+        )?;
+
+        if surplus > 0 {
+            transfer_stablecoin(
+                ctx.accounts.stablecoin_token_program.to_account_info(),
+                TransferChecked {
+                    from: ctx.accounts.liquidator_stablecoin_account.to_account_info(),
+                    mint: ctx.accounts.stablecoin_mint.to_account_info(),
+                    to: ctx.accounts.surplus_vault.to_account_info(),
+                    authority: ctx.accounts.liquidator.to_account_info(),
+                },
+                hook_accounts,
+                surplus,
+                ctx.accounts.stablecoin_mint.decimals,
+                &[],
+            )?;
+        }
+
+        let vault = &mut ctx.accounts.vault;
+        let (collateral_before, debt_before) = (primary_amount(vault), vault.borrowed);
+        vault.bond_amount -= seize_amount;
+        vault.borrowed = total_debt - repay_amount;
+        vault.surplus = vault
+            .surplus
+            .checked_add(surplus)
+            .ok_or(ErrorCode::MathOverflow)?;
+        settle_repayment(
+            vault,
+            RepaymentAccounts {
+                global_state: &mut ctx.accounts.global_state,
+                collateral_config: &mut ctx.accounts.collateral_config,
+                issuer_exposure: &mut ctx.accounts.issuer_exposure,
+                sorted_vaults: &mut ctx.accounts.sorted_vaults,
+                config: &ctx.accounts.config,
+                rate_model: &ctx.accounts.rate_model,
+            },
+            Some(InterestMint {
+                ledger: &mut ctx.accounts.supply_ledger,
+                token_program: &ctx.accounts.stablecoin_token_program,
+                stablecoin_mint: &ctx.accounts.stablecoin_mint,
+                mint_authority: &ctx.accounts.mint_authority,
+                recipients: [
+                    &ctx.accounts.staking_reward_vault,
+                    &ctx.accounts.treasury,
+                    &ctx.accounts.reserve,
+                    &ctx.accounts.insurance_fund,
+                ],
+            }),
+            Repayment {
+                collateral_before,
+                debt_before,
+                interest,
+                repaid: repay_amount,
+                now,
+            },
+            hint,
+            node_accounts,
+        )?;
+
+        emit!(LiquidateEvent {
+            vault: vault.key(),
+            owner,
+            liquidator: ctx.accounts.liquidator.key(),
+            debt_repaid: repay_amount,
+            collateral_seized: seize_amount,
+            remaining_debt: total_debt - repay_amount,
+            surplus,
+        });
+
+        Ok(())
+    }
+
+    pub fn deleverage<'info>(
+        ctx: Context<'_, '_, 'info, 'info, Deleverage<'info>>,
+        bond_amount: u64,
+        min_repay: u64,
+        swap_data: Vec<u8>,
+        hint: ListHint,
+        list_nodes: u8,
+    ) -> Result<()> {
+        require_not_paused(&ctx.accounts.config, PAUSE_WITHDRAW | PAUSE_DELEVERAGE)?;
+        require!(
+            ctx.accounts.deny_entry.data_is_empty(),
+            ErrorCode::AccountDenied
+        );
+        sync_position_owner(&mut ctx.accounts.vault, ctx.accounts.owner.key());
+        require!(
+            ctx.accounts.swap_program.key() != crate::ID,
+            ErrorCode::InvalidSwapProgram
+        );
+        require!(
+            (list_nodes as usize) <= ctx.remaining_accounts.len(),
+            ErrorCode::InvalidRemainingAccounts
+        );
+        let (node_accounts, swap_accounts) = ctx.remaining_accounts.split_at(list_nodes as usize);
+
+        load_cached_price(
+            &mut ctx.accounts.collateral_config,
+            &ctx.accounts.price_cache,
+            false,
+        )?;
+        consume_pending_withdrawal(
+            &mut ctx.accounts.vault,
+            &ctx.accounts.config,
+            bond_amount,
+            Clock::get()?.unix_timestamp,
+        )?;
+        let vault = &ctx.accounts.vault;
+        accrue_borrow_index(
+            &mut ctx.accounts.collateral_config,
+            &ctx.accounts.rate_model,
+            Clock::get()?.unix_timestamp,
+        )?;
+        let config = &ctx.accounts.config;
+        let rate_model = &ctx.accounts.rate_model;
+        let collateral = &ctx.accounts.collateral_config;
+        let risk_collateral = risk_view(collateral, &ctx.accounts.price_twap);
+        let recovery = is_recovery_mode(&ctx.accounts.global_state, config)?;
+
+        require!(bond_amount > 0, ErrorCode::InvalidAmount);
+        require!(primary_amount(vault) >= bond_amount, ErrorCode::NotEnoughCollateral);
+
+        let now = Clock::get()?.unix_timestamp;
+        let interest = pending_interest(vault, collateral, rate_model, config, now)?;
+        let total_debt = vault
+            .borrowed
+            .checked_add(interest)
+            .ok_or(ErrorCode::MathOverflow)?;
+        require!(total_debt > 0, ErrorCode::NothingToRepay);
+        let values_before = vault_values(vault, &risk_collateral, config)?;
+        let ratio_before = collateral_ratio_bps(values_before.market_value, 1, total_debt)?;
+
+        anchor_spl::token::burn(
+            CpiContext::new(
+                ctx.accounts.token_program.to_account_info(),
+                Burn {
+                    mint: ctx.accounts.receipt_mint.to_account_info(),
+                    from: ctx.accounts.owner_receipt_account.to_account_info(),
+                    authority: ctx.accounts.owner.to_account_info(),
+                },
+            ),
+            bond_amount,
+        )?;
+
+        let owner = vault.owner;
+        let creator = vault.creator;
+        let collateral_mint = vault.collateral_mint;
+        let bump = [vault.bump];
+        let signer_seeds: &[&[&[u8]]] =
+            &[&[b"vault", creator.as_ref(), collateral_mint.as_ref(), &bump]];
+
+        anchor_spl::token::transfer(
+            CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                Transfer {
+                    from: ctx.accounts.escrow.to_account_info(),
+                    to: ctx.accounts.owner_bond_account.to_account_info(),
+                    authority: ctx.accounts.vault.to_account_info(),
+                },
+                signer_seeds,
+            ),
+            bond_amount,
+        )?;
+
+        let balance_before = ctx.accounts.owner_stablecoin_account.amount;
+        invoke_swap(&ctx.accounts.swap_program, swap_accounts, swap_data)?;
+        ctx.accounts.owner_stablecoin_account.reload()?;
+        let proceeds = ctx
+            .accounts
+            .owner_stablecoin_account
+            .amount
+            .saturating_sub(balance_before);
+
+        let repay_amount = proceeds.min(total_debt);
+        require!(repay_amount > 0, ErrorCode::InvalidAmount);
+        require!(repay_amount >= min_repay, ErrorCode::SlippageExceeded);
+        let remaining_debt = total_debt - repay_amount;
+        require!(
+            remaining_debt == 0 || remaining_debt >= config.min_debt,
+            ErrorCode::DebtBelowMinimum
+        );
+
+        token_interface::burn(
+            CpiContext::new(
+                ctx.accounts.stablecoin_token_program.to_account_info(),
+                token_interface::Burn {
+                    mint: ctx.accounts.stablecoin_mint.to_account_info(),
+                    from: ctx.accounts.owner_stablecoin_account.to_account_info(),
+                    authority: ctx.accounts.owner.to_account_info(),
+                },
+            ),
+            repay_amount,
+        )?;
+
+        // A vault already over its limit may still deleverage as long as the sale improves
+        // its ratio.
+        let vault = &ctx.accounts.vault;
+        let remaining_bonds = vault.bond_amount - bond_amount;
+        let values = projected_values(
+            primary_amount(vault) - bond_amount,
+            &vault.leg_values,
+            &risk_collateral,
+            config,
+        )?;
+        require!(
+            remaining_debt <= borrow_limit(&values, config, recovery)?
+                || collateral_ratio_bps(values.market_value, 1, remaining_debt)? >= ratio_before,
+            ErrorCode::UndercollateralizedVault
+        );
+
+        let vault = &mut ctx.accounts.vault;
+        let (collateral_before, debt_before) = (primary_amount(vault), vault.borrowed);
+        vault.bond_amount = remaining_bonds;
+        vault.borrowed = remaining_debt;
+        settle_repayment(
+            vault,
+            RepaymentAccounts {
+                global_state: &mut ctx.accounts.global_state,
+                collateral_config: &mut ctx.accounts.collateral_config,
+                issuer_exposure: &mut ctx.accounts.issuer_exposure,
+                sorted_vaults: &mut ctx.accounts.sorted_vaults,
+                config: &ctx.accounts.config,
+                rate_model: &ctx.accounts.rate_model,
+            },
+            Some(InterestMint {
+                ledger: &mut ctx.accounts.supply_ledger,
+                token_program: &ctx.accounts.stablecoin_token_program,
+                stablecoin_mint: &ctx.accounts.stablecoin_mint,
+                mint_authority: &ctx.accounts.mint_authority,
+                recipients: [
+                    &ctx.accounts.staking_reward_vault,
+                    &ctx.accounts.treasury,
+                    &ctx.accounts.reserve,
+                    &ctx.accounts.insurance_fund,
+                ],
+            }),
+            Repayment {
+                collateral_before,
+                debt_before,
+                interest,
+                repaid: repay_amount,
+                now,
+            },
+            hint,
+            node_accounts,
+        )?;
+
+        emit!(DeleverageEvent {
+            vault: vault.key(),
+            owner,
+            collateral_sold: bond_amount,
+            debt_repaid: repay_amount,
+            remaining_debt,
+        });
+
+        Ok(())
+    }
+
+    pub fn liquidate_many<'info>(
+        ctx: Context<'_, '_, 'info, 'info, LiquidateMany<'info>>,
+        hints: Vec<ListHint>,
+        list_nodes: u8,
+        hook_accounts: u8,
+    ) -> Result<()> {
+        let (remaining, hook_accounts) =
+            split_hook_accounts(ctx.remaining_accounts, hook_accounts)?;
+        require_not_paused(&ctx.accounts.config, PAUSE_LIQUIDATION)?;
+        require!(
+            ctx.accounts.deny_entry.data_is_empty(),
+            ErrorCode::AccountDenied
+        );
+        let config = &ctx.accounts.config;
+        let rate_model = &ctx.accounts.rate_model;
+        let recovery = is_recovery_mode(&ctx.accounts.global_state, config)?;
+
+        require!(
+            (list_nodes as usize) <= remaining.len(),
+            ErrorCode::InvalidRemainingAccounts
+        );
+        let (vault_accounts, node_accounts) =
+            remaining.split_at(remaining.len() - list_nodes as usize);
+        require!(
+            vault_accounts.len() % 6 == 0 && vault_accounts.len() / 6 == hints.len(),
+            ErrorCode::InvalidRemainingAccounts
+        );
+
+        let now = Clock::get()?.unix_timestamp;
+        let mut available = ctx.accounts.liquidator_stablecoin_account.amount;
+        let mut total_interest: u64 = 0;
+        let mut total_repaid: u64 = 0;
+        let mut total_surplus: u64 = 0;
+        let mut liquidated: u64 = 0;
+
+        for (accounts, hint) in vault_accounts.chunks(6).zip(hints) {
+            let vault_info = &accounts[0];
+            let escrow_info = &accounts[1];
+            let liquidator_bond_info = &accounts[2];
+            let collateral_info = &accounts[3];
+            let exposure_info = &accounts[4];
+            let price_cache_info = &accounts[5];
+
+            let mut vault = Account::<Vault>::try_from(vault_info)?;
+            let escrow = InterfaceAccount::<TokenAccount>::try_from(escrow_info)?;
+            require!(
+                escrow.owner == vault.key() && escrow.mint == vault.collateral_mint,
+                ErrorCode::InvalidEscrow
+            );
+            let mut collateral_config = Account::<CollateralConfig>::try_from(collateral_info)?;
+            require!(
+                collateral_config.mint == vault.collateral_mint
+                    && collateral_config.stablecoin_mint == ctx.accounts.stablecoin_mint.key(),
+                ErrorCode::InvalidRemainingAccounts
+            );
+            let mut issuer_exposure = Account::<IssuerExposure>::try_from(exposure_info)?;
+            require!(
+                issuer_exposure.issuer == collateral_config.issuer,
+                ErrorCode::InvalidRemainingAccounts
+            );
+            let price_cache = Account::<PriceCache>::try_from(price_cache_info)?;
+            require!(
+                price_cache.mint == vault.collateral_mint,
+                ErrorCode::InvalidRemainingAccounts
+            );
+            load_cached_price(&mut collateral_config, &price_cache, true)?;
+            accrue_borrow_index(&mut collateral_config, rate_model, now)?;
+            let collateral = &collateral_config;
+
+            let total_debt = vault
+                .borrowed
+                .checked_add(pending_interest(
+                    &vault, collateral, rate_model, config, now,
+                )?)
+                .ok_or(ErrorCode::MathOverflow)?;
+            let values = vault_values(&vault, collateral, config)?;
+            if total_debt == 0
+                || !(is_past_due(&vault, now)
+                    || is_liquidatable(&values, total_debt, config, recovery)?)
+            {
+                continue;
+            }
+            let LiquidationPlan {
+                interest,
+                total_debt,
+                repay_amount,
+                seize_amount,
+                surplus,
+            } = match plan_liquidation(
+                &vault,
+                collateral,
+                collateral,
+                rate_model,
+                config,
+                recovery,
+                now,
+                max_liquidation_repay(total_debt, config),
+                primary_amount(&vault),
+            ) {
+                Ok(plan) => plan,
+                Err(_) => continue,
+            };
+            if repay_amount + surplus > available {
+                continue;
+            }
+            available -= repay_amount + surplus;
+
+            let owner = vault.owner;
+            let creator = vault.creator;
+            let collateral_mint = vault.collateral_mint;
+            let bump = [vault.bump];
+            let signer_seeds: &[&[&[u8]]] =
+                &[&[b"vault", creator.as_ref(), collateral_mint.as_ref(), &bump]];
+
+            anchor_spl::token::transfer(
+                CpiContext::new_with_signer(
+                    ctx.accounts.token_program.to_account_info(),
+                    Transfer {
+                        from: escrow_info.clone(),
+                        to: liquidator_bond_info.clone(),
+                        authority: vault_info.clone(),
+                    },
+                    signer_seeds,
+                ),
+                seize_amount,
+            )?;
+
+            let (collateral_before, debt_before) = (primary_amount(&vault), vault.borrowed);
+            vault.bond_amount -= seize_amount;
+            vault.borrowed = total_debt - repay_amount;
+            vault.surplus = vault
+                .surplus
+                .checked_add(surplus)
+                .ok_or(ErrorCode::MathOverflow)?;
+            let interest_paid = settle_repayment(
+                &mut vault,
+                RepaymentAccounts {
+                    global_state: &mut ctx.accounts.global_state,
+                    collateral_config: &mut collateral_config,
+                    issuer_exposure: &mut issuer_exposure,
+                    sorted_vaults: &mut ctx.accounts.sorted_vaults,
+                    config: &ctx.accounts.config,
+                    rate_model: &ctx.accounts.rate_model,
+                },
+                None,
+                Repayment {
+                    collateral_before,
+                    debt_before,
+                    interest,
+                    repaid: repay_amount,
+                    now,
+                },
+                hint,
+                node_accounts,
+            )?;
+            issuer_exposure.exit(&crate::ID)?;
+            vault.exit(&crate::ID)?;
+            collateral_config.exit(&crate::ID)?;
+
+            emit!(LiquidateEvent {
+                vault: vault.key(),
+                owner,
+                liquidator: ctx.accounts.liquidator.key(),
+                debt_repaid: repay_amount,
+                collateral_seized: seize_amount,
+                remaining_debt: vault.borrowed,
+                surplus,
+            });
+
+            total_interest += interest_paid;
+            total_repaid += repay_amount;
+            total_surplus += surplus;
+            liquidated += 1;
+        }
+
+        require!(liquidated > 0, ErrorCode::NothingToLiquidate);
+
+        mint_interest(
+            &mut ctx.accounts.supply_ledger,
+            &ctx.accounts.stablecoin_token_program,
+            &ctx.accounts.stablecoin_mint,
+            &ctx.accounts.mint_authority,
+            [
+                &ctx.accounts.staking_reward_vault,
+                &ctx.accounts.treasury,
+                &ctx.accounts.reserve,
+                &ctx.accounts.insurance_fund,
+            ],
+            &ctx.accounts.config,
+            total_interest,
+        )?;
+
+        token_interface::burn(
+            CpiContext::new(
+                ctx.accounts.stablecoin_token_program.to_account_info(),
+                token_interface::Burn {
+                    mint: ctx.accounts.stablecoin_mint.to_account_info(),
+                    from: ctx.accounts.liquidator_stablecoin_account.to_account_info(),
+                    authority: ctx.accounts.liquidator.to_account_info(),
+                },
+            ),
+            total_repaid,
+        )?;
+        record_repay_burn(
+            &mut ctx.accounts.supply_ledger,
+            total_repaid,
+            total_interest,
+        )?;
+
+        pay_keeper_bounty(
+            &ctx.accounts.stablecoin_token_program,
+            &ctx.accounts.stablecoin_mint,
+            &ctx.accounts.treasury,
+            &ctx.accounts.liquidator_stablecoin_account,
+            &ctx.accounts.global_state,
+            hook_accounts,
+            ctx.accounts.config.keeper_bounty.saturating_mul(liquidated),
+        )?;
+
+        if total_surplus > 0 {
+            transfer_stablecoin(
+                ctx.accounts.stablecoin_token_program.to_account_info(),
+                TransferChecked {
+                    from: ctx.accounts.liquidator_stablecoin_account.to_account_info(),
+                    mint: ctx.accounts.stablecoin_mint.to_account_info(),
+                    to: ctx.accounts.surplus_vault.to_account_info(),
+                    authority: ctx.accounts.liquidator.to_account_info(),
+                },
+                hook_accounts,
+                total_surplus,
+                ctx.accounts.stablecoin_mint.decimals,
+                &[],
+            )?;
+        }
+
+        Ok(())
+    }
+
+    pub fn cover_bad_debt<'info>(
+        ctx: Context<'_, '_, 'info, 'info, CoverBadDebt<'info>>,
+        hint: ListHint,
+    ) -> Result<()> {
+        accrue_borrow_index(
+            &mut ctx.accounts.collateral_config,
+            &ctx.accounts.rate_model,
+            Clock::get()?.unix_timestamp,
+        )?;
+        let vault = &ctx.accounts.vault;
+        require!(vault.bond_amount == 0, ErrorCode::VaultNotInsolvent);
+        require!(vault.borrowed > 0, ErrorCode::NothingToRepay);
+        let covered = vault.borrowed.min(ctx.accounts.reserve.amount);
+        require!(covered > 0, ErrorCode::ReserveEmpty);
+
+        let bump = [ctx.accounts.global_state.bump];
+        let signer_seeds: &[&[&[u8]]] = &[&[b"global_state", &bump]];
+
+        token_interface::burn(
+            CpiContext::new_with_signer(
+                ctx.accounts.stablecoin_token_program.to_account_info(),
+                token_interface::Burn {
+                    mint: ctx.accounts.stablecoin_mint.to_account_info(),
+                    from: ctx.accounts.reserve.to_account_info(),
+                    authority: ctx.accounts.global_state.to_account_info(),
+                },
+                signer_seeds,
+            ),
+            covered,
+        )?;
+        record_burn(
+            &mut ctx.accounts.supply_ledger,
+            SupplySource::CdpDebt,
+            covered,
+        )?;
+
+        let vault = &mut ctx.accounts.vault;
+        let (collateral_before, debt_before) = (primary_amount(vault), vault.borrowed);
+        vault.borrowed -= covered;
+        vault.unpaid_interest = vault.unpaid_interest.min(vault.borrowed);
+        checkpoint_debt(
+            vault,
+            &ctx.accounts.collateral_config,
+            Clock::get()?.unix_timestamp,
+        );
+        track_vault(
+            &mut ctx.accounts.global_state,
+            &mut ctx.accounts.collateral_config,
+            &ctx.accounts.config,
+            collateral_before,
+            debt_before,
+            vault,
+        )?;
+        track_issuer_debt(&mut ctx.accounts.issuer_exposure, debt_before, vault.borrowed)?;
+
+        let vault_key = vault.key();
+        update_sorted_position(
+            &mut ctx.accounts.sorted_vaults,
+            vault_key,
+            vault,
+            hint,
+            ctx.remaining_accounts,
+        )?;
+
+        emit!(BadDebtCoveredEvent {
+            vault: vault_key,
+            owner: vault.owner,
+            covered,
+            remaining_debt: vault.borrowed,
+        });
+
+        Ok(())
+    }
+
+    // Bad debt the reserve can't absorb is auctioned off for a fixed amount of stablecoin.
+    // Bidders compete by accepting fewer governance tokens, which are minted to the winner.
+    // An auction that closes without bids can be restarted with a larger lot.
+    pub fn start_debt_auction(ctx: Context<StartDebtAuction>, lot: u64) -> Result<()> {
+        let vault = &ctx.accounts.vault;
+        require!(vault.bond_amount == 0, ErrorCode::VaultNotInsolvent);
+        require!(vault.borrowed > 0, ErrorCode::NothingToRepay);
+        require!(
+            ctx.accounts.reserve.amount == 0 && ctx.accounts.insurance_fund.amount == 0,
+            ErrorCode::ReserveNotExhausted
+        );
+        require!(lot > 0, ErrorCode::InvalidAmount);
+
+        let now = Clock::get()?.unix_timestamp;
+        let auction = &mut ctx.accounts.debt_auction;
+        require!(
+            !auction.active || (auction.bidder == Pubkey::default() && now >= auction.ends_at),
+            ErrorCode::AuctionActive
+        );
+        auction.vault = vault.key();
+        auction.debt = vault.borrowed;
+        auction.lot = lot;
+        auction.bidder = Pubkey::default();
+        auction.ends_at = now + ctx.accounts.config.debt_auction_duration_secs;
+        auction.active = true;
+        auction.bump = ctx.bumps.debt_auction;
+
+        emit!(DebtAuctionStartedEvent {
+            vault: auction.vault,
+            debt: auction.debt,
+            lot,
+            ends_at: auction.ends_at,
+        });
+
+        Ok(())
+    }
+
+    pub fn bid_debt_auction(ctx: Context<BidDebtAuction>, lot: u64) -> Result<()> {
+        let auction = &ctx.accounts.debt_auction;
+        require!(auction.active, ErrorCode::AuctionInactive);
+        require!(
+            Clock::get()?.unix_timestamp < auction.ends_at,
+            ErrorCode::AuctionEnded
+        );
+        require!(lot > 0, ErrorCode::InvalidAmount);
+        let decrement_bps = ctx.accounts.config.min_bid_decrement_bps;
+        let max_lot = if auction.bidder == Pubkey::default() {
+            auction.lot
+        } else {
+            ((auction.lot as u128) * ((10000 - decrement_bps) as u128) / 10000) as u64
+        };
+        require!(lot <= max_lot, ErrorCode::BidTooHigh);
+
+        let vault_key = auction.vault;
+        let bump = [auction.bump];
+        let signer_seeds: &[&[&[u8]]] = &[&[b"debt_auction", vault_key.as_ref(), &bump]];
+
+        if auction.bidder != Pubkey::default() {
+            let Some(previous) = &ctx.accounts.previous_bidder_stablecoin_account else {
+                return err!(ErrorCode::InvalidParameter);
+            };
+            require!(previous.owner == auction.bidder, ErrorCode::Unauthorized);
+            transfer_stablecoin(
+                ctx.accounts.stablecoin_token_program.to_account_info(),
+                TransferChecked {
+                    from: ctx.accounts.auction_escrow.to_account_info(),
+                    mint: ctx.accounts.stablecoin_mint.to_account_info(),
+                    to: previous.to_account_info(),
+                    authority: ctx.accounts.debt_auction.to_account_info(),
+                },
+                ctx.remaining_accounts,
+                auction.debt,
+                ctx.accounts.stablecoin_mint.decimals,
+                signer_seeds,
+            )?;
+        }
+
+        transfer_stablecoin(
+            ctx.accounts.stablecoin_token_program.to_account_info(),
+            TransferChecked {
+                from: ctx.accounts.bidder_stablecoin_account.to_account_info(),
+                mint: ctx.accounts.stablecoin_mint.to_account_info(),
+                to: ctx.accounts.auction_escrow.to_account_info(),
+                authority: ctx.accounts.bidder.to_account_info(),
+            },
+            ctx.remaining_accounts,
+            auction.debt,
+            ctx.accounts.stablecoin_mint.decimals,
+            &[],
+        )?;
+
+        let auction = &mut ctx.accounts.debt_auction;
+        auction.lot = lot;
+        auction.bidder = ctx.accounts.bidder.key();
+
+        emit!(DebtAuctionBidEvent {
+            vault: vault_key,
+            bidder: auction.bidder,
+            debt: auction.debt,
+            lot,
+        });
+
+        Ok(())
+    }
+
+    // Debt the vault shed while the auction ran, e.g. through a refilled reserve, is
+    // refunded to the winner rather than burned.
+    pub fn settle_debt_auction<'info>(
+        ctx: Context<'_, '_, 'info, 'info, SettleDebtAuction<'info>>,
+        hint: ListHint,
+        hook_accounts: u8,
+    ) -> Result<()> {
+        let (remaining, hook_accounts) =
+            split_hook_accounts(ctx.remaining_accounts, hook_accounts)?;
+        let auction = &ctx.accounts.debt_auction;
+        require!(auction.active, ErrorCode::AuctionInactive);
+        require!(
+            Clock::get()?.unix_timestamp >= auction.ends_at,
+            ErrorCode::AuctionActive
+        );
+        require!(auction.bidder != Pubkey::default(), ErrorCode::NoBids);
+        accrue_borrow_index(
+            &mut ctx.accounts.collateral_config,
+            &ctx.accounts.rate_model,
+            Clock::get()?.unix_timestamp,
+        )?;
+        let (debt, lot) = (auction.debt, auction.lot);
+        let covered = debt.min(ctx.accounts.vault.borrowed);
+
+        let vault_key = auction.vault;
+        let bump = [auction.bump];
+        let signer_seeds: &[&[&[u8]]] = &[&[b"debt_auction", vault_key.as_ref(), &bump]];
+
+        token_interface::burn(
+            CpiContext::new_with_signer(
+                ctx.accounts.stablecoin_token_program.to_account_info(),
+                token_interface::Burn {
+                    mint: ctx.accounts.stablecoin_mint.to_account_info(),
+                    from: ctx.accounts.auction_escrow.to_account_info(),
+                    authority: ctx.accounts.debt_auction.to_account_info(),
+                },
+                signer_seeds,
+            ),
+            covered,
+        )?;
+        record_burn(
+            &mut ctx.accounts.supply_ledger,
+            SupplySource::CdpDebt,
+            covered,
+        )?;
+        if debt > covered {
+            transfer_stablecoin(
+                ctx.accounts.stablecoin_token_program.to_account_info(),
+                TransferChecked {
+                    from: ctx.accounts.auction_escrow.to_account_info(),
+                    mint: ctx.accounts.stablecoin_mint.to_account_info(),
+                    to: ctx.accounts.winner_stablecoin_account.to_account_info(),
+                    authority: ctx.accounts.debt_auction.to_account_info(),
+                },
+                hook_accounts,
+                debt - covered,
+                ctx.accounts.stablecoin_mint.decimals,
+                signer_seeds,
+            )?;
+        }
+
+        let state_bump = [ctx.accounts.global_state.bump];
+        let state_seeds: &[&[&[u8]]] = &[&[b"global_state", &state_bump]];
+        token_interface::mint_to(
+            CpiContext::new_with_signer(
+                ctx.accounts.governance_token_program.to_account_info(),
+                token_interface::MintTo {
+                    mint: ctx.accounts.governance_mint.to_account_info(),
+                    to: ctx.accounts.winner_governance_account.to_account_info(),
+                    authority: ctx.accounts.global_state.to_account_info(),
+                },
+                state_seeds,
+            ),
+            lot,
+        )?;
+
+        let vault = &mut ctx.accounts.vault;
+        let (collateral_before, debt_before) = (primary_amount(vault), vault.borrowed);
+        vault.borrowed -= covered;
+        vault.unpaid_interest = vault.unpaid_interest.min(vault.borrowed);
+        checkpoint_debt(
+            vault,
+            &ctx.accounts.collateral_config,
+            Clock::get()?.unix_timestamp,
+        );
+        track_vault(
+            &mut ctx.accounts.global_state,
+            &mut ctx.accounts.collateral_config,
+            &ctx.accounts.config,
+            collateral_before,
+            debt_before,
+            vault,
+        )?;
+        track_issuer_debt(
+            &mut ctx.accounts.issuer_exposure,
+            debt_before,
+            vault.borrowed,
+        )?;
+
+        update_sorted_position(
+            &mut ctx.accounts.sorted_vaults,
+            vault_key,
+            vault,
+            hint,
+            remaining,
+        )?;
+
+        let auction = &mut ctx.accounts.debt_auction;
+        auction.active = false;
+
+        emit!(DebtAuctionSettledEvent {
+            vault: vault_key,
+            winner: auction.bidder,
+            covered,
+            governance_minted: lot,
+            remaining_debt: ctx.accounts.vault.borrowed,
+        });
+
+        Ok(())
+    }
+
+    // The treasury doubles as the surplus buffer: whatever it holds above the cap is put up
+    // as a fixed lot, bidders compete in governance tokens and the winning bid is burned.
+    pub fn start_surplus_auction(ctx: Context<StartSurplusAuction>) -> Result<()> {
+        let cap = ctx.accounts.config.surplus_buffer_cap;
+        let lot = ctx.accounts.treasury.amount.saturating_sub(cap);
+        require!(lot > 0, ErrorCode::SurplusBelowThreshold);
+        let now = Clock::get()?.unix_timestamp;
+        let auction = &mut ctx.accounts.surplus_auction;
+        require!(!auction.active, ErrorCode::AuctionActive);
+        auction.lot = lot;
+        auction.bid = 0;
+        auction.bidder = Pubkey::default();
+        auction.ends_at = now + ctx.accounts.config.surplus_auction_duration_secs;
+        auction.active = true;
+        auction.bump = ctx.bumps.surplus_auction;
+
+        let bump = [ctx.accounts.global_state.bump];
+        let signer_seeds: &[&[&[u8]]] = &[&[b"global_state", &bump]];
+        transfer_stablecoin(
+            ctx.accounts.stablecoin_token_program.to_account_info(),
+            TransferChecked {
+                from: ctx.accounts.treasury.to_account_info(),
+                mint: ctx.accounts.stablecoin_mint.to_account_info(),
+                to: ctx.accounts.surplus_lot_escrow.to_account_info(),
+                authority: ctx.accounts.global_state.to_account_info(),
+            },
+            ctx.remaining_accounts,
+            lot,
+            ctx.accounts.stablecoin_mint.decimals,
+            signer_seeds,
+        )?;
+
+        emit!(SurplusAuctionStartedEvent {
+            lot,
+            ends_at: ctx.accounts.surplus_auction.ends_at,
+        });
+
+        Ok(())
+    }
+
+    pub fn bid_surplus_auction(ctx: Context<BidSurplusAuction>, bid: u64) -> Result<()> {
+        let auction = &ctx.accounts.surplus_auction;
+        require!(auction.active, ErrorCode::AuctionInactive);
+        require!(
+            Clock::get()?.unix_timestamp < auction.ends_at,
+            ErrorCode::AuctionEnded
+        );
+        let increment_bps = ctx.accounts.config.min_bid_increment_bps;
+        let min_bid = ((auction.bid as u128) * ((10000 + increment_bps) as u128) / 10000) as u64;
+        require!(bid > auction.bid && bid >= min_bid, ErrorCode::BidTooLow);
+
+        let bump = [auction.bump];
+        let signer_seeds: &[&[&[u8]]] = &[&[b"surplus_auction", &bump]];
+
+        if auction.bidder != Pubkey::default() {
+            let Some(previous) = &ctx.accounts.previous_bidder_governance_account else {
+                return err!(ErrorCode::InvalidParameter);
+            };
+            require!(previous.owner == auction.bidder, ErrorCode::Unauthorized);
+            token_interface::transfer_checked(
+                CpiContext::new_with_signer(
+                    ctx.accounts.governance_token_program.to_account_info(),
+                    TransferChecked {
+                        from: ctx.accounts.surplus_bid_escrow.to_account_info(),
+                        mint: ctx.accounts.governance_mint.to_account_info(),
+                        to: previous.to_account_info(),
+                        authority: ctx.accounts.surplus_auction.to_account_info(),
+                    },
+                    signer_seeds,
+                ),
+                auction.bid,
+                ctx.accounts.governance_mint.decimals,
+            )?;
+        }
+
+        token_interface::transfer_checked(
+            CpiContext::new(
+                ctx.accounts.governance_token_program.to_account_info(),
+                TransferChecked {
+                    from: ctx.accounts.bidder_governance_account.to_account_info(),
+                    mint: ctx.accounts.governance_mint.to_account_info(),
+                    to: ctx.accounts.surplus_bid_escrow.to_account_info(),
+                    authority: ctx.accounts.bidder.to_account_info(),
+                },
+            ),
+            bid,
+            ctx.accounts.governance_mint.decimals,
+        )?;
+
+        let auction = &mut ctx.accounts.surplus_auction;
+        auction.bid = bid;
+        auction.bidder = ctx.accounts.bidder.key();
+
+        emit!(SurplusAuctionBidEvent {
+            bidder: auction.bidder,
+            lot: auction.lot,
+            bid,
+        });
+
+        Ok(())
+    }
+
+    // Without a bid the lot goes back to the treasury.
+    pub fn settle_surplus_auction(ctx: Context<SettleSurplusAuction>) -> Result<()> {
+        let auction = &ctx.accounts.surplus_auction;
+        require!(auction.active, ErrorCode::AuctionInactive);
+        require!(
+            Clock::get()?.unix_timestamp >= auction.ends_at,
+            ErrorCode::AuctionActive
+        );
+        let (lot, bid, bidder) = (auction.lot, auction.bid, auction.bidder);
+
+        let bump = [auction.bump];
+        let signer_seeds: &[&[&[u8]]] = &[&[b"surplus_auction", &bump]];
+        let recipient = if bidder == Pubkey::default() {
+            ctx.accounts.treasury.to_account_info()
+        } else {
+            let Some(winner) = &ctx.accounts.winner_stablecoin_account else {
+                return err!(ErrorCode::InvalidParameter);
+            };
+            require!(winner.owner == bidder, ErrorCode::Unauthorized);
+            token_interface::burn(
+                CpiContext::new_with_signer(
+                    ctx.accounts.governance_token_program.to_account_info(),
+                    token_interface::Burn {
+                        mint: ctx.accounts.governance_mint.to_account_info(),
+                        from: ctx.accounts.surplus_bid_escrow.to_account_info(),
+                        authority: ctx.accounts.surplus_auction.to_account_info(),
+                    },
+                    signer_seeds,
+                ),
+                bid,
+            )?;
+            winner.to_account_info()
+        };
+
+        transfer_stablecoin(
+            ctx.accounts.stablecoin_token_program.to_account_info(),
+            TransferChecked {
+                from: ctx.accounts.surplus_lot_escrow.to_account_info(),
+                mint: ctx.accounts.stablecoin_mint.to_account_info(),
+                to: recipient,
+                authority: ctx.accounts.surplus_auction.to_account_info(),
+            },
+            ctx.remaining_accounts,
+            lot,
+            ctx.accounts.stablecoin_mint.decimals,
+            signer_seeds,
+        )?;
+
+        ctx.accounts.surplus_auction.active = false;
+
+        emit!(SurplusAuctionSettledEvent {
+            winner: bidder,
+            lot,
+            governance_burned: bid,
+        });
+
+        Ok(())
+    }
+
+    // The insurance fund is the first-loss buffer for insolvent vaults; the risk authority
+    // draws on it before any shortfall is passed on to holders.
+    pub fn draw_insurance_fund<'info>(
+        ctx: Context<'_, '_, 'info, 'info, DrawInsuranceFund<'info>>,
+        hint: ListHint,
+    ) -> Result<()> {
+        accrue_borrow_index(
+            &mut ctx.accounts.collateral_config,
+            &ctx.accounts.rate_model,
+            Clock::get()?.unix_timestamp,
+        )?;
+        let vault = &ctx.accounts.vault;
+        require!(vault.bond_amount == 0, ErrorCode::VaultNotInsolvent);
+        require!(vault.borrowed > 0, ErrorCode::NothingToRepay);
+        let drawn = vault.borrowed.min(ctx.accounts.insurance_fund.amount);
+        require!(drawn > 0, ErrorCode::InsuranceFundEmpty);
+
+        let bump = [ctx.accounts.global_state.bump];
+        let signer_seeds: &[&[&[u8]]] = &[&[b"global_state", &bump]];
+
+        token_interface::burn(
+            CpiContext::new_with_signer(
+                ctx.accounts.stablecoin_token_program.to_account_info(),
+                token_interface::Burn {
+                    mint: ctx.accounts.stablecoin_mint.to_account_info(),
+                    from: ctx.accounts.insurance_fund.to_account_info(),
+                    authority: ctx.accounts.global_state.to_account_info(),
+                },
+                signer_seeds,
+            ),
+            drawn,
+        )?;
+        record_burn(
+            &mut ctx.accounts.supply_ledger,
+            SupplySource::CdpDebt,
+            drawn,
+        )?;
+
+        let vault = &mut ctx.accounts.vault;
+        let (collateral_before, debt_before) = (primary_amount(vault), vault.borrowed);
+        vault.borrowed -= drawn;
+        vault.unpaid_interest = vault.unpaid_interest.min(vault.borrowed);
+        checkpoint_debt(
+            vault,
+            &ctx.accounts.collateral_config,
+            Clock::get()?.unix_timestamp,
+        );
+        track_vault(
+            &mut ctx.accounts.global_state,
+            &mut ctx.accounts.collateral_config,
+            &ctx.accounts.config,
+            collateral_before,
+            debt_before,
+            vault,
+        )?;
+        track_issuer_debt(
+            &mut ctx.accounts.issuer_exposure,
+            debt_before,
+            vault.borrowed,
+        )?;
+
+        let vault_key = vault.key();
+        update_sorted_position(
+            &mut ctx.accounts.sorted_vaults,
+            vault_key,
+            vault,
+            hint,
+            ctx.remaining_accounts,
+        )?;
+
+        emit!(InsuranceFundDrawnEvent {
+            vault: vault_key,
+            risk_authority: ctx.accounts.risk_authority.key(),
+            drawn,
+            remaining_debt: ctx.accounts.vault.borrowed,
+            fund_balance: ctx.accounts.insurance_fund.amount - drawn,
+        });
+
+        Ok(())
+    }
+
+    // Last resort once the reserve and insurance fund are empty: the vault's debt is written
+    // off and burned out of the staking reward vault, haircutting savers pro rata through
+    // the share price. Anything stakers can't absorb is recorded as unbacked supply.
+    pub fn socialize_bad_debt<'info>(
+        ctx: Context<'_, '_, 'info, 'info, SocializeBadDebt<'info>>,
+        hint: ListHint,
+    ) -> Result<()> {
+        accrue_borrow_index(
+            &mut ctx.accounts.collateral_config,
+            &ctx.accounts.rate_model,
+            Clock::get()?.unix_timestamp,
+        )?;
+        let vault = &ctx.accounts.vault;
+        require!(vault.bond_amount == 0, ErrorCode::VaultNotInsolvent);
+        require!(vault.borrowed > 0, ErrorCode::NothingToRepay);
+        require!(
+            ctx.accounts.reserve.amount == 0 && ctx.accounts.insurance_fund.amount == 0,
+            ErrorCode::ReserveNotExhausted
+        );
+        let shortfall = vault.borrowed;
+        let haircut = shortfall.min(ctx.accounts.staking_reward_vault.amount);
+        let unbacked = shortfall - haircut;
+
+        if haircut > 0 {
+            let bump = [ctx.accounts.savings_state.bump];
+            let signer_seeds: &[&[&[u8]]] = &[&[b"savings", &bump]];
+            token_interface::burn(
+                CpiContext::new_with_signer(
+                    ctx.accounts.stablecoin_token_program.to_account_info(),
+                    token_interface::Burn {
+                        mint: ctx.accounts.stablecoin_mint.to_account_info(),
+                        from: ctx.accounts.staking_reward_vault.to_account_info(),
+                        authority: ctx.accounts.savings_state.to_account_info(),
+                    },
+                    signer_seeds,
+                ),
+                haircut,
+            )?;
+            record_burn(
+                &mut ctx.accounts.supply_ledger,
+                SupplySource::CdpDebt,
+                haircut,
+            )?;
+        }
+
+        let bad_debt = &mut ctx.accounts.bad_debt;
+        bad_debt.total_haircut = bad_debt
+            .total_haircut
+            .checked_add(haircut)
+            .ok_or(ErrorCode::MathOverflow)?;
+        bad_debt.unbacked_supply = bad_debt
+            .unbacked_supply
+            .checked_add(unbacked)
+            .ok_or(ErrorCode::MathOverflow)?;
+        bad_debt.bump = ctx.bumps.bad_debt;
+
+        let vault = &mut ctx.accounts.vault;
+        let (collateral_before, debt_before) = (primary_amount(vault), vault.borrowed);
+        vault.borrowed = 0;
+        vault.unpaid_interest = 0;
+        checkpoint_debt(
+            vault,
+            &ctx.accounts.collateral_config,
+            Clock::get()?.unix_timestamp,
+        );
+        track_vault(
+            &mut ctx.accounts.global_state,
+            &mut ctx.accounts.collateral_config,
+            &ctx.accounts.config,
+            collateral_before,
+            debt_before,
+            vault,
+        )?;
+        track_issuer_debt(
+            &mut ctx.accounts.issuer_exposure,
+            debt_before,
+            vault.borrowed,
+        )?;
+
+        let vault_key = vault.key();
+        update_sorted_position(
+            &mut ctx.accounts.sorted_vaults,
+            vault_key,
+            vault,
+            hint,
+            ctx.remaining_accounts,
+        )?;
+
+        emit!(BadDebtSocializedEvent {
+            vault: vault_key,
+            shortfall,
+            haircut,
+            unbacked,
+            unbacked_supply: ctx.accounts.bad_debt.unbacked_supply,
+        });
+
+        Ok(())
+    }
+
+    // Settlement freezes the system: minting stops, every collateral's price is frozen
+    // and vault debt is cancelled against collateral moved into a per-collateral pool
+    // that stablecoin holders later redeem from pro-rata.
+    pub fn trigger_settlement(ctx: Context<TriggerSettlement>) -> Result<()> {
+        let now = Clock::get()?.unix_timestamp;
+        let settlement = &mut ctx.accounts.settlement;
+        settlement.triggered_at = now;
+        settlement.finalized = false;
+        settlement.collateral_count = 0;
+        settlement.fixed_count = 0;
+        settlement.bump = ctx.bumps.settlement;
+        settlement.supply = 0;
+        settlement.psm_reserve = 0;
+
+        emit!(SettlementTriggeredEvent {
+            guardian: ctx.accounts.guardian.key(),
+            triggered_at: now,
+        });
+
+        Ok(())
+    }
+
+    pub fn freeze_collateral(ctx: Context<FreezeCollateral>) -> Result<()> {
+        load_cached_price(
+            &mut ctx.accounts.collateral_config,
+            &ctx.accounts.price_cache,
+            false,
+        )?;
+        let collateral = &ctx.accounts.collateral_config;
+        require!(collateral.unit_value > 0, ErrorCode::InvalidPrice);
+        let settlement = &mut ctx.accounts.settlement;
+        require!(!settlement.finalized, ErrorCode::SettlementFinalized);
+        settlement.collateral_count += 1;
+
+        let frozen = &mut ctx.accounts.settlement_collateral;
+        frozen.mint = collateral.mint;
+        frozen.stablecoin_mint = collateral.stablecoin_mint;
+        frozen.unit_value = collateral.unit_value;
+        frozen.fix = 0;
+        frozen.fixed = false;
+        frozen.bump = ctx.bumps.settlement_collateral;
+        frozen.supply = 0;
+
+        emit!(CollateralFrozenEvent {
+            mint: frozen.mint,
+            unit_value: frozen.unit_value,
+        });
+
+        Ok(())
+    }
+
+    // Moves the collateral backing a vault's debt at the frozen price into the settlement
+    // pool and cancels the debt; whatever is left stays with the vault for its owner. Debt
+    // the primary collateral can't cover stays on the vault for settle_vault_leg while legs
+    // remain, and is written off once the vault holds nothing else.
+    pub fn settle_vault<'info>(
+        ctx: Context<'_, '_, 'info, 'info, SettleVault<'info>>,
+        hint: ListHint,
+    ) -> Result<()> {
+        let now = Clock::get()?.unix_timestamp;
+        accrue_borrow_index(
+            &mut ctx.accounts.collateral_config,
+            &ctx.accounts.rate_model,
+            now,
+        )?;
+        let vault = &ctx.accounts.vault;
+        let frozen = &ctx.accounts.settlement_collateral;
+        require!(!frozen.fixed, ErrorCode::CollateralAlreadyFixed);
+        let interest = pending_interest(
+            vault,
+            &ctx.accounts.collateral_config,
+            &ctx.accounts.rate_model,
+            &ctx.accounts.config,
+            now,
+        )?;
+        let debt = vault
+            .borrowed
+            .checked_add(interest)
+            .ok_or(ErrorCode::MathOverflow)?;
+        require!(debt > 0, ErrorCode::NothingToRepay);
+        let claimed = debt.div_ceil(frozen.unit_value).min(primary_amount(vault));
+        let covered = settled_debt(debt, claimed, frozen.unit_value);
+
+        let creator = vault.creator;
+        let collateral_mint = vault.collateral_mint;
+        let bump = [vault.bump];
+        let signer_seeds: &[&[&[u8]]] =
+            &[&[b"vault", creator.as_ref(), collateral_mint.as_ref(), &bump]];
+
+        if claimed > 0 {
+            anchor_spl::token::transfer(
+                CpiContext::new_with_signer(
+                    ctx.accounts.token_program.to_account_info(),
+                    Transfer {
+                        from: ctx.accounts.escrow.to_account_info(),
+                        to: ctx.accounts.settlement_escrow.to_account_info(),
+                        authority: ctx.accounts.vault.to_account_info(),
+                    },
+                    signer_seeds,
+                ),
+                claimed,
+            )?;
+        }
+
+        let vault = &mut ctx.accounts.vault;
+        let (collateral_before, debt_before) = (primary_amount(vault), vault.borrowed);
+        vault.bond_amount -= claimed;
+        vault.borrowed = remaining_settlement_debt(vault, debt, covered);
+        vault.unpaid_interest = 0;
+        checkpoint_debt(vault, &ctx.accounts.collateral_config, now);
+        track_vault(
+            &mut ctx.accounts.global_state,
+            &mut ctx.accounts.collateral_config,
+            &ctx.accounts.config,
+            collateral_before,
+            debt_before,
+            vault,
+        )?;
+        track_issuer_debt(
+            &mut ctx.accounts.issuer_exposure,
+            debt_before,
+            vault.borrowed,
+        )?;
+
+        let vault_key = vault.key();
+        update_sorted_position(
+            &mut ctx.accounts.sorted_vaults,
+            vault_key,
+            vault,
+            hint,
+            ctx.remaining_accounts,
+        )?;
+
+        emit!(VaultSettledEvent {
+            vault: vault_key,
+            debt,
+            collateral_claimed: claimed,
+            remaining_collateral: vault.bond_amount,
+        });
+
+        Ok(())
+    }
+
+    // Settles a vault's remaining debt against one of its legs at the leg collateral's
+    // frozen price, moving the claimed bonds into that collateral's settlement pool.
+    pub fn settle_vault_leg<'info>(
+        ctx: Context<'_, '_, 'info, 'info, SettleVaultLeg<'info>>,
+        hint: ListHint,
+    ) -> Result<()> {
+        let now = Clock::get()?.unix_timestamp;
+        accrue_borrow_index(
+            &mut ctx.accounts.collateral_config,
+            &ctx.accounts.rate_model,
+            now,
+        )?;
+        let vault = &ctx.accounts.vault;
+        let frozen = &ctx.accounts.settlement_collateral;
+        require!(!frozen.fixed, ErrorCode::CollateralAlreadyFixed);
+        let interest = pending_interest(
+            vault,
+            &ctx.accounts.collateral_config,
+            &ctx.accounts.rate_model,
+            &ctx.accounts.config,
+            now,
+        )?;
+        let debt = vault
+            .borrowed
+            .checked_add(interest)
+            .ok_or(ErrorCode::MathOverflow)?;
+        require!(debt > 0, ErrorCode::NothingToRepay);
+        let leg_before = ctx.accounts.leg.bond_amount;
+        let claimed = debt.div_ceil(frozen.unit_value).min(leg_before);
+        let covered = settled_debt(debt, claimed, frozen.unit_value);
+
+        let creator = vault.creator;
+        let collateral_mint = vault.collateral_mint;
+        let bump = [vault.bump];
+        let signer_seeds: &[&[&[u8]]] =
+            &[&[b"vault", creator.as_ref(), collateral_mint.as_ref(), &bump]];
+
+        if claimed > 0 {
+            anchor_spl::token::transfer(
+                CpiContext::new_with_signer(
+                    ctx.accounts.token_program.to_account_info(),
+                    Transfer {
+                        from: ctx.accounts.leg_escrow.to_account_info(),
+                        to: ctx.accounts.settlement_escrow.to_account_info(),
+                        authority: ctx.accounts.vault.to_account_info(),
+                    },
+                    signer_seeds,
+                ),
+                claimed,
+            )?;
+        }
+
+        let leg = &mut ctx.accounts.leg;
+        leg.bond_amount -= claimed;
+        let leg_rating = leg.rating;
+
+        let vault = &mut ctx.accounts.vault;
+        revalue_leg(
+            vault,
+            leg,
+            leg_rating,
+            &ctx.accounts.leg_collateral_config,
+            &ctx.accounts.config,
+        )?;
+        let (collateral_before, debt_before) = (primary_amount(vault), vault.borrowed);
+        vault.bond_amount -= claimed;
+        vault.leg_amount -= claimed;
+        vault.borrowed = remaining_settlement_debt(vault, debt, covered);
+        vault.unpaid_interest = 0;
+        checkpoint_debt(vault, &ctx.accounts.collateral_config, now);
+        track_vault(
+            &mut ctx.accounts.global_state,
+            &mut ctx.accounts.collateral_config,
+            &ctx.accounts.config,
+            collateral_before,
+            debt_before,
+            vault,
+        )?;
+        track_issuer_debt(
+            &mut ctx.accounts.issuer_exposure,
+            debt_before,
+            vault.borrowed,
+        )?;
+
+        let vault_key = vault.key();
+        update_sorted_position(
+            &mut ctx.accounts.sorted_vaults,
+            vault_key,
+            vault,
+            hint,
+            ctx.remaining_accounts,
+        )?;
+
+        emit!(VaultSettledEvent {
+            vault: vault_key,
+            debt,
+            collateral_claimed: claimed,
+            remaining_collateral: vault.bond_amount,
+        });
+
+        Ok(())
+    }
+
+    pub fn reclaim_collateral<'info>(
+        ctx: Context<'_, '_, 'info, 'info, ReclaimCollateral<'info>>,
+        hint: ListHint,
+    ) -> Result<()> {
+        sync_position_owner(&mut ctx.accounts.vault, ctx.accounts.owner.key());
+        let vault = &ctx.accounts.vault;
+        require!(vault.borrowed == 0, ErrorCode::OutstandingDebt);
+        require!(vault.leg_amount == 0, ErrorCode::LegsOutstanding);
+        let bond_amount = vault.bond_amount;
+        require!(bond_amount > 0, ErrorCode::NotEnoughCollateral);
+
+        let creator = vault.creator;
+        let collateral_mint = vault.collateral_mint;
+        let bump = [vault.bump];
+        let signer_seeds: &[&[&[u8]]] =
+            &[&[b"vault", creator.as_ref(), collateral_mint.as_ref(), &bump]];
+
+        anchor_spl::token::burn(
+            CpiContext::new(
+                ctx.accounts.token_program.to_account_info(),
+                Burn {
+                    mint: ctx.accounts.receipt_mint.to_account_info(),
+                    from: ctx.accounts.owner_receipt_account.to_account_info(),
+                    authority: ctx.accounts.owner.to_account_info(),
+                },
+            ),
+            bond_amount,
+        )?;
+
+        anchor_spl::token::transfer(
+            CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                Transfer {
+                    from: ctx.accounts.escrow.to_account_info(),
+                    to: ctx.accounts.owner_bond_account.to_account_info(),
+                    authority: ctx.accounts.vault.to_account_info(),
+                },
+                signer_seeds,
+            ),
+            bond_amount,
+        )?;
+
+        let vault = &mut ctx.accounts.vault;
+        let (collateral_before, debt_before) = (primary_amount(vault), vault.borrowed);
+        vault.bond_amount = 0;
+        track_vault(
+            &mut ctx.accounts.global_state,
+            &mut ctx.accounts.collateral_config,
+            &ctx.accounts.config,
+            collateral_before,
+            debt_before,
+            vault,
+        )?;
+
+        let vault_key = vault.key();
+        update_sorted_position(
+            &mut ctx.accounts.sorted_vaults,
+            vault_key,
+            vault,
+            hint,
+            ctx.remaining_accounts,
+        )?;
+
+        emit!(CollateralReclaimedEvent {
+            vault: vault_key,
+            owner: vault.owner,
+            bond_amount,
+        });
+
+        Ok(())
+    }
+
+    // Supply is snapshotted once here so every fix divides by the same amount. Stablecoin in
+    // the reserve and insurance fund can't be redeemed and stays out of the snapshot, and the
+    // PSM reserve is paid out pro-rata alongside the collateral. Every registered collateral
+    // must have been frozen, and remaining accounts are (settlement collateral, stablecoin
+    // mint) pairs covering all of them in ascending key order.
+    pub fn finalize_settlement<'info>(
+        ctx: Context<'_, '_, 'info, 'info, FinalizeSettlement<'info>>,
+    ) -> Result<()> {
+        let now = Clock::get()?.unix_timestamp;
+        let settlement = &mut ctx.accounts.settlement;
+        require!(!settlement.finalized, ErrorCode::SettlementFinalized);
+        require!(
+            now >= settlement.triggered_at + ctx.accounts.config.settlement_wait_secs,
+            ErrorCode::SettlementPending
+        );
+        require!(
+            settlement.collateral_count == ctx.accounts.config.collateral_config_count,
+            ErrorCode::CollateralNotFrozen
+        );
+        require!(
+            ctx.remaining_accounts.len() == 2 * settlement.collateral_count as usize,
+            ErrorCode::InvalidRemainingAccounts
+        );
+        settlement.finalized = true;
+        settlement.supply = ctx
+            .accounts
+            .stablecoin_mint
+            .supply
+            .checked_sub(ctx.accounts.reserve.amount)
+            .and_then(|supply| supply.checked_sub(ctx.accounts.insurance_fund.amount))
+            .ok_or(ErrorCode::MathOverflow)?;
+        settlement.psm_reserve = ctx
+            .accounts
+            .psm_reserve
+            .amount
+            .min(ctx.accounts.psm_state.psm_supply);
+
+        let stablecoin_mint = ctx.accounts.stablecoin_mint.key();
+        let mut previous = Pubkey::default();
+        for accounts in ctx.remaining_accounts.chunks(2) {
+            let mut frozen = Account::<SettlementCollateral>::try_from(&accounts[0])?;
+            require!(frozen.key() > previous, ErrorCode::InvalidRemainingAccounts);
+            previous = frozen.key();
+            let mint = InterfaceAccount::<Mint>::try_from(&accounts[1])?;
+            require!(
+                mint.key() == frozen.stablecoin_mint,
+                ErrorCode::InvalidRemainingAccounts
+            );
+            frozen.supply = if mint.key() == stablecoin_mint {
+                settlement.supply
+            } else {
+                mint.supply
+            };
+            frozen.exit(&crate::ID)?;
+        }
+
+        emit!(SettlementFinalizedEvent {
+            finalized_at: now,
+            collateral_count: settlement.collateral_count,
+        });
+
+        Ok(())
+    }
+
+    // Fixes how much of a collateral each stablecoin unit of its market redeems for. Legs
+    // settle into other collaterals' pools, so nothing is fixed until every vault is settled.
+    pub fn fix_collateral(ctx: Context<FixCollateral>) -> Result<()> {
+        let settlement = &mut ctx.accounts.settlement;
+        require!(settlement.finalized, ErrorCode::SettlementPending);
+        require!(
+            ctx.accounts.global_state.total_debt == 0,
+            ErrorCode::VaultsUnsettled
+        );
+        let frozen = &mut ctx.accounts.settlement_collateral;
+        require!(!frozen.fixed, ErrorCode::CollateralAlreadyFixed);
+        let escrowed = ctx.accounts.settlement_escrow.amount;
+        let supply = frozen.supply;
+        frozen.fix = if supply == 0 {
+            0
+        } else {
+            (escrowed as u128) * P_SCALE / supply as u128
+        };
+        frozen.fixed = true;
+        settlement.fixed_count += 1;
+
+        emit!(CollateralFixedEvent {
+            mint: frozen.mint,
+            escrowed,
+            supply,
+            fix: frozen.fix,
+        });
+
+        Ok(())
+    }
+
+    // Remaining accounts are (settlement collateral, settlement escrow, redeemer bond
+    // account) triples covering every frozen collateral in ascending key order.
+    pub fn settlement_redeem<'info>(
+        ctx: Context<'_, '_, 'info, 'info, SettlementRedeem<'info>>,
+        amount: u64,
+    ) -> Result<()> {
+        require!(
+            ctx.accounts.deny_entry.data_is_empty(),
+            ErrorCode::AccountDenied
+        );
+        let settlement = &ctx.accounts.settlement;
+        require!(amount > 0, ErrorCode::InvalidAmount);
+        require!(
+            settlement.finalized && settlement.fixed_count == settlement.collateral_count,
+            ErrorCode::CollateralNotFixed
+        );
+        require!(
+            ctx.remaining_accounts.len() == 3 * settlement.collateral_count as usize,
+            ErrorCode::InvalidRemainingAccounts
+        );
+
+        let stablecoin_mint = ctx.accounts.stablecoin_mint.key();
+        let psm_backed = stablecoin_mint == ctx.accounts.config.stablecoin_mint;
+        let usdc_paid = if psm_backed && settlement.supply > 0 {
+            let share = (amount as u128) * (settlement.psm_reserve as u128);
+            (share / settlement.supply as u128) as u64
+        } else {
+            0
+        };
+
+        token_interface::burn(
+            CpiContext::new(
+                ctx.accounts.stablecoin_token_program.to_account_info(),
+                token_interface::Burn {
+                    mint: ctx.accounts.stablecoin_mint.to_account_info(),
+                    from: ctx.accounts.redeemer_stablecoin_account.to_account_info(),
+                    authority: ctx.accounts.redeemer.to_account_info(),
+                },
+            ),
+            amount,
+        )?;
+        let from_psm = usdc_paid.min(ctx.accounts.supply_ledger.psm);
+        record_burn(&mut ctx.accounts.supply_ledger, SupplySource::Psm, from_psm)?;
+        record_burn(
+            &mut ctx.accounts.supply_ledger,
+            SupplySource::CdpDebt,
+            amount - from_psm,
+        )?;
+
+        if usdc_paid > 0 {
+            let bump = [ctx.accounts.psm_state.bump];
+            let signer_seeds: &[&[&[u8]]] = &[&[b"psm", &bump]];
+            anchor_spl::token::transfer(
+                CpiContext::new_with_signer(
+                    ctx.accounts.token_program.to_account_info(),
+                    Transfer {
+                        from: ctx.accounts.psm_reserve.to_account_info(),
+                        to: ctx.accounts.redeemer_usdc_account.to_account_info(),
+                        authority: ctx.accounts.psm_state.to_account_info(),
+                    },
+                    signer_seeds,
+                ),
+                usdc_paid,
+            )?;
+            let psm = &mut ctx.accounts.psm_state;
+            psm.psm_supply = psm
+                .psm_supply
+                .checked_sub(usdc_paid)
+                .ok_or(ErrorCode::MathOverflow)?;
+
+            emit!(SettlementRedeemedEvent {
+                redeemer: ctx.accounts.redeemer.key(),
+                collateral_mint: psm.usdc_mint,
+                amount,
+                collateral_paid: usdc_paid,
+            });
+        }
+
+        let bump = [settlement.bump];
+        let signer_seeds: &[&[&[u8]]] = &[&[b"settlement", &bump]];
+        let mut previous = Pubkey::default();
+        for accounts in ctx.remaining_accounts.chunks(3) {
+            let frozen_info = &accounts[0];
+            let escrow_info = &accounts[1];
+            let redeemer_bond_info = &accounts[2];
+
+            let frozen = Account::<SettlementCollateral>::try_from(frozen_info)?;
+            require!(frozen.key() > previous, ErrorCode::InvalidRemainingAccounts);
+            previous = frozen.key();
+            let escrow = InterfaceAccount::<TokenAccount>::try_from(escrow_info)?;
+            require!(
+                escrow.owner == settlement.key() && escrow.mint == frozen.mint,
+                ErrorCode::InvalidEscrow
+            );
+            if frozen.stablecoin_mint != stablecoin_mint {
+                continue;
+            }
+            let payout = ((amount as u128) * frozen.fix / P_SCALE) as u64;
+            if payout == 0 {
+                continue;
+            }
+
+            anchor_spl::token::transfer(
+                CpiContext::new_with_signer(
+                    ctx.accounts.token_program.to_account_info(),
+                    Transfer {
+                        from: escrow_info.clone(),
+                        to: redeemer_bond_info.clone(),
+                        authority: ctx.accounts.settlement.to_account_info(),
+                    },
+                    signer_seeds,
+                ),
+                payout,
+            )?;
+
+            emit!(SettlementRedeemedEvent {
+                redeemer: ctx.accounts.redeemer.key(),
+                collateral_mint: frozen.mint,
+                amount,
+                collateral_paid: payout,
+            });
+        }
+
+        Ok(())
+    }
+
+    // Protocol-owned liquidity: treasury stablecoin and USDC held by the pol PDA are
+    // deployed into the designated pool through instruction data built off-chain, with
+    // the outcome bounded by the balances the PDA holds before and after the call.
+    pub fn pol_deposit<'info>(
+        ctx: Context<'_, '_, 'info, 'info, ManagePol<'info>>,
+        stablecoin_amount: u64,
+        usdc_amount: u64,
+        min_lp_out: u64,
+        amm_data: Vec<u8>,
+        hook_accounts: u8,
+    ) -> Result<()> {
+        require_not_paused(&ctx.accounts.config, PAUSE_POL)?;
+        let (remaining, hook_accounts) =
+            split_hook_accounts(ctx.remaining_accounts, hook_accounts)?;
+        require!(
+            stablecoin_amount > 0 || usdc_amount > 0,
+            ErrorCode::InvalidAmount
+        );
+        require!(
+            ctx.accounts.pol_usdc.amount >= usdc_amount,
+            ErrorCode::InvalidAmount
+        );
+
+        if stablecoin_amount > 0 {
+            let bump = [ctx.accounts.global_state.bump];
+            let signer_seeds: &[&[&[u8]]] = &[&[b"global_state", &bump]];
+            transfer_stablecoin(
+                ctx.accounts.stablecoin_token_program.to_account_info(),
+                TransferChecked {
+                    from: ctx.accounts.treasury.to_account_info(),
+                    mint: ctx.accounts.stablecoin_mint.to_account_info(),
+                    to: ctx.accounts.pol_stablecoin.to_account_info(),
+                    authority: ctx.accounts.global_state.to_account_info(),
+                },
+                hook_accounts,
+                stablecoin_amount,
+                ctx.accounts.stablecoin_mint.decimals,
+                signer_seeds,
+            )?;
+            ctx.accounts.pol_stablecoin.reload()?;
+        }
+
+        let (stablecoin_before, usdc_before, lp_before) = pol_balances(ctx.accounts);
+        invoke_pol(ctx.accounts, remaining, amm_data)?;
+        reload_pol_balances(ctx.accounts)?;
+        let (stablecoin_after, usdc_after, lp_after) = pol_balances(ctx.accounts);
+
+        let stablecoin_spent = stablecoin_before.saturating_sub(stablecoin_after);
+        let usdc_spent = usdc_before.saturating_sub(usdc_after);
+        let lp_minted = lp_after.saturating_sub(lp_before);
+        require!(
+            stablecoin_spent <= stablecoin_amount && usdc_spent <= usdc_amount,
+            ErrorCode::SlippageExceeded
+        );
+        require!(lp_minted >= min_lp_out, ErrorCode::SlippageExceeded);
+
+        // Whatever the pool did not take goes straight back to the treasury.
+        let unspent = stablecoin_amount - stablecoin_spent;
+        return_pol_stablecoin(ctx.accounts, hook_accounts, unspent)?;
+
+        let pol = &mut ctx.accounts.pol;
+        pol.stablecoin_deployed = pol
+            .stablecoin_deployed
+            .checked_add(stablecoin_spent)
+            .ok_or(ErrorCode::MathOverflow)?;
+        pol.usdc_deployed = pol
+            .usdc_deployed
+            .checked_add(usdc_spent)
+            .ok_or(ErrorCode::MathOverflow)?;
+
+        emit!(PolDepositEvent {
+            pool: pol.pool,
+            stablecoin_amount: stablecoin_spent,
+            usdc_amount: usdc_spent,
+            lp_minted,
+        });
+
+        Ok(())
+    }
+
+    pub fn pol_withdraw<'info>(
+        ctx: Context<'_, '_, 'info, 'info, ManagePol<'info>>,
+        lp_amount: u64,
+        min_stablecoin_out: u64,
+        min_usdc_out: u64,
+        amm_data: Vec<u8>,
+        hook_accounts: u8,
+    ) -> Result<()> {
+        require_not_paused(&ctx.accounts.config, PAUSE_POL)?;
+        let (remaining, hook_accounts) =
+            split_hook_accounts(ctx.remaining_accounts, hook_accounts)?;
+        require!(lp_amount > 0, ErrorCode::InvalidAmount);
+
+        let (stablecoin_before, usdc_before, lp_before) = pol_balances(ctx.accounts);
+        invoke_pol(ctx.accounts, remaining, amm_data)?;
+        reload_pol_balances(ctx.accounts)?;
+        let (stablecoin_after, usdc_after, lp_after) = pol_balances(ctx.accounts);
+
+        let lp_burned = lp_before.saturating_sub(lp_after);
+        let stablecoin_out = stablecoin_after.saturating_sub(stablecoin_before);
+        let usdc_out = usdc_after.saturating_sub(usdc_before);
+        require!(lp_burned <= lp_amount, ErrorCode::SlippageExceeded);
+        require!(
+            stablecoin_out >= min_stablecoin_out && usdc_out >= min_usdc_out,
+            ErrorCode::SlippageExceeded
+        );
+
+        return_pol_stablecoin(ctx.accounts, hook_accounts, stablecoin_out)?;
+
+        let pol = &mut ctx.accounts.pol;
+        pol.stablecoin_deployed = pol.stablecoin_deployed.saturating_sub(stablecoin_out);
+        pol.usdc_deployed = pol.usdc_deployed.saturating_sub(usdc_out);
+
+        emit!(PolWithdrawEvent {
+            pool: pol.pool,
+            lp_burned,
+            stablecoin_amount: stablecoin_out,
+            usdc_amount: usdc_out,
+        });
+
+        Ok(())
+    }
+
+    // Harvesting must leave the LP position untouched; stablecoin fees go to the treasury
+    // and USDC fees stay with the pol PDA for the next deposit.
+    pub fn pol_harvest<'info>(
+        ctx: Context<'_, '_, 'info, 'info, ManagePol<'info>>,
+        amm_data: Vec<u8>,
+        hook_accounts: u8,
+    ) -> Result<()> {
+        require_not_paused(&ctx.accounts.config, PAUSE_POL)?;
+        let (remaining, hook_accounts) =
+            split_hook_accounts(ctx.remaining_accounts, hook_accounts)?;
+        let (stablecoin_before, usdc_before, lp_before) = pol_balances(ctx.accounts);
+        invoke_pol(ctx.accounts, remaining, amm_data)?;
+        reload_pol_balances(ctx.accounts)?;
+        let (stablecoin_after, usdc_after, lp_after) = pol_balances(ctx.accounts);
+
+        require!(lp_after == lp_before, ErrorCode::SlippageExceeded);
+        require!(
+            stablecoin_after >= stablecoin_before && usdc_after >= usdc_before,
+            ErrorCode::SlippageExceeded
+        );
+        let stablecoin_harvested = stablecoin_after - stablecoin_before;
+        let usdc_harvested = usdc_after - usdc_before;
+
+        return_pol_stablecoin(ctx.accounts, hook_accounts, stablecoin_harvested)?;
+
+        emit!(PolHarvestEvent {
+            pool: ctx.accounts.pol.pool,
+            stablecoin_amount: stablecoin_harvested,
+            usdc_amount: usdc_harvested,
+        });
+
+        Ok(())
+    }
+
+    pub fn claim_fees(ctx: Context<ClaimFees>, source: FeeSource, amount: u64) -> Result<()> {
+        require!(amount > 0, ErrorCode::InvalidAmount);
+        require!(
+            ctx.accounts.settlement.data_is_empty(),
+            ErrorCode::SettlementTriggered
+        );
+        let from = match source {
+            FeeSource::Treasury => &ctx.accounts.treasury,
+            FeeSource::Reserve => &ctx.accounts.reserve,
+        };
+        require!(from.amount >= amount, ErrorCode::InsufficientFees);
+
+        let bump = [ctx.accounts.global_state.bump];
+        let signer_seeds: &[&[&[u8]]] = &[&[b"global_state", &bump]];
+
+        transfer_stablecoin(
+            ctx.accounts.stablecoin_token_program.to_account_info(),
+            TransferChecked {
+                from: from.to_account_info(),
+                mint: ctx.accounts.stablecoin_mint.to_account_info(),
+                to: ctx.accounts.admin_stablecoin_account.to_account_info(),
+                authority: ctx.accounts.global_state.to_account_info(),
+            },
+            ctx.remaining_accounts,
+            amount,
+            ctx.accounts.stablecoin_mint.decimals,
+            signer_seeds,
+        )?;
+
+        let config = &mut ctx.accounts.config;
+        config.total_fees_claimed = config
+            .total_fees_claimed
+            .checked_add(amount)
+            .ok_or(ErrorCode::MathOverflow)?;
+
+        emit!(FeesClaimedEvent {
+            admin: config.admin,
+            source,
+            amount,
+            total_fees_claimed: config.total_fees_claimed,
+        });
+
+        Ok(())
+    }
+
+    // Anything the reserve holds above its target buffer is paid out to stakers. Anyone can
+    // crank it once the excess reaches the configured minimum.
+    pub fn distribute_surplus(ctx: Context<DistributeSurplus>) -> Result<()> {
+        require!(
+            ctx.accounts.settlement.data_is_empty(),
+            ErrorCode::SettlementTriggered
+        );
+        let config = &ctx.accounts.config;
+        let excess = ctx
+            .accounts
+            .reserve
+            .amount
+            .saturating_sub(config.reserve_target);
+        require!(
+            excess > 0 && excess >= config.min_surplus_distribution,
+            ErrorCode::SurplusBelowThreshold
+        );
+
+        let bump = [ctx.accounts.global_state.bump];
+        let signer_seeds: &[&[&[u8]]] = &[&[b"global_state", &bump]];
+
+        transfer_stablecoin(
+            ctx.accounts.stablecoin_token_program.to_account_info(),
+            TransferChecked {
+                from: ctx.accounts.reserve.to_account_info(),
+                mint: ctx.accounts.stablecoin_mint.to_account_info(),
+                to: ctx.accounts.staking_reward_vault.to_account_info(),
+                authority: ctx.accounts.global_state.to_account_info(),
+            },
+            ctx.remaining_accounts,
+            excess,
+            ctx.accounts.stablecoin_mint.decimals,
+            signer_seeds,
+        )?;
+
+        emit!(SurplusDistributedEvent {
+            amount: excess,
+            reserve_balance: ctx.accounts.reserve.amount - excess,
+            reserve_target: config.reserve_target,
+        });
+
+        Ok(())
+    }
+
+    pub fn claim_surplus(ctx: Context<ClaimSurplus>) -> Result<()> {
+        sync_position_owner(&mut ctx.accounts.vault, ctx.accounts.owner.key());
+        let amount = ctx.accounts.vault.surplus;
+        require!(amount > 0, ErrorCode::NoSurplus);
+
+        let bump = [ctx.accounts.global_state.bump];
+        let signer_seeds: &[&[&[u8]]] = &[&[b"global_state", &bump]];
+
+        transfer_stablecoin(
+            ctx.accounts.stablecoin_token_program.to_account_info(),
+            TransferChecked {
+                from: ctx.accounts.surplus_vault.to_account_info(),
+                mint: ctx.accounts.stablecoin_mint.to_account_info(),
+                to: ctx.accounts.owner_stablecoin_account.to_account_info(),
+                authority: ctx.accounts.global_state.to_account_info(),
+            },
+            ctx.remaining_accounts,
+            amount,
+            ctx.accounts.stablecoin_mint.decimals,
+            signer_seeds,
+        )?;
+
+        let vault = &mut ctx.accounts.vault;
+        vault.surplus = 0;
+
+        emit!(ClaimSurplusEvent {
+            vault: vault.key(),
+            owner: vault.owner,
+            amount,
+        });
+
+        Ok(())
+    }
+
+    pub fn initialize_stability_pool(ctx: Context<InitializeStabilityPool>) -> Result<()> {
+        let pool = &mut ctx.accounts.pool;
+        pool.collateral_mint = ctx.accounts.collateral_mint.key();
+        pool.total_deposits = 0;
+        pool.product_p = P_SCALE;
+        pool.sum_s = 0;
+        pool.current_epoch = 0;
+        pool.current_scale = 0;
+        pool.scale_sums = Vec::new();
+        pool.bump = ctx.bumps.pool;
+        Ok(())
+    }
+
+    pub fn provide_to_stability_pool(ctx: Context<ProvideToStabilityPool>, amount: u64) -> Result<()> {
+        require!(amount > 0, ErrorCode::InvalidAmount);
+
+        let deposit = &ctx.accounts.deposit;
+        let pool = &ctx.accounts.pool;
+        let compounded = compounded_deposit(pool, deposit)?;
+        let collateral_gain = stability_collateral_gain(pool, deposit)?;
+
+        let collateral_mint = pool.collateral_mint;
+        let bump = [pool.bump];
+        let signer_seeds: &[&[&[u8]]] = &[&[b"stability_pool", collateral_mint.as_ref(), &bump]];
+
+        if collateral_gain > 0 {
+            anchor_spl::token::transfer(
+                CpiContext::new_with_signer(
+                    ctx.accounts.token_program.to_account_info(),
+                    Transfer {
+                        from: ctx.accounts.pool_collateral.to_account_info(),
+                        to: ctx.accounts.depositor_bond_account.to_account_info(),
+                        authority: ctx.accounts.pool.to_account_info(),
+                    },
+                    signer_seeds,
+                ),
+                collateral_gain,
+            )?;
+        }
+
+        transfer_stablecoin(
+            ctx.accounts.stablecoin_token_program.to_account_info(),
+            TransferChecked {
+                from: ctx.accounts.depositor_stablecoin_account.to_account_info(),
+                mint: ctx.accounts.stablecoin_mint.to_account_info(),
+                to: ctx.accounts.pool_stablecoin.to_account_info(),
+                authority: ctx.accounts.depositor.to_account_info(),
+            },
+            ctx.remaining_accounts,
+            amount,
+            ctx.accounts.stablecoin_mint.decimals,
+            &[],
+        )?;
+
+        let pool = &mut ctx.accounts.pool;
+        pool.total_deposits = pool
+            .total_deposits
+            .checked_add(amount)
+            .ok_or(ErrorCode::MathOverflow)?;
+
+        let deposit = &mut ctx.accounts.deposit;
+        deposit.owner = ctx.accounts.depositor.key();
+        deposit.pool = pool.key();
+        deposit.initial_deposit = compounded
+            .checked_add(amount)
+            .ok_or(ErrorCode::MathOverflow)?;
+        deposit.snapshot_p = pool.product_p;
+        deposit.snapshot_s = pool.sum_s;
+        deposit.snapshot_epoch = pool.current_epoch;
+        deposit.snapshot_scale = pool.current_scale;
+        deposit.bump = ctx.bumps.deposit;
+
+        emit!(StabilityDepositEvent {
+            pool: pool.key(),
+            depositor: deposit.owner,
+            amount,
+            collateral_gain,
+            deposit: deposit.initial_deposit,
+        });
+
+        Ok(())
+    }
+
+    pub fn withdraw_from_stability_pool(
+        ctx: Context<WithdrawFromStabilityPool>,
+        amount: u64,
+    ) -> Result<()> {
+        let deposit = &ctx.accounts.deposit;
+        let pool = &ctx.accounts.pool;
+        let compounded = compounded_deposit(pool, deposit)?;
+        let collateral_gain = stability_collateral_gain(pool, deposit)?;
+        require!(amount <= compounded, ErrorCode::InsufficientStabilityDeposit);
+
+        let collateral_mint = pool.collateral_mint;
+        let bump = [pool.bump];
+        let signer_seeds: &[&[&[u8]]] = &[&[b"stability_pool", collateral_mint.as_ref(), &bump]];
+
+        if collateral_gain > 0 {
+            anchor_spl::token::transfer(
+                CpiContext::new_with_signer(
+                    ctx.accounts.token_program.to_account_info(),
+                    Transfer {
+                        from: ctx.accounts.pool_collateral.to_account_info(),
+                        to: ctx.accounts.depositor_bond_account.to_account_info(),
+                        authority: ctx.accounts.pool.to_account_info(),
+                    },
+                    signer_seeds,
+                ),
+                collateral_gain,
+            )?;
+        }
+
+        if amount > 0 {
+            transfer_stablecoin(
+                ctx.accounts.stablecoin_token_program.to_account_info(),
+                TransferChecked {
+                    from: ctx.accounts.pool_stablecoin.to_account_info(),
+                    mint: ctx.accounts.stablecoin_mint.to_account_info(),
+                    to: ctx.accounts.depositor_stablecoin_account.to_account_info(),
+                    authority: ctx.accounts.pool.to_account_info(),
+                },
+                ctx.remaining_accounts,
+                amount,
+                ctx.accounts.stablecoin_mint.decimals,
+                signer_seeds,
+            )?;
+        }
+
+        let pool = &mut ctx.accounts.pool;
+        pool.total_deposits = pool.total_deposits.saturating_sub(amount);
+
+        let deposit = &mut ctx.accounts.deposit;
+        deposit.initial_deposit = compounded - amount;
+        deposit.snapshot_p = pool.product_p;
+        deposit.snapshot_s = pool.sum_s;
+        deposit.snapshot_epoch = pool.current_epoch;
+        deposit.snapshot_scale = pool.current_scale;
+
+        emit!(StabilityWithdrawEvent {
+            pool: pool.key(),
+            depositor: deposit.owner,
+            amount,
+            collateral_gain,
+            deposit: deposit.initial_deposit,
+        });
+
+        Ok(())
+    }
+
+    pub fn liquidate_via_stability_pool<'info>(
+        ctx: Context<'_, '_, 'info, 'info, LiquidateViaStabilityPool<'info>>,
+        hint: ListHint,
+        hook_accounts: u8,
+    ) -> Result<()> {
+        let (remaining, hook_accounts) =
+            split_hook_accounts(ctx.remaining_accounts, hook_accounts)?;
+        require_not_paused(&ctx.accounts.config, PAUSE_LIQUIDATION)?;
+        load_cached_price(
+            &mut ctx.accounts.collateral_config,
+            &ctx.accounts.price_cache,
+            true,
+        )?;
+        let vault = &ctx.accounts.vault;
+        accrue_borrow_index(
+            &mut ctx.accounts.collateral_config,
+            &ctx.accounts.rate_model,
+            Clock::get()?.unix_timestamp,
+        )?;
+        let config = &ctx.accounts.config;
+        let rate_model = &ctx.accounts.rate_model;
+        let collateral = &ctx.accounts.collateral_config;
+        let recovery = is_recovery_mode(&ctx.accounts.global_state, config)?;
+        let pool = &ctx.accounts.pool;
+
+        let now = Clock::get()?.unix_timestamp;
+        let interest = pending_interest(vault, collateral, rate_model, config, now)?;
+        let total_debt = vault
+            .borrowed
+            .checked_add(interest)
+            .ok_or(ErrorCode::MathOverflow)?;
+        require!(total_debt > 0, ErrorCode::NothingToLiquidate);
+        require!(
+            is_past_due(vault, now)
+                || is_liquidatable(
+                    &vault_values(vault, collateral, config)?,
+                    total_debt,
+                    config,
+                    recovery
+                )?,
+            ErrorCode::VaultHealthy
+        );
+        require_grace_elapsed(vault, config, now)?;
+        require!(pool.total_deposits > 0, ErrorCode::StabilityPoolEmpty);
+        let debt_offset = total_debt.min(pool.total_deposits);
+
+        let seize_value = (debt_offset as u128)
+            * (10000 + collateral.liquidation_penalty_bps as u128)
+            / 10000;
+        let seize_amount = (seize_value / collateral.unit_value as u128)
+            .min(primary_amount(vault) as u128) as u64;
+        require!(
+            seize_amount > 0 || debt_offset == total_debt,
+            ErrorCode::LiquidationTooSmall
+        );
+
+        let collateral_mint = pool.collateral_mint;
+        let pool_bump = [pool.bump];
+        let pool_seeds: &[&[&[u8]]] =
+            &[&[b"stability_pool", collateral_mint.as_ref(), &pool_bump]];
+
+        token_interface::burn(
+            CpiContext::new_with_signer(
+                ctx.accounts.stablecoin_token_program.to_account_info(),
+                token_interface::Burn {
+                    mint: ctx.accounts.stablecoin_mint.to_account_info(),
+                    from: ctx.accounts.pool_stablecoin.to_account_info(),
+                    authority: ctx.accounts.pool.to_account_info(),
+                },
+                pool_seeds,
+            ),
+            debt_offset,
+        )?;
+
+        let creator = vault.creator;
+        let vault_bump = [vault.bump];
+        let vault_seeds: &[&[&[u8]]] =
+            &[&[b"vault", creator.as_ref(), collateral_mint.as_ref(), &vault_bump]];
+
+        anchor_spl::token::transfer(
+            CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                Transfer {
+                    from: ctx.accounts.escrow.to_account_info(),
+                    to: ctx.accounts.pool_collateral.to_account_info(),
+                    authority: ctx.accounts.vault.to_account_info(),
+                },
+                vault_seeds,
+            ),
+            seize_amount,
+        )?;
+
+        offset_stability_pool(&mut ctx.accounts.pool, debt_offset, seize_amount)?;
+
+        let vault = &mut ctx.accounts.vault;
+        let (collateral_before, debt_before) = (primary_amount(vault), vault.borrowed);
+        vault.bond_amount -= seize_amount;
+        vault.borrowed = total_debt - debt_offset;
+        settle_repayment(
+            vault,
+            RepaymentAccounts {
+                global_state: &mut ctx.accounts.global_state,
+                collateral_config: &mut ctx.accounts.collateral_config,
+                issuer_exposure: &mut ctx.accounts.issuer_exposure,
+                sorted_vaults: &mut ctx.accounts.sorted_vaults,
+                config: &ctx.accounts.config,
+                rate_model: &ctx.accounts.rate_model,
+            },
+            Some(InterestMint {
+                ledger: &mut ctx.accounts.supply_ledger,
+                token_program: &ctx.accounts.stablecoin_token_program,
+                stablecoin_mint: &ctx.accounts.stablecoin_mint,
+                mint_authority: &ctx.accounts.mint_authority,
+                recipients: [
+                    &ctx.accounts.staking_reward_vault,
+                    &ctx.accounts.treasury,
+                    &ctx.accounts.reserve,
+                    &ctx.accounts.insurance_fund,
+                ],
+            }),
+            Repayment {
+                collateral_before,
+                debt_before,
+                interest,
+                repaid: debt_offset,
+                now,
+            },
+            hint,
+            remaining,
+        )?;
+
+        pay_keeper_bounty(
+            &ctx.accounts.stablecoin_token_program,
+            &ctx.accounts.stablecoin_mint,
+            &ctx.accounts.treasury,
+            &ctx.accounts.keeper_stablecoin_account,
+            &ctx.accounts.global_state,
+            hook_accounts,
+            ctx.accounts.config.keeper_bounty,
+        )?;
+
+        emit!(StabilityPoolOffsetEvent {
+            pool: pool.key(),
+            vault: vault.key(),
+            debt_offset,
+            collateral_seized: seize_amount,
+            remaining_debt: vault.borrowed,
+        });
+
+        Ok(())
+    }
+
+    pub fn redeem<'info>(
+        ctx: Context<'_, '_, 'info, 'info, Redeem<'info>>,
+        amount: u64,
+        hints: Vec<ListHint>,
+        list_nodes: u8,
+        hook_accounts: u8,
+    ) -> Result<()> {
+        let (remaining, hook_accounts) =
+            split_hook_accounts(ctx.remaining_accounts, hook_accounts)?;
+        require_not_paused(&ctx.accounts.config, PAUSE_REDEMPTION)?;
+        require!(
+            ctx.accounts.deny_entry.data_is_empty(),
+            ErrorCode::AccountDenied
+        );
+        let config = &ctx.accounts.config;
+        let rate_model = &ctx.accounts.rate_model;
+
+        require!(amount > 0, ErrorCode::InvalidAmount);
+        require!(
+            (list_nodes as usize) <= remaining.len(),
+            ErrorCode::InvalidRemainingAccounts
+        );
+        let (vault_accounts, node_accounts) =
+            remaining.split_at(remaining.len() - list_nodes as usize);
+        require!(
+            vault_accounts.len() % 6 == 0 && vault_accounts.len() / 6 == hints.len(),
+            ErrorCode::InvalidRemainingAccounts
+        );
+
+        let now = Clock::get()?.unix_timestamp;
+        let mut left = amount;
+        let mut total_redeemed: u64 = 0;
+        let mut total_interest: u64 = 0;
+        let mut expected = ctx.accounts.sorted_vaults.head;
+        let mut last_cr: u128 = 0;
+
+        for (accounts, hint) in vault_accounts.chunks(6).zip(hints) {
+            let vault_info = &accounts[0];
+            let escrow_info = &accounts[1];
+            let redeemer_bond_info = &accounts[2];
+            let collateral_info = &accounts[3];
+            let exposure_info = &accounts[4];
+            let price_cache_info = &accounts[5];
+
+            let mut vault = Account::<Vault>::try_from(vault_info)?;
+            let (collateral_before, debt_before) = (primary_amount(&vault), vault.borrowed);
+            require!(vault.key() == expected, ErrorCode::RedemptionOrder);
+            expected = vault.next;
+            let escrow = InterfaceAccount::<TokenAccount>::try_from(escrow_info)?;
+            require!(
+                escrow.owner == vault.key() && escrow.mint == vault.collateral_mint,
+                ErrorCode::InvalidEscrow
+            );
+            let mut collateral_config = Account::<CollateralConfig>::try_from(collateral_info)?;
+            require!(
+                collateral_config.mint == vault.collateral_mint
+                    && collateral_config.stablecoin_mint == ctx.accounts.stablecoin_mint.key(),
+                ErrorCode::InvalidRemainingAccounts
+            );
+            let mut issuer_exposure = Account::<IssuerExposure>::try_from(exposure_info)?;
+            require!(
+                issuer_exposure.issuer == collateral_config.issuer,
+                ErrorCode::InvalidRemainingAccounts
+            );
+            // Redemptions pay out collateral at its price, so they need a fresh one.
+            let price_cache = Account::<PriceCache>::try_from(price_cache_info)?;
+            require!(
+                price_cache.mint == vault.collateral_mint,
+                ErrorCode::InvalidRemainingAccounts
+            );
+            load_cached_price(&mut collateral_config, &price_cache, false)?;
+            accrue_borrow_index(&mut collateral_config, rate_model, now)?;
+            let collateral = &collateral_config;
+
+            let interest = pending_interest(&vault, collateral, rate_model, config, now)?;
+            let vault_debt = vault
+                .borrowed
+                .checked_add(interest)
+                .ok_or(ErrorCode::MathOverflow)?;
+            last_cr = check_redemption_order(&vault, vault_debt, last_cr)?;
+
+            let unit_value = collateral.unit_value;
+            let units = (left / unit_value)
+                .min(vault_debt / unit_value)
+                .min(primary_amount(&vault));
+            let redeemed = units * unit_value;
+            let remaining_debt = vault_debt - redeemed;
+            if units == 0 || (remaining_debt != 0 && remaining_debt < config.min_debt) {
+                continue;
+            }
+
+            let creator = vault.creator;
+            let collateral_mint = vault.collateral_mint;
+            let bump = [vault.bump];
+            let signer_seeds: &[&[&[u8]]] =
+                &[&[b"vault", creator.as_ref(), collateral_mint.as_ref(), &bump]];
+
+            anchor_spl::token::transfer(
+                CpiContext::new_with_signer(
+                    ctx.accounts.token_program.to_account_info(),
+                    Transfer {
+                        from: escrow_info.clone(),
+                        to: redeemer_bond_info.clone(),
+                        authority: vault_info.clone(),
+                    },
+                    signer_seeds,
+                ),
+                units,
+            )?;
+
+            vault.bond_amount -= units;
+            vault.borrowed = remaining_debt;
+            let interest_paid = settle_repayment(
+                &mut vault,
+                RepaymentAccounts {
+                    global_state: &mut ctx.accounts.global_state,
+                    collateral_config: &mut collateral_config,
+                    issuer_exposure: &mut issuer_exposure,
+                    sorted_vaults: &mut ctx.accounts.sorted_vaults,
+                    config: &ctx.accounts.config,
+                    rate_model: &ctx.accounts.rate_model,
+                },
+                None,
+                Repayment {
+                    collateral_before,
+                    debt_before,
+                    interest,
+                    repaid: redeemed,
+                    now,
+                },
+                hint,
+                node_accounts,
+            )?;
+            issuer_exposure.exit(&crate::ID)?;
+            vault.exit(&crate::ID)?;
+            collateral_config.exit(&crate::ID)?;
+
+            emit!(VaultRedeemedEvent {
+                vault: vault.key(),
+                redeemer: ctx.accounts.redeemer.key(),
+                debt_redeemed: redeemed,
+                collateral_redeemed: units,
+            });
+
+            total_interest = total_interest
+                .checked_add(interest_paid)
+                .ok_or(ErrorCode::MathOverflow)?;
+            total_redeemed += redeemed;
+            left -= redeemed;
+            if left == 0 {
+                break;
+            }
+        }
+
+        require!(total_redeemed > 0, ErrorCode::NothingToRedeem);
+
+        let fee_state = &mut ctx.accounts.fee_state;
+        let base_rate = bump_base_rate(
+            fee_state,
+            total_redeemed,
+            ctx.accounts.stablecoin_mint.supply,
+            now,
+        );
+        let fee_rate = (config.redemption_fee_bps as u128 * FEE_PRECISION / 10000 + base_rate)
+            .min(FEE_PRECISION);
+        let fee = ((total_redeemed as u128) * fee_rate / FEE_PRECISION) as u64;
+
+        mint_interest(
+            &mut ctx.accounts.supply_ledger,
+            &ctx.accounts.stablecoin_token_program,
+            &ctx.accounts.stablecoin_mint,
+            &ctx.accounts.mint_authority,
+            [
+                &ctx.accounts.staking_reward_vault,
+                &ctx.accounts.treasury,
+                &ctx.accounts.reserve,
+                &ctx.accounts.insurance_fund,
+            ],
+            &ctx.accounts.config,
+            total_interest,
+        )?;
+
+        token_interface::burn(
+            CpiContext::new(
+                ctx.accounts.stablecoin_token_program.to_account_info(),
+                token_interface::Burn {
+                    mint: ctx.accounts.stablecoin_mint.to_account_info(),
+                    from: ctx.accounts.redeemer_stablecoin_account.to_account_info(),
+                    authority: ctx.accounts.redeemer.to_account_info(),
+                },
+            ),
+            total_redeemed,
+        )?;
+        record_repay_burn(
+            &mut ctx.accounts.supply_ledger,
+            total_redeemed,
+            total_interest,
+        )?;
+
+        transfer_stablecoin(
+            ctx.accounts.stablecoin_token_program.to_account_info(),
+            TransferChecked {
+                from: ctx.accounts.redeemer_stablecoin_account.to_account_info(),
+                mint: ctx.accounts.stablecoin_mint.to_account_info(),
+                to: ctx.accounts.staking_reward_vault.to_account_info(),
+                authority: ctx.accounts.redeemer.to_account_info(),
+            },
+            hook_accounts,
+            fee,
+            ctx.accounts.stablecoin_mint.decimals,
+            &[],
+        )?;
+
+        emit!(RedeemEvent {
+            redeemer: ctx.accounts.redeemer.key(),
+            amount_redeemed: total_redeemed,
+            fee,
+            base_rate: base_rate as u64,
+        });
+
+        Ok(())
+    }
+
+    pub fn initialize_psm(
+        ctx: Context<InitializePsm>,
+        mint_fee_bps: u64,
+        redeem_fee_bps: u64,
+        supply_cap: u64,
+    ) -> Result<()> {
+        require!(
+            ctx.accounts.usdc_mint.decimals == ctx.accounts.stablecoin_mint.decimals,
+            ErrorCode::InvalidParameter
+        );
+        require!(
+            mint_fee_bps <= 10000 && redeem_fee_bps <= 10000,
+            ErrorCode::InvalidParameter
+        );
+        let psm = &mut ctx.accounts.psm_state;
+        psm.usdc_mint = ctx.accounts.usdc_mint.key();
+        psm.mint_fee_bps = mint_fee_bps;
+        psm.redeem_fee_bps = redeem_fee_bps;
+        psm.supply_cap = supply_cap;
+        psm.bump = ctx.bumps.psm_state;
+        Ok(())
+    }
+
+    pub fn set_psm_params(
+        ctx: Context<SetPsmParams>,
+        mint_fee_bps: u64,
+        redeem_fee_bps: u64,
+        supply_cap: u64,
+    ) -> Result<()> {
+        require!(
+            mint_fee_bps <= 10000 && redeem_fee_bps <= 10000,
+            ErrorCode::InvalidParameter
+        );
+        let psm = &mut ctx.accounts.psm_state;
+        psm.mint_fee_bps = mint_fee_bps;
+        psm.redeem_fee_bps = redeem_fee_bps;
+        psm.supply_cap = supply_cap;
+        Ok(())
+    }
+
+    // Every stablecoin the PSM puts into circulation, fees included, is matched by a
+    // USDC unit in its reserve; the mint fee is paid to the treasury in stablecoin.
+    pub fn psm_mint(ctx: Context<PsmMint>, amount: u64) -> Result<()> {
+        require_not_paused(&ctx.accounts.config, PAUSE_PSM)?;
+        require!(
+            ctx.accounts.deny_entry.data_is_empty(),
+            ErrorCode::AccountDenied
+        );
+        require!(amount > 0, ErrorCode::InvalidAmount);
+        require!(
+            ctx.accounts.settlement.data_is_empty(),
+            ErrorCode::SettlementTriggered
+        );
+        let psm = &mut ctx.accounts.psm_state;
+        let psm_supply = psm
+            .psm_supply
+            .checked_add(amount)
+            .ok_or(ErrorCode::MathOverflow)?;
+        require!(psm_supply <= psm.supply_cap, ErrorCode::PsmCapExceeded);
+        let fee = psm_fee(amount, psm.mint_fee_bps);
+        let minted = amount - fee;
+        limit_mint(&ctx.accounts.config, &mut ctx.accounts.rate_limit, amount)?;
+
+        anchor_spl::token::transfer(
+            CpiContext::new(
+                ctx.accounts.token_program.to_account_info(),
+                Transfer {
+                    from: ctx.accounts.user_usdc_account.to_account_info(),
+                    to: ctx.accounts.psm_reserve.to_account_info(),
+                    authority: ctx.accounts.user.to_account_info(),
+                },
+            ),
+            amount,
+        )?;
+
+        mint_stablecoin(
+            ctx.accounts.stablecoin_token_program.to_account_info(),
+            token_interface::MintTo {
+                mint: ctx.accounts.stablecoin_mint.to_account_info(),
+                to: ctx.accounts.user_stablecoin_account.to_account_info(),
+                authority: ctx.accounts.mint_authority.to_account_info(),
+            },
+            ctx.accounts.config.mint_authority_bump,
+            minted,
+        )?;
+        record_mint(&mut ctx.accounts.supply_ledger, SupplySource::Psm, minted)?;
+
+        mint_stablecoin(
+            ctx.accounts.stablecoin_token_program.to_account_info(),
+            token_interface::MintTo {
+                mint: ctx.accounts.stablecoin_mint.to_account_info(),
+                to: ctx.accounts.treasury.to_account_info(),
+                authority: ctx.accounts.mint_authority.to_account_info(),
+            },
+            ctx.accounts.config.mint_authority_bump,
+            fee,
+        )?;
+        record_mint(&mut ctx.accounts.supply_ledger, SupplySource::Psm, fee)?;
+
+        psm.psm_supply = psm_supply;
+
+        emit!(PsmMintEvent {
+            user: ctx.accounts.user.key(),
+            usdc_in: amount,
+            minted,
+            fee,
+            psm_supply,
+        });
+
+        Ok(())
+    }
+
+    // Redemptions burn the stablecoin net of the fee, which goes to the treasury, and pay
+    // out the same amount of USDC. Only supply the PSM itself backs can be redeemed.
+    pub fn psm_redeem(ctx: Context<PsmRedeem>, amount: u64) -> Result<()> {
+        require_not_paused(&ctx.accounts.config, PAUSE_PSM)?;
+        require!(
+            ctx.accounts.deny_entry.data_is_empty(),
+            ErrorCode::AccountDenied
+        );
+        require!(amount > 0, ErrorCode::InvalidAmount);
+        require!(
+            ctx.accounts.settlement.data_is_empty(),
+            ErrorCode::SettlementTriggered
+        );
+        let psm = &ctx.accounts.psm_state;
+        let fee = psm_fee(amount, psm.redeem_fee_bps);
+        let usdc_out = amount - fee;
+        require!(
+            usdc_out <= psm.psm_supply && usdc_out <= ctx.accounts.psm_reserve.amount,
+            ErrorCode::PsmInsufficientReserve
+        );
+
+        transfer_stablecoin(
+            ctx.accounts.stablecoin_token_program.to_account_info(),
+            TransferChecked {
+                from: ctx.accounts.user_stablecoin_account.to_account_info(),
+                mint: ctx.accounts.stablecoin_mint.to_account_info(),
+                to: ctx.accounts.treasury.to_account_info(),
+                authority: ctx.accounts.user.to_account_info(),
+            },
+            ctx.remaining_accounts,
+            fee,
+            ctx.accounts.stablecoin_mint.decimals,
+            &[],
+        )?;
+
+        token_interface::burn(
+            CpiContext::new(
+                ctx.accounts.stablecoin_token_program.to_account_info(),
+                token_interface::Burn {
+                    mint: ctx.accounts.stablecoin_mint.to_account_info(),
+                    from: ctx.accounts.user_stablecoin_account.to_account_info(),
+                    authority: ctx.accounts.user.to_account_info(),
+                },
+            ),
+            usdc_out,
+        )?;
+        record_burn(&mut ctx.accounts.supply_ledger, SupplySource::Psm, usdc_out)?;
+
+        let bump = [psm.bump];
+        let signer_seeds: &[&[&[u8]]] = &[&[b"psm", &bump]];
+        anchor_spl::token::transfer(
+            CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                Transfer {
+                    from: ctx.accounts.psm_reserve.to_account_info(),
+                    to: ctx.accounts.user_usdc_account.to_account_info(),
+                    authority: ctx.accounts.psm_state.to_account_info(),
+                },
+                signer_seeds,
+            ),
+            usdc_out,
+        )?;
+
+        let psm = &mut ctx.accounts.psm_state;
+        psm.psm_supply -= usdc_out;
+
+        emit!(PsmRedeemEvent {
+            user: ctx.accounts.user.key(),
+            amount,
+            usdc_out,
+            fee,
+            psm_supply: psm.psm_supply,
+        });
+
+        Ok(())
+    }
+
+    // Runs the PSM leg and the market leg of a peg trade in one instruction. Above the
+    // peg the keeper mints through the PSM and sells on the route; below it the keeper
+    // buys on the route and redeems through the PSM. The keeper's USDC must end up at
+    // least min_profit higher, which bounds slippage on the route.
+    pub fn arb_peg<'info>(
+        ctx: Context<'_, '_, 'info, 'info, ArbPeg<'info>>,
+        direction: ArbDirection,
+        amount: u64,
+        min_profit: u64,
+        swap_data: Vec<u8>,
+        hook_accounts: u8,
+    ) -> Result<()> {
+        let (remaining, hook_accounts) =
+            split_hook_accounts(ctx.remaining_accounts, hook_accounts)?;
+        require_not_paused(&ctx.accounts.config, PAUSE_PSM)?;
+        require!(
+            ctx.accounts.deny_entry.data_is_empty(),
+            ErrorCode::AccountDenied
+        );
+        require!(amount > 0, ErrorCode::InvalidAmount);
+        require!(
+            ctx.accounts.swap_program.key() != crate::ID,
+            ErrorCode::InvalidSwapProgram
+        );
+        let price = ctx.accounts.rate_model.twap_price;
+        let threshold =
+            ((PEG_PRICE as u128) * (ctx.accounts.config.arb_threshold_bps as u128) / 10000) as u64;
+        let deviated = match direction {
+            ArbDirection::MintAndSell => price >= PEG_PRICE + threshold,
+            ArbDirection::BuyAndRedeem => price > 0 && price + threshold <= PEG_PRICE,
+        };
+        require!(deviated, ErrorCode::PegWithinThreshold);
+
+        let usdc_before = ctx.accounts.keeper_usdc_account.amount;
+        let (psm_amount, fee) = match direction {
+            ArbDirection::MintAndSell => {
+                require!(
+                    ctx.accounts.settlement.data_is_empty(),
+                    ErrorCode::SettlementTriggered
+                );
+                let psm = &mut ctx.accounts.psm_state;
+                let psm_supply = psm
+                    .psm_supply
+                    .checked_add(amount)
+                    .ok_or(ErrorCode::MathOverflow)?;
+                require!(psm_supply <= psm.supply_cap, ErrorCode::PsmCapExceeded);
+                let fee = psm_fee(amount, psm.mint_fee_bps);
+                let minted = amount - fee;
+                psm.psm_supply = psm_supply;
+                limit_mint(&ctx.accounts.config, &mut ctx.accounts.rate_limit, amount)?;
+
+                anchor_spl::token::transfer(
+                    CpiContext::new(
+                        ctx.accounts.token_program.to_account_info(),
+                        Transfer {
+                            from: ctx.accounts.keeper_usdc_account.to_account_info(),
+                            to: ctx.accounts.psm_reserve.to_account_info(),
+                            authority: ctx.accounts.keeper.to_account_info(),
+                        },
+                    ),
+                    amount,
+                )?;
+
+                mint_stablecoin(
+                    ctx.accounts.stablecoin_token_program.to_account_info(),
+                    token_interface::MintTo {
+                        mint: ctx.accounts.stablecoin_mint.to_account_info(),
+                        to: ctx.accounts.keeper_stablecoin_account.to_account_info(),
+                        authority: ctx.accounts.mint_authority.to_account_info(),
+                    },
+                    ctx.accounts.config.mint_authority_bump,
+                    minted,
+                )?;
+                record_mint(&mut ctx.accounts.supply_ledger, SupplySource::Psm, minted)?;
+
+                mint_stablecoin(
+                    ctx.accounts.stablecoin_token_program.to_account_info(),
+                    token_interface::MintTo {
+                        mint: ctx.accounts.stablecoin_mint.to_account_info(),
+                        to: ctx.accounts.treasury.to_account_info(),
+                        authority: ctx.accounts.mint_authority.to_account_info(),
+                    },
+                    ctx.accounts.config.mint_authority_bump,
+                    fee,
+                )?;
+                record_mint(&mut ctx.accounts.supply_ledger, SupplySource::Psm, fee)?;
+
+                invoke_swap(&ctx.accounts.swap_program, remaining, swap_data)?;
+                (minted, fee)
+            }
+            ArbDirection::BuyAndRedeem => {
+                let stablecoin_before = ctx.accounts.keeper_stablecoin_account.amount;
+                invoke_swap(&ctx.accounts.swap_program, remaining, swap_data)?;
+                ctx.accounts.keeper_usdc_account.reload()?;
+                ctx.accounts.keeper_stablecoin_account.reload()?;
+                require!(
+                    usdc_before.saturating_sub(ctx.accounts.keeper_usdc_account.amount) <= amount,
+                    ErrorCode::SlippageExceeded
+                );
+                let bought = ctx
+                    .accounts
+                    .keeper_stablecoin_account
+                    .amount
+                    .saturating_sub(stablecoin_before);
+                require!(bought > 0, ErrorCode::SlippageExceeded);
+
+                let psm = &ctx.accounts.psm_state;
+                let fee = psm_fee(bought, psm.redeem_fee_bps);
+                let usdc_out = bought - fee;
+                require!(
+                    usdc_out <= psm.psm_supply && usdc_out <= ctx.accounts.psm_reserve.amount,
+                    ErrorCode::PsmInsufficientReserve
+                );
+
+                transfer_stablecoin(
+                    ctx.accounts.stablecoin_token_program.to_account_info(),
+                    TransferChecked {
+                        from: ctx.accounts.keeper_stablecoin_account.to_account_info(),
+                        mint: ctx.accounts.stablecoin_mint.to_account_info(),
+                        to: ctx.accounts.treasury.to_account_info(),
+                        authority: ctx.accounts.keeper.to_account_info(),
+                    },
+                    hook_accounts,
+                    fee,
+                    ctx.accounts.stablecoin_mint.decimals,
+                    &[],
+                )?;
+
+                token_interface::burn(
+                    CpiContext::new(
+                        ctx.accounts.stablecoin_token_program.to_account_info(),
+                        token_interface::Burn {
+                            mint: ctx.accounts.stablecoin_mint.to_account_info(),
+                            from: ctx.accounts.keeper_stablecoin_account.to_account_info(),
+                            authority: ctx.accounts.keeper.to_account_info(),
+                        },
+                    ),
+                    usdc_out,
+                )?;
+                record_burn(&mut ctx.accounts.supply_ledger, SupplySource::Psm, usdc_out)?;
+
+                let bump = [psm.bump];
+                let signer_seeds: &[&[&[u8]]] = &[&[b"psm", &bump]];
+                anchor_spl::token::transfer(
+                    CpiContext::new_with_signer(
+                        ctx.accounts.token_program.to_account_info(),
+                        Transfer {
+                            from: ctx.accounts.psm_reserve.to_account_info(),
+                            to: ctx.accounts.keeper_usdc_account.to_account_info(),
+                            authority: ctx.accounts.psm_state.to_account_info(),
+                        },
+                        signer_seeds,
+                    ),
+                    usdc_out,
+                )?;
+
+                ctx.accounts.psm_state.psm_supply -= usdc_out;
+                (usdc_out, fee)
+            }
+        };
+
+        ctx.accounts.keeper_usdc_account.reload()?;
+        let usdc_after = ctx.accounts.keeper_usdc_account.amount;
+        let min_usdc = usdc_before
+            .checked_add(min_profit)
+            .ok_or(ErrorCode::MathOverflow)?;
+        require!(usdc_after >= min_usdc, ErrorCode::SlippageExceeded);
+
+        emit!(PegArbitragedEvent {
+            keeper: ctx.accounts.keeper.key(),
+            direction,
+            price,
+            psm_amount,
+            fee,
+            profit: usdc_after - usdc_before,
+        });
+
+        Ok(())
+    }
+
+    pub fn initialize_savings(ctx: Context<InitializeSavings>) -> Result<()> {
+        let savings = &mut ctx.accounts.savings_state;
+        savings.share_mint = ctx.accounts.share_mint.key();
+        savings.last_share_price = P_SCALE;
+        savings.last_synced_at = Clock::get()?.unix_timestamp;
+        savings.bump = ctx.bumps.savings_state;
+        Ok(())
+    }
+
+    // Savings shares are claims on the staking reward vault, so interest routed into it
+    // raises the share price for every holder without any per-deposit bookkeeping.
+    pub fn savings_deposit(ctx: Context<SavingsDeposit>, amount: u64) -> Result<()> {
+        require_not_paused(&ctx.accounts.config, PAUSE_SAVINGS)?;
+        require!(amount > 0, ErrorCode::InvalidAmount);
+        let assets = ctx.accounts.staking_reward_vault.amount;
+        let supply = ctx.accounts.share_mint.supply;
+        let shares = ((amount as u128) * (supply as u128 + SAVINGS_VIRTUAL_AMOUNT)
+            / (assets as u128 + SAVINGS_VIRTUAL_AMOUNT)) as u64;
+        require!(shares > 0, ErrorCode::InvalidAmount);
+
+        transfer_stablecoin(
+            ctx.accounts.stablecoin_token_program.to_account_info(),
+            TransferChecked {
+                from: ctx.accounts.user_stablecoin_account.to_account_info(),
+                mint: ctx.accounts.stablecoin_mint.to_account_info(),
+                to: ctx.accounts.staking_reward_vault.to_account_info(),
+                authority: ctx.accounts.user.to_account_info(),
+            },
+            ctx.remaining_accounts,
+            amount,
+            ctx.accounts.stablecoin_mint.decimals,
+            &[],
+        )?;
+
+        let bump = [ctx.accounts.savings_state.bump];
+        let signer_seeds: &[&[&[u8]]] = &[&[b"savings", &bump]];
+        token_interface::mint_to(
+            CpiContext::new_with_signer(
+                ctx.accounts.share_token_program.to_account_info(),
+                token_interface::MintTo {
+                    mint: ctx.accounts.share_mint.to_account_info(),
+                    to: ctx.accounts.user_share_account.to_account_info(),
+                    authority: ctx.accounts.savings_state.to_account_info(),
+                },
+                signer_seeds,
+            ),
+            shares,
+        )?;
+
+        emit!(SavingsDepositEvent {
+            user: ctx.accounts.user.key(),
+            amount,
+            shares,
+            share_price: share_price(assets + amount, supply + shares),
+        });
+
+        Ok(())
+    }
+
+    // Annualizes the share price growth since the last sync and publishes it as the
+    // stablecoin mint's interest-bearing rate, which the global state holds authority over.
+    pub fn sync_savings_rate(ctx: Context<SyncSavingsRate>) -> Result<()> {
+        let now = Clock::get()?.unix_timestamp;
+        let savings = &mut ctx.accounts.savings_state;
+        let elapsed = now - savings.last_synced_at;
+        require!(elapsed > 0, ErrorCode::InvalidParameter);
+        let price = share_price(
+            ctx.accounts.staking_reward_vault.amount,
+            ctx.accounts.share_mint.supply,
+        );
+        let growth = price.saturating_sub(savings.last_share_price);
+        let rate_bps = growth * 10000 * (SECONDS_PER_YEAR as u128)
+            / (savings.last_share_price * (elapsed as u128));
+        let rate_bps = rate_bps.min(i16::MAX as u128) as i16;
+        savings.last_share_price = price;
+        savings.last_synced_at = now;
+
+        let bump = [ctx.accounts.global_state.bump];
+        let signer_seeds: &[&[&[u8]]] = &[&[b"global_state", &bump]];
+        token_interface::interest_bearing_mint_update_rate(
+            CpiContext::new_with_signer(
+                ctx.accounts.stablecoin_token_program.to_account_info(),
+                token_interface::InterestBearingMintUpdateRate {
+                    token_program_id: ctx.accounts.stablecoin_token_program.to_account_info(),
+                    mint: ctx.accounts.stablecoin_mint.to_account_info(),
+                    rate_authority: ctx.accounts.global_state.to_account_info(),
+                },
+                signer_seeds,
+            ),
+            rate_bps,
+        )?;
+
+        emit!(SavingsRateSyncedEvent {
+            share_price: price,
+            rate_bps,
+        });
+
+        Ok(())
+    }
+
+    pub fn savings_withdraw(ctx: Context<SavingsWithdraw>, shares: u64) -> Result<()> {
+        require_not_paused(&ctx.accounts.config, PAUSE_SAVINGS)?;
+        require!(shares > 0, ErrorCode::InvalidAmount);
+        let assets = ctx.accounts.staking_reward_vault.amount;
+        let supply = ctx.accounts.share_mint.supply;
+        require!(shares <= supply, ErrorCode::InvalidAmount);
+        let amount = ((shares as u128) * (assets as u128 + SAVINGS_VIRTUAL_AMOUNT)
+            / (supply as u128 + SAVINGS_VIRTUAL_AMOUNT)) as u64;
+
+        token_interface::burn(
+            CpiContext::new(
+                ctx.accounts.share_token_program.to_account_info(),
+                token_interface::Burn {
+                    mint: ctx.accounts.share_mint.to_account_info(),
+                    from: ctx.accounts.user_share_account.to_account_info(),
+                    authority: ctx.accounts.user.to_account_info(),
+                },
+            ),
+            shares,
+        )?;
+
+        let bump = [ctx.accounts.savings_state.bump];
+        let signer_seeds: &[&[&[u8]]] = &[&[b"savings", &bump]];
+        transfer_stablecoin(
+            ctx.accounts.stablecoin_token_program.to_account_info(),
+            TransferChecked {
+                from: ctx.accounts.staking_reward_vault.to_account_info(),
+                mint: ctx.accounts.stablecoin_mint.to_account_info(),
+                to: ctx.accounts.user_stablecoin_account.to_account_info(),
+                authority: ctx.accounts.savings_state.to_account_info(),
+            },
+            ctx.remaining_accounts,
+            amount,
+            ctx.accounts.stablecoin_mint.decimals,
+            signer_seeds,
+        )?;
+
+        emit!(SavingsWithdrawEvent {
+            user: ctx.accounts.user.key(),
+            amount,
+            shares,
+            share_price: share_price(assets - amount, supply - shares),
+        });
+
+        Ok(())
+    }
+
+    // A flash mint is only allowed when a flash_burn follows later in the same transaction;
+    // if that burn fails the whole transaction, mint included, is rolled back.
+    pub fn flash_mint(ctx: Context<FlashMint>, amount: u64) -> Result<()> {
+        require_not_paused(&ctx.accounts.config, PAUSE_FLASH_MINT)?;
+        require!(
+            ctx.accounts.deny_entry.data_is_empty(),
+            ErrorCode::AccountDenied
+        );
+        require!(amount > 0, ErrorCode::InvalidAmount);
+        require!(
+            ctx.accounts.settlement.data_is_empty(),
+            ErrorCode::SettlementTriggered
+        );
+        require!(!ctx.accounts.flash_lock.active, ErrorCode::FlashLoanActive);
+        require_flash_burn_follows(&ctx.accounts.instructions)?;
+        let fee = ((amount as u128) * (ctx.accounts.config.flash_fee_bps as u128) / 10000) as u64;
+        limit_mint(&ctx.accounts.config, &mut ctx.accounts.rate_limit, amount)?;
+
+        mint_stablecoin(
+            ctx.accounts.stablecoin_token_program.to_account_info(),
+            token_interface::MintTo {
+                mint: ctx.accounts.stablecoin_mint.to_account_info(),
+                to: ctx.accounts.borrower_stablecoin_account.to_account_info(),
+                authority: ctx.accounts.mint_authority.to_account_info(),
+            },
+            ctx.accounts.config.mint_authority_bump,
+            amount,
+        )?;
+        record_mint(
+            &mut ctx.accounts.supply_ledger,
+            SupplySource::FlashMint,
+            amount,
+        )?;
+
+        let lock = &mut ctx.accounts.flash_lock;
+        lock.active = true;
+        lock.amount = amount;
+        lock.fee = fee;
+
+        emit!(FlashMintEvent {
+            borrower: ctx.accounts.borrower.key(),
+            amount,
+            fee,
+        });
+
+        Ok(())
+    }
+
+    pub fn flash_burn(ctx: Context<FlashBurn>) -> Result<()> {
+        require!(
+            ctx.accounts.deny_entry.data_is_empty(),
+            ErrorCode::AccountDenied
+        );
+        require!(
+            ctx.accounts.settlement.data_is_empty(),
+            ErrorCode::SettlementTriggered
+        );
+        let lock = &ctx.accounts.flash_lock;
+        require!(lock.active, ErrorCode::FlashLoanInactive);
+        let (amount, fee) = (lock.amount, lock.fee);
+
+        token_interface::burn(
+            CpiContext::new(
+                ctx.accounts.stablecoin_token_program.to_account_info(),
+                token_interface::Burn {
+                    mint: ctx.accounts.stablecoin_mint.to_account_info(),
+                    from: ctx.accounts.payer_stablecoin_account.to_account_info(),
+                    authority: ctx.accounts.payer.to_account_info(),
+                },
+            ),
+            amount,
+        )?;
+        record_burn(
+            &mut ctx.accounts.supply_ledger,
+            SupplySource::FlashMint,
+            amount,
+        )?;
+
+        transfer_stablecoin(
+            ctx.accounts.stablecoin_token_program.to_account_info(),
+            TransferChecked {
+                from: ctx.accounts.payer_stablecoin_account.to_account_info(),
+                mint: ctx.accounts.stablecoin_mint.to_account_info(),
+                to: ctx.accounts.treasury.to_account_info(),
+                authority: ctx.accounts.payer.to_account_info(),
+            },
+            ctx.remaining_accounts,
+            fee,
+            ctx.accounts.stablecoin_mint.decimals,
+            &[],
+        )?;
+
+        let lock = &mut ctx.accounts.flash_lock;
+        lock.active = false;
+        lock.amount = 0;
+        lock.fee = 0;
+
+        emit!(FlashBurnEvent {
+            payer: ctx.accounts.payer.key(),
+            amount,
+            fee,
+        });
+
+        Ok(())
+    }
+
+    pub fn set_ntt_authority(ctx: Context<SetNttAuthority>, ntt_authority: Pubkey) -> Result<()> {
+        let bridge = &mut ctx.accounts.bridge_config;
+        bridge.ntt_authority = ntt_authority;
+        bridge.bump = ctx.bumps.bridge_config;
+        Ok(())
+    }
+
+    pub fn register_bridge_peer(
+        ctx: Context<RegisterBridgePeer>,
+        chain_id: u16,
+        peer_address: [u8; 32],
+        window_slots: u64,
+        outbound_limit: u64,
+        inbound_limit: u64,
+    ) -> Result<()> {
+        require!(window_slots > 0, ErrorCode::InvalidParameter);
+        let peer = &mut ctx.accounts.bridge_peer;
+        peer.chain_id = chain_id;
+        peer.peer_address = peer_address;
+        peer.bump = ctx.bumps.bridge_peer;
+        let outbound = &mut ctx.accounts.outbound_limit;
+        outbound.window_slots = window_slots;
+        outbound.max_mint_per_window = outbound_limit;
+        outbound.bump = ctx.bumps.outbound_limit;
+        let inbound = &mut ctx.accounts.inbound_limit;
+        inbound.window_slots = window_slots;
+        inbound.max_mint_per_window = inbound_limit;
+        inbound.bump = ctx.bumps.inbound_limit;
+
+        emit!(BridgePeerRegisteredEvent {
+            chain_id,
+            peer_address,
+            outbound_limit,
+            inbound_limit,
+        });
+
+        Ok(())
+    }
+
+    // NTT runs in burn-and-mint mode: the NTT manager signs through its authority PDA,
+    // and stablecoin burned here is tracked as supply circulating on other chains.
+    pub fn ntt_burn(ctx: Context<NttBurn>, amount: u64) -> Result<()> {
+        require_not_paused(&ctx.accounts.config, PAUSE_BRIDGE)?;
+        require!(
+            ctx.accounts.deny_entry.data_is_empty(),
+            ErrorCode::AccountDenied
+        );
+        require!(amount > 0, ErrorCode::InvalidAmount);
+        consume_mint_allowance(&mut ctx.accounts.outbound_limit, amount, Clock::get()?.slot)?;
+
+        token_interface::burn(
+            CpiContext::new(
+                ctx.accounts.stablecoin_token_program.to_account_info(),
+                token_interface::Burn {
+                    mint: ctx.accounts.stablecoin_mint.to_account_info(),
+                    from: ctx.accounts.sender_stablecoin_account.to_account_info(),
+                    authority: ctx.accounts.sender.to_account_info(),
+                },
+            ),
+            amount,
+        )?;
+        record_burn(
+            &mut ctx.accounts.supply_ledger,
+            SupplySource::Bridge,
+            amount,
+        )?;
+
+        let bridge = &mut ctx.accounts.bridge_config;
+        bridge.bridged_out = bridge
+            .bridged_out
+            .checked_add(amount)
+            .ok_or(ErrorCode::MathOverflow)?;
+
+        emit!(BridgeBurnEvent {
+            chain_id: ctx.accounts.bridge_peer.chain_id,
+            sender: ctx.accounts.sender.key(),
+            amount,
+            bridged_out: bridge.bridged_out,
+        });
+
+        Ok(())
+    }
+
+    // Inbound transfers can only return supply that previously left through ntt_burn.
+    pub fn ntt_mint(ctx: Context<NttMint>, amount: u64) -> Result<()> {
+        require_not_paused(&ctx.accounts.config, PAUSE_BRIDGE)?;
+        require!(
+            ctx.accounts.deny_entry.data_is_empty(),
+            ErrorCode::AccountDenied
+        );
+        require!(amount > 0, ErrorCode::InvalidAmount);
+        require!(
+            ctx.accounts.settlement.data_is_empty(),
+            ErrorCode::SettlementTriggered
+        );
+        let bridge = &mut ctx.accounts.bridge_config;
+        require!(
+            amount <= bridge.bridged_out,
+            ErrorCode::BridgeSupplyExceeded
+        );
+        consume_mint_allowance(&mut ctx.accounts.inbound_limit, amount, Clock::get()?.slot)?;
+        bridge.bridged_out -= amount;
+
+        mint_stablecoin(
+            ctx.accounts.stablecoin_token_program.to_account_info(),
+            token_interface::MintTo {
+                mint: ctx.accounts.stablecoin_mint.to_account_info(),
+                to: ctx.accounts.recipient_stablecoin_account.to_account_info(),
+                authority: ctx.accounts.mint_authority.to_account_info(),
+            },
+            ctx.accounts.config.mint_authority_bump,
+            amount,
+        )?;
+        record_mint(
+            &mut ctx.accounts.supply_ledger,
+            SupplySource::Bridge,
+            amount,
+        )?;
+
+        emit!(BridgeMintEvent {
+            chain_id: ctx.accounts.bridge_peer.chain_id,
+            recipient: ctx.accounts.recipient_stablecoin_account.owner,
+            amount,
+            bridged_out: ctx.accounts.bridge_config.bridged_out,
+        });
+
+        Ok(())
+    }
+
+    // The global state is the mint's confidential transfer authority; compliance decides
+    // whether new accounts are auto-approved and which ElGamal key can audit amounts.
+    pub fn configure_confidential_transfers(
+        ctx: Context<ConfigureConfidentialTransfers>,
+        auto_approve_new_accounts: bool,
+        auditor_elgamal_pubkey: Option<[u8; 32]>,
+    ) -> Result<()> {
+        let ix = confidential_transfer::instruction::update_mint(
+            &ctx.accounts.token_2022_program.key(),
+            &ctx.accounts.stablecoin_mint.key(),
+            &ctx.accounts.global_state.key(),
+            &[],
+            auto_approve_new_accounts,
+            auditor_elgamal_pubkey.map(pod::ElGamalPubkey),
+        )?;
+        let bump = [ctx.accounts.global_state.bump];
+        let signer_seeds: &[&[&[u8]]] = &[&[b"global_state", &bump]];
+        invoke_signed(
+            &ix,
+            &[
+                ctx.accounts.stablecoin_mint.to_account_info(),
+                ctx.accounts.global_state.to_account_info(),
+            ],
+            signer_seeds,
+        )?;
+
+        emit!(ConfidentialTransfersConfiguredEvent {
+            mint: ctx.accounts.stablecoin_mint.key(),
+            auto_approve_new_accounts,
+            auditor_elgamal_pubkey,
+        });
+
+        Ok(())
+    }
+
+    pub fn approve_confidential_account(ctx: Context<ApproveConfidentialAccount>) -> Result<()> {
+        let ix = confidential_transfer::instruction::approve_account(
+            &ctx.accounts.token_2022_program.key(),
+            &ctx.accounts.token_account.key(),
+            &ctx.accounts.stablecoin_mint.key(),
+            &ctx.accounts.global_state.key(),
+            &[],
+        )?;
+        let bump = [ctx.accounts.global_state.bump];
+        let signer_seeds: &[&[&[u8]]] = &[&[b"global_state", &bump]];
+        invoke_signed(
+            &ix,
+            &[
+                ctx.accounts.token_account.to_account_info(),
+                ctx.accounts.stablecoin_mint.to_account_info(),
+                ctx.accounts.global_state.to_account_info(),
+            ],
+            signer_seeds,
+        )?;
+
+        emit!(ConfidentialAccountApprovedEvent {
+            account: ctx.accounts.token_account.key(),
+            owner: ctx.accounts.token_account.owner,
+        });
+
+        Ok(())
+    }
+
+    pub fn reconcile_supply(ctx: Context<ReconcileSupply>) -> Result<()> {
+        let ledger = &ctx.accounts.supply_ledger;
+        let ledger_total = ledger_total(ledger);
+        let supply = ctx.accounts.stablecoin_mint.supply;
+
+        emit!(SupplyReconciledEvent {
+            supply,
+            cdp_debt: ledger.cdp_debt,
+            interest: ledger.interest,
+            psm: ledger.psm,
+            flash_mint: ledger.flash_mint,
+            bridged_net: ledger.bridged_net,
+            discrepancy: supply as i128 - ledger_total,
+        });
+
+        Ok(())
+    }
+}
+
+// Liquity-style base rate: it halves every half_life_secs since the last redemption and
+// grows by the redeemed share of supply divided by REDEMPTION_BETA, so bursts of
+// redemptions price themselves out.
+fn bump_base_rate(
+    fee_state: &mut RedemptionFeeState,
+    redeemed: u64,
+    supply: u64,
+    now: i64,
+) -> u128 {
+    let elapsed = (now - fee_state.last_redemption_at).max(0);
+    let half_life = fee_state.half_life_secs;
+    let halvings = elapsed / half_life;
+    let decayed = if halvings >= 128 {
+        0
+    } else {
+        let rem = (elapsed % half_life) as u128;
+        let half_life = half_life as u128;
+        (fee_state.base_rate >> halvings) * (2 * half_life - rem) / (2 * half_life)
+    };
+    let increase = if supply == 0 {
+        0
+    } else {
+        (redeemed as u128) * FEE_PRECISION / (supply as u128) / REDEMPTION_BETA
+    };
+    fee_state.base_rate = (decayed + increase).min(FEE_PRECISION);
+    fee_state.last_redemption_at = now;
+    fee_state.base_rate
+}
+
+fn haircut_value(bond_amount: u64, collateral: &CollateralConfig, config: &Config) -> Result<u128> {
+    let value = (collateral.unit_value as u128)
+        .checked_mul(bond_amount as u128)
+        .ok_or(ErrorCode::MathOverflow)?;
+    let haircut_bps = config.rating_haircuts_bps[collateral.rating as usize] as u128;
+    Ok(value * (10000 - haircut_bps) / 10000)
+}
+
+// Each coupon and the face value are discounted with simple interest at the rate for their
+// time to payment; coupons are assumed annual, counted back from maturity.
+fn present_value(bond: &BondMetadata, now: i64, rate_bps: impl Fn(i64) -> u64) -> u64 {
+    let remaining = bond.maturity - now;
+    if remaining <= 0 {
+        return bond.face_value;
+    }
+    let discount = |amount: u128, secs: i64| -> u128 {
+        let year_bps = 10000 * SECONDS_PER_YEAR as u128;
+        amount * year_bps / (year_bps + (rate_bps(secs) as u128) * (secs as u128))
+    };
+
+    let face = bond.face_value as u128;
+    let coupon = face * (bond.coupon_rate_bps as u128) / 10000;
+    let mut value = discount(face, remaining);
+    let mut until_payment = remaining;
+    let mut periods = 0;
+    while until_payment > 0 && periods < MAX_COUPON_PERIODS {
+        value += discount(coupon, until_payment);
+        until_payment -= SECONDS_PER_YEAR;
+        periods += 1;
+    }
+    value.min(u64::MAX as u128) as u64
+}
+
+// Rates are interpolated linearly between published tenors and held flat beyond either end.
+fn curve_rate_bps(curve: &YieldCurve, secs: i64) -> u64 {
+    let points = curve.point_count as usize;
+    let (tenors, rates) = (&curve.tenors_secs[..points], &curve.rates_bps[..points]);
+    if secs <= tenors[0] {
+        return rates[0];
+    }
+    for (tenor, rate) in tenors.windows(2).zip(rates.windows(2)) {
+        if secs <= tenor[1] {
+            let (t0, t1) = (tenor[0] as i128, tenor[1] as i128);
+            let (r0, r1) = (rate[0] as i128, rate[1] as i128);
+            return (r0 + (r1 - r0) * (secs as i128 - t0) / (t1 - t0)) as u64;
+        }
+    }
+    rates[points - 1]
+}
+
+// The crank passes the collateral's feed accounts as remaining accounts, in configured order.
+// Each fresh feed yields a value relative to par (Pyth at the lower edge of its confidence
+// interval) and the collateral is valued at their median, provided at least the quorum of
+// feeds pass the staleness and confidence guards. Short of quorum, the first rejection is
+// reported. The median is in the bond's currency and is converted to USD at the FX rate.
+fn apply_oracle_price<'info>(
+    collateral: &mut CollateralConfig,
+    cache: &mut PriceCache,
+    remaining_accounts: &'info [AccountInfo<'info>],
+    (fx_rate, fx_scale): (u128, u128),
+) -> Result<()> {
+    let feed_count = collateral.oracle_feed_count as usize;
+    require!(
+        remaining_accounts.len() >= feed_count,
+        ErrorCode::PriceFeedRequired
+    );
+
+    let clock = Clock::get()?;
+    let mut values: Vec<u128> = Vec::with_capacity(feed_count);
+    let mut sources_used: u8 = 0;
+    let mut rejection: Option<ErrorCode> = None;
+    for (index, (feed, info)) in collateral.oracle_feeds[..feed_count]
+        .iter()
+        .zip(remaining_accounts)
+        .enumerate()
+    {
+        let quote = match feed.source {
+            PriceSource::Pyth => {
+                let update = Account::<PriceUpdateV2>::try_from(info)?;
+                pyth_quote(&update, &feed.id, collateral, clock.unix_timestamp)?
+            }
+            PriceSource::Switchboard => {
+                require_keys_eq!(
+                    info.key(),
+                    Pubkey::new_from_array(feed.id),
+                    ErrorCode::InvalidOracleAccount
+                );
+                let aggregator = AccountLoader::<AggregatorAccountData>::try_from(info)?;
+                switchboard_quote(&aggregator, collateral, clock.unix_timestamp)?
+            }
+            PriceSource::Custodian => {
+                require_keys_eq!(
+                    info.key(),
+                    Pubkey::new_from_array(feed.id),
+                    ErrorCode::InvalidOracleAccount
+                );
+                let attestation = Account::<NavAttestation>::try_from(info)?;
+                custodian_quote(&attestation, collateral, clock.unix_timestamp)
+            }
+            PriceSource::Chainlink => {
+                require_keys_eq!(
+                    info.key(),
+                    Pubkey::new_from_array(feed.id),
+                    ErrorCode::InvalidOracleAccount
+                );
+                chainlink_quote(info, collateral, clock.unix_timestamp)?
+            }
+        };
+        match quote {
+            Ok((price, scale)) => {
+                values.push((collateral.face_value as u128) * price / scale);
+                sources_used |= 1 << index;
+            }
+            Err(reason) => {
+                rejection.get_or_insert(reason);
+            }
+        }
+    }
+    if values.len() < collateral.oracle_quorum as usize {
+        return Err(rejection.unwrap_or(ErrorCode::InvalidPrice).into());
+    }
+
+    values.sort_unstable();
+    let mid = values.len() / 2;
+    let median = if values.len() % 2 == 0 {
+        (values[mid - 1] + values[mid]) / 2
+    } else {
+        values[mid]
+    };
+    let median = median * fx_rate / fx_scale;
+    // A price outside the band is held back rather than applied: borrowing against the
+    // collateral pauses until the risk authority acknowledges it.
+    let median = median.min(u64::MAX as u128) as u64;
+    if breaches_price_band(collateral, median) {
+        collateral.borrowing_paused = true;
+        collateral.pending_unit_value = median;
+        emit!(PriceBandBreachedEvent {
+            mint: collateral.mint,
+            unit_value: collateral.unit_value,
+            rejected_value: median,
+        });
+    } else {
+        collateral.unit_value = median;
+        collateral.value_updated_at = clock.unix_timestamp;
+        cache.unit_value = median;
+        cache.sources_used = sources_used;
+        cache.updated_at = clock.unix_timestamp;
+    }
+
+    emit!(OraclePriceUsedEvent {
+        mint: collateral.mint,
+        sources_used,
+        unit_value: collateral.unit_value,
+    });
+    Ok(())
+}
+
+// Instructions read oracle prices through the cache, which must have been refreshed within
+// the collateral's maximum price age. During an outage the unexpired emergency price stands
+// in, but only for operations that don't take on more risk against it.
+fn load_cached_price(
+    collateral: &mut CollateralConfig,
+    cache: &PriceCache,
+    allow_emergency: bool,
+) -> Result<()> {
+    let now = Clock::get()?.unix_timestamp;
+    if collateral.oracle_feed_count == 0 {
+        // Without an automated feed, an unexpired manual mark overrides the model value.
+        if cache.manual_unit_value > 0 && now < cache.manual_expires_at {
+            collateral.unit_value = cache.manual_unit_value;
+            collateral.value_updated_at = now;
+            emit!(ManualMarkAppliedEvent {
+                mint: collateral.mint,
+                unit_value: cache.manual_unit_value,
+                expires_at: cache.manual_expires_at,
+            });
+        }
+        return Ok(());
+    }
+    let fresh =
+        cache.unit_value > 0 && now - cache.updated_at <= collateral.max_price_age_secs as i64;
+    if fresh {
+        collateral.unit_value = cache.unit_value;
+        collateral.value_updated_at = cache.updated_at;
+        return Ok(());
+    }
+    require!(
+        allow_emergency && cache.emergency_unit_value > 0 && now < cache.emergency_expires_at,
+        ErrorCode::StaleOracle
+    );
+    collateral.unit_value = cache.emergency_unit_value;
+    collateral.value_updated_at = now;
+    Ok(())
+}
+
+// The attestation is carried by an ed25519 program instruction placed immediately before
+// this one, holding a single signature whose key and message sit in its own data.
+fn verify_ed25519_signature(
+    instructions: &AccountInfo,
+    signer: &Pubkey,
+    message: &[u8],
+) -> Result<()> {
+    let current = load_current_index_checked(instructions)? as usize;
+    require!(current > 0, ErrorCode::InvalidAttestation);
+    let ix = load_instruction_at_checked(current - 1, instructions)?;
+    require!(
+        ix.program_id == ed25519_program::ID && ix.accounts.is_empty(),
+        ErrorCode::InvalidAttestation
+    );
+
+    let data = &ix.data;
+    let read_u16 = |at: usize| -> Result<usize> {
+        let bytes = data.get(at..at + 2).ok_or(ErrorCode::InvalidAttestation)?;
+        Ok(u16::from_le_bytes([bytes[0], bytes[1]]) as usize)
+    };
+    require!(data.first() == Some(&1), ErrorCode::InvalidAttestation);
+    // Offsets follow a two byte header; every instruction index must point at this data.
+    let signature_ix = read_u16(4)?;
+    let pubkey_offset = read_u16(6)?;
+    let pubkey_ix = read_u16(8)?;
+    let message_offset = read_u16(10)?;
+    let message_size = read_u16(12)?;
+    let message_ix = read_u16(14)?;
+    require!(
+        [signature_ix, pubkey_ix, message_ix]
+            .iter()
+            .all(|index| *index == u16::MAX as usize),
+        ErrorCode::InvalidAttestation
+    );
+
+    let pubkey = data
+        .get(pubkey_offset..pubkey_offset + 32)
+        .ok_or(ErrorCode::InvalidAttestation)?;
+    let signed = data
+        .get(message_offset..message_offset + message_size)
+        .ok_or(ErrorCode::InvalidAttestation)?;
+    require!(
+        pubkey == signer.as_ref() && signed == message,
+        ErrorCode::InvalidAttestation
+    );
+    Ok(())
+}
+
+fn chainlink_quote(
+    info: &AccountInfo,
+    collateral: &CollateralConfig,
+    now: i64,
+) -> Result<std::result::Result<(u128, u128), ErrorCode>> {
+    let data = info.try_borrow_data()?;
+    let feed =
+        read_feed_v2(data, info.owner.to_bytes()).map_err(|_| ErrorCode::InvalidOracleAccount)?;
+    let round = match feed.latest_round_data() {
+        Some(round) => round,
+        None => return Ok(Err(ErrorCode::StaleOracle)),
+    };
+    if round.answer <= 0 {
+        return Ok(Err(ErrorCode::InvalidPrice));
+    }
+    // OCR2 rounds carry no confidence interval, only the aggregated answer.
+    let answer = round.answer as u128;
+    if let Err(reason) = vet_price(answer, 0, round.timestamp as i64, collateral, now) {
+        return Ok(Err(reason));
+    }
+    Ok(Ok((answer, 10u128.pow(feed.decimals() as u32))))
+}
+
+fn custodian_quote(
+    attestation: &NavAttestation,
+    collateral: &CollateralConfig,
+    now: i64,
+) -> std::result::Result<(u128, u128), ErrorCode> {
+    let nav = attestation.nav as u128;
+    vet_price(nav, 0, attestation.attested_at, collateral, now)?;
+    // NAV is already per bond unit, so it is scaled back against the face value.
+    Ok((nav, collateral.face_value as u128))
+}
+
+// Risk-increasing operations value collateral at the lower of spot and its TWAP, so a short
+// spike in the oracle price can't be borrowed against.
+fn risk_view(collateral: &CollateralConfig, price_twap: &PriceTwap) -> CollateralConfig {
+    let mut view = collateral.clone();
+    if price_twap.twap_value > 0 {
+        view.unit_value = view.unit_value.min(price_twap.twap_value);
+    }
+    view
+}
+
+fn feed_entry(
+    mint: Pubkey,
+    feeds: &[OracleFeed],
+    quorum: u8,
+    max_price_age_secs: u64,
+    max_confidence_bps: u64,
+) -> Result<FeedEntry> {
+    require!(feeds.len() <= MAX_ORACLE_FEEDS, ErrorCode::InvalidParameter);
+    require!(
+        feeds.is_empty()
+            || (quorum > 0 && quorum as usize <= feeds.len() && max_price_age_secs > 0),
+        ErrorCode::InvalidParameter
+    );
+    require!(
+        feeds.is_empty() || (max_confidence_bps > 0 && max_confidence_bps < 10000),
+        ErrorCode::InvalidParameter
+    );
+    let mut slots = [OracleFeed {
+        source: PriceSource::Pyth,
+        id: [0; 32],
+    }; MAX_ORACLE_FEEDS];
+    slots[..feeds.len()].copy_from_slice(feeds);
+    Ok(FeedEntry {
+        mint,
+        feeds: slots,
+        feed_count: feeds.len() as u8,
+        quorum,
+        max_price_age_secs,
+        max_confidence_bps,
+    })
+}
+
+// The registry is the managed record; the collateral keeps a copy so pricing doesn't need
+// the registry account.
+fn apply_feed_entry(collateral: &mut CollateralConfig, entry: &FeedEntry) {
+    collateral.oracle_feeds = entry.feeds;
+    collateral.oracle_feed_count = entry.feed_count;
+    collateral.oracle_quorum = entry.quorum;
+    collateral.max_price_age_secs = entry.max_price_age_secs;
+    collateral.max_confidence_bps = entry.max_confidence_bps;
+}
+
+// Bonds are valued in their own currency and converted into the currency of the market
+// they borrow against. FX feeds quote against USD, so a USD market multiplies through by
+// the bond currency's rate and a foreign market divides by its own.
+fn fx_conversion(
+    collateral: &CollateralConfig,
+    fx_feed: Option<&FxFeed>,
+    fx_price_update: Option<&PriceUpdateV2>,
+    now: i64,
+) -> Result<(u128, u128)> {
+    let (from, to) = (collateral.currency, collateral.market_currency);
+    if from == to {
+        return Ok((1, 1));
+    }
+    require!(
+        from == Currency::Usd || to == Currency::Usd,
+        ErrorCode::UnsupportedCurrencyPair
+    );
+    let foreign = if from == Currency::Usd { to } else { from };
+    let (Some(fx_feed), Some(update)) = (fx_feed, fx_price_update) else {
+        return err!(ErrorCode::FxFeedRequired);
+    };
+    require!(fx_feed.currency == foreign, ErrorCode::InvalidOracleAccount);
+    require!(
+        update.verification_level.gte(VerificationLevel::Full),
+        ErrorCode::InvalidOracleAccount
+    );
+    let price = update
+        .get_price_unchecked(&fx_feed.feed_id)
+        .map_err(|_| ErrorCode::InvalidOracleAccount)?;
+    require!(
+        price.price > 0 && price.exponent <= 0,
+        ErrorCode::InvalidPrice
+    );
+    require!(
+        now - price.publish_time <= fx_feed.max_price_age_secs as i64,
+        ErrorCode::StaleOracle
+    );
+    let scale = 10u128.pow(price.exponent.unsigned_abs());
+    if from == Currency::Usd {
+        return Ok((scale, price.price as u128));
+    }
+    Ok((price.price as u128, scale))
+}
+
+fn breaches_price_band(collateral: &CollateralConfig, value: u64) -> bool {
+    if (collateral.min_unit_value > 0 && value < collateral.min_unit_value)
+        || (collateral.max_unit_value > 0 && value > collateral.max_unit_value)
+    {
+        return true;
+    }
+    let last = collateral.unit_value as u128;
+    collateral.max_price_change_bps > 0
+        && last > 0
+        && (value as u128).abs_diff(last) * 10000 > last * collateral.max_price_change_bps as u128
+}
+
+fn vet_price(
+    price: u128,
+    conf: u128,
+    published_at: i64,
+    collateral: &CollateralConfig,
+    now: i64,
+) -> std::result::Result<(), ErrorCode> {
+    if now - published_at > collateral.max_price_age_secs as i64 {
+        return Err(ErrorCode::StaleOracle);
+    }
+    if conf.saturating_mul(10000) > price * collateral.max_confidence_bps as u128 {
+        return Err(ErrorCode::WideConfidence);
+    }
+    Ok(())
+}
+
+fn pyth_quote(
+    update: &PriceUpdateV2,
+    feed_id: &[u8; 32],
+    collateral: &CollateralConfig,
+    now: i64,
+) -> Result<std::result::Result<(u128, u128), ErrorCode>> {
+    require!(
+        update.verification_level.gte(VerificationLevel::Full),
+        ErrorCode::InvalidOracleAccount
+    );
+    let price = update
+        .get_price_unchecked(feed_id)
+        .map_err(|_| ErrorCode::InvalidOracleAccount)?;
+    if price.price <= 0 || price.exponent > 0 {
+        return Ok(Err(ErrorCode::InvalidPrice));
+    }
+    let (value, conf) = (price.price as u128, price.conf as u128);
+    if let Err(reason) = vet_price(value, conf, price.publish_time, collateral, now) {
+        return Ok(Err(reason));
+    }
+    let scale = 10u128.pow(price.exponent.unsigned_abs());
+    Ok(Ok((value - conf, scale)))
+}
+
+fn switchboard_quote(
+    aggregator: &AccountLoader<AggregatorAccountData>,
+    collateral: &CollateralConfig,
+    now: i64,
+) -> Result<std::result::Result<(u128, u128), ErrorCode>> {
+    let feed = aggregator.load()?;
+    let result = feed.get_result()?;
+    if result.mantissa <= 0 {
+        return Ok(Err(ErrorCode::InvalidPrice));
+    }
+    // The round's standard deviation is brought to the result's scale to compare like
+    // with like.
+    let round = &feed.latest_confirmed_round;
+    let deviation = round.std_deviation;
+    let conf = deviation
+        .mantissa
+        .unsigned_abs()
+        .saturating_mul(10u128.pow(result.scale))
+        / 10u128.pow(deviation.scale);
+    let value = result.mantissa as u128;
+    if let Err(reason) = vet_price(value, conf, round.round_open_timestamp, collateral, now) {
+        return Ok(Err(reason));
+    }
+    Ok(Ok((value, 10u128.pow(result.scale))))
+}
+
+// Values an amount of one collateral with that collateral's own price, rating haircut and
+// LTVs.
+fn collateral_values(
+    amount: u64,
+    collateral: &CollateralConfig,
+    config: &Config,
+) -> Result<CollateralValues> {
+    let haircut = haircut_value(amount, collateral, config)?;
+    let value = |value: u128| u64::try_from(value).map_err(|_| ErrorCode::MathOverflow);
+    Ok(CollateralValues {
+        face_value: value(amount as u128 * collateral.face_value as u128)?,
+        market_value: value(amount as u128 * collateral.unit_value as u128)?,
+        haircut_value: value(haircut)?,
+        borrow_value: value(haircut * collateral.max_ltv_bps as u128 / 10000)?,
+        liquidation_value: value(haircut * collateral.liquidation_ltv_bps as u128 / 10000)?,
+    })
+}
+
+// A vault's collateral is its primary amount valued with the primary config plus the values
+// cached for its legs.
+fn projected_values(
+    primary: u64,
+    legs: &CollateralValues,
+    collateral: &CollateralConfig,
+    config: &Config,
+) -> Result<CollateralValues> {
+    collateral_values(primary, collateral, config)?.checked_add(legs)
+}
+
+fn vault_values(
+    vault: &Vault,
+    collateral: &CollateralConfig,
+    config: &Config,
+) -> Result<CollateralValues> {
+    projected_values(primary_amount(vault), &vault.leg_values, collateral, config)
+}
+
+fn collateral_ratio_bps(bond_amount: u64, unit_value: u64, debt: u64) -> Result<u64> {
+    if debt == 0 {
+        return Ok(u64::MAX);
+    }
+    let collateral_value = (unit_value as u128)
+        .checked_mul(bond_amount as u128)
+        .ok_or(ErrorCode::MathOverflow)?;
+    Ok((collateral_value * 10000 / (debt as u128)).min(u64::MAX as u128) as u64)
+}
+
+fn is_recovery_mode(global: &GlobalState, config: &Config) -> Result<bool> {
+    if config.critical_cr_bps == 0 || global.total_debt == 0 {
+        return Ok(false);
+    }
+    let ratio = collateral_ratio_bps(global.total_collateral_value, 1, global.total_debt)?;
+    Ok(ratio < config.critical_cr_bps)
+}
+
+fn borrow_limit(values: &CollateralValues, config: &Config, recovery: bool) -> Result<u64> {
+    let limit = values.borrow_value;
+    if !recovery {
+        return Ok(limit);
+    }
+    let recovery_limit = (values.haircut_value as u128) * 10000 / (config.critical_cr_bps as u128);
+    Ok((recovery_limit as u64).min(limit))
+}
+
+fn is_liquidatable(
+    values: &CollateralValues,
+    debt: u64,
+    config: &Config,
+    recovery: bool,
+) -> Result<bool> {
+    if health_factor_bps(values, debt)? < 10000 {
+        return Ok(true);
+    }
+    Ok(recovery && collateral_ratio_bps(values.market_value, 1, debt)? < config.critical_cr_bps)
+}
+
+fn is_eligible_bond(bond: &BondMetadata, now: i64) -> bool {
+    bond.status == BondStatus::Active && now < bond.maturity
+}
+
+fn require_eligible_bond(bond: &BondMetadata, now: i64) -> Result<()> {
+    require!(is_eligible_bond(bond, now), ErrorCode::BondNotEligible);
+    Ok(())
+}
+
+fn release_position<'info>(
+    vault: &Account<'info, Vault>,
+    position_escrow: &InterfaceAccount<'info, TokenAccount>,
+    to: &InterfaceAccount<'info, TokenAccount>,
+    token_program: &Program<'info, Token>,
+) -> Result<()> {
+    let creator = vault.creator;
+    let collateral_mint = vault.collateral_mint;
+    let bump = [vault.bump];
+    let signer_seeds: &[&[&[u8]]] =
+        &[&[b"vault", creator.as_ref(), collateral_mint.as_ref(), &bump]];
+
+    anchor_spl::token::transfer(
+        CpiContext::new_with_signer(
+            token_program.to_account_info(),
+            Transfer {
+                from: position_escrow.to_account_info(),
+                to: to.to_account_info(),
+                authority: vault.to_account_info(),
+            },
+            signer_seeds,
+        ),
+        1,
+    )
+}
+
+fn is_vault_operator(vault: &Vault, position: &TokenAccount, key: &Pubkey) -> bool {
+    *key == position.owner || (vault.delegate != Pubkey::default() && *key == vault.delegate)
+}
+
+// Control follows the position NFT, so the recorded owner is refreshed from whoever holds it;
+// a delegate appointed by a previous holder does not survive the handover.
+fn sync_position_owner(vault: &mut Vault, holder: Pubkey) {
+    if vault.owner != holder {
+        vault.owner = holder;
+        vault.delegate = Pubkey::default();
+    }
+}
+
+// Withdrawals are summed over a window of withdraw_delay_secs; once the window's total goes
+// above the threshold each withdrawal must match a request whose delay has elapsed, and the
+// request is used up by the withdrawal it authorises.
+fn consume_pending_withdrawal(
+    vault: &mut Vault,
+    config: &Config,
+    amount: u64,
+    now: i64,
+) -> Result<()> {
+    if config.withdraw_delay_secs == 0 {
+        return Ok(());
+    }
+    if now >= vault.withdrawal_window_start + config.withdraw_delay_secs {
+        vault.withdrawal_window_start = now;
+        vault.window_withdrawn = 0;
+    }
+    vault.window_withdrawn = vault
+        .window_withdrawn
+        .checked_add(amount)
+        .ok_or(ErrorCode::MathOverflow)?;
+    if vault.window_withdrawn <= config.withdraw_threshold {
+        return Ok(());
+    }
+    require!(
+        vault.pending_withdrawal > 0 && amount <= vault.pending_withdrawal,
+        ErrorCode::NoPendingWithdrawal
+    );
+    require!(
+        now >= vault.withdrawal_ready_at,
+        ErrorCode::WithdrawalCooldownActive
+    );
+    vault.pending_withdrawal = 0;
+    vault.withdrawal_ready_at = 0;
+    Ok(())
+}
+
+fn settled_debt(debt: u64, claimed: u64, unit_value: u64) -> u64 {
+    ((claimed as u128) * (unit_value as u128)).min(debt as u128) as u64
+}
+
+// Settlement leaves uncovered debt on a vault only while it still has collateral to settle.
+fn remaining_settlement_debt(vault: &Vault, debt: u64, covered: u64) -> u64 {
+    if vault.bond_amount > 0 {
+        debt - covered
+    } else {
+        0
+    }
+}
+
+fn primary_amount(vault: &Vault) -> u64 {
+    vault.bond_amount - vault.leg_amount
+}
+
+fn track_vault(
+    global: &mut GlobalState,
+    collateral: &mut CollateralConfig,
+    config: &Config,
+    collateral_before: u64,
+    debt_before: u64,
+    vault: &mut Vault,
+) -> Result<()> {
+    // collateral_before is the primary amount, tracked per mint on its config; legs count at
+    // the values cached for them on the vault.
+    let primary = primary_amount(vault);
+    let face_value = (primary as u128)
+        .checked_mul(collateral.face_value as u128)
+        .and_then(|value| value.checked_add(vault.leg_values.face_value as u128))
+        .and_then(|value| u64::try_from(value).ok())
+        .ok_or(ErrorCode::MathOverflow)?;
+    global.total_face_value = global
+        .total_face_value
+        .checked_sub(vault.collateral_face_value)
+        .and_then(|total| total.checked_add(face_value))
+        .ok_or(ErrorCode::MathOverflow)?;
+    vault.collateral_face_value = face_value;
+    collateral.total_collateral = collateral
+        .total_collateral
+        .checked_sub(collateral_before)
+        .and_then(|total| total.checked_add(primary))
+        .ok_or(ErrorCode::MathOverflow)?;
+    revalue_collateral(global, collateral, config)?;
+    global.total_collateral_value = global
+        .total_collateral_value
+        .checked_sub(vault.leg_collateral_value)
+        .and_then(|total| total.checked_add(vault.leg_values.haircut_value))
+        .ok_or(ErrorCode::MathOverflow)?;
+    vault.leg_collateral_value = vault.leg_values.haircut_value;
+    global.total_debt = global
+        .total_debt
+        .checked_sub(debt_before)
+        .and_then(|total| total.checked_add(vault.borrowed))
+        .ok_or(ErrorCode::MathOverflow)?;
+    collateral.total_debt = collateral
+        .total_debt
+        .checked_sub(debt_before)
+        .and_then(|total| total.checked_add(vault.borrowed))
+        .ok_or(ErrorCode::MathOverflow)?;
+    Ok(())
+}
+
+// The aggregate collateral value is refreshed one collateral type at a time, at the price and
+// haircut in effect whenever a vault of that type is touched.
+fn revalue_collateral(
+    global: &mut GlobalState,
+    collateral: &mut CollateralConfig,
+    config: &Config,
+) -> Result<()> {
+    let value = haircut_value(collateral.total_collateral, collateral, config)?;
+    let value = u64::try_from(value).map_err(|_| ErrorCode::MathOverflow)?;
+    global.total_collateral_value = global
+        .total_collateral_value
+        .checked_sub(collateral.collateral_value)
+        .and_then(|total| total.checked_add(value))
+        .ok_or(ErrorCode::MathOverflow)?;
+    collateral.collateral_value = value;
+    Ok(())
+}
+
+fn require_within_debt_ceiling(
+    collateral: &CollateralConfig,
+    debt_before: u64,
+    debt_after: u64,
+) -> Result<()> {
+    let total = collateral
+        .total_debt
+        .checked_sub(debt_before)
+        .and_then(|total| total.checked_add(debt_after))
+        .ok_or(ErrorCode::MathOverflow)?;
+    require!(total <= collateral.debt_ceiling, ErrorCode::DebtCeilingExceeded);
+    Ok(())
+}
+
+fn require_within_global_ceiling(
+    global: &GlobalState,
+    config: &Config,
+    debt_before: u64,
+    debt_after: u64,
+) -> Result<()> {
+    if config.max_total_debt == 0 {
+        return Ok(());
+    }
+    let total = global
+        .total_debt
+        .checked_sub(debt_before)
+        .and_then(|total| total.checked_add(debt_after))
+        .ok_or(ErrorCode::MathOverflow)?;
+    require!(
+        total <= config.max_total_debt,
+        ErrorCode::GlobalDebtCeilingExceeded
+    );
+    Ok(())
+}
+
+fn track_issuer_debt(
+    exposure: &mut IssuerExposure,
+    debt_before: u64,
+    debt_after: u64,
+) -> Result<()> {
+    exposure.total_debt = exposure
+        .total_debt
+        .checked_sub(debt_before)
+        .and_then(|total| total.checked_add(debt_after))
+        .ok_or(ErrorCode::MathOverflow)?;
+    Ok(())
+}
+
+fn require_within_market_ceiling(
+    market: &StablecoinMarket,
+    supply: u64,
+    minted: u64,
+) -> Result<()> {
+    let total = supply.checked_add(minted).ok_or(ErrorCode::MathOverflow)?;
+    require!(
+        total <= market.debt_ceiling,
+        ErrorCode::MarketDebtCeilingExceeded
+    );
+    Ok(())
+}
+
+fn require_within_issuer_cap(
+    exposure: &IssuerExposure,
+    debt_before: u64,
+    debt_after: u64,
+) -> Result<()> {
+    if exposure.debt_cap == 0 {
+        return Ok(());
+    }
+    let total = exposure
+        .total_debt
+        .checked_sub(debt_before)
+        .and_then(|total| total.checked_add(debt_after))
+        .ok_or(ErrorCode::MathOverflow)?;
+    require!(total <= exposure.debt_cap, ErrorCode::IssuerCapExceeded);
+    Ok(())
+}
+
+// The mint authority is a program PDA, so every stablecoin mint is signed with its seeds.
+fn mint_stablecoin<'info>(
+    token_program: AccountInfo<'info>,
+    accounts: token_interface::MintTo<'info>,
+    mint_authority_bump: u8,
+    amount: u64,
+) -> Result<()> {
+    let bump = [mint_authority_bump];
+    let signer_seeds: &[&[&[u8]]] = &[&[b"mint_authority", &bump]];
+    token_interface::mint_to(
+        CpiContext::new_with_signer(token_program, accounts, signer_seeds),
+        amount,
+    )
+}
+
+// Interest is split between the staking reward vault, the treasury, the reserve and the
+// insurance fund; stakers receive whatever the other shares leave over.
+fn mint_interest<'info>(
+    ledger: &mut SupplyLedger,
+    token_program: &Interface<'info, TokenInterface>,
+    stablecoin_mint: &InterfaceAccount<'info, Mint>,
+    mint_authority: &AccountInfo<'info>,
+    [staking_vault, treasury, reserve, insurance_fund]: [&InterfaceAccount<'info, TokenAccount>; 4],
+    config: &Config,
+    interest: u64,
+) -> Result<()> {
+    let share = |bps: u64| ((interest as u128) * (bps as u128) / 10000) as u64;
+    let treasury_share = share(config.treasury_share_bps);
+    let reserve_share = share(config.reserve_factor_bps);
+    let insurance_share = share(config.insurance_share_bps);
+    let staker_share = interest - treasury_share - reserve_share - insurance_share;
+    for (to, amount) in [
+        (treasury, treasury_share),
+        (reserve, reserve_share),
+        (insurance_fund, insurance_share),
+        (staking_vault, staker_share),
+    ] {
+        mint_stablecoin(
+            token_program.to_account_info(),
+            token_interface::MintTo {
+                mint: stablecoin_mint.to_account_info(),
+                to: to.to_account_info(),
+                authority: mint_authority.clone(),
+            },
+            config.mint_authority_bump,
+            amount,
+        )?;
+    }
+    record_mint(ledger, SupplySource::Interest, interest)
+}
+
+fn record_mint(ledger: &mut SupplyLedger, source: SupplySource, amount: u64) -> Result<()> {
+    let bucket = match source {
+        SupplySource::CdpDebt => &mut ledger.cdp_debt,
+        SupplySource::Interest => &mut ledger.interest,
+        SupplySource::Psm => &mut ledger.psm,
+        SupplySource::FlashMint => &mut ledger.flash_mint,
+        SupplySource::Bridge => {
+            ledger.bridged_net = ledger
+                .bridged_net
+                .checked_add(amount as i64)
+                .ok_or(ErrorCode::MathOverflow)?;
+            return Ok(());
+        }
+    };
+    *bucket = bucket.checked_add(amount).ok_or(ErrorCode::MathOverflow)?;
+    Ok(())
+}
+
+// Bad debt written off against burned stablecoin can include interest that was never
+// minted, so a CDP burn larger than the outstanding CDP debt takes the remainder out of the
+// interest bucket.
+fn record_burn(ledger: &mut SupplyLedger, source: SupplySource, amount: u64) -> Result<()> {
+    let bucket = match source {
+        SupplySource::CdpDebt => {
+            let from_debt = amount.min(ledger.cdp_debt);
+            ledger.cdp_debt -= from_debt;
+            ledger.interest = ledger
+                .interest
+                .checked_sub(amount - from_debt)
+                .ok_or(ErrorCode::SupplyLedgerUnderflow)?;
+            return Ok(());
+        }
+        SupplySource::Interest => &mut ledger.interest,
+        SupplySource::Psm => &mut ledger.psm,
+        SupplySource::FlashMint => &mut ledger.flash_mint,
+        SupplySource::Bridge => {
+            ledger.bridged_net = ledger
+                .bridged_net
+                .checked_sub(amount as i64)
+                .ok_or(ErrorCode::MathOverflow)?;
+            return Ok(());
+        }
+    };
+    *bucket = bucket
+        .checked_sub(amount)
+        .ok_or(ErrorCode::SupplyLedgerUnderflow)?;
+    Ok(())
+}
+
+// Repayments burn principal and settled interest together. The interest share comes out of
+// the bucket mint_interest credited, so this runs after the interest has been minted.
+fn record_repay_burn(ledger: &mut SupplyLedger, amount: u64, interest_paid: u64) -> Result<()> {
+    let principal = amount
+        .checked_sub(interest_paid)
+        .ok_or(ErrorCode::MathOverflow)?;
+    record_burn(ledger, SupplySource::CdpDebt, principal)?;
+    record_burn(ledger, SupplySource::Interest, interest_paid)
+}
+
+struct RepaymentAccounts<'a> {
+    global_state: &'a mut GlobalState,
+    collateral_config: &'a mut CollateralConfig,
+    issuer_exposure: &'a mut IssuerExposure,
+    sorted_vaults: &'a mut SortedVaults,
+    config: &'a Config,
+    rate_model: &'a InterestRateModel,
+}
+
+struct InterestMint<'a, 'info> {
+    ledger: &'a mut SupplyLedger,
+    token_program: &'a Interface<'info, TokenInterface>,
+    stablecoin_mint: &'a InterfaceAccount<'info, Mint>,
+    mint_authority: &'a AccountInfo<'info>,
+    recipients: [&'a InterfaceAccount<'info, TokenAccount>; 4],
+}
+
+struct Repayment {
+    collateral_before: u64,
+    debt_before: u64,
+    interest: u64,
+    repaid: u64,
+    now: i64,
+}
+
+// Every path that pays down a vault's debt ends here once the vault's collateral and borrowed
+// amount are final. Batched callers pass no interest mint and mint the settled interest for the
+// whole batch at the end.
+fn settle_repayment<'info>(
+    vault: &mut Account<'info, Vault>,
+    accounts: RepaymentAccounts<'_>,
+    interest_mint: Option<InterestMint<'_, 'info>>,
+    repayment: Repayment,
+    hint: ListHint,
+    node_infos: &'info [AccountInfo<'info>],
+) -> Result<u64> {
+    let Repayment {
+        collateral_before,
+        debt_before,
+        interest,
+        repaid,
+        now,
+    } = repayment;
+    checkpoint_debt(vault, accounts.collateral_config, now);
+    let interest_paid = settle_interest(vault, interest, repaid);
+    if let Some(mint) = interest_mint {
+        mint_interest(
+            mint.ledger,
+            mint.token_program,
+            mint.stablecoin_mint,
+            mint.mint_authority,
+            mint.recipients,
+            accounts.config,
+            interest_paid,
+        )?;
+        record_repay_burn(mint.ledger, repaid, interest_paid)?;
+    }
+
+    if vault.borrowed == 0 {
+        vault.term_maturity = 0;
+        vault.term_rate_bps = 0;
+        vault.fixed_rate_bps = 0;
+    }
+    let recovery = is_recovery_mode(accounts.global_state, accounts.config)?;
+    refresh_unhealthy_flag(
+        vault,
+        accounts.collateral_config,
+        accounts.rate_model,
+        accounts.config,
+        recovery,
+        now,
+    )?;
+    track_vault(
+        accounts.global_state,
+        accounts.collateral_config,
+        accounts.config,
+        collateral_before,
+        debt_before,
+        vault,
+    )?;
+    track_issuer_debt(accounts.issuer_exposure, debt_before, vault.borrowed)?;
+
+    let vault_key = vault.key();
+    update_sorted_position(accounts.sorted_vaults, vault_key, vault, hint, node_infos)?;
+    Ok(interest_paid)
+}
+
+// Rescales a token amount between mints, rounding down so the result is always backed.
+fn scale_decimals(amount: u64, from: u8, to: u8) -> Result<u64> {
+    let factor = 10u128.checked_pow(from.abs_diff(to) as u32);
+    let scaled = if to >= from {
+        factor.and_then(|factor| (amount as u128).checked_mul(factor))
+    } else {
+        Some(factor.map_or(0, |factor| amount as u128 / factor))
+    };
+    let scaled = scaled
+        .and_then(|value| u64::try_from(value).ok())
+        .ok_or(ErrorCode::MathOverflow)?;
+    Ok(scaled)
+}
+
+fn ledger_total(ledger: &SupplyLedger) -> i128 {
+    (ledger.cdp_debt as i128)
+        + (ledger.interest as i128)
+        + (ledger.psm as i128)
+        + (ledger.flash_mint as i128)
+        + (ledger.bridged_net as i128)
+}
+
+fn require_flash_burn_follows(instructions: &AccountInfo) -> Result<()> {
+    let mut index = load_current_index_checked(instructions)? as usize + 1;
+    while let Ok(ix) = load_instruction_at_checked(index, instructions) {
+        if ix.program_id == crate::ID
+            && ix
+                .data
+                .starts_with(&crate::instruction::FlashBurn::DISCRIMINATOR)
+        {
+            return Ok(());
+        }
+        index += 1;
+    }
+    err!(ErrorCode::FlashBurnMissing)
+}
+
+// Savings shares are priced against a virtual deposit so a first depositor can't inflate the
+// price by donating to the vault and round later deposits down to nothing.
+fn share_price(assets: u64, supply: u64) -> u128 {
+    (assets as u128 + SAVINGS_VIRTUAL_AMOUNT) * P_SCALE / (supply as u128 + SAVINGS_VIRTUAL_AMOUNT)
+}
+
+// The stablecoin mint carries a transfer hook, so every transfer has to forward the hook
+// program, its extra-account-metas list and the deny entries of both owners. Token-2022
+// resolves them by key, so callers may pass a superset.
+fn transfer_stablecoin<'info>(
+    token_program: AccountInfo<'info>,
+    accounts: TransferChecked<'info>,
+    hook_accounts: &[AccountInfo<'info>],
+    amount: u64,
+    decimals: u8,
+    signer_seeds: &[&[&[u8]]],
+) -> Result<()> {
+    invoke_transfer_checked(
+        token_program.key,
+        accounts.from,
+        accounts.mint,
+        accounts.to,
+        accounts.authority,
+        hook_accounts,
+        amount,
+        decimals,
+        signer_seeds,
+    )
+    .map_err(Into::into)
+}
+
+// Instructions that already consume remaining_accounts take the hook accounts from the tail.
+fn split_hook_accounts<'a, 'info>(
+    remaining_accounts: &'a [AccountInfo<'info>],
+    hook_accounts: u8,
+) -> Result<(&'a [AccountInfo<'info>], &'a [AccountInfo<'info>])> {
+    let split = remaining_accounts
+        .len()
+        .checked_sub(hook_accounts as usize)
+        .ok_or(ErrorCode::InvalidRemainingAccounts)?;
+    Ok(remaining_accounts.split_at(split))
+}
+
+// Crank bounties come out of the treasury and are capped at whatever it currently holds,
+// so an empty treasury never blocks the crank itself.
+fn pay_keeper_bounty<'info>(
+    token_program: &Interface<'info, TokenInterface>,
+    stablecoin_mint: &InterfaceAccount<'info, Mint>,
+    treasury: &InterfaceAccount<'info, TokenAccount>,
+    keeper_account: &InterfaceAccount<'info, TokenAccount>,
+    global_state: &Account<'info, GlobalState>,
+    hook_accounts: &[AccountInfo<'info>],
+    bounty: u64,
+) -> Result<u64> {
+    let amount = bounty.min(treasury.amount);
+    if amount == 0 {
+        return Ok(0);
+    }
+
+    let bump = [global_state.bump];
+    let signer_seeds: &[&[&[u8]]] = &[&[b"global_state", &bump]];
+    transfer_stablecoin(
+        token_program.to_account_info(),
+        TransferChecked {
+            from: treasury.to_account_info(),
+            mint: stablecoin_mint.to_account_info(),
+            to: keeper_account.to_account_info(),
+            authority: global_state.to_account_info(),
+        },
+        hook_accounts,
+        amount,
+        stablecoin_mint.decimals,
+        signer_seeds,
+    )?;
+
+    emit!(KeeperBountyPaidEvent {
+        keeper: keeper_account.owner,
+        amount,
+    });
+    Ok(amount)
+}
+
+// Accrual bounties never exceed the treasury's share of the interest they accrued, so
+// cranking a vault more often than its interest justifies doesn't pay.
+fn accrual_bounty(config: &Config, interest: u64) -> u64 {
+    let share = (interest as u128) * (config.treasury_share_bps as u128) / 10000;
+    config.keeper_bounty.min(share as u64)
+}
+
+// Interest is capitalized into debt as it accrues and only paid out once a repayment
+// covers it; repayments settle outstanding interest before principal.
+fn accrue_vault(
+    vault: &mut Vault,
+    collateral: &mut CollateralConfig,
+    exposure: &mut IssuerExposure,
+    global: &mut GlobalState,
+    rate_model: &InterestRateModel,
+    config: &Config,
+    now: i64,
+) -> Result<u64> {
+    // Accrual inside the minimum interval, or one that rounds to nothing, leaves the
+    // checkpoint untouched so repeated calls can't shave interest off through rounding.
+    if now - vault.last_borrow_timestamp < config.min_accrual_interval_secs {
+        return Ok(0);
+    }
+    let interest = pending_interest(vault, collateral, rate_model, config, now)?;
+    if interest == 0 {
+        return Ok(0);
+    }
+    let (collateral_before, debt_before) = (primary_amount(vault), vault.borrowed);
+    let new_debt = vault
+        .borrowed
+        .checked_add(interest)
+        .ok_or(ErrorCode::MathOverflow)?;
+    require_within_global_ceiling(global, config, debt_before, new_debt)?;
+
+    accrue_borrow_index(collateral, rate_model, now)?;
+    vault.borrowed = new_debt;
+    vault.unpaid_interest += interest;
+    checkpoint_debt(vault, collateral, now);
+    track_vault(global, collateral, config, collateral_before, debt_before, vault)?;
+    track_issuer_debt(exposure, debt_before, vault.borrowed)?;
+    Ok(interest)
+}
+
+fn settle_interest(vault: &mut Vault, accrued: u64, repaid: u64) -> u64 {
+    let outstanding = vault.unpaid_interest + accrued;
+    let paid = outstanding.min(repaid);
+    vault.unpaid_interest = outstanding - paid;
+    paid
+}
+
+fn psm_fee(amount: u64, fee_bps: u64) -> u64 {
+    ((amount as u128) * (fee_bps as u128) / 10000) as u64
+}
+
+fn borrow_fee(amount: u64, config: &Config) -> u64 {
+    ((amount as u128) * (config.borrow_fee_bps as u128) / 10000) as u64
+}
+
+// Borrowing, the PSM, flash mints and peg arbitrage are held to the per-transaction cap and
+// draw on the same rate-limit window. Interest and matured-bond proceeds are exempt since
+// they mint against value already owed or received, and bridge mints have their own window.
+fn limit_mint(config: &Config, rate_limit: &mut RateLimit, amount: u64) -> Result<()> {
+    require!(
+        config.max_mint_per_tx == 0 || amount <= config.max_mint_per_tx,
+        ErrorCode::MintCapExceeded
+    );
+    consume_mint_allowance(rate_limit, amount, Clock::get()?.slot)
+}
+
+// Windows are fixed-length slot ranges; the minted total resets when a new window begins.
+fn consume_mint_allowance(rate_limit: &mut RateLimit, amount: u64, slot: u64) -> Result<()> {
+    let window_end = rate_limit
+        .window_start
+        .saturating_add(rate_limit.window_slots);
+    if slot >= window_end {
+        rate_limit.window_start = slot - slot % rate_limit.window_slots;
+        rate_limit.minted_in_window = 0;
+    }
+    let minted = rate_limit
+        .minted_in_window
+        .checked_add(amount)
+        .ok_or(ErrorCode::MathOverflow)?;
+    require!(
+        rate_limit.max_mint_per_window == 0 || minted <= rate_limit.max_mint_per_window,
+        ErrorCode::MintRateLimited
+    );
+    rate_limit.minted_in_window = minted;
+    Ok(())
+}
+
+fn require_not_paused(config: &Config, flag: u64) -> Result<()> {
+    require!(
+        config.paused_flags & flag == 0,
+        ErrorCode::InstructionPaused
+    );
+    Ok(())
+}
+
+fn require_no_timelock(config: &Config) -> Result<()> {
+    require!(
+        config.param_change_delay_secs == 0,
+        ErrorCode::TimelockActive
+    );
+    Ok(())
+}
+
+// Zero leaves the ceiling or the step unbounded.
+fn validate_borrow_rate(config: &Config, previous_rate_bps: u64, rate_bps: u64) -> Result<()> {
+    require!(
+        config.max_borrow_rate_bps == 0 || rate_bps <= config.max_borrow_rate_bps,
+        ErrorCode::BorrowRateTooHigh
+    );
+    require!(
+        config.max_rate_step_bps == 0
+            || rate_bps.abs_diff(previous_rate_bps) <= config.max_rate_step_bps,
+        ErrorCode::RateStepTooLarge
+    );
+    Ok(())
+}
+
+fn validate_param_value(param: RiskParam, value: u64) -> Result<()> {
+    let valid = match param {
+        RiskParam::BorrowRate
+        | RiskParam::BaseRate
+        | RiskParam::Slope1
+        | RiskParam::Slope2
+        | RiskParam::LiquidationLtv => value <= 10000,
+        RiskParam::MaxLtv | RiskParam::Kink => value > 0 && value <= 10000,
+        RiskParam::ChangeDelay => value <= i64::MAX as u64,
+        RiskParam::DebtCeiling
+        | RiskParam::MarketDebtCeiling
+        | RiskParam::MaxTotalDebt
+        | RiskParam::MaxVaultDebt => true,
+    };
+    require!(valid, ErrorCode::InvalidParameter);
+    Ok(())
+}
+
+fn require_within_vault_cap(config: &Config, debt: u64) -> Result<()> {
+    require!(
+        config.max_vault_debt == 0 || debt <= config.max_vault_debt,
+        ErrorCode::VaultDebtCapExceeded
+    );
+    Ok(())
+}
+
+// Vaults due for settlement or past their loan term can be liquidated whatever their LTV.
+fn is_past_due(vault: &Vault, now: i64) -> bool {
+    vault.settlement_due || (vault.term_maturity != 0 && now >= vault.term_maturity)
+}
+
+fn require_grace_elapsed(vault: &Vault, config: &Config, now: i64) -> Result<()> {
+    if config.grace_period_secs == 0 {
+        return Ok(());
+    }
+    require!(vault.unhealthy_since != 0, ErrorCode::NotFlagged);
+    require!(
+        now - vault.unhealthy_since >= config.grace_period_secs,
+        ErrorCode::GracePeriodActive
+    );
+    Ok(())
+}
+
+fn refresh_unhealthy_flag(
+    vault: &mut Vault,
+    collateral: &CollateralConfig,
+    rate_model: &InterestRateModel,
+    config: &Config,
+    recovery: bool,
+    now: i64,
+) -> Result<()> {
+    let total_debt = vault
+        .borrowed
+        .checked_add(pending_interest(
+            vault, collateral, rate_model, config, now,
+        )?)
+        .ok_or(ErrorCode::MathOverflow)?;
+    if total_debt == 0
+        || !(is_past_due(vault, now)
+            || is_liquidatable(
+                &vault_values(vault, collateral, config)?,
+                total_debt,
+                config,
+                recovery,
+            )?)
+    {
+        vault.unhealthy_since = 0;
+    }
+    Ok(())
+}
+
+fn health_factor_bps(values: &CollateralValues, debt: u64) -> Result<u64> {
+    if debt == 0 {
+        return Ok(u64::MAX);
+    }
+    let health = (values.liquidation_value as u128) * 10000 / (debt as u128);
+    Ok(health.min(u64::MAX as u128) as u64)
+}
+
+// Deposits from an earlier epoch were fully absorbed, and a deposit two or more scales
+// behind has been reduced below one unit.
+fn compounded_deposit(pool: &StabilityPool, deposit: &StabilityDeposit) -> Result<u64> {
+    if deposit.initial_deposit == 0 || deposit.snapshot_epoch != pool.current_epoch {
+        return Ok(0);
+    }
+    let divisor = match pool.current_scale - deposit.snapshot_scale {
+        0 => deposit.snapshot_p,
+        1 => deposit.snapshot_p * SCALE_FACTOR,
+        _ => return Ok(0),
+    };
+    let compounded = (deposit.initial_deposit as u128)
+        .checked_mul(pool.product_p)
+        .ok_or(ErrorCode::MathOverflow)?
+        / divisor;
+    Ok(compounded as u64)
+}
+
+fn scale_sum(pool: &StabilityPool, epoch: u64, scale: u64) -> u128 {
+    if epoch == pool.current_epoch && scale == pool.current_scale {
+        return pool.sum_s;
+    }
+    pool.scale_sums
+        .iter()
+        .find(|entry| entry.epoch == epoch && entry.scale == scale)
+        .map_or(0, |entry| entry.sum)
+}
+
+// Gains accrue at the snapshot scale plus, rescaled, the scale after it; anything later
+// is below precision for this deposit.
+fn stability_collateral_gain(pool: &StabilityPool, deposit: &StabilityDeposit) -> Result<u64> {
+    if deposit.initial_deposit == 0 {
+        return Ok(0);
+    }
+    let (epoch, scale) = (deposit.snapshot_epoch, deposit.snapshot_scale);
+    let first = scale_sum(pool, epoch, scale).saturating_sub(deposit.snapshot_s);
+    let second = scale_sum(pool, epoch, scale + 1) / SCALE_FACTOR;
+    let gain = (deposit.initial_deposit as u128)
+        .checked_mul(first + second)
+        .ok_or(ErrorCode::MathOverflow)?
+        / deposit.snapshot_p;
+    Ok(gain as u64)
+}
+
+// Only the most recent MAX_SCALE_SUMS closed scales are kept.
+fn archive_scale_sum(pool: &mut StabilityPool) {
+    if pool.scale_sums.len() == MAX_SCALE_SUMS {
+        pool.scale_sums.remove(0);
+    }
+    pool.scale_sums.push(ScaleSum {
+        epoch: pool.current_epoch,
+        scale: pool.current_scale,
+        sum: pool.sum_s,
+    });
+    pool.sum_s = 0;
+}
+
+// Emptying the pool starts a new epoch; otherwise P is rescaled by SCALE_FACTOR whenever
+// it would fall below it so the product never truncates to zero.
+fn offset_stability_pool(
+    pool: &mut StabilityPool,
+    debt_offset: u64,
+    seize_amount: u64,
+) -> Result<()> {
+    let total_deposits = pool.total_deposits as u128;
+    pool.sum_s = pool
+        .sum_s
+        .checked_add((seize_amount as u128) * pool.product_p / total_deposits)
+        .ok_or(ErrorCode::MathOverflow)?;
+    pool.total_deposits -= debt_offset;
+
+    if pool.total_deposits == 0 {
+        archive_scale_sum(pool);
+        pool.current_epoch += 1;
+        pool.current_scale = 0;
+        pool.product_p = P_SCALE;
+        return Ok(());
+    }
+
+    let scaled = pool.product_p * pool.total_deposits as u128;
+    let mut product_p = scaled / total_deposits;
+    let mut factor = 1;
+    while product_p < SCALE_FACTOR && factor < SCALE_FACTOR * SCALE_FACTOR {
+        if factor == 1 {
+            archive_scale_sum(pool);
+        }
+        factor *= SCALE_FACTOR;
+        pool.current_scale += 1;
+        product_p =
+            scaled / total_deposits * factor + (scaled % total_deposits) * factor / total_deposits;
+    }
+    pool.product_p = product_p;
+    Ok(())
+}
+
+// Positions only refresh when a vault is touched, so the redemption walk is checked against
+// live debt; a stale position has to be fixed with reinsert_vault first.
+fn check_redemption_order(vault: &Vault, debt: u64, last_cr: u128) -> Result<u128> {
+    if debt == 0 {
+        return Ok(last_cr);
+    }
+    let cr = (vault.collateral_face_value as u128) * NICR_PRECISION / (debt as u128);
+    require!(cr >= last_cr, ErrorCode::RedemptionOrder);
+    Ok(cr)
+}
+
+fn nominal_cr(vault: &Vault) -> u128 {
+    if vault.borrowed == 0 {
+        return u128::MAX;
+    }
+    (vault.collateral_face_value as u128) * NICR_PRECISION / (vault.borrowed as u128)
+}
+
+fn list_node<'a, 'info>(
+    nodes: &'a mut [Account<'info, Vault>],
+    key: Pubkey,
+) -> Result<&'a mut Account<'info, Vault>> {
+    nodes
+        .iter_mut()
+        .find(|node| node.key() == key)
+        .ok_or_else(|| error!(ErrorCode::MissingListNode))
+}
+
+fn update_sorted_position<'info>(
+    list: &mut SortedVaults,
+    vault_key: Pubkey,
+    vault: &mut Vault,
+    hint: ListHint,
+    node_infos: &'info [AccountInfo<'info>],
+) -> Result<()> {
+    let mut nodes: Vec<Account<'info, Vault>> = Vec::with_capacity(node_infos.len());
+    // Batched callers share one node slice across vaults, so the vault being moved may appear
+    // in it and is skipped.
+    for info in node_infos {
+        if info.key() == vault_key {
+            continue;
+        }
+        require!(
+            nodes.iter().all(|node| node.key() != info.key()),
+            ErrorCode::InvalidListHint
+        );
+        nodes.push(Account::<Vault>::try_from(info)?);
+    }
+
+    if vault.in_list {
+        let (prev, next) = (vault.prev, vault.next);
+        if list.head == vault_key {
+            list.head = next;
+        } else {
+            list_node(&mut nodes, prev)?.next = next;
+        }
+        if list.tail == vault_key {
+            list.tail = prev;
+        } else {
+            list_node(&mut nodes, next)?.prev = prev;
+        }
+        list.size -= 1;
+        vault.in_list = false;
+        vault.prev = Pubkey::default();
+        vault.next = Pubkey::default();
+    }
+
+    if vault.borrowed > 0 {
+        let cr = nominal_cr(vault);
+        if hint.prev == Pubkey::default() {
+            require!(list.head == hint.next, ErrorCode::InvalidListHint);
+        } else {
+            let prev = list_node(&mut nodes, hint.prev)?;
+            require!(
+                prev.in_list && prev.next == hint.next && nominal_cr(prev) <= cr,
+                ErrorCode::InvalidListHint
+            );
+            prev.next = vault_key;
+        }
+        if hint.next == Pubkey::default() {
+            require!(list.tail == hint.prev, ErrorCode::InvalidListHint);
+        } else {
+            let next = list_node(&mut nodes, hint.next)?;
+            require!(
+                next.in_list && next.prev == hint.prev && cr <= nominal_cr(next),
+                ErrorCode::InvalidListHint
+            );
+            next.prev = vault_key;
+        }
+        if hint.prev == Pubkey::default() {
+            list.head = vault_key;
+        }
+        if hint.next == Pubkey::default() {
+            list.tail = vault_key;
+        }
+        list.size += 1;
+        vault.in_list = true;
+        vault.prev = hint.prev;
+        vault.next = hint.next;
+    }
+
+    for node in nodes.iter() {
+        node.exit(&crate::ID)?;
+    }
+
+    Ok(())
+}
+
+fn invoke_swap<'info>(
+    swap_program: &AccountInfo<'info>,
+    accounts: &[AccountInfo<'info>],
+    data: Vec<u8>,
+) -> Result<()> {
+    let metas = accounts
+        .iter()
+        .map(|account| AccountMeta {
+            pubkey: account.key(),
+            is_signer: account.is_signer,
+            is_writable: account.is_writable,
+        })
+        .collect();
+    invoke(
+        &Instruction {
+            program_id: swap_program.key(),
+            accounts: metas,
+            data,
+        },
+        accounts,
+    )?;
+    Ok(())
+}
+
+fn pol_balances(accounts: &ManagePol) -> (u64, u64, u64) {
+    (
+        accounts.pol_stablecoin.amount,
+        accounts.pol_usdc.amount,
+        accounts.pol_lp.amount,
+    )
+}
+
+fn reload_pol_balances(accounts: &mut ManagePol) -> Result<()> {
+    accounts.pol_stablecoin.reload()?;
+    accounts.pol_usdc.reload()?;
+    accounts.pol_lp.reload()?;
+    Ok(())
+}
+
+// The AMM call is signed by the pol PDA and must reference the designated pool.
+fn invoke_pol<'info>(
+    accounts: &ManagePol<'info>,
+    remaining: &[AccountInfo<'info>],
+    data: Vec<u8>,
+) -> Result<()> {
+    let pol = &accounts.pol;
+    require!(
+        remaining.iter().any(|account| account.key() == pol.pool),
+        ErrorCode::InvalidRemainingAccounts
+    );
+    let pol_key = pol.key();
+    let metas = remaining
+        .iter()
+        .map(|account| AccountMeta {
+            pubkey: account.key(),
+            is_signer: account.is_signer || account.key() == pol_key,
+            is_writable: account.is_writable,
+        })
+        .collect();
+    let bump = [pol.bump];
+    let signer_seeds: &[&[&[u8]]] = &[&[b"pol", &bump]];
+    invoke_signed(
+        &Instruction {
+            program_id: accounts.amm_program.key(),
+            accounts: metas,
+            data,
+        },
+        remaining,
+        signer_seeds,
+    )?;
+    Ok(())
+}
+
+fn return_pol_stablecoin<'info>(
+    accounts: &ManagePol<'info>,
+    hook_accounts: &[AccountInfo<'info>],
+    amount: u64,
+) -> Result<()> {
+    if amount == 0 {
+        return Ok(());
+    }
+    let bump = [accounts.pol.bump];
+    let signer_seeds: &[&[&[u8]]] = &[&[b"pol", &bump]];
+    transfer_stablecoin(
+        accounts.stablecoin_token_program.to_account_info(),
+        TransferChecked {
+            from: accounts.pol_stablecoin.to_account_info(),
+            mint: accounts.stablecoin_mint.to_account_info(),
+            to: accounts.treasury.to_account_info(),
+            authority: accounts.pol.to_account_info(),
+        },
+        hook_accounts,
+        amount,
+        accounts.stablecoin_mint.decimals,
+        signer_seeds,
+    )
+}
+
+struct LiquidationPlan {
+    interest: u64,
+    total_debt: u64,
+    repay_amount: u64,
+    seize_amount: u64,
+    surplus: u64,
+}
+
+// An unset close factor means liquidations may close the whole debt.
+fn max_liquidation_repay(total_debt: u64, config: &Config) -> u64 {
+    let close_factor_bps = if config.close_factor_bps == 0 {
+        10000
+    } else {
+        config.close_factor_bps
+    };
+    let closeable = ((total_debt as u128) * (close_factor_bps as u128) / 10000) as u64;
+    if total_debt - closeable < config.min_debt {
+        total_debt
+    } else {
+        closeable
+    }
+}
+
+fn plan_liquidation(
+    vault: &Vault,
+    collateral: &CollateralConfig,
+    seized: &CollateralConfig,
+    rate_model: &InterestRateModel,
+    config: &Config,
+    recovery: bool,
+    now: i64,
+    repay_amount: u64,
+    seizable: u64,
+) -> Result<LiquidationPlan> {
+    let interest = pending_interest(vault, collateral, rate_model, config, now)?;
+    let total_debt = vault
+        .borrowed
+        .checked_add(interest)
+        .ok_or(ErrorCode::MathOverflow)?;
+    require!(total_debt > 0, ErrorCode::NothingToLiquidate);
+    let values = vault_values(vault, collateral, config)?;
+    require!(
+        is_past_due(vault, now) || is_liquidatable(&values, total_debt, config, recovery)?,
+        ErrorCode::VaultHealthy
+    );
+    require_grace_elapsed(vault, config, now)?;
+    require!(repay_amount > 0, ErrorCode::InvalidAmount);
+    require!(
+        repay_amount <= max_liquidation_repay(total_debt, config),
+        ErrorCode::ExceedsCloseFactor
+    );
+
+    // Closing liquidations buy out all collateral at the penalty discount; whatever
+    // the sale raises beyond the debt is held as surplus for the vault owner. That is
+    // only possible when the seized escrow holds every bond backing the vault. The seized
+    // collateral is priced and penalised with its own config.
+    let sale_price =
+        (values.market_value as u128) * 10000 / (10000 + seized.liquidation_penalty_bps as u128);
+    let (seize_amount, surplus) = if repay_amount == total_debt
+        && seizable == vault.bond_amount
+        && sale_price > total_debt as u128
+    {
+        (vault.bond_amount, sale_price as u64 - total_debt)
+    } else {
+        let seize_value =
+            (repay_amount as u128) * (10000 + seized.liquidation_penalty_bps as u128) / 10000;
+        let seize_amount = (seize_value / seized.unit_value as u128).min(seizable as u128) as u64;
+        (seize_amount, 0)
+    };
+    require!(
+        seize_amount > 0 || repay_amount == total_debt,
+        ErrorCode::LiquidationTooSmall
+    );
+
+    Ok(LiquidationPlan {
+        interest,
+        total_debt,
+        repay_amount,
+        seize_amount,
+        surplus,
+    })
+}
+
+// Kinked model: the rate climbs along slope1 up to the kink utilization of the
+// collateral's debt ceiling, then along the steeper slope2 above it.
+fn model_rate_bps(rate_model: &InterestRateModel, collateral: &CollateralConfig) -> Result<u64> {
+    let utilization_bps = if collateral.debt_ceiling == 0 {
+        0
+    } else {
+        ((collateral.total_debt as u128) * 10000 / (collateral.debt_ceiling as u128)).min(10000)
+            as u64
+    };
+    let kink = rate_model.kink_bps;
+    let rate = if utilization_bps <= kink {
+        rate_model
+            .base_rate_bps
+            .checked_add(rate_model.slope1_bps * utilization_bps / kink)
+    } else {
+        rate_model
+            .base_rate_bps
+            .checked_add(rate_model.slope1_bps)
+            .and_then(|rate| {
+                rate.checked_add(rate_model.slope2_bps * (utilization_bps - kink) / (10000 - kink))
+            })
+    }
+    .ok_or(ErrorCode::MathOverflow)?;
+    Ok(peg_adjusted_rate_bps(rate_model, rate))
+}
+
+// Below the peg the rate rises by peg_sensitivity_bps of the deviation to pull supply in,
+// above it the rate falls, within max_peg_adjustment_bps either way.
+fn peg_adjusted_rate_bps(rate_model: &InterestRateModel, rate: u64) -> u64 {
+    if rate_model.twap_price == 0 {
+        return rate;
+    }
+    let deviation_bps =
+        (PEG_PRICE as i128 - rate_model.twap_price as i128) * 10000 / PEG_PRICE as i128;
+    let max_adjustment = rate_model.max_peg_adjustment_bps as i128;
+    let adjustment = (deviation_bps * rate_model.peg_sensitivity_bps as i128 / 10000)
+        .clamp(-max_adjustment, max_adjustment);
+    (rate as i128 + adjustment).max(0) as u64
+}
+
+// Primary collateral and every leg pay the primary config's rate for their own rating,
+// weighted by face value.
+fn rating_rate_bps(vault: &Vault, collateral: &CollateralConfig) -> u64 {
+    let primary_rate = collateral.rating_rates_bps[collateral.rating as usize];
+    let primary_face = (primary_amount(vault) as u128) * (collateral.face_value as u128);
+    let total_face = primary_face + vault.leg_values.face_value as u128;
+    if total_face == 0 {
+        return primary_rate;
+    }
+    let mut weighted = primary_face * (primary_rate as u128);
+    let legs = vault.leg_rating_amounts.iter().zip(collateral.rating_rates_bps);
+    for (face_value, rate) in legs {
+        weighted += (*face_value as u128) * (rate as u128);
+    }
+    (weighted / total_face) as u64
+}
+
+// Leg values are cached on the vault, re-priced with the leg's own config whenever the leg is
+// touched, and their face value is bucketed by rating so accrual doesn't need every leg account.
+fn revalue_leg(
+    vault: &mut Vault,
+    leg: &mut CollateralLeg,
+    rating: CreditRating,
+    collateral: &CollateralConfig,
+    config: &Config,
+) -> Result<()> {
+    let values = collateral_values(leg.bond_amount, collateral, config)?;
+    vault.leg_rating_amounts[leg.rating as usize] -= leg.values.face_value;
+    leg.rating = rating;
+    vault.leg_rating_amounts[rating as usize] += values.face_value;
+    vault.leg_values = vault
+        .leg_values
+        .checked_sub(&leg.values)?
+        .checked_add(&values)?;
+    leg.values = values;
+    Ok(())
+}
+
+// The vault's leg values as they would be with this leg holding amount.
+fn replace_leg_values(
+    vault: &Vault,
+    leg: &CollateralLeg,
+    amount: u64,
+    collateral: &CollateralConfig,
+    config: &Config,
+) -> Result<CollateralValues> {
+    vault
+        .leg_values
+        .checked_sub(&leg.values)?
+        .checked_add(&collateral_values(amount, collateral, config)?)
+}
+
+// The collateral's rate is charged on top of the protocol-wide model rate; both compound
+// through the collateral's borrow index.
+fn index_rate_bps(collateral: &CollateralConfig, rate_model: &InterestRateModel) -> Result<u64> {
+    let rate = model_rate_bps(rate_model, collateral)?
+        .checked_add(collateral.borrow_rate_bps)
+        .ok_or(ErrorCode::MathOverflow)?;
+    Ok(rate)
+}
+
+fn variable_rate_bps(
+    vault: &Vault,
+    collateral: &CollateralConfig,
+    rate_model: &InterestRateModel,
+) -> Result<u64> {
+    let rate = index_rate_bps(collateral, rate_model)?
+        .checked_add(rating_rate_bps(vault, collateral))
+        .ok_or(ErrorCode::MathOverflow)?;
+    Ok(rate)
+}
+
+// Compounds continuously since the last checkpoint, using the first terms of e^(rt) - 1.
+fn current_borrow_index(
+    collateral: &CollateralConfig,
+    rate_model: &InterestRateModel,
+    now: i64,
+) -> Result<u128> {
+    let start = collateral.index_updated_at;
+    if now <= start {
+        return Ok(collateral.borrow_index);
+    }
+    let rate = index_rate_bps(collateral, rate_model)?;
+    let switch_at = collateral.rate_effective_at;
+    if switch_at == 0 || switch_at >= now {
+        return compound_index(collateral.borrow_index, rate, now - start);
+    }
+
+    // A scheduled rate that took effect since the last accrual splits the period in two.
+    let switch_at = switch_at.max(start);
+    let new_rate = (rate - collateral.borrow_rate_bps)
+        .checked_add(collateral.scheduled_borrow_rate_bps)
+        .ok_or(ErrorCode::MathOverflow)?;
+    let index = compound_index(collateral.borrow_index, rate, switch_at - start)?;
+    compound_index(index, new_rate, now - switch_at)
+}
+
+fn compound_index(index: u128, rate_bps: u64, elapsed: i64) -> Result<u128> {
+    if elapsed <= 0 {
+        return Ok(index);
+    }
+    let mul = |a: u128, b: u128| a.checked_mul(b).ok_or(ErrorCode::MathOverflow);
+    let x = mul(rate_bps as u128 * P_SCALE / 10000, elapsed as u128)? / SECONDS_PER_YEAR as u128;
+    let x2 = mul(x, x)? / P_SCALE;
+    let x3 = mul(x2, x)? / P_SCALE;
+    let growth = P_SCALE + x + x2 / 2 + x3 / 6;
+    Ok(mul(index, growth)? / P_SCALE)
+}
+
+// Must run before the collateral's total debt changes, so the elapsed period is
+// compounded at the utilization that actually applied to it.
+fn accrue_borrow_index(
+    collateral: &mut CollateralConfig,
+    rate_model: &InterestRateModel,
+    now: i64,
+) -> Result<()> {
+    collateral.borrow_index = current_borrow_index(collateral, rate_model, now)?;
+    collateral.index_updated_at = now;
+    if collateral.rate_effective_at != 0 && now >= collateral.rate_effective_at {
+        collateral.borrow_rate_bps = collateral.scheduled_borrow_rate_bps;
+        collateral.scheduled_borrow_rate_bps = 0;
+        collateral.rate_effective_at = 0;
+    }
+    Ok(())
+}
+
+// Model changes only apply from now on, so every collateral index is accrued under the old
+// parameters first; all collateral configs must be passed, once each.
+fn accrue_all_collateral<'info>(
+    config: &Config,
+    rate_model: &InterestRateModel,
+    collateral_infos: &'info [AccountInfo<'info>],
+    now: i64,
+) -> Result<()> {
+    require!(
+        collateral_infos.len() == config.collateral_config_count as usize,
+        ErrorCode::InvalidRemainingAccounts
+    );
+    for (i, info) in collateral_infos.iter().enumerate() {
+        require!(
+            collateral_infos[..i]
+                .iter()
+                .all(|other| other.key() != info.key()),
+            ErrorCode::InvalidRemainingAccounts
+        );
+        let mut collateral = Account::<CollateralConfig>::try_from(info)?;
+        accrue_borrow_index(&mut collateral, rate_model, now)?;
+        collateral.exit(&crate::ID)?;
+    }
+    Ok(())
+}
+
+// Re-bases the vault's debt at `now`; the collateral index must already be accrued.
+fn checkpoint_debt(vault: &mut Vault, collateral: &CollateralConfig, now: i64) {
+    vault.last_borrow_timestamp = now;
+    vault.index_snapshot = collateral.borrow_index;
+}
+
+// Fixed-rate vaults carry the debt-weighted average of the rates in force at each borrow.
+fn lock_fixed_rate(
+    vault: &mut Vault,
+    collateral: &CollateralConfig,
+    rate_model: &InterestRateModel,
+    prior_debt: u64,
+    new_debt: u64,
+) -> Result<()> {
+    if vault.rate_mode != RateMode::Fixed || new_debt == 0 {
+        return Ok(());
+    }
+    let current_rate = variable_rate_bps(vault, collateral, rate_model)?;
+    let weighted = (prior_debt as u128) * (vault.fixed_rate_bps as u128)
+        + ((new_debt - prior_debt) as u128) * (current_rate as u128);
+    vault.fixed_rate_bps = (weighted / new_debt as u128) as u64;
+    Ok(())
+}
+
+fn pending_interest(
+    vault: &Vault,
+    collateral: &CollateralConfig,
+    rate_model: &InterestRateModel,
+    config: &Config,
+    now: i64,
+) -> Result<u64> {
+    let elapsed = now - vault.last_borrow_timestamp;
+    let accrue = |rate_bps: u64, secs: i64| -> u128 {
+        ((vault.borrowed as u128) * (rate_bps as u128) * (secs as u128)) / (10000 * 365 * 24 * 3600)
+    };
+
+    // Fixed-term vaults pay their locked rate up to maturity and the late rate after it.
+    let interest = if vault.term_maturity != 0 {
+        let on_term = (vault.term_maturity.min(now) - vault.last_borrow_timestamp).max(0);
+        accrue(vault.term_rate_bps, on_term) + accrue(config.late_rate_bps, elapsed - on_term)
+    } else if vault.rate_mode == RateMode::Fixed {
+        accrue(vault.fixed_rate_bps, elapsed)
+    } else if vault.borrowed == 0 {
+        0
+    } else {
+        // Variable debt grows with the collateral's borrow index; the vault's own rating
+        // spread accrues on top of it.
+        // A vault that never took a snapshot has nothing compounded yet.
+        let index = current_borrow_index(collateral, rate_model, now)?;
+        let compounded = if vault.index_snapshot == 0 {
+            vault.borrowed as u128
+        } else {
+            (vault.borrowed as u128)
+                .checked_mul(index)
+                .ok_or(ErrorCode::MathOverflow)?
+                / vault.index_snapshot
+        };
+        compounded.saturating_sub(vault.borrowed as u128)
+            + accrue(rating_rate_bps(vault, collateral), elapsed)
+    };
+
+    Ok(interest as u64)
+}
+
+#[account]
+pub struct Vault {
+    pub owner: Pubkey,
+    pub creator: Pubkey,
+    pub position_mint: Pubkey,
+    pub pending_owner: Pubkey,
+    pub delegate: Pubkey,
+    pub collateral_mint: Pubkey,
+    pub receipt_mint: Pubkey,
+    pub bond_amount: u64,
+    pub leg_amount: u64,
+    pub borrowed: u64,
+    pub last_borrow_timestamp: i64,
+    pub prev: Pubkey,
+    pub next: Pubkey,
+    pub in_list: bool,
+    pub surplus: u64,
+    pub unhealthy_since: i64,
+    pub settlement_due: bool,
+    pub pending_withdrawal: u64,
+    pub withdrawal_ready_at: i64,
+    pub withdrawal_window_start: i64,
+    pub window_withdrawn: u64,
+    pub term_maturity: i64,
+    pub term_rate_bps: u64,
+    pub rate_mode: RateMode,
+    pub fixed_rate_bps: u64,
+    pub leg_rating_amounts: [u64; 5],
+    pub index_snapshot: u128,
+    pub unpaid_interest: u64,
+    pub leg_values: CollateralValues,
+    pub collateral_face_value: u64,
+    pub leg_collateral_value: u64,
+    pub bump: u8,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq)]
+pub enum RateMode {
+    Variable,
+    Fixed,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq)]
+pub enum PriceSource {
+    Pyth,
+    Switchboard,
+    Custodian,
+    Chainlink,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq)]
+pub enum FeeSource {
+    Treasury,
+    Reserve,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq)]
+pub enum ArbDirection {
+    MintAndSell,
+    BuyAndRedeem,
+}
+
+#[account]
+pub struct CollateralLeg {
+    pub vault: Pubkey,
+    pub mint: Pubkey,
+    pub escrow: Pubkey,
+    pub bond_amount: u64,
+    pub rating: CreditRating,
+    pub values: CollateralValues,
+    pub bump: u8,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Default)]
+pub struct CollateralValues {
+    pub face_value: u64,
+    pub market_value: u64,
+    pub haircut_value: u64,
+    pub borrow_value: u64,
+    pub liquidation_value: u64,
+}
+
+impl CollateralValues {
+    fn checked_add(&self, other: &CollateralValues) -> Result<CollateralValues> {
+        self.combine(other, u64::checked_add)
+    }
+
+    fn checked_sub(&self, other: &CollateralValues) -> Result<CollateralValues> {
+        self.combine(other, u64::checked_sub)
+    }
+
+    fn combine(
+        &self,
+        other: &CollateralValues,
+        op: impl Fn(u64, u64) -> Option<u64>,
+    ) -> Result<CollateralValues> {
+        let field = |a: u64, b: u64| op(a, b).ok_or(ErrorCode::MathOverflow);
+        Ok(CollateralValues {
+            face_value: field(self.face_value, other.face_value)?,
+            market_value: field(self.market_value, other.market_value)?,
+            haircut_value: field(self.haircut_value, other.haircut_value)?,
+            borrow_value: field(self.borrow_value, other.borrow_value)?,
+            liquidation_value: field(self.liquidation_value, other.liquidation_value)?,
+        })
+    }
+}
+
+#[account]
+pub struct CollateralApproval {
+    pub mint: Pubkey,
+    pub issuer: Pubkey,
+    pub bump: u8,
+}
+
+#[account]
+pub struct IssuerExposure {
+    pub issuer: Pubkey,
+    pub debt_cap: u64,
+    pub total_debt: u64,
+    pub bump: u8,
+}
+
+#[account]
+pub struct CollateralConfig {
+    pub mint: Pubkey,
+    pub issuer: Pubkey,
+    pub max_ltv_bps: u64,
+    pub liquidation_ltv_bps: u64,
+    pub liquidation_penalty_bps: u64,
+    pub borrow_rate_bps: u64,
+    pub debt_ceiling: u64,
+    pub total_debt: u64,
+    pub rating: CreditRating,
+    pub face_value: u64,
+    pub unit_value: u64,
+    pub value_updated_at: i64,
+    pub rating_rates_bps: [u64; 5],
+    pub borrow_index: u128,
+    pub index_updated_at: i64,
+    pub oracle_feeds: [OracleFeed; MAX_ORACLE_FEEDS],
+    pub oracle_feed_count: u8,
+    pub oracle_quorum: u8,
+    pub max_price_age_secs: u64,
+    pub max_confidence_bps: u64,
+    pub min_unit_value: u64,
+    pub max_unit_value: u64,
+    pub max_price_change_bps: u64,
+    pub pending_unit_value: u64,
+    pub borrowing_paused: bool,
+    pub currency: Currency,
+    pub stablecoin_mint: Pubkey,
+    pub market_currency: Currency,
+    pub scheduled_borrow_rate_bps: u64,
+    pub rate_effective_at: i64,
+    pub total_collateral: u64,
+    pub collateral_value: u64,
+    pub bump: u8,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq)]
+pub struct OracleFeed {
+    pub source: PriceSource,
+    pub id: [u8; 32],
+}
+
+#[account]
+pub struct FxFeed {
+    pub currency: Currency,
+    pub feed_id: [u8; 32],
+    pub max_price_age_secs: u64,
+    pub bump: u8,
+}
+
+// Supply of the market's mint stands in for its outstanding debt when enforcing the
+// ceiling, so the cap covers everything minted in that currency.
+#[account]
+pub struct StablecoinMarket {
+    pub mint: Pubkey,
+    pub currency: Currency,
+    pub debt_ceiling: u64,
+    pub bump: u8,
+}
+
+#[account]
+pub struct PendingParamChanges {
+    pub next_id: u64,
+    pub changes: Vec<ParamChange>,
+    pub bump: u8,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq)]
+pub struct ParamChange {
+    pub id: u64,
+    pub param: RiskParam,
+    pub target: Pubkey,
+    pub value: u64,
+    pub eta: i64,
+}
+
+// Collateral parameters target the collateral mint and the market ceiling targets the
+// stablecoin mint; Config and rate model parameters ignore the target.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq)]
+pub enum RiskParam {
+    BorrowRate,
+    MaxLtv,
+    LiquidationLtv,
+    DebtCeiling,
+    MarketDebtCeiling,
+    MaxTotalDebt,
+    MaxVaultDebt,
+    BaseRate,
+    Slope1,
+    Slope2,
+    Kink,
+    ChangeDelay,
+}
+
+#[account]
+pub struct FeedRegistry {
+    pub entries: Vec<FeedEntry>,
+    pub bump: u8,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq)]
+pub struct FeedEntry {
+    pub mint: Pubkey,
+    pub feeds: [OracleFeed; MAX_ORACLE_FEEDS],
+    pub feed_count: u8,
+    pub quorum: u8,
+    pub max_price_age_secs: u64,
+    pub max_confidence_bps: u64,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq)]
+pub enum SupplySource {
+    CdpDebt,
+    Interest,
+    Psm,
+    FlashMint,
+    Bridge,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq)]
+pub enum Currency {
+    Usd,
+    Eur,
+    Gbp,
+}
+
+impl Currency {
+    pub fn code(&self) -> &'static [u8] {
+        match self {
+            Currency::Usd => b"USD",
+            Currency::Eur => b"EUR",
+            Currency::Gbp => b"GBP",
+        }
+    }
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq)]
+pub enum RegistryAction {
+    Added,
+    Updated,
+    Removed,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq)]
+pub enum CreditRating {
+    Aaa,
+    Aa,
+    A,
+    Bbb,
+    HighYield,
+}
+
+#[account]
+pub struct Config {
+    pub admin: Pubkey,
+    pub min_debt: u64,
+    pub close_factor_bps: u64,
+    pub redemption_fee_bps: u64,
+    pub critical_cr_bps: u64,
+    pub grace_period_secs: i64,
+    pub rating_haircuts_bps: [u64; 5],
+    pub discount_rate_bps: u64,
+    pub max_total_debt: u64,
+    pub max_vault_debt: u64,
+    pub max_mint_per_tx: u64,
+    pub borrow_fee_bps: u64,
+    pub treasury_share_bps: u64,
+    pub reserve_factor_bps: u64,
+    pub total_fees_claimed: u64,
+    pub withdraw_threshold: u64,
+    pub withdraw_delay_secs: i64,
+    pub late_rate_bps: u64,
+    pub keeper_bounty: u64,
+    pub min_accrual_interval_secs: i64,
+    pub risk_authority: Pubkey,
+    pub rating_spreads_bps: [u64; 5],
+    pub oracle_manager: Pubkey,
+    pub flash_fee_bps: u64,
+    pub reserve_target: u64,
+    pub min_surplus_distribution: u64,
+    pub governance_mint: Pubkey,
+    pub debt_auction_duration_secs: i64,
+    pub min_bid_decrement_bps: u64,
+    pub surplus_buffer_cap: u64,
+    pub surplus_auction_duration_secs: i64,
+    pub min_bid_increment_bps: u64,
+    pub insurance_share_bps: u64,
+    pub guardian: Pubkey,
+    pub settlement_wait_secs: i64,
+    pub arb_threshold_bps: u64,
+    pub stablecoin_mint: Pubkey,
+    pub mint_authority_bump: u8,
+    pub bump: u8,
+    pub pending_admin: Pubkey,
+    pub param_change_delay_secs: i64,
+    pub max_borrow_rate_bps: u64,
+    pub max_rate_step_bps: u64,
+    pub paused_flags: u64,
+    pub collateral_config_count: u64,
+}
+
+#[account]
+pub struct PriceCache {
+    pub mint: Pubkey,
+    pub unit_value: u64,
+    pub sources_used: u8,
+    pub updated_at: i64,
+    pub emergency_unit_value: u64,
+    pub emergency_expires_at: i64,
+    pub manual_unit_value: u64,
+    pub manual_expires_at: i64,
+    pub bump: u8,
+}
+
+#[account]
+pub struct PriceTwap {
+    pub mint: Pubkey,
+    pub window_secs: i64,
+    pub last_value: u64,
+    pub last_update: i64,
+    pub twap_value: u64,
+    pub bump: u8,
+}
+
+#[account]
+pub struct NavAttestation {
+    pub mint: Pubkey,
+    pub custodian: Pubkey,
+    pub nav: u64,
+    pub attested_at: i64,
+    pub bump: u8,
+}
+
+#[account]
+pub struct YieldCurve {
+    pub publisher: Pubkey,
+    pub tenors_secs: [i64; YIELD_CURVE_POINTS],
+    pub rates_bps: [u64; YIELD_CURVE_POINTS],
+    pub point_count: u8,
+    pub updated_at: i64,
+    pub bump: u8,
+}
+
+#[account]
+pub struct InterestRateModel {
+    pub base_rate_bps: u64,
+    pub slope1_bps: u64,
+    pub slope2_bps: u64,
+    pub kink_bps: u64,
+    pub peg_feeder: Pubkey,
+    pub peg_sensitivity_bps: u64,
+    pub max_peg_adjustment_bps: u64,
+    pub twap_window_secs: i64,
+    pub last_price: u64,
+    pub last_price_at: i64,
+    pub twap_price: u64,
+    pub bump: u8,
+}
+
+#[account]
+pub struct RedemptionFeeState {
+    pub base_rate: u128,
+    pub last_redemption_at: i64,
+    pub half_life_secs: i64,
+    pub bump: u8,
+}
+
+#[account]
+pub struct RateLimit {
+    pub window_slots: u64,
+    pub max_mint_per_window: u64,
+    pub window_start: u64,
+    pub minted_in_window: u64,
+    pub bump: u8,
+}
+
+#[account]
+pub struct GlobalState {
+    pub total_face_value: u64,
+    pub total_debt: u64,
+    pub total_collateral_value: u64,
+    pub bump: u8,
+}
+
+#[account]
+pub struct SortedVaults {
+    pub head: Pubkey,
+    pub tail: Pubkey,
+    pub size: u64,
+    pub bump: u8,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy)]
+pub struct VaultHealth {
+    pub collateral_value: u64,
+    pub debt: u64,
+    pub health_factor_bps: u64,
+    pub liquidatable: bool,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Default)]
+pub struct ListHint {
+    pub prev: Pubkey,
+    pub next: Pubkey,
+}
+
+#[account]
+pub struct StabilityPool {
+    pub collateral_mint: Pubkey,
+    pub total_deposits: u64,
+    pub product_p: u128,
+    pub sum_s: u128,
+    pub current_epoch: u64,
+    pub current_scale: u64,
+    pub scale_sums: Vec<ScaleSum>,
+    pub bump: u8,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq)]
+pub struct ScaleSum {
+    pub epoch: u64,
+    pub scale: u64,
+    pub sum: u128,
+}
+
+#[account]
+pub struct StabilityDeposit {
+    pub owner: Pubkey,
+    pub pool: Pubkey,
+    pub initial_deposit: u64,
+    pub snapshot_p: u128,
+    pub snapshot_s: u128,
+    pub snapshot_epoch: u64,
+    pub snapshot_scale: u64,
+    pub bump: u8,
+}
+
+#[account]
+pub struct PsmState {
+    pub usdc_mint: Pubkey,
+    pub mint_fee_bps: u64,
+    pub redeem_fee_bps: u64,
+    pub supply_cap: u64,
+    pub psm_supply: u64,
+    pub bump: u8,
+}
+
+#[account]
+pub struct FlashLock {
+    pub active: bool,
+    pub amount: u64,
+    pub fee: u64,
+    pub bump: u8,
+}
+
+// Outstanding stablecoin by origin. Bridged supply is net of what left through NTT, so it
+// goes negative while more has been bridged out than in.
+#[account]
+pub struct SupplyLedger {
+    pub cdp_debt: u64,
+    pub interest: u64,
+    pub psm: u64,
+    pub flash_mint: u64,
+    pub bridged_net: i64,
+    pub bump: u8,
+}
+
+#[account]
+pub struct BridgeConfig {
+    pub ntt_authority: Pubkey,
+    pub bridged_out: u64,
+    pub bump: u8,
+}
+
+#[account]
+pub struct BridgePeer {
+    pub chain_id: u16,
+    pub peer_address: [u8; 32],
+    pub bump: u8,
+}
+
+#[account]
+pub struct DebtAuction {
+    pub vault: Pubkey,
+    pub debt: u64,
+    pub lot: u64,
+    pub bidder: Pubkey,
+    pub ends_at: i64,
+    pub active: bool,
+    pub bump: u8,
+}
+
+#[account]
+pub struct BadDebt {
+    pub total_haircut: u64,
+    pub unbacked_supply: u64,
+    pub bump: u8,
+}
+
+#[account]
+pub struct SurplusAuction {
+    pub lot: u64,
+    pub bid: u64,
+    pub bidder: Pubkey,
+    pub ends_at: i64,
+    pub active: bool,
+    pub bump: u8,
+}
+
+#[account]
+pub struct Settlement {
+    pub triggered_at: i64,
+    pub finalized: bool,
+    pub collateral_count: u64,
+    pub fixed_count: u64,
+    pub bump: u8,
+    pub supply: u64,
+    pub psm_reserve: u64,
+}
+
+#[account]
+pub struct SettlementCollateral {
+    pub mint: Pubkey,
+    pub stablecoin_mint: Pubkey,
+    pub unit_value: u64,
+    pub fix: u128,
+    pub fixed: bool,
+    pub bump: u8,
+    pub supply: u64,
+}
+
+#[account]
+pub struct ProtocolLiquidity {
+    pub amm_program: Pubkey,
+    pub pool: Pubkey,
+    pub lp_mint: Pubkey,
+    pub stablecoin_deployed: u64,
+    pub usdc_deployed: u64,
+    pub bump: u8,
+}
+
+#[account]
+pub struct SavingsState {
+    pub share_mint: Pubkey,
+    pub last_share_price: u128,
+    pub last_synced_at: i64,
+    pub bump: u8,
+}
+
+#[derive(Accounts)]
+pub struct SetRateModel<'info> {
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    #[account(seeds = [b"config"], bump = config.bump, has_one = admin)]
+    pub config: Account<'info, Config>,
+    #[account(
+        init_if_needed,
+        payer = admin,
+        space = 8 + 8 + 8 + 8 + 8 + 32 + 8 + 8 + 8 + 8 + 8 + 8 + 1,
+        seeds = [b"rate_model"],
+        bump
+    )]
+    pub rate_model: Account<'info, InterestRateModel>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct QueueParamChange<'info> {
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    #[account(seeds = [b"config"], bump = config.bump, has_one = admin)]
+    pub config: Account<'info, Config>,
+    #[account(
+        init_if_needed,
+        payer = admin,
+        space = 8 + 8 + 4 + (8 + 1 + 32 + 8 + 8) * MAX_PENDING_CHANGES + 1,
+        seeds = [b"pending_changes"],
+        bump
+    )]
+    pub pending_changes: Account<'info, PendingParamChanges>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct CancelParamChange<'info> {
+    pub admin: Signer<'info>,
+    #[account(seeds = [b"config"], bump = config.bump, has_one = admin)]
+    pub config: Account<'info, Config>,
+    #[account(mut, seeds = [b"pending_changes"], bump = pending_changes.bump)]
+    pub pending_changes: Account<'info, PendingParamChanges>,
+}
+
+#[derive(Accounts)]
+pub struct ExecuteParamChange<'info> {
+    #[account(mut, seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, Config>,
+    #[account(mut, seeds = [b"pending_changes"], bump = pending_changes.bump)]
+    pub pending_changes: Account<'info, PendingParamChanges>,
+    #[account(mut, seeds = [b"rate_model"], bump = rate_model.bump)]
+    pub rate_model: Account<'info, InterestRateModel>,
+    #[account(
+        mut,
+        seeds = [b"collateral_config", collateral_config.mint.as_ref()],
+        bump = collateral_config.bump,
+    )]
+    pub collateral_config: Option<Account<'info, CollateralConfig>>,
+    #[account(
+        mut,
+        seeds = [b"stablecoin_market", stablecoin_market.mint.as_ref()],
+        bump = stablecoin_market.bump,
+    )]
+    pub stablecoin_market: Option<Account<'info, StablecoinMarket>>,
+}
+
+#[derive(Accounts)]
+pub struct SetPauseFlags<'info> {
+    pub authority: Signer<'info>,
+    #[account(
+        mut,
+        seeds = [b"config"],
+        bump = config.bump,
+        constraint = authority.key() == config.admin
+            || authority.key() == config.guardian @ ErrorCode::Unauthorized
+    )]
+    pub config: Account<'info, Config>,
+}
+
+#[derive(Accounts)]
+pub struct AcceptAdmin<'info> {
+    #[account(
+        mut,
+        seeds = [b"config"],
+        bump = config.bump,
+        constraint = config.pending_admin == pending_admin.key() @ ErrorCode::Unauthorized
+    )]
+    pub config: Account<'info, Config>,
+    pub pending_admin: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateConfig<'info> {
+    #[account(mut, seeds = [b"config"], bump = config.bump, has_one = admin)]
+    pub config: Account<'info, Config>,
+    pub admin: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct SetRateLimit<'info> {
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    #[account(seeds = [b"config"], bump = config.bump, has_one = admin)]
+    pub config: Account<'info, Config>,
+    #[account(
+        init_if_needed,
+        payer = admin,
+        space = 8 + 8 + 8 + 8 + 8 + 1,
+        seeds = [b"rate_limit"],
+        bump
+    )]
+    pub rate_limit: Account<'info, RateLimit>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct SetCollateralConfig<'info> {
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    #[account(mut, seeds = [b"config"], bump = config.bump, has_one = admin)]
+    pub config: Account<'info, Config>,
+    pub collateral_mint: InterfaceAccount<'info, Mint>,
+    #[account(
+        seeds = [b"bond_metadata", collateral_mint.key().as_ref()],
+        seeds::program = bond_tokenization::ID,
+        bump,
+    )]
+    pub bond_metadata: Account<'info, BondMetadata>,
+    #[account(
+        init_if_needed,
+        payer = admin,
+        space = 8 + 32 + 32 + 8 + 8 + 8 + 8 + 8 + 8 + 1 + 8 + 8 + 8 + 8 * 5 + 16 + 8
+            + (1 + 32) * MAX_ORACLE_FEEDS + 1 + 1 + 8 + 8 + 8 + 8 + 8 + 8 + 1 + 1 + 32 + 1
+            + 8 + 8 + 8 + 8 + 1,
+        seeds = [b"collateral_config", collateral_mint.key().as_ref()],
+        bump
+    )]
+    pub collateral_config: Account<'info, CollateralConfig>,
+    #[account(
+        init_if_needed,
+        payer = admin,
+        space = 8 + 32 + 8 + 8 + 8 + 8 + 1,
+        seeds = [b"price_twap", collateral_mint.key().as_ref()],
+        bump
+    )]
+    pub price_twap: Account<'info, PriceTwap>,
+    #[account(
+        init_if_needed,
+        payer = admin,
+        space = 8 + 32 + 8 + 1 + 8 + 8 + 8 + 8 + 8 + 1,
+        seeds = [b"price_cache", collateral_mint.key().as_ref()],
+        bump
+    )]
+    pub price_cache: Account<'info, PriceCache>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct SetRedemptionDecay<'info> {
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    #[account(seeds = [b"config"], bump = config.bump, has_one = admin)]
+    pub config: Account<'info, Config>,
+    #[account(
+        init_if_needed,
+        payer = admin,
+        space = 8 + 16 + 8 + 8 + 1,
+        seeds = [b"fee_state"],
+        bump
+    )]
+    pub fee_state: Account<'info, RedemptionFeeState>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateRateModel<'info> {
+    pub admin: Signer<'info>,
+    #[account(seeds = [b"config"], bump = config.bump, has_one = admin)]
+    pub config: Account<'info, Config>,
+    #[account(mut, seeds = [b"rate_model"], bump = rate_model.bump)]
+    pub rate_model: Account<'info, InterestRateModel>,
+}
+
+#[derive(Accounts)]
+pub struct UpdatePegPrice<'info> {
+    pub feeder: Signer<'info>,
+    #[account(
+        mut,
+        seeds = [b"rate_model"],
+        bump = rate_model.bump,
+        constraint = rate_model.peg_feeder == feeder.key() @ ErrorCode::Unauthorized
+    )]
+    pub rate_model: Account<'info, InterestRateModel>,
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, Config>,
+}
+
+#[derive(Accounts)]
+pub struct InitializeFeedRegistry<'info> {
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    #[account(seeds = [b"config"], bump = config.bump, has_one = admin)]
+    pub config: Account<'info, Config>,
+    #[account(
+        init,
+        payer = admin,
+        space = 8 + 4 + (32 + (1 + 32) * MAX_ORACLE_FEEDS + 1 + 1 + 8 + 8) * MAX_REGISTRY_ENTRIES
+            + 1,
+        seeds = [b"feed_registry"],
+        bump
+    )]
+    pub feed_registry: Account<'info, FeedRegistry>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ManageFeedRegistry<'info> {
+    pub oracle_manager: Signer<'info>,
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump,
+        constraint = config.oracle_manager == oracle_manager.key() @ ErrorCode::Unauthorized
+    )]
+    pub config: Account<'info, Config>,
+    #[account(mut, seeds = [b"feed_registry"], bump = feed_registry.bump)]
+    pub feed_registry: Account<'info, FeedRegistry>,
+    #[account(
+        mut,
+        seeds = [b"collateral_config", collateral_config.mint.as_ref()],
+        bump = collateral_config.bump,
+    )]
+    pub collateral_config: Account<'info, CollateralConfig>,
+}
+
+#[derive(Accounts)]
+#[instruction(currency: Currency)]
+pub struct SetFxFeed<'info> {
+    #[account(mut)]
+    pub oracle_manager: Signer<'info>,
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump,
+        constraint = config.oracle_manager == oracle_manager.key() @ ErrorCode::Unauthorized
+    )]
+    pub config: Account<'info, Config>,
+    #[account(
+        init_if_needed,
+        payer = oracle_manager,
+        space = 8 + 1 + 32 + 8 + 1,
+        seeds = [b"fx_feed", currency.code()],
+        bump
+    )]
+    pub fx_feed: Account<'info, FxFeed>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct RegisterStablecoinMarket<'info> {
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    #[account(seeds = [b"config"], bump = config.bump, has_one = admin)]
+    pub config: Account<'info, Config>,
+    pub stablecoin_mint: InterfaceAccount<'info, Mint>,
+    #[account(
+        init,
+        payer = admin,
+        space = 8 + 32 + 1 + 8 + 1,
+        seeds = [b"stablecoin_market", stablecoin_mint.key().as_ref()],
+        bump
+    )]
+    pub stablecoin_market: Account<'info, StablecoinMarket>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct SetMarketDebtCeiling<'info> {
+    pub admin: Signer<'info>,
+    #[account(seeds = [b"config"], bump = config.bump, has_one = admin)]
+    pub config: Account<'info, Config>,
+    #[account(
+        mut,
+        seeds = [b"stablecoin_market", stablecoin_market.mint.as_ref()],
+        bump = stablecoin_market.bump,
+    )]
+    pub stablecoin_market: Account<'info, StablecoinMarket>,
+}
+
+#[derive(Accounts)]
+pub struct SetCollateralMarket<'info> {
+    pub admin: Signer<'info>,
+    #[account(seeds = [b"config"], bump = config.bump, has_one = admin)]
+    pub config: Account<'info, Config>,
+    #[account(
+        mut,
+        seeds = [b"collateral_config", collateral_config.mint.as_ref()],
+        bump = collateral_config.bump,
+    )]
+    pub collateral_config: Account<'info, CollateralConfig>,
+    #[account(
+        seeds = [b"stablecoin_market", stablecoin_market.mint.as_ref()],
+        bump = stablecoin_market.bump,
+    )]
+    pub stablecoin_market: Account<'info, StablecoinMarket>,
+}
+
+#[derive(Accounts)]
+pub struct SetCollateralCurrency<'info> {
+    pub admin: Signer<'info>,
+    #[account(seeds = [b"config"], bump = config.bump, has_one = admin)]
+    pub config: Account<'info, Config>,
+    #[account(
+        mut,
+        seeds = [b"collateral_config", collateral_config.mint.as_ref()],
+        bump = collateral_config.bump,
+    )]
+    pub collateral_config: Account<'info, CollateralConfig>,
+}
+
+#[derive(Accounts)]
+pub struct SetBorrowRate<'info> {
+    pub admin: Signer<'info>,
+    #[account(seeds = [b"config"], bump = config.bump, has_one = admin)]
+    pub config: Account<'info, Config>,
+    #[account(
+        mut,
+        seeds = [b"collateral_config", collateral_config.mint.as_ref()],
+        bump = collateral_config.bump,
+    )]
+    pub collateral_config: Account<'info, CollateralConfig>,
+    #[account(seeds = [b"rate_model"], bump = rate_model.bump)]
+    pub rate_model: Account<'info, InterestRateModel>,
+}
+
+#[derive(Accounts)]
+pub struct ScheduleBorrowRate<'info> {
+    pub admin: Signer<'info>,
+    #[account(seeds = [b"config"], bump = config.bump, has_one = admin)]
+    pub config: Account<'info, Config>,
+    #[account(
+        mut,
+        seeds = [b"collateral_config", collateral_config.mint.as_ref()],
+        bump = collateral_config.bump,
+    )]
+    pub collateral_config: Account<'info, CollateralConfig>,
+    #[account(seeds = [b"rate_model"], bump = rate_model.bump)]
+    pub rate_model: Account<'info, InterestRateModel>,
+}
+
+#[derive(Accounts)]
+pub struct SetPriceBand<'info> {
+    pub admin: Signer<'info>,
+    #[account(seeds = [b"config"], bump = config.bump, has_one = admin)]
+    pub config: Account<'info, Config>,
+    #[account(
+        mut,
+        seeds = [b"collateral_config", collateral_config.mint.as_ref()],
+        bump = collateral_config.bump,
+    )]
+    pub collateral_config: Account<'info, CollateralConfig>,
+}
+
+#[derive(Accounts)]
+pub struct SetRatingRates<'info> {
+    pub admin: Signer<'info>,
+    #[account(seeds = [b"config"], bump = config.bump, has_one = admin)]
+    pub config: Account<'info, Config>,
+    #[account(
+        mut,
+        seeds = [b"collateral_config", collateral_config.mint.as_ref()],
+        bump = collateral_config.bump,
+    )]
+    pub collateral_config: Account<'info, CollateralConfig>,
+}
+
+#[derive(Accounts)]
+pub struct SetCustodian<'info> {
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    #[account(seeds = [b"config"], bump = config.bump, has_one = admin)]
+    pub config: Account<'info, Config>,
+    #[account(
+        seeds = [b"collateral_config", collateral_config.mint.as_ref()],
+        bump = collateral_config.bump,
+    )]
+    pub collateral_config: Account<'info, CollateralConfig>,
+    #[account(
+        init_if_needed,
+        payer = admin,
+        space = 8 + 32 + 32 + 8 + 8 + 1,
+        seeds = [b"nav_attestation", collateral_config.mint.as_ref()],
+        bump
+    )]
+    pub nav_attestation: Account<'info, NavAttestation>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct SubmitNavAttestation<'info> {
+    #[account(
+        mut,
+        seeds = [b"nav_attestation", nav_attestation.mint.as_ref()],
+        bump = nav_attestation.bump,
+    )]
+    pub nav_attestation: Account<'info, NavAttestation>,
+    /// CHECK: instructions sysvar, checked by address
+    #[account(address = anchor_lang::solana_program::sysvar::instructions::ID)]
+    pub instructions: AccountInfo<'info>,
+}
+
+#[derive(Accounts)]
+pub struct SetCurvePublisher<'info> {
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    #[account(seeds = [b"config"], bump = config.bump, has_one = admin)]
+    pub config: Account<'info, Config>,
+    #[account(
+        init_if_needed,
+        payer = admin,
+        space = 8 + 32 + 8 * YIELD_CURVE_POINTS + 8 * YIELD_CURVE_POINTS + 1 + 8 + 1,
+        seeds = [b"yield_curve"],
+        bump
+    )]
+    pub yield_curve: Account<'info, YieldCurve>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct PublishYieldCurve<'info> {
+    pub publisher: Signer<'info>,
+    #[account(
+        mut,
+        seeds = [b"yield_curve"],
+        bump = yield_curve.bump,
+        constraint = yield_curve.publisher == publisher.key() @ ErrorCode::Unauthorized
+    )]
+    pub yield_curve: Account<'info, YieldCurve>,
+}
+
+#[derive(Accounts)]
+pub struct SetEmergencyPrice<'info> {
+    pub authority: Signer<'info>,
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump,
+        constraint = authority.key() == config.admin
+            || authority.key() == config.risk_authority @ ErrorCode::Unauthorized
+    )]
+    pub config: Account<'info, Config>,
+    #[account(
+        mut,
+        seeds = [b"price_cache", price_cache.mint.as_ref()],
+        bump = price_cache.bump,
+    )]
+    pub price_cache: Account<'info, PriceCache>,
+}
+
+#[derive(Accounts)]
+pub struct SetManualMark<'info> {
+    pub risk_authority: Signer<'info>,
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump,
+        constraint = config.risk_authority == risk_authority.key() @ ErrorCode::Unauthorized
+    )]
+    pub config: Account<'info, Config>,
+    #[account(
+        mut,
+        seeds = [b"price_cache", price_cache.mint.as_ref()],
+        bump = price_cache.bump,
+    )]
+    pub price_cache: Account<'info, PriceCache>,
+}
+
+#[derive(Accounts)]
+pub struct AcknowledgePriceBand<'info> {
+    pub risk_authority: Signer<'info>,
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump,
+        constraint = config.risk_authority == risk_authority.key() @ ErrorCode::Unauthorized
+    )]
+    pub config: Account<'info, Config>,
+    #[account(
+        mut,
+        seeds = [b"collateral_config", collateral_config.mint.as_ref()],
+        bump = collateral_config.bump,
+    )]
+    pub collateral_config: Account<'info, CollateralConfig>,
+    #[account(
+        mut,
+        seeds = [b"price_cache", collateral_config.mint.as_ref()],
+        bump = price_cache.bump,
+    )]
+    pub price_cache: Account<'info, PriceCache>,
+}
+
+#[derive(Accounts)]
+pub struct SetTwapWindow<'info> {
+    pub admin: Signer<'info>,
+    #[account(seeds = [b"config"], bump = config.bump, has_one = admin)]
+    pub config: Account<'info, Config>,
+    #[account(mut, seeds = [b"price_twap", price_twap.mint.as_ref()], bump = price_twap.bump)]
+    pub price_twap: Account<'info, PriceTwap>,
+}
+
+#[derive(Accounts)]
+pub struct UpdatePriceTwap<'info> {
+    #[account(
+        seeds = [b"collateral_config", collateral_config.mint.as_ref()],
+        bump = collateral_config.bump,
+    )]
+    pub collateral_config: Account<'info, CollateralConfig>,
+    #[account(
+        mut,
+        seeds = [b"price_twap", collateral_config.mint.as_ref()],
+        bump = price_twap.bump,
+    )]
+    pub price_twap: Account<'info, PriceTwap>,
+}
+
+#[derive(Accounts)]
+pub struct RefreshPriceCache<'info> {
+    #[account(
+        mut,
+        seeds = [b"collateral_config", collateral_config.mint.as_ref()],
+        bump = collateral_config.bump,
+    )]
+    pub collateral_config: Account<'info, CollateralConfig>,
+    #[account(
+        mut,
+        seeds = [b"price_cache", collateral_config.mint.as_ref()],
+        bump = price_cache.bump,
+    )]
+    pub price_cache: Account<'info, PriceCache>,
+    #[account(seeds = [b"fx_feed", fx_feed.currency.code()], bump = fx_feed.bump)]
+    pub fx_feed: Option<Account<'info, FxFeed>>,
+    pub fx_price_update: Option<Account<'info, PriceUpdateV2>>,
+}
+
+#[derive(Accounts)]
+pub struct RefreshCollateralValue<'info> {
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, Config>,
+    #[account(
+        mut,
+        seeds = [b"collateral_config", collateral_config.mint.as_ref()],
+        bump = collateral_config.bump,
+    )]
+    pub collateral_config: Account<'info, CollateralConfig>,
+    #[account(
+        seeds = [b"bond_metadata", collateral_config.mint.as_ref()],
+        seeds::program = bond_tokenization::ID,
+        bump,
+    )]
+    pub bond_metadata: Account<'info, BondMetadata>,
+    #[account(seeds = [b"yield_curve"], bump = yield_curve.bump)]
+    pub yield_curve: Option<Account<'info, YieldCurve>>,
+    #[account(seeds = [b"fx_feed", fx_feed.currency.code()], bump = fx_feed.bump)]
+    pub fx_feed: Option<Account<'info, FxFeed>>,
+    pub fx_price_update: Option<Account<'info, PriceUpdateV2>>,
+}
+
+#[derive(Accounts)]
+#[instruction(issuer: Pubkey)]
+pub struct SetIssuerCap<'info> {
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    #[account(seeds = [b"config"], bump = config.bump, has_one = admin)]
+    pub config: Account<'info, Config>,
+    #[account(
+        init_if_needed,
+        payer = admin,
+        space = 8 + 32 + 8 + 8 + 1,
+        seeds = [b"issuer_exposure", issuer.as_ref()],
+        bump
+    )]
+    pub issuer_exposure: Account<'info, IssuerExposure>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ApproveCollateral<'info> {
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    #[account(seeds = [b"config"], bump = config.bump, has_one = admin)]
+    pub config: Account<'info, Config>,
+    pub collateral_mint: InterfaceAccount<'info, Mint>,
+    #[account(
+        init,
+        payer = admin,
+        space = 8 + 32 + 32 + 1,
+        seeds = [b"collateral_approval", collateral_mint.key().as_ref()],
+        bump
+    )]
+    pub approval: Account<'info, CollateralApproval>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct RevokeCollateral<'info> {
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    #[account(seeds = [b"config"], bump = config.bump, has_one = admin)]
+    pub config: Account<'info, Config>,
+    #[account(
+        mut,
+        close = admin,
+        seeds = [b"collateral_approval", approval.mint.as_ref()],
+        bump = approval.bump,
+    )]
+    pub approval: Account<'info, CollateralApproval>,
+}
+
+#[derive(Accounts)]
+pub struct InitializeConfig<'info> {
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    #[account(
+        init,
+        payer = admin,
+        space = 8 + 32 * 6 + 8 * 5 * 2 + 8 * 29 + 1 + 1 + 32 + 8 + 8 + 8 + 8 + 8,
+        seeds = [b"config"],
+        bump
+    )]
+    pub config: Account<'info, Config>,
+    #[account(
+        constraint = stablecoin_mint.mint_authority == COption::Some(mint_authority.key())
+            @ ErrorCode::InvalidMintAuthority
+    )]
+    pub stablecoin_mint: InterfaceAccount<'info, Mint>,
+    /// CHECK: PDA authority
+    #[account(seeds = [b"mint_authority"], bump)]
+    pub mint_authority: AccountInfo<'info>,
+    #[account(constraint = program.programdata_address()? == Some(program_data.key()))]
+    pub program: Program<'info, crate::program::CdpStablecoin>,
+    #[account(
+        constraint = program_data.upgrade_authority_address == Some(admin.key())
+            @ ErrorCode::Unauthorized
+    )]
+    pub program_data: Account<'info, ProgramData>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct InitializeGlobalState<'info> {
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    #[account(seeds = [b"config"], bump = config.bump, has_one = admin)]
+    pub config: Account<'info, Config>,
+    #[account(
+        init,
+        payer = admin,
+        space = 8 + 8 + 8 + 8 + 1,
+        seeds = [b"global_state"],
+        bump
+    )]
+    pub global_state: Account<'info, GlobalState>,
+    pub stablecoin_mint: InterfaceAccount<'info, Mint>,
+    #[account(
+        init,
+        payer = admin,
+        seeds = [b"surplus_vault"],
+        bump,
+        token::mint = stablecoin_mint,
+        token::authority = global_state,
+        token::token_program = stablecoin_token_program
+    )]
+    pub surplus_vault: InterfaceAccount<'info, TokenAccount>,
+    pub stablecoin_token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct SetStablecoinMetadata<'info> {
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    #[account(seeds = [b"config"], bump = config.bump, has_one = admin)]
+    pub config: Account<'info, Config>,
+    #[account(seeds = [b"global_state"], bump = global_state.bump)]
+    pub global_state: Account<'info, GlobalState>,
+    pub stablecoin_mint: InterfaceAccount<'info, Mint>,
+    /// CHECK: PDA authority
+    #[account(seeds = [b"mint_authority"], bump = config.mint_authority_bump)]
+    pub mint_authority: AccountInfo<'info>,
+    /// CHECK: Token Metadata PDA for the stablecoin mint, checked by seeds
+    #[account(
+        mut,
+        seeds = [
+            b"metadata",
+            token_metadata_program.key().as_ref(),
+            stablecoin_mint.key().as_ref()
+        ],
+        seeds::program = token_metadata_program.key(),
+        bump
+    )]
+    pub metadata: UncheckedAccount<'info>,
+    pub token_metadata_program: Program<'info, Metadata>,
+    pub system_program: Program<'info, System>,
+    pub rent: Sysvar<'info, Rent>,
+}
+
+#[derive(Accounts)]
+pub struct InitializeTreasury<'info> {
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    #[account(seeds = [b"config"], bump = config.bump, has_one = admin)]
+    pub config: Account<'info, Config>,
+    #[account(seeds = [b"global_state"], bump = global_state.bump)]
+    pub global_state: Account<'info, GlobalState>,
+    pub stablecoin_mint: InterfaceAccount<'info, Mint>,
+    #[account(
+        init,
+        payer = admin,
+        seeds = [b"treasury"],
+        bump,
+        token::mint = stablecoin_mint,
+        token::authority = global_state,
+        token::token_program = stablecoin_token_program
+    )]
+    pub treasury: InterfaceAccount<'info, TokenAccount>,
+    pub stablecoin_token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct InitializeReserve<'info> {
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    #[account(seeds = [b"config"], bump = config.bump, has_one = admin)]
+    pub config: Account<'info, Config>,
+    #[account(seeds = [b"global_state"], bump = global_state.bump)]
+    pub global_state: Account<'info, GlobalState>,
+    pub stablecoin_mint: InterfaceAccount<'info, Mint>,
+    #[account(
+        init,
+        payer = admin,
+        seeds = [b"reserve"],
+        bump,
+        token::mint = stablecoin_mint,
+        token::authority = global_state,
+        token::token_program = stablecoin_token_program
+    )]
+    pub reserve: InterfaceAccount<'info, TokenAccount>,
+    pub stablecoin_token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct InitializeSortedVaults<'info> {
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    #[account(seeds = [b"config"], bump = config.bump, has_one = admin)]
+    pub config: Account<'info, Config>,
+    #[account(
+        init,
+        payer = admin,
+        space = 8 + 32 + 32 + 8 + 1,
+        seeds = [b"sorted_vaults"],
+        bump
+    )]
+    pub sorted_vaults: Account<'info, SortedVaults>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct DepositCollateral<'info> {
+    #[account(mut)]
+    pub user: Signer<'info>,
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, Config>,
+    pub collateral_mint: InterfaceAccount<'info, Mint>,
+    #[account(
+        seeds = [b"collateral_approval", collateral_mint.key().as_ref()],
+        bump = approval.bump,
+    )]
+    pub approval: Account<'info, CollateralApproval>,
+    #[account(
+        seeds = [b"bond_metadata", collateral_mint.key().as_ref()],
+        seeds::program = bond_tokenization::ID,
+        bump,
+        constraint = bond_metadata.authority == approval.issuer @ ErrorCode::UnknownIssuer
+    )]
+    pub bond_metadata: Account<'info, BondMetadata>,
+    #[account(mut, token::mint = collateral_mint, token::authority = user)]
+    pub user_bond_account: InterfaceAccount<'info, TokenAccount>,
+    #[account(
+        init,
+        payer = user,
+        space = 8 + 32 + 32 + 32 + 32 + 32 + 32 + 32 + 8 + 8 + 8 + 8 + 32 + 32 + 1 + 8 + 8 + 1
+            + 8 + 8 + 8 + 8 + 1 + 8 + 8 * 5 + 16 + 8 + 8 * 5 + 8 + 8 + 8 + 8 + 1,
+        seeds = [b"vault", user.key().as_ref(), collateral_mint.key().as_ref()],
+        bump
+    )]
+    pub vault: Account<'info, Vault>,
+    #[account(
+        init,
+        payer = user,
+        seeds = [b"escrow", vault.key().as_ref()],
+        bump,
+        token::mint = collateral_mint,
+        token::authority = vault
+    )]
+    pub escrow: InterfaceAccount<'info, TokenAccount>,
+    #[account(
+        init,
+        payer = user,
+        seeds = [b"receipt", vault.key().as_ref()],
+        bump,
+        mint::decimals = 0,
+        mint::authority = vault
+    )]
+    pub receipt_mint: InterfaceAccount<'info, Mint>,
+    #[account(
+        init,
+        payer = user,
+        associated_token::mint = receipt_mint,
+        associated_token::authority = user
+    )]
+    pub user_receipt_account: InterfaceAccount<'info, TokenAccount>,
+    #[account(
+        init,
+        payer = user,
+        seeds = [b"position", vault.key().as_ref()],
+        bump,
+        mint::decimals = 0,
+        mint::authority = vault
+    )]
+    pub position_mint: InterfaceAccount<'info, Mint>,
+    #[account(
+        init,
+        payer = user,
+        associated_token::mint = position_mint,
+        associated_token::authority = user
+    )]
+    pub user_position_account: InterfaceAccount<'info, TokenAccount>,
+    #[account(
+        mut,
+        seeds = [b"collateral_config", collateral_mint.key().as_ref()],
+        bump = collateral_config.bump,
+    )]
+    pub collateral_config: Account<'info, CollateralConfig>,
+    #[account(mut, seeds = [b"global_state"], bump = global_state.bump)]
+    pub global_state: Account<'info, GlobalState>,
+    pub token_program: Program<'info, Token>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct AddCollateral<'info> {
+    pub authority: Signer<'info>,
+    #[account(
+        mut,
+        has_one = receipt_mint,
+        seeds = [b"vault", vault.creator.as_ref(), vault.collateral_mint.as_ref()],
+        bump = vault.bump,
+    )]
+    pub vault: Account<'info, Vault>,
+    #[account(
+        token::mint = vault.position_mint,
+        constraint = position_account.amount == 1 @ ErrorCode::PositionRequired,
+        constraint = is_vault_operator(&vault, &position_account, &authority.key())
+            @ ErrorCode::Unauthorized
+    )]
+    pub position_account: InterfaceAccount<'info, TokenAccount>,
+    #[account(mut)]
+    pub receipt_mint: InterfaceAccount<'info, Mint>,
+    #[account(
+        mut,
+        token::mint = receipt_mint,
+        token::authority = position_account.owner,
+        constraint = owner_receipt_account.amount >= vault.bond_amount @ ErrorCode::ReceiptRequired
+    )]
+    pub owner_receipt_account: InterfaceAccount<'info, TokenAccount>,
+    #[account(mut, token::mint = vault.collateral_mint, token::authority = authority)]
+    pub authority_bond_account: InterfaceAccount<'info, TokenAccount>,
+    #[account(mut, seeds = [b"escrow", vault.key().as_ref()], bump)]
+    pub escrow: InterfaceAccount<'info, TokenAccount>,
+    #[account(mut, seeds = [b"sorted_vaults"], bump = sorted_vaults.bump)]
+    pub sorted_vaults: Account<'info, SortedVaults>,
+    #[account(
+        seeds = [b"collateral_approval", vault.collateral_mint.as_ref()],
+        bump = approval.bump,
+    )]
+    pub approval: Account<'info, CollateralApproval>,
+    #[account(
+        seeds = [b"bond_metadata", vault.collateral_mint.as_ref()],
+        seeds::program = bond_tokenization::ID,
+        bump,
+    )]
+    pub bond_metadata: Account<'info, BondMetadata>,
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, Config>,
+    #[account(seeds = [b"rate_model"], bump = rate_model.bump)]
+    pub rate_model: Account<'info, InterestRateModel>,
+    #[account(
+        mut,
+        seeds = [b"collateral_config", vault.collateral_mint.as_ref()],
+        bump = collateral_config.bump,
+    )]
+    pub collateral_config: Account<'info, CollateralConfig>,
+    #[account(seeds = [b"price_cache", vault.collateral_mint.as_ref()], bump = price_cache.bump)]
+    pub price_cache: Account<'info, PriceCache>,
+    #[account(mut, seeds = [b"global_state"], bump = global_state.bump)]
+    pub global_state: Account<'info, GlobalState>,
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct Borrow<'info> {
+    pub owner: Signer<'info>,
+    #[account(
+        mut,
+        seeds = [b"vault", vault.creator.as_ref(), vault.collateral_mint.as_ref()],
+        bump = vault.bump,
+    )]
+    pub vault: Account<'info, Vault>,
+    #[account(
+        token::mint = vault.position_mint,
+        token::authority = owner,
+        constraint = position_account.amount == 1 @ ErrorCode::PositionRequired
+    )]
+    pub position_account: InterfaceAccount<'info, TokenAccount>,
+    #[account(
+        token::mint = vault.receipt_mint,
+        token::authority = owner,
+        constraint = owner_receipt_account.amount >= vault.bond_amount @ ErrorCode::ReceiptRequired
+    )]
+    pub owner_receipt_account: InterfaceAccount<'info, TokenAccount>,
+    #[account(mut)]
+    pub owner_stablecoin_account: InterfaceAccount<'info, TokenAccount>,
+    /// CHECK: deny entry for the account owner; empty unless the owner is denied
+    #[account(
+        seeds = [b"deny", owner_stablecoin_account.owner.as_ref()],
+        seeds::program = stablecoin_transfer_hook::ID,
+        bump
+    )]
+    pub deny_entry: UncheckedAccount<'info>,
+    #[account(mut, address = collateral_config.stablecoin_mint)]
+    pub stablecoin_mint: InterfaceAccount<'info, Mint>,
+    #[account(mut, seeds = [b"treasury"], bump)]
+    pub treasury: InterfaceAccount<'info, TokenAccount>,
+    /// CHECK: PDA authority
+    #[account(seeds = [b"mint_authority"], bump = config.mint_authority_bump)]
+    pub mint_authority: AccountInfo<'info>,
+    #[account(
+        seeds = [b"bond_metadata", vault.collateral_mint.as_ref()],
+        seeds::program = bond_tokenization::ID,
+        bump,
+    )]
+    pub bond_metadata: Account<'info, BondMetadata>,
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, Config>,
+    #[account(seeds = [b"rate_model"], bump = rate_model.bump)]
+    pub rate_model: Account<'info, InterestRateModel>,
+    #[account(
+        mut,
+        seeds = [b"collateral_config", vault.collateral_mint.as_ref()],
+        bump = collateral_config.bump,
+    )]
+    pub collateral_config: Account<'info, CollateralConfig>,
+    #[account(seeds = [b"price_cache", vault.collateral_mint.as_ref()], bump = price_cache.bump)]
+    pub price_cache: Account<'info, PriceCache>,
+    #[account(seeds = [b"price_twap", vault.collateral_mint.as_ref()], bump = price_twap.bump)]
+    pub price_twap: Account<'info, PriceTwap>,
+    #[account(
+        mut,
+        seeds = [b"issuer_exposure", collateral_config.issuer.as_ref()],
+        bump = issuer_exposure.bump,
+    )]
+    pub issuer_exposure: Account<'info, IssuerExposure>,
+    #[account(mut, seeds = [b"sorted_vaults"], bump = sorted_vaults.bump)]
+    pub sorted_vaults: Account<'info, SortedVaults>,
+    #[account(mut, seeds = [b"global_state"], bump = global_state.bump)]
+    pub global_state: Account<'info, GlobalState>,
+    #[account(mut, seeds = [b"rate_limit"], bump = rate_limit.bump)]
+    pub rate_limit: Account<'info, RateLimit>,
+    #[account(mut, seeds = [b"supply_ledger"], bump = supply_ledger.bump)]
+    pub supply_ledger: Account<'info, SupplyLedger>,
+    #[account(
+        seeds = [b"stablecoin_market", collateral_config.stablecoin_mint.as_ref()],
+        bump = stablecoin_market.bump,
+    )]
+    pub stablecoin_market: Account<'info, StablecoinMarket>,
+    /// CHECK: settlement marker; empty until settlement is triggered
+    #[account(seeds = [b"settlement"], bump)]
+    pub settlement: UncheckedAccount<'info>,
+    pub stablecoin_token_program: Interface<'info, TokenInterface>,
+}
+
+#[derive(Accounts)]
+pub struct LoopBorrow<'info> {
+    pub owner: Signer<'info>,
+    #[account(
+        mut,
+        has_one = receipt_mint,
+        seeds = [b"vault", vault.creator.as_ref(), vault.collateral_mint.as_ref()],
+        bump = vault.bump,
+    )]
+    pub vault: Account<'info, Vault>,
+    #[account(
+        token::mint = vault.position_mint,
+        token::authority = owner,
+        constraint = position_account.amount == 1 @ ErrorCode::PositionRequired
+    )]
+    pub position_account: InterfaceAccount<'info, TokenAccount>,
+    #[account(mut)]
+    pub receipt_mint: InterfaceAccount<'info, Mint>,
+    #[account(
+        mut,
+        token::mint = receipt_mint,
+        token::authority = owner,
+        constraint = owner_receipt_account.amount >= vault.bond_amount @ ErrorCode::ReceiptRequired
+    )]
+    pub owner_receipt_account: InterfaceAccount<'info, TokenAccount>,
+    #[account(mut, token::mint = vault.collateral_mint, token::authority = owner)]
+    pub owner_bond_account: InterfaceAccount<'info, TokenAccount>,
+    #[account(mut, seeds = [b"escrow", vault.key().as_ref()], bump)]
+    pub escrow: InterfaceAccount<'info, TokenAccount>,
+    #[account(mut, token::authority = owner)]
+    pub owner_stablecoin_account: InterfaceAccount<'info, TokenAccount>,
+    /// CHECK: deny entry for the account owner; empty unless the owner is denied
+    #[account(
+        seeds = [b"deny", owner_stablecoin_account.owner.as_ref()],
+        seeds::program = stablecoin_transfer_hook::ID,
+        bump
+    )]
+    pub deny_entry: UncheckedAccount<'info>,
+    #[account(mut, address = collateral_config.stablecoin_mint)]
+    pub stablecoin_mint: InterfaceAccount<'info, Mint>,
+    #[account(mut, seeds = [b"treasury"], bump)]
+    pub treasury: InterfaceAccount<'info, TokenAccount>,
+    /// CHECK: PDA authority
+    #[account(seeds = [b"mint_authority"], bump = config.mint_authority_bump)]
+    pub mint_authority: AccountInfo<'info>,
+    #[account(
+        seeds = [b"collateral_approval", vault.collateral_mint.as_ref()],
+        bump = approval.bump,
+    )]
+    pub approval: Account<'info, CollateralApproval>,
+    #[account(
+        seeds = [b"bond_metadata", vault.collateral_mint.as_ref()],
+        seeds::program = bond_tokenization::ID,
+        bump,
+    )]
+    pub bond_metadata: Account<'info, BondMetadata>,
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, Config>,
+    #[account(seeds = [b"rate_model"], bump = rate_model.bump)]
+    pub rate_model: Account<'info, InterestRateModel>,
+    #[account(
+        mut,
+        seeds = [b"collateral_config", vault.collateral_mint.as_ref()],
+        bump = collateral_config.bump,
+    )]
+    pub collateral_config: Account<'info, CollateralConfig>,
+    #[account(seeds = [b"price_cache", vault.collateral_mint.as_ref()], bump = price_cache.bump)]
+    pub price_cache: Account<'info, PriceCache>,
+    #[account(seeds = [b"price_twap", vault.collateral_mint.as_ref()], bump = price_twap.bump)]
+    pub price_twap: Account<'info, PriceTwap>,
+    #[account(
+        mut,
+        seeds = [b"issuer_exposure", collateral_config.issuer.as_ref()],
+        bump = issuer_exposure.bump,
+    )]
+    pub issuer_exposure: Account<'info, IssuerExposure>,
+    #[account(mut, seeds = [b"sorted_vaults"], bump = sorted_vaults.bump)]
+    pub sorted_vaults: Account<'info, SortedVaults>,
+    #[account(mut, seeds = [b"global_state"], bump = global_state.bump)]
+    pub global_state: Account<'info, GlobalState>,
+    #[account(mut, seeds = [b"rate_limit"], bump = rate_limit.bump)]
+    pub rate_limit: Account<'info, RateLimit>,
+    pub token_program: Program<'info, Token>,
+    #[account(mut, seeds = [b"supply_ledger"], bump = supply_ledger.bump)]
+    pub supply_ledger: Account<'info, SupplyLedger>,
+    #[account(
+        seeds = [b"stablecoin_market", collateral_config.stablecoin_mint.as_ref()],
+        bump = stablecoin_market.bump,
+    )]
+    pub stablecoin_market: Account<'info, StablecoinMarket>,
+    /// CHECK: settlement marker; empty until settlement is triggered
+    #[account(seeds = [b"settlement"], bump)]
+    pub settlement: UncheckedAccount<'info>,
+    pub stablecoin_token_program: Interface<'info, TokenInterface>,
+    /// CHECK: Swap route invoked with the remaining accounts
+    pub swap_program: UncheckedAccount<'info>,
+}
+
+#[derive(Accounts)]
+pub struct GetVaultHealth<'info> {
+    #[account(
+        seeds = [b"vault", vault.creator.as_ref(), vault.collateral_mint.as_ref()],
+        bump = vault.bump,
+    )]
+    pub vault: Account<'info, Vault>,
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, Config>,
+    #[account(seeds = [b"rate_model"], bump = rate_model.bump)]
+    pub rate_model: Account<'info, InterestRateModel>,
+    #[account(
+        seeds = [b"collateral_config", vault.collateral_mint.as_ref()],
+        bump = collateral_config.bump,
+    )]
+    pub collateral_config: Account<'info, CollateralConfig>,
+    #[account(seeds = [b"price_cache", vault.collateral_mint.as_ref()], bump = price_cache.bump)]
+    pub price_cache: Account<'info, PriceCache>,
+    #[account(seeds = [b"global_state"], bump = global_state.bump)]
+    pub global_state: Account<'info, GlobalState>,
+}
+
+#[derive(Accounts)]
+pub struct AccrueInterest<'info> {
+    #[account(
+        mut,
+        seeds = [b"vault", vault.creator.as_ref(), vault.collateral_mint.as_ref()],
+        bump = vault.bump,
+    )]
+    pub vault: Account<'info, Vault>,
+    #[account(mut, seeds = [b"sorted_vaults"], bump = sorted_vaults.bump)]
+    pub sorted_vaults: Account<'info, SortedVaults>,
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, Config>,
+    #[account(seeds = [b"rate_model"], bump = rate_model.bump)]
+    pub rate_model: Account<'info, InterestRateModel>,
+    #[account(
+        mut,
+        seeds = [b"collateral_config", vault.collateral_mint.as_ref()],
+        bump = collateral_config.bump,
+    )]
+    pub collateral_config: Account<'info, CollateralConfig>,
+    #[account(
+        mut,
+        seeds = [b"issuer_exposure", collateral_config.issuer.as_ref()],
+        bump = issuer_exposure.bump,
+    )]
+    pub issuer_exposure: Account<'info, IssuerExposure>,
+    #[account(mut, seeds = [b"global_state"], bump = global_state.bump)]
+    pub global_state: Account<'info, GlobalState>,
+    #[account(mut, seeds = [b"treasury"], bump)]
+    pub treasury: InterfaceAccount<'info, TokenAccount>,
+    #[account(mut, token::mint = treasury.mint)]
+    pub keeper_stablecoin_account: InterfaceAccount<'info, TokenAccount>,
+    #[account(address = treasury.mint)]
+    pub stablecoin_mint: InterfaceAccount<'info, Mint>,
+    pub stablecoin_token_program: Interface<'info, TokenInterface>,
+}
+
+#[derive(Accounts)]
+pub struct AccrueMany<'info> {
+    pub caller: Signer<'info>,
+    #[account(mut, seeds = [b"sorted_vaults"], bump = sorted_vaults.bump)]
+    pub sorted_vaults: Account<'info, SortedVaults>,
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, Config>,
+    #[account(seeds = [b"rate_model"], bump = rate_model.bump)]
+    pub rate_model: Account<'info, InterestRateModel>,
+    #[account(mut, seeds = [b"global_state"], bump = global_state.bump)]
+    pub global_state: Account<'info, GlobalState>,
+    #[account(mut, seeds = [b"treasury"], bump)]
+    pub treasury: InterfaceAccount<'info, TokenAccount>,
+    #[account(mut, token::mint = treasury.mint)]
+    pub keeper_stablecoin_account: InterfaceAccount<'info, TokenAccount>,
+    #[account(address = treasury.mint)]
+    pub stablecoin_mint: InterfaceAccount<'info, Mint>,
+    pub stablecoin_token_program: Interface<'info, TokenInterface>,
+}
+
+#[derive(Accounts)]
+pub struct Repay<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    #[account(
+        mut,
+        seeds = [b"vault", vault.creator.as_ref(), vault.collateral_mint.as_ref()],
+        bump = vault.bump,
+    )]
+    pub vault: Account<'info, Vault>,
+    #[account(
+        token::mint = vault.position_mint,
+        constraint = position_account.amount == 1 @ ErrorCode::PositionRequired,
+        constraint = is_vault_operator(&vault, &position_account, &authority.key())
+            @ ErrorCode::Unauthorized
+    )]
+    pub position_account: InterfaceAccount<'info, TokenAccount>,
+    #[account(
+        token::mint = vault.receipt_mint,
+        token::authority = position_account.owner,
+        constraint = owner_receipt_account.amount >= vault.bond_amount @ ErrorCode::ReceiptRequired
+    )]
+    pub owner_receipt_account: InterfaceAccount<'info, TokenAccount>,
+    #[account(mut, token::authority = authority)]
+    pub authority_stablecoin_account: InterfaceAccount<'info, TokenAccount>,
+    /// CHECK: deny entry for the account owner; empty unless the owner is denied
+    #[account(
+        seeds = [b"deny", authority_stablecoin_account.owner.as_ref()],
+        seeds::program = stablecoin_transfer_hook::ID,
+        bump
+    )]
+    pub deny_entry: UncheckedAccount<'info>,
+    #[account(mut, address = collateral_config.stablecoin_mint)]
+    pub stablecoin_mint: InterfaceAccount<'info, Mint>,
+    #[account(mut, seeds = [b"staking_reward_vault"], bump)]
+    pub staking_reward_vault: InterfaceAccount<'info, TokenAccount>,
+    #[account(mut, seeds = [b"treasury"], bump)]
+    pub treasury: InterfaceAccount<'info, TokenAccount>,
+    #[account(mut, seeds = [b"reserve"], bump)]
+    pub reserve: InterfaceAccount<'info, TokenAccount>,
+    #[account(mut, seeds = [b"insurance_fund"], bump)]
+    pub insurance_fund: InterfaceAccount<'info, TokenAccount>,
+    /// CHECK: PDA authority
+    #[account(seeds = [b"mint_authority"], bump = config.mint_authority_bump)]
+    pub mint_authority: AccountInfo<'info>,
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, Config>,
+    #[account(seeds = [b"rate_model"], bump = rate_model.bump)]
+    pub rate_model: Account<'info, InterestRateModel>,
+    #[account(
+        mut,
+        seeds = [b"collateral_config", vault.collateral_mint.as_ref()],
+        bump = collateral_config.bump,
+    )]
+    pub collateral_config: Account<'info, CollateralConfig>,
+    #[account(
+        mut,
+        seeds = [b"issuer_exposure", collateral_config.issuer.as_ref()],
+        bump = issuer_exposure.bump,
+    )]
+    pub issuer_exposure: Account<'info, IssuerExposure>,
+    #[account(mut, seeds = [b"sorted_vaults"], bump = sorted_vaults.bump)]
+    pub sorted_vaults: Account<'info, SortedVaults>,
+    #[account(mut, seeds = [b"global_state"], bump = global_state.bump)]
+    pub global_state: Account<'info, GlobalState>,
+    #[account(mut, seeds = [b"supply_ledger"], bump = supply_ledger.bump)]
+    pub supply_ledger: Account<'info, SupplyLedger>,
+    pub stablecoin_token_program: Interface<'info, TokenInterface>,
+}
+
+#[derive(Accounts)]
+pub struct RepayFor<'info> {
+    pub payer: Signer<'info>,
+    #[account(
+        mut,
+        seeds = [b"vault", vault.creator.as_ref(), vault.collateral_mint.as_ref()],
+        bump = vault.bump,
+    )]
+    pub vault: Account<'info, Vault>,
+    #[account(
+        mut,
+        constraint = payer_stablecoin_account.owner == payer.key()
+            || payer_stablecoin_account.delegate == COption::Some(payer.key())
+            @ ErrorCode::Unauthorized
+    )]
+    pub payer_stablecoin_account: InterfaceAccount<'info, TokenAccount>,
+    /// CHECK: deny entry for the account owner; empty unless the owner is denied
+    #[account(
+        seeds = [b"deny", payer_stablecoin_account.owner.as_ref()],
+        seeds::program = stablecoin_transfer_hook::ID,
+        bump
+    )]
+    pub deny_entry: UncheckedAccount<'info>,
+    #[account(mut, address = collateral_config.stablecoin_mint)]
+    pub stablecoin_mint: InterfaceAccount<'info, Mint>,
+    #[account(mut, seeds = [b"staking_reward_vault"], bump)]
+    pub staking_reward_vault: InterfaceAccount<'info, TokenAccount>,
+    #[account(mut, seeds = [b"treasury"], bump)]
+    pub treasury: InterfaceAccount<'info, TokenAccount>,
+    #[account(mut, seeds = [b"reserve"], bump)]
+    pub reserve: InterfaceAccount<'info, TokenAccount>,
+    #[account(mut, seeds = [b"insurance_fund"], bump)]
+    pub insurance_fund: InterfaceAccount<'info, TokenAccount>,
+    /// CHECK: PDA authority
+    #[account(seeds = [b"mint_authority"], bump = config.mint_authority_bump)]
+    pub mint_authority: AccountInfo<'info>,
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, Config>,
+    #[account(seeds = [b"rate_model"], bump = rate_model.bump)]
+    pub rate_model: Account<'info, InterestRateModel>,
+    #[account(
+        mut,
+        seeds = [b"collateral_config", vault.collateral_mint.as_ref()],
+        bump = collateral_config.bump,
+    )]
+    pub collateral_config: Account<'info, CollateralConfig>,
+    #[account(
+        mut,
+        seeds = [b"issuer_exposure", collateral_config.issuer.as_ref()],
+        bump = issuer_exposure.bump,
+    )]
+    pub issuer_exposure: Account<'info, IssuerExposure>,
+    #[account(mut, seeds = [b"sorted_vaults"], bump = sorted_vaults.bump)]
+    pub sorted_vaults: Account<'info, SortedVaults>,
+    #[account(mut, seeds = [b"global_state"], bump = global_state.bump)]
+    pub global_state: Account<'info, GlobalState>,
+    #[account(mut, seeds = [b"supply_ledger"], bump = supply_ledger.bump)]
+    pub supply_ledger: Account<'info, SupplyLedger>,
+    pub stablecoin_token_program: Interface<'info, TokenInterface>,
+}
+
+#[derive(Accounts)]
+pub struct WithdrawCollateral<'info> {
+    pub owner: Signer<'info>,
+    #[account(
+        mut,
+        has_one = receipt_mint,
+        seeds = [b"vault", vault.creator.as_ref(), vault.collateral_mint.as_ref()],
+        bump = vault.bump,
+    )]
+    pub vault: Account<'info, Vault>,
+    #[account(
+        token::mint = vault.position_mint,
+        token::authority = owner,
+        constraint = position_account.amount == 1 @ ErrorCode::PositionRequired
+    )]
+    pub position_account: InterfaceAccount<'info, TokenAccount>,
+    #[account(mut)]
+    pub receipt_mint: InterfaceAccount<'info, Mint>,
+    #[account(
+        mut,
+        token::mint = receipt_mint,
+        token::authority = owner,
+        constraint = owner_receipt_account.amount >= vault.bond_amount @ ErrorCode::ReceiptRequired
+    )]
+    pub owner_receipt_account: InterfaceAccount<'info, TokenAccount>,
+    #[account(mut, token::mint = vault.collateral_mint)]
+    pub owner_bond_account: InterfaceAccount<'info, TokenAccount>,
+    #[account(mut, seeds = [b"escrow", vault.key().as_ref()], bump)]
+    pub escrow: InterfaceAccount<'info, TokenAccount>,
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, Config>,
+    #[account(seeds = [b"rate_model"], bump = rate_model.bump)]
+    pub rate_model: Account<'info, InterestRateModel>,
+    #[account(
+        mut,
+        seeds = [b"collateral_config", vault.collateral_mint.as_ref()],
+        bump = collateral_config.bump,
+    )]
+    pub collateral_config: Account<'info, CollateralConfig>,
+    #[account(seeds = [b"price_cache", vault.collateral_mint.as_ref()], bump = price_cache.bump)]
+    pub price_cache: Account<'info, PriceCache>,
+    #[account(seeds = [b"price_twap", vault.collateral_mint.as_ref()], bump = price_twap.bump)]
+    pub price_twap: Account<'info, PriceTwap>,
+    #[account(mut, seeds = [b"sorted_vaults"], bump = sorted_vaults.bump)]
+    pub sorted_vaults: Account<'info, SortedVaults>,
+    #[account(mut, seeds = [b"global_state"], bump = global_state.bump)]
+    pub global_state: Account<'info, GlobalState>,
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct DepositCollateralLeg<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    #[account(
+        mut,
+        has_one = receipt_mint,
+        seeds = [b"vault", vault.creator.as_ref(), vault.collateral_mint.as_ref()],
+        bump = vault.bump,
+    )]
+    pub vault: Account<'info, Vault>,
+    #[account(
+        token::mint = vault.position_mint,
+        constraint = position_account.amount == 1 @ ErrorCode::PositionRequired,
+        constraint = is_vault_operator(&vault, &position_account, &authority.key())
+            @ ErrorCode::Unauthorized
+    )]
+    pub position_account: InterfaceAccount<'info, TokenAccount>,
+    #[account(constraint = leg_mint.key() != vault.collateral_mint @ ErrorCode::InvalidParameter)]
+    pub leg_mint: InterfaceAccount<'info, Mint>,
+    #[account(
+        seeds = [b"collateral_approval", leg_mint.key().as_ref()],
+        bump = approval.bump,
+    )]
+    pub approval: Account<'info, CollateralApproval>,
+    #[account(
+        seeds = [b"bond_metadata", leg_mint.key().as_ref()],
+        seeds::program = bond_tokenization::ID,
+        bump,
+        constraint = bond_metadata.authority == approval.issuer @ ErrorCode::UnknownIssuer
+    )]
+    pub bond_metadata: Account<'info, BondMetadata>,
+    #[account(
+        seeds = [b"collateral_config", leg_mint.key().as_ref()],
+        bump = leg_collateral_config.bump,
+    )]
+    pub leg_collateral_config: Account<'info, CollateralConfig>,
+    #[account(
+        init_if_needed,
+        payer = authority,
+        space = 8 + 32 + 32 + 32 + 8 + 1 + 8 * 5 + 1,
+        seeds = [b"leg", vault.key().as_ref(), leg_mint.key().as_ref()],
+        bump
+    )]
+    pub leg: Account<'info, CollateralLeg>,
+    #[account(
+        init_if_needed,
+        payer = authority,
+        seeds = [b"leg_escrow", leg.key().as_ref()],
+        bump,
+        token::mint = leg_mint,
+        token::authority = vault
+    )]
+    pub leg_escrow: InterfaceAccount<'info, TokenAccount>,
+    #[account(mut)]
+    pub receipt_mint: InterfaceAccount<'info, Mint>,
+    #[account(
+        mut,
+        token::mint = receipt_mint,
+        token::authority = position_account.owner,
+        constraint = owner_receipt_account.amount >= vault.bond_amount @ ErrorCode::ReceiptRequired
+    )]
+    pub owner_receipt_account: InterfaceAccount<'info, TokenAccount>,
+    #[account(mut, token::mint = leg_mint, token::authority = authority)]
+    pub authority_bond_account: InterfaceAccount<'info, TokenAccount>,
+    #[account(mut, seeds = [b"sorted_vaults"], bump = sorted_vaults.bump)]
+    pub sorted_vaults: Account<'info, SortedVaults>,
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, Config>,
+    #[account(seeds = [b"rate_model"], bump = rate_model.bump)]
+    pub rate_model: Account<'info, InterestRateModel>,
+    #[account(
+        mut,
+        seeds = [b"collateral_config", vault.collateral_mint.as_ref()],
+        bump = collateral_config.bump,
+    )]
+    pub collateral_config: Account<'info, CollateralConfig>,
+    #[account(mut, seeds = [b"global_state"], bump = global_state.bump)]
+    pub global_state: Account<'info, GlobalState>,
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct WithdrawCollateralLeg<'info> {
+    pub owner: Signer<'info>,
+    #[account(
+        mut,
+        has_one = receipt_mint,
+        seeds = [b"vault", vault.creator.as_ref(), vault.collateral_mint.as_ref()],
+        bump = vault.bump,
+    )]
+    pub vault: Account<'info, Vault>,
+    #[account(
+        token::mint = vault.position_mint,
+        token::authority = owner,
+        constraint = position_account.amount == 1 @ ErrorCode::PositionRequired
+    )]
+    pub position_account: InterfaceAccount<'info, TokenAccount>,
+    #[account(
+        mut,
+        has_one = vault,
+        seeds = [b"leg", vault.key().as_ref(), leg.mint.as_ref()],
+        bump = leg.bump,
+    )]
+    pub leg: Account<'info, CollateralLeg>,
+    #[account(mut, address = leg.escrow)]
+    pub leg_escrow: InterfaceAccount<'info, TokenAccount>,
+    #[account(
+        seeds = [b"collateral_config", leg.mint.as_ref()],
+        bump = leg_collateral_config.bump,
+    )]
+    pub leg_collateral_config: Account<'info, CollateralConfig>,
+    #[account(seeds = [b"price_cache", leg.mint.as_ref()], bump = leg_price_cache.bump)]
+    pub leg_price_cache: Account<'info, PriceCache>,
+    #[account(seeds = [b"price_twap", leg.mint.as_ref()], bump = leg_price_twap.bump)]
+    pub leg_price_twap: Account<'info, PriceTwap>,
+    #[account(mut)]
+    pub receipt_mint: InterfaceAccount<'info, Mint>,
+    #[account(
+        mut,
+        token::mint = receipt_mint,
+        token::authority = owner,
+        constraint = owner_receipt_account.amount >= vault.bond_amount @ ErrorCode::ReceiptRequired
+    )]
+    pub owner_receipt_account: InterfaceAccount<'info, TokenAccount>,
+    #[account(mut, token::mint = leg.mint)]
+    pub owner_bond_account: InterfaceAccount<'info, TokenAccount>,
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, Config>,
+    #[account(seeds = [b"rate_model"], bump = rate_model.bump)]
+    pub rate_model: Account<'info, InterestRateModel>,
+    #[account(
+        mut,
+        seeds = [b"collateral_config", vault.collateral_mint.as_ref()],
+        bump = collateral_config.bump,
+    )]
+    pub collateral_config: Account<'info, CollateralConfig>,
+    #[account(seeds = [b"price_cache", vault.collateral_mint.as_ref()], bump = price_cache.bump)]
+    pub price_cache: Account<'info, PriceCache>,
+    #[account(seeds = [b"price_twap", vault.collateral_mint.as_ref()], bump = price_twap.bump)]
+    pub price_twap: Account<'info, PriceTwap>,
+    #[account(mut, seeds = [b"sorted_vaults"], bump = sorted_vaults.bump)]
+    pub sorted_vaults: Account<'info, SortedVaults>,
+    #[account(mut, seeds = [b"global_state"], bump = global_state.bump)]
+    pub global_state: Account<'info, GlobalState>,
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct SubstituteCollateral<'info> {
+    #[account(mut)]
+    pub owner: Signer<'info>,
+    #[account(
+        mut,
+        has_one = receipt_mint,
+        seeds = [b"vault", vault.creator.as_ref(), vault.collateral_mint.as_ref()],
+        bump = vault.bump,
+    )]
+    pub vault: Account<'info, Vault>,
+    #[account(
+        token::mint = vault.position_mint,
+        token::authority = owner,
+        constraint = position_account.amount == 1 @ ErrorCode::PositionRequired
+    )]
+    pub position_account: InterfaceAccount<'info, TokenAccount>,
+    #[account(seeds = [b"escrow", vault.key().as_ref()], bump)]
+    pub escrow: InterfaceAccount<'info, TokenAccount>,
+    #[account(mut)]
+    pub source_leg: Option<Account<'info, CollateralLeg>>,
+    #[account(mut)]
+    pub source_escrow: InterfaceAccount<'info, TokenAccount>,
+    #[account(
+        seeds = [b"collateral_config", source_collateral_config.mint.as_ref()],
+        bump = source_collateral_config.bump,
+    )]
+    pub source_collateral_config: Account<'info, CollateralConfig>,
+    #[account(
+        seeds = [b"price_cache", source_collateral_config.mint.as_ref()],
+        bump = source_price_cache.bump,
+    )]
+    pub source_price_cache: Account<'info, PriceCache>,
+    #[account(
+        seeds = [b"price_twap", source_collateral_config.mint.as_ref()],
+        bump = source_price_twap.bump,
+    )]
+    pub source_price_twap: Account<'info, PriceTwap>,
+    #[account(mut, token::authority = owner)]
+    pub owner_source_account: InterfaceAccount<'info, TokenAccount>,
+    #[account(
+        constraint = target_mint.key() != vault.collateral_mint @ ErrorCode::InvalidParameter
+    )]
+    pub target_mint: InterfaceAccount<'info, Mint>,
+    #[account(
+        seeds = [b"collateral_approval", target_mint.key().as_ref()],
+        bump = approval.bump,
+    )]
+    pub approval: Account<'info, CollateralApproval>,
+    #[account(
+        seeds = [b"bond_metadata", target_mint.key().as_ref()],
+        seeds::program = bond_tokenization::ID,
+        bump,
+        constraint = bond_metadata.authority == approval.issuer @ ErrorCode::UnknownIssuer
+    )]
+    pub bond_metadata: Account<'info, BondMetadata>,
+    #[account(
+        seeds = [b"collateral_config", target_mint.key().as_ref()],
+        bump = target_collateral_config.bump,
+    )]
+    pub target_collateral_config: Account<'info, CollateralConfig>,
+    #[account(
+        seeds = [b"price_cache", target_mint.key().as_ref()],
+        bump = target_price_cache.bump,
+    )]
+    pub target_price_cache: Account<'info, PriceCache>,
+    #[account(
+        seeds = [b"price_twap", target_mint.key().as_ref()],
+        bump = target_price_twap.bump,
+    )]
+    pub target_price_twap: Account<'info, PriceTwap>,
+    #[account(
+        init_if_needed,
+        payer = owner,
+        space = 8 + 32 + 32 + 32 + 8 + 1 + 8 * 5 + 1,
+        seeds = [b"leg", vault.key().as_ref(), target_mint.key().as_ref()],
+        bump
+    )]
+    pub leg: Account<'info, CollateralLeg>,
+    #[account(
+        init_if_needed,
+        payer = owner,
+        seeds = [b"leg_escrow", leg.key().as_ref()],
+        bump,
+        token::mint = target_mint,
+        token::authority = vault
+    )]
+    pub leg_escrow: InterfaceAccount<'info, TokenAccount>,
+    #[account(mut, token::mint = target_mint, token::authority = owner)]
+    pub owner_target_account: InterfaceAccount<'info, TokenAccount>,
+    #[account(mut)]
+    pub receipt_mint: InterfaceAccount<'info, Mint>,
+    #[account(
+        mut,
+        token::mint = receipt_mint,
+        token::authority = owner,
+        constraint = owner_receipt_account.amount >= vault.bond_amount @ ErrorCode::ReceiptRequired
+    )]
+    pub owner_receipt_account: InterfaceAccount<'info, TokenAccount>,
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, Config>,
+    #[account(seeds = [b"rate_model"], bump = rate_model.bump)]
+    pub rate_model: Account<'info, InterestRateModel>,
+    #[account(
+        mut,
+        seeds = [b"collateral_config", vault.collateral_mint.as_ref()],
+        bump = collateral_config.bump,
+    )]
+    pub collateral_config: Account<'info, CollateralConfig>,
+    #[account(seeds = [b"price_cache", vault.collateral_mint.as_ref()], bump = price_cache.bump)]
+    pub price_cache: Account<'info, PriceCache>,
+    #[account(seeds = [b"price_twap", vault.collateral_mint.as_ref()], bump = price_twap.bump)]
+    pub price_twap: Account<'info, PriceTwap>,
+    #[account(mut, seeds = [b"sorted_vaults"], bump = sorted_vaults.bump)]
+    pub sorted_vaults: Account<'info, SortedVaults>,
+    #[account(mut, seeds = [b"global_state"], bump = global_state.bump)]
+    pub global_state: Account<'info, GlobalState>,
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct CloseCollateralLeg<'info> {
+    #[account(mut)]
+    pub owner: Signer<'info>,
+    #[account(
+        mut,
+        seeds = [b"vault", vault.creator.as_ref(), vault.collateral_mint.as_ref()],
+        bump = vault.bump,
+    )]
+    pub vault: Account<'info, Vault>,
+    #[account(
+        token::mint = vault.position_mint,
+        token::authority = owner,
+        constraint = position_account.amount == 1 @ ErrorCode::PositionRequired
+    )]
+    pub position_account: InterfaceAccount<'info, TokenAccount>,
+    #[account(
+        mut,
+        has_one = vault,
+        close = owner,
+        seeds = [b"leg", vault.key().as_ref(), leg.mint.as_ref()],
+        bump = leg.bump,
+    )]
+    pub leg: Account<'info, CollateralLeg>,
+    #[account(mut, address = leg.escrow)]
+    pub leg_escrow: InterfaceAccount<'info, TokenAccount>,
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct RevalueCollateralLeg<'info> {
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, Config>,
+    #[account(
+        mut,
+        seeds = [b"vault", vault.creator.as_ref(), vault.collateral_mint.as_ref()],
+        bump = vault.bump,
+    )]
+    pub vault: Account<'info, Vault>,
+    #[account(
+        mut,
+        has_one = vault,
+        seeds = [b"leg", vault.key().as_ref(), leg.mint.as_ref()],
+        bump = leg.bump,
+    )]
+    pub leg: Account<'info, CollateralLeg>,
+    #[account(
+        seeds = [b"collateral_config", leg.mint.as_ref()],
+        bump = leg_collateral_config.bump,
+    )]
+    pub leg_collateral_config: Account<'info, CollateralConfig>,
+    #[account(seeds = [b"price_cache", leg.mint.as_ref()], bump = price_cache.bump)]
+    pub price_cache: Account<'info, PriceCache>,
+}
+
+#[derive(Accounts)]
+pub struct ReinsertVault<'info> {
+    #[account(
+        mut,
+        seeds = [b"vault", vault.creator.as_ref(), vault.collateral_mint.as_ref()],
+        bump = vault.bump,
+    )]
+    pub vault: Account<'info, Vault>,
+    #[account(mut, seeds = [b"sorted_vaults"], bump = sorted_vaults.bump)]
+    pub sorted_vaults: Account<'info, SortedVaults>,
+}
+
+#[derive(Accounts)]
+pub struct CloseVault<'info> {
+    #[account(mut)]
+    pub owner: Signer<'info>,
+    #[account(
+        mut,
+        close = owner,
+        seeds = [b"vault", vault.creator.as_ref(), vault.collateral_mint.as_ref()],
+        bump = vault.bump,
+    )]
+    pub vault: Account<'info, Vault>,
+    #[account(
+        mut,
+        token::mint = vault.position_mint,
+        token::authority = owner,
+        constraint = position_account.amount == 1 @ ErrorCode::PositionRequired
+    )]
+    pub position_account: InterfaceAccount<'info, TokenAccount>,
+    #[account(mut, seeds = [b"escrow", vault.key().as_ref()], bump)]
+    pub escrow: InterfaceAccount<'info, TokenAccount>,
+    #[account(mut, address = vault.position_mint)]
+    pub position_mint: InterfaceAccount<'info, Mint>,
+    #[account(mut, seeds = [b"sorted_vaults"], bump = sorted_vaults.bump)]
+    pub sorted_vaults: Account<'info, SortedVaults>,
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct ProposeVaultOwner<'info> {
+    #[account(mut)]
+    pub owner: Signer<'info>,
+    #[account(
+        mut,
+        constraint = vault.pending_owner == Pubkey::default() @ ErrorCode::OwnerTransferPending,
+        seeds = [b"vault", vault.creator.as_ref(), vault.collateral_mint.as_ref()],
+        bump = vault.bump,
+    )]
+    pub vault: Account<'info, Vault>,
+    #[account(
+        mut,
+        token::mint = vault.position_mint,
+        token::authority = owner,
+        constraint = position_account.amount == 1 @ ErrorCode::PositionRequired
+    )]
+    pub position_account: InterfaceAccount<'info, TokenAccount>,
+    #[account(address = vault.position_mint)]
+    pub position_mint: InterfaceAccount<'info, Mint>,
+    #[account(
+        init_if_needed,
+        payer = owner,
+        seeds = [b"position_escrow", vault.key().as_ref()],
+        bump,
+        token::mint = position_mint,
+        token::authority = vault
+    )]
+    pub position_escrow: InterfaceAccount<'info, TokenAccount>,
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct AcceptVaultOwner<'info> {
+    #[account(mut)]
+    pub new_owner: Signer<'info>,
+    #[account(
+        mut,
+        constraint = vault.pending_owner == new_owner.key() @ ErrorCode::NotPendingOwner,
+        seeds = [b"vault", vault.creator.as_ref(), vault.collateral_mint.as_ref()],
+        bump = vault.bump,
+    )]
+    pub vault: Account<'info, Vault>,
+    #[account(address = vault.position_mint)]
+    pub position_mint: InterfaceAccount<'info, Mint>,
+    #[account(mut, seeds = [b"position_escrow", vault.key().as_ref()], bump)]
+    pub position_escrow: InterfaceAccount<'info, TokenAccount>,
+    #[account(
+        init_if_needed,
+        payer = new_owner,
+        associated_token::mint = position_mint,
+        associated_token::authority = new_owner
+    )]
+    pub new_owner_position_account: InterfaceAccount<'info, TokenAccount>,
+    pub token_program: Program<'info, Token>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct CancelVaultOwnerTransfer<'info> {
+    pub owner: Signer<'info>,
+    #[account(
+        mut,
+        has_one = owner,
+        constraint = vault.pending_owner != Pubkey::default() @ ErrorCode::NoPendingOwner,
+        seeds = [b"vault", vault.creator.as_ref(), vault.collateral_mint.as_ref()],
+        bump = vault.bump,
+    )]
+    pub vault: Account<'info, Vault>,
+    #[account(mut, seeds = [b"position_escrow", vault.key().as_ref()], bump)]
+    pub position_escrow: InterfaceAccount<'info, TokenAccount>,
+    #[account(mut, token::mint = vault.position_mint, token::authority = owner)]
+    pub position_account: InterfaceAccount<'info, TokenAccount>,
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct StartFixedTerm<'info> {
+    pub owner: Signer<'info>,
+    #[account(
+        mut,
+        seeds = [b"vault", vault.creator.as_ref(), vault.collateral_mint.as_ref()],
+        bump = vault.bump,
+    )]
+    pub vault: Account<'info, Vault>,
+    #[account(
+        token::mint = vault.position_mint,
+        token::authority = owner,
+        constraint = position_account.amount == 1 @ ErrorCode::PositionRequired
+    )]
+    pub position_account: InterfaceAccount<'info, TokenAccount>,
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, Config>,
+    #[account(seeds = [b"rate_model"], bump = rate_model.bump)]
+    pub rate_model: Account<'info, InterestRateModel>,
+    #[account(
+        seeds = [b"collateral_config", vault.collateral_mint.as_ref()],
+        bump = collateral_config.bump,
+    )]
+    pub collateral_config: Account<'info, CollateralConfig>,
+}
+
+#[derive(Accounts)]
+pub struct SetRateMode<'info> {
+    pub owner: Signer<'info>,
+    #[account(
+        mut,
+        seeds = [b"vault", vault.creator.as_ref(), vault.collateral_mint.as_ref()],
+        bump = vault.bump,
+    )]
+    pub vault: Account<'info, Vault>,
+    #[account(
+        token::mint = vault.position_mint,
+        token::authority = owner,
+        constraint = position_account.amount == 1 @ ErrorCode::PositionRequired
+    )]
+    pub position_account: InterfaceAccount<'info, TokenAccount>,
+}
+
+#[derive(Accounts)]
+pub struct ManageWithdrawal<'info> {
+    pub owner: Signer<'info>,
+    #[account(
+        mut,
+        seeds = [b"vault", vault.creator.as_ref(), vault.collateral_mint.as_ref()],
+        bump = vault.bump,
+    )]
+    pub vault: Account<'info, Vault>,
+    #[account(
+        token::mint = vault.position_mint,
+        token::authority = owner,
+        constraint = position_account.amount == 1 @ ErrorCode::PositionRequired
+    )]
+    pub position_account: InterfaceAccount<'info, TokenAccount>,
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, Config>,
+}
+
+#[derive(Accounts)]
+pub struct SetVaultDelegate<'info> {
+    pub owner: Signer<'info>,
+    #[account(
+        mut,
+        seeds = [b"vault", vault.creator.as_ref(), vault.collateral_mint.as_ref()],
+        bump = vault.bump,
+    )]
+    pub vault: Account<'info, Vault>,
+    #[account(
+        token::mint = vault.position_mint,
+        token::authority = owner,
+        constraint = position_account.amount == 1 @ ErrorCode::PositionRequired
+    )]
+    pub position_account: InterfaceAccount<'info, TokenAccount>,
+}
+
+#[derive(Accounts)]
+pub struct FlagUnhealthy<'info> {
+    #[account(
+        mut,
+        seeds = [b"vault", vault.creator.as_ref(), vault.collateral_mint.as_ref()],
+        bump = vault.bump,
+    )]
+    pub vault: Account<'info, Vault>,
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, Config>,
+    #[account(seeds = [b"rate_model"], bump = rate_model.bump)]
+    pub rate_model: Account<'info, InterestRateModel>,
+    #[account(
+        seeds = [b"collateral_config", vault.collateral_mint.as_ref()],
+        bump = collateral_config.bump,
+    )]
+    pub collateral_config: Account<'info, CollateralConfig>,
+    #[account(seeds = [b"price_cache", vault.collateral_mint.as_ref()], bump = price_cache.bump)]
+    pub price_cache: Account<'info, PriceCache>,
+    #[account(seeds = [b"global_state"], bump = global_state.bump)]
+    pub global_state: Account<'info, GlobalState>,
+}
+
+#[derive(Accounts)]
+pub struct FlagForSettlement<'info> {
+    #[account(
+        mut,
+        seeds = [b"vault", vault.creator.as_ref(), vault.collateral_mint.as_ref()],
+        bump = vault.bump,
+    )]
+    pub vault: Account<'info, Vault>,
+    pub leg: Option<Account<'info, CollateralLeg>>,
+    #[account(
+        seeds = [b"bond_metadata", bond_metadata.mint.as_ref()],
+        seeds::program = bond_tokenization::ID,
+        bump,
+    )]
+    pub bond_metadata: Account<'info, BondMetadata>,
+}
+
+#[derive(Accounts)]
+pub struct SettleMatured<'info> {
+    #[account(
+        mut,
+        seeds = [b"vault", vault.creator.as_ref(), vault.collateral_mint.as_ref()],
+        bump = vault.bump,
+    )]
+    pub vault: Account<'info, Vault>,
+    #[account(mut, seeds = [b"escrow", vault.key().as_ref()], bump)]
+    pub escrow: InterfaceAccount<'info, TokenAccount>,
+    #[account(mut, address = vault.collateral_mint)]
+    pub collateral_mint: InterfaceAccount<'info, Mint>,
+    #[account(
+        seeds = [b"bond_metadata", vault.collateral_mint.as_ref()],
+        seeds::program = bond_tokenization::ID,
+        bump,
+    )]
+    pub bond_metadata: Account<'info, BondMetadata>,
+    #[account(
+        mut,
+        seeds = [b"redemption_vault", vault.collateral_mint.as_ref()],
+        seeds::program = bond_tokenization::ID,
+        bump,
+        constraint = redemption_vault.mint == psm_state.usdc_mint @ ErrorCode::InvalidPaymentMint,
+    )]
+    pub redemption_vault: InterfaceAccount<'info, TokenAccount>,
+    #[account(address = psm_state.usdc_mint)]
+    pub payment_mint: InterfaceAccount<'info, Mint>,
+    #[account(mut, seeds = [b"psm"], bump = psm_state.bump)]
+    pub psm_state: Account<'info, PsmState>,
+    #[account(mut, seeds = [b"psm_reserve"], bump)]
+    pub psm_reserve: InterfaceAccount<'info, TokenAccount>,
+    #[account(mut, seeds = [b"surplus_vault"], bump)]
+    pub surplus_vault: InterfaceAccount<'info, TokenAccount>,
+    #[account(mut, address = collateral_config.stablecoin_mint)]
+    pub stablecoin_mint: InterfaceAccount<'info, Mint>,
+    #[account(mut, seeds = [b"staking_reward_vault"], bump)]
+    pub staking_reward_vault: InterfaceAccount<'info, TokenAccount>,
+    #[account(mut, seeds = [b"treasury"], bump)]
+    pub treasury: InterfaceAccount<'info, TokenAccount>,
+    #[account(mut, seeds = [b"reserve"], bump)]
+    pub reserve: InterfaceAccount<'info, TokenAccount>,
+    #[account(mut, seeds = [b"insurance_fund"], bump)]
+    pub insurance_fund: InterfaceAccount<'info, TokenAccount>,
+    /// CHECK: PDA authority
+    #[account(seeds = [b"mint_authority"], bump = config.mint_authority_bump)]
+    pub mint_authority: AccountInfo<'info>,
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, Config>,
+    #[account(seeds = [b"rate_model"], bump = rate_model.bump)]
+    pub rate_model: Account<'info, InterestRateModel>,
+    #[account(
+        mut,
+        seeds = [b"collateral_config", vault.collateral_mint.as_ref()],
+        bump = collateral_config.bump,
+    )]
+    pub collateral_config: Account<'info, CollateralConfig>,
+    #[account(
+        mut,
+        seeds = [b"issuer_exposure", collateral_config.issuer.as_ref()],
+        bump = issuer_exposure.bump,
+    )]
+    pub issuer_exposure: Account<'info, IssuerExposure>,
+    #[account(mut, seeds = [b"global_state"], bump = global_state.bump)]
+    pub global_state: Account<'info, GlobalState>,
+    #[account(mut, seeds = [b"sorted_vaults"], bump = sorted_vaults.bump)]
+    pub sorted_vaults: Account<'info, SortedVaults>,
+    pub bond_program: Program<'info, BondTokenization>,
+    pub token_program: Program<'info, Token>,
+    #[account(mut, seeds = [b"supply_ledger"], bump = supply_ledger.bump)]
+    pub supply_ledger: Account<'info, SupplyLedger>,
+    pub stablecoin_token_program: Interface<'info, TokenInterface>,
+}
+
+#[derive(Accounts)]
+pub struct Liquidate<'info> {
+    pub liquidator: Signer<'info>,
+    #[account(
+        mut,
+        seeds = [b"vault", vault.creator.as_ref(), vault.collateral_mint.as_ref()],
+        bump = vault.bump,
+    )]
+    pub vault: Account<'info, Vault>,
+    #[account(mut, seeds = [b"sorted_vaults"], bump = sorted_vaults.bump)]
+    pub sorted_vaults: Account<'info, SortedVaults>,
+    #[account(mut, seeds = [b"escrow", vault.key().as_ref()], bump)]
+    pub escrow: InterfaceAccount<'info, TokenAccount>,
+    #[account(mut, token::mint = vault.collateral_mint)]
+    pub liquidator_bond_account: InterfaceAccount<'info, TokenAccount>,
+    #[account(mut, token::authority = liquidator)]
+    pub liquidator_stablecoin_account: InterfaceAccount<'info, TokenAccount>,
+    /// CHECK: deny entry for the account owner; empty unless the owner is denied
+    #[account(
+        seeds = [b"deny", liquidator_stablecoin_account.owner.as_ref()],
+        seeds::program = stablecoin_transfer_hook::ID,
+        bump
+    )]
+    pub deny_entry: UncheckedAccount<'info>,
+    #[account(mut, seeds = [b"surplus_vault"], bump)]
+    pub surplus_vault: InterfaceAccount<'info, TokenAccount>,
+    #[account(mut, address = collateral_config.stablecoin_mint)]
+    pub stablecoin_mint: InterfaceAccount<'info, Mint>,
+    #[account(mut, seeds = [b"staking_reward_vault"], bump)]
+    pub staking_reward_vault: InterfaceAccount<'info, TokenAccount>,
+    #[account(mut, seeds = [b"treasury"], bump)]
+    pub treasury: InterfaceAccount<'info, TokenAccount>,
+    #[account(mut, seeds = [b"reserve"], bump)]
+    pub reserve: InterfaceAccount<'info, TokenAccount>,
+    #[account(mut, seeds = [b"insurance_fund"], bump)]
+    pub insurance_fund: InterfaceAccount<'info, TokenAccount>,
+    /// CHECK: PDA authority
+    #[account(seeds = [b"mint_authority"], bump = config.mint_authority_bump)]
+    pub mint_authority: AccountInfo<'info>,
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, Config>,
+    #[account(seeds = [b"rate_model"], bump = rate_model.bump)]
+    pub rate_model: Account<'info, InterestRateModel>,
+    #[account(
+        mut,
+        seeds = [b"collateral_config", vault.collateral_mint.as_ref()],
+        bump = collateral_config.bump,
+    )]
+    pub collateral_config: Account<'info, CollateralConfig>,
+    #[account(seeds = [b"price_cache", vault.collateral_mint.as_ref()], bump = price_cache.bump)]
+    pub price_cache: Account<'info, PriceCache>,
+    #[account(
+        mut,
+        seeds = [b"issuer_exposure", collateral_config.issuer.as_ref()],
+        bump = issuer_exposure.bump,
+    )]
+    pub issuer_exposure: Account<'info, IssuerExposure>,
+    #[account(mut, seeds = [b"global_state"], bump = global_state.bump)]
+    pub global_state: Account<'info, GlobalState>,
+    pub token_program: Program<'info, Token>,
+    #[account(mut, seeds = [b"supply_ledger"], bump = supply_ledger.bump)]
+    pub supply_ledger: Account<'info, SupplyLedger>,
+    pub stablecoin_token_program: Interface<'info, TokenInterface>,
+}
+
+#[derive(Accounts)]
+pub struct LiquidateLeg<'info> {
+    pub liquidator: Signer<'info>,
+    #[account(
+        mut,
+        seeds = [b"vault", vault.creator.as_ref(), vault.collateral_mint.as_ref()],
+        bump = vault.bump,
+    )]
+    pub vault: Account<'info, Vault>,
+    #[account(mut, seeds = [b"sorted_vaults"], bump = sorted_vaults.bump)]
+    pub sorted_vaults: Account<'info, SortedVaults>,
+    #[account(
+        mut,
+        has_one = vault,
+        seeds = [b"leg", vault.key().as_ref(), leg.mint.as_ref()],
+        bump = leg.bump,
+    )]
+    pub leg: Account<'info, CollateralLeg>,
+    #[account(mut, address = leg.escrow)]
+    pub leg_escrow: InterfaceAccount<'info, TokenAccount>,
+    #[account(
+        seeds = [b"collateral_config", leg.mint.as_ref()],
+        bump = leg_collateral_config.bump,
+    )]
+    pub leg_collateral_config: Account<'info, CollateralConfig>,
+    #[account(seeds = [b"price_cache", leg.mint.as_ref()], bump = leg_price_cache.bump)]
+    pub leg_price_cache: Account<'info, PriceCache>,
+    #[account(mut, token::mint = leg.mint)]
+    pub liquidator_bond_account: InterfaceAccount<'info, TokenAccount>,
+    #[account(mut, token::authority = liquidator)]
+    pub liquidator_stablecoin_account: InterfaceAccount<'info, TokenAccount>,
+    /// CHECK: deny entry for the account owner; empty unless the owner is denied
+    #[account(
+        seeds = [b"deny", liquidator_stablecoin_account.owner.as_ref()],
+        seeds::program = stablecoin_transfer_hook::ID,
+        bump
+    )]
+    pub deny_entry: UncheckedAccount<'info>,
+    #[account(mut, seeds = [b"surplus_vault"], bump)]
+    pub surplus_vault: InterfaceAccount<'info, TokenAccount>,
+    #[account(mut, address = collateral_config.stablecoin_mint)]
+    pub stablecoin_mint: InterfaceAccount<'info, Mint>,
+    #[account(mut, seeds = [b"staking_reward_vault"], bump)]
+    pub staking_reward_vault: InterfaceAccount<'info, TokenAccount>,
+    #[account(mut, seeds = [b"treasury"], bump)]
+    pub treasury: InterfaceAccount<'info, TokenAccount>,
+    #[account(mut, seeds = [b"reserve"], bump)]
+    pub reserve: InterfaceAccount<'info, TokenAccount>,
+    #[account(mut, seeds = [b"insurance_fund"], bump)]
+    pub insurance_fund: InterfaceAccount<'info, TokenAccount>,
+    /// CHECK: PDA authority
+    #[account(seeds = [b"mint_authority"], bump = config.mint_authority_bump)]
+    pub mint_authority: AccountInfo<'info>,
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, Config>,
+    #[account(seeds = [b"rate_model"], bump = rate_model.bump)]
+    pub rate_model: Account<'info, InterestRateModel>,
+    #[account(
+        mut,
+        seeds = [b"collateral_config", vault.collateral_mint.as_ref()],
+        bump = collateral_config.bump,
+    )]
+    pub collateral_config: Account<'info, CollateralConfig>,
+    #[account(seeds = [b"price_cache", vault.collateral_mint.as_ref()], bump = price_cache.bump)]
+    pub price_cache: Account<'info, PriceCache>,
+    #[account(
+        mut,
+        seeds = [b"issuer_exposure", collateral_config.issuer.as_ref()],
+        bump = issuer_exposure.bump,
+    )]
+    pub issuer_exposure: Account<'info, IssuerExposure>,
+    #[account(mut, seeds = [b"global_state"], bump = global_state.bump)]
+    pub global_state: Account<'info, GlobalState>,
+    pub token_program: Program<'info, Token>,
+    #[account(mut, seeds = [b"supply_ledger"], bump = supply_ledger.bump)]
+    pub supply_ledger: Account<'info, SupplyLedger>,
+    pub stablecoin_token_program: Interface<'info, TokenInterface>,
+}
+
+#[derive(Accounts)]
+pub struct FlashLiquidate<'info> {
+    pub liquidator: Signer<'info>,
+    #[account(
+        mut,
+        seeds = [b"vault", vault.creator.as_ref(), vault.collateral_mint.as_ref()],
+        bump = vault.bump,
+    )]
+    pub vault: Account<'info, Vault>,
+    #[account(mut, seeds = [b"sorted_vaults"], bump = sorted_vaults.bump)]
+    pub sorted_vaults: Account<'info, SortedVaults>,
+    #[account(mut, seeds = [b"escrow", vault.key().as_ref()], bump)]
+    pub escrow: InterfaceAccount<'info, TokenAccount>,
+    #[account(mut, token::mint = vault.collateral_mint)]
+    pub liquidator_bond_account: InterfaceAccount<'info, TokenAccount>,
+    #[account(mut, token::authority = liquidator)]
+    pub liquidator_stablecoin_account: InterfaceAccount<'info, TokenAccount>,
+    /// CHECK: deny entry for the account owner; empty unless the owner is denied
+    #[account(
+        seeds = [b"deny", liquidator_stablecoin_account.owner.as_ref()],
+        seeds::program = stablecoin_transfer_hook::ID,
+        bump
+    )]
+    pub deny_entry: UncheckedAccount<'info>,
+    #[account(mut, seeds = [b"surplus_vault"], bump)]
+    pub surplus_vault: InterfaceAccount<'info, TokenAccount>,
+    #[account(mut, address = collateral_config.stablecoin_mint)]
+    pub stablecoin_mint: InterfaceAccount<'info, Mint>,
+    #[account(mut, seeds = [b"staking_reward_vault"], bump)]
+    pub staking_reward_vault: InterfaceAccount<'info, TokenAccount>,
+    #[account(mut, seeds = [b"treasury"], bump)]
+    pub treasury: InterfaceAccount<'info, TokenAccount>,
+    #[account(mut, seeds = [b"reserve"], bump)]
+    pub reserve: InterfaceAccount<'info, TokenAccount>,
+    #[account(mut, seeds = [b"insurance_fund"], bump)]
+    pub insurance_fund: InterfaceAccount<'info, TokenAccount>,
+    /// CHECK: PDA authority
+    #[account(seeds = [b"mint_authority"], bump = config.mint_authority_bump)]
+    pub mint_authority: AccountInfo<'info>,
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, Config>,
+    #[account(seeds = [b"rate_model"], bump = rate_model.bump)]
+    pub rate_model: Account<'info, InterestRateModel>,
+    #[account(
+        mut,
+        seeds = [b"collateral_config", vault.collateral_mint.as_ref()],
+        bump = collateral_config.bump,
+    )]
+    pub collateral_config: Account<'info, CollateralConfig>,
+    #[account(seeds = [b"price_cache", vault.collateral_mint.as_ref()], bump = price_cache.bump)]
+    pub price_cache: Account<'info, PriceCache>,
+    #[account(
+        mut,
+        seeds = [b"issuer_exposure", collateral_config.issuer.as_ref()],
+        bump = issuer_exposure.bump,
+    )]
+    pub issuer_exposure: Account<'info, IssuerExposure>,
+    #[account(mut, seeds = [b"global_state"], bump = global_state.bump)]
+    pub global_state: Account<'info, GlobalState>,
+    pub token_program: Program<'info, Token>,
+    #[account(mut, seeds = [b"supply_ledger"], bump = supply_ledger.bump)]
+    pub supply_ledger: Account<'info, SupplyLedger>,
+    pub stablecoin_token_program: Interface<'info, TokenInterface>,
+    /// CHECK: Swap route invoked with the remaining accounts
+    pub swap_program: UncheckedAccount<'info>,
+}
+
+#[derive(Accounts)]
+pub struct Deleverage<'info> {
+    pub owner: Signer<'info>,
+    #[account(
+        mut,
+        has_one = receipt_mint,
+        seeds = [b"vault", vault.creator.as_ref(), vault.collateral_mint.as_ref()],
+        bump = vault.bump,
+    )]
+    pub vault: Account<'info, Vault>,
+    #[account(
+        token::mint = vault.position_mint,
+        token::authority = owner,
+        constraint = position_account.amount == 1 @ ErrorCode::PositionRequired
+    )]
+    pub position_account: InterfaceAccount<'info, TokenAccount>,
+    #[account(mut)]
+    pub receipt_mint: InterfaceAccount<'info, Mint>,
+    #[account(
+        mut,
+        token::mint = receipt_mint,
+        token::authority = owner,
+        constraint = owner_receipt_account.amount >= vault.bond_amount @ ErrorCode::ReceiptRequired
+    )]
+    pub owner_receipt_account: InterfaceAccount<'info, TokenAccount>,
+    #[account(mut, token::mint = vault.collateral_mint, token::authority = owner)]
+    pub owner_bond_account: InterfaceAccount<'info, TokenAccount>,
+    #[account(mut, seeds = [b"escrow", vault.key().as_ref()], bump)]
+    pub escrow: InterfaceAccount<'info, TokenAccount>,
+    #[account(mut, token::authority = owner)]
+    pub owner_stablecoin_account: InterfaceAccount<'info, TokenAccount>,
+    /// CHECK: deny entry for the account owner; empty unless the owner is denied
+    #[account(
+        seeds = [b"deny", owner_stablecoin_account.owner.as_ref()],
+        seeds::program = stablecoin_transfer_hook::ID,
+        bump
+    )]
+    pub deny_entry: UncheckedAccount<'info>,
+    #[account(mut, address = collateral_config.stablecoin_mint)]
+    pub stablecoin_mint: InterfaceAccount<'info, Mint>,
+    #[account(mut, seeds = [b"staking_reward_vault"], bump)]
+    pub staking_reward_vault: InterfaceAccount<'info, TokenAccount>,
+    #[account(mut, seeds = [b"treasury"], bump)]
+    pub treasury: InterfaceAccount<'info, TokenAccount>,
+    #[account(mut, seeds = [b"reserve"], bump)]
+    pub reserve: InterfaceAccount<'info, TokenAccount>,
+    #[account(mut, seeds = [b"insurance_fund"], bump)]
+    pub insurance_fund: InterfaceAccount<'info, TokenAccount>,
+    /// CHECK: PDA authority
+    #[account(seeds = [b"mint_authority"], bump = config.mint_authority_bump)]
+    pub mint_authority: AccountInfo<'info>,
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, Config>,
+    #[account(seeds = [b"rate_model"], bump = rate_model.bump)]
+    pub rate_model: Account<'info, InterestRateModel>,
+    #[account(
+        mut,
+        seeds = [b"collateral_config", vault.collateral_mint.as_ref()],
+        bump = collateral_config.bump,
+    )]
+    pub collateral_config: Account<'info, CollateralConfig>,
+    #[account(seeds = [b"price_cache", vault.collateral_mint.as_ref()], bump = price_cache.bump)]
+    pub price_cache: Account<'info, PriceCache>,
+    #[account(seeds = [b"price_twap", vault.collateral_mint.as_ref()], bump = price_twap.bump)]
+    pub price_twap: Account<'info, PriceTwap>,
+    #[account(
+        mut,
+        seeds = [b"issuer_exposure", collateral_config.issuer.as_ref()],
+        bump = issuer_exposure.bump,
+    )]
+    pub issuer_exposure: Account<'info, IssuerExposure>,
+    #[account(mut, seeds = [b"global_state"], bump = global_state.bump)]
+    pub global_state: Account<'info, GlobalState>,
+    #[account(mut, seeds = [b"sorted_vaults"], bump = sorted_vaults.bump)]
+    pub sorted_vaults: Account<'info, SortedVaults>,
+    pub token_program: Program<'info, Token>,
+    #[account(mut, seeds = [b"supply_ledger"], bump = supply_ledger.bump)]
+    pub supply_ledger: Account<'info, SupplyLedger>,
+    pub stablecoin_token_program: Interface<'info, TokenInterface>,
+    /// CHECK: Swap route invoked with the remaining accounts
+    pub swap_program: UncheckedAccount<'info>,
+}
+
+#[derive(Accounts)]
+pub struct LiquidateMany<'info> {
+    pub liquidator: Signer<'info>,
+    #[account(mut, token::authority = liquidator)]
+    pub liquidator_stablecoin_account: InterfaceAccount<'info, TokenAccount>,
+    /// CHECK: deny entry for the account owner; empty unless the owner is denied
+    #[account(
+        seeds = [b"deny", liquidator_stablecoin_account.owner.as_ref()],
+        seeds::program = stablecoin_transfer_hook::ID,
+        bump
+    )]
+    pub deny_entry: UncheckedAccount<'info>,
+    #[account(mut, seeds = [b"surplus_vault"], bump)]
+    pub surplus_vault: InterfaceAccount<'info, TokenAccount>,
+    #[account(mut)]
+    pub stablecoin_mint: InterfaceAccount<'info, Mint>,
+    #[account(mut, seeds = [b"staking_reward_vault"], bump)]
+    pub staking_reward_vault: InterfaceAccount<'info, TokenAccount>,
+    #[account(mut, seeds = [b"treasury"], bump)]
+    pub treasury: InterfaceAccount<'info, TokenAccount>,
+    #[account(mut, seeds = [b"reserve"], bump)]
+    pub reserve: InterfaceAccount<'info, TokenAccount>,
+    #[account(mut, seeds = [b"insurance_fund"], bump)]
+    pub insurance_fund: InterfaceAccount<'info, TokenAccount>,
+    /// CHECK: PDA authority
+    #[account(seeds = [b"mint_authority"], bump = config.mint_authority_bump)]
+    pub mint_authority: AccountInfo<'info>,
+    #[account(mut, seeds = [b"sorted_vaults"], bump = sorted_vaults.bump)]
+    pub sorted_vaults: Account<'info, SortedVaults>,
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, Config>,
+    #[account(seeds = [b"rate_model"], bump = rate_model.bump)]
+    pub rate_model: Account<'info, InterestRateModel>,
+    #[account(mut, seeds = [b"global_state"], bump = global_state.bump)]
+    pub global_state: Account<'info, GlobalState>,
+    pub token_program: Program<'info, Token>,
+    #[account(mut, seeds = [b"supply_ledger"], bump = supply_ledger.bump)]
+    pub supply_ledger: Account<'info, SupplyLedger>,
+    pub stablecoin_token_program: Interface<'info, TokenInterface>,
+}
+
+#[derive(Accounts)]
+pub struct CoverBadDebt<'info> {
+    #[account(
+        mut,
+        seeds = [b"vault", vault.creator.as_ref(), vault.collateral_mint.as_ref()],
+        bump = vault.bump,
+    )]
+    pub vault: Account<'info, Vault>,
+    #[account(mut, seeds = [b"reserve"], bump)]
+    pub reserve: InterfaceAccount<'info, TokenAccount>,
+    #[account(mut, address = collateral_config.stablecoin_mint)]
+    pub stablecoin_mint: InterfaceAccount<'info, Mint>,
+    #[account(
+        mut,
+        seeds = [b"collateral_config", vault.collateral_mint.as_ref()],
+        bump = collateral_config.bump,
+    )]
+    pub collateral_config: Account<'info, CollateralConfig>,
+    #[account(seeds = [b"rate_model"], bump = rate_model.bump)]
+    pub rate_model: Account<'info, InterestRateModel>,
+    #[account(
+        mut,
+        seeds = [b"issuer_exposure", collateral_config.issuer.as_ref()],
+        bump = issuer_exposure.bump,
+    )]
+    pub issuer_exposure: Account<'info, IssuerExposure>,
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, Config>,
+    #[account(mut, seeds = [b"global_state"], bump = global_state.bump)]
+    pub global_state: Account<'info, GlobalState>,
+    #[account(mut, seeds = [b"sorted_vaults"], bump = sorted_vaults.bump)]
+    pub sorted_vaults: Account<'info, SortedVaults>,
+    #[account(mut, seeds = [b"supply_ledger"], bump = supply_ledger.bump)]
+    pub supply_ledger: Account<'info, SupplyLedger>,
+    pub stablecoin_token_program: Interface<'info, TokenInterface>,
+}
+
+#[derive(Accounts)]
+pub struct StartDebtAuction<'info> {
+    #[account(mut)]
+    pub risk_authority: Signer<'info>,
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump,
+        constraint = config.risk_authority == risk_authority.key() @ ErrorCode::Unauthorized
+    )]
+    pub config: Account<'info, Config>,
+    #[account(
+        seeds = [b"vault", vault.creator.as_ref(), vault.collateral_mint.as_ref()],
+        bump = vault.bump,
+    )]
+    pub vault: Account<'info, Vault>,
+    #[account(seeds = [b"reserve"], bump)]
+    pub reserve: InterfaceAccount<'info, TokenAccount>,
+    #[account(seeds = [b"insurance_fund"], bump)]
+    pub insurance_fund: InterfaceAccount<'info, TokenAccount>,
+    #[account(
+        init_if_needed,
+        payer = risk_authority,
+        space = 8 + 32 + 8 + 8 + 32 + 8 + 1 + 1,
+        seeds = [b"debt_auction", vault.key().as_ref()],
+        bump
+    )]
+    pub debt_auction: Account<'info, DebtAuction>,
+    #[account(address = reserve.mint)]
+    pub stablecoin_mint: InterfaceAccount<'info, Mint>,
+    #[account(
+        init_if_needed,
+        payer = risk_authority,
+        seeds = [b"debt_auction_escrow", vault.key().as_ref()],
+        bump,
+        token::mint = stablecoin_mint,
+        token::authority = debt_auction,
+        token::token_program = stablecoin_token_program
+    )]
+    pub auction_escrow: InterfaceAccount<'info, TokenAccount>,
+    pub stablecoin_token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct BidDebtAuction<'info> {
+    pub bidder: Signer<'info>,
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, Config>,
+    #[account(
+        mut,
+        seeds = [b"debt_auction", debt_auction.vault.as_ref()],
+        bump = debt_auction.bump,
+    )]
+    pub debt_auction: Account<'info, DebtAuction>,
+    #[account(mut, seeds = [b"debt_auction_escrow", debt_auction.vault.as_ref()], bump)]
+    pub auction_escrow: InterfaceAccount<'info, TokenAccount>,
+    #[account(mut, token::mint = stablecoin_mint, token::authority = bidder)]
+    pub bidder_stablecoin_account: InterfaceAccount<'info, TokenAccount>,
+    #[account(mut, token::mint = stablecoin_mint)]
+    pub previous_bidder_stablecoin_account: Option<InterfaceAccount<'info, TokenAccount>>,
+    #[account(address = auction_escrow.mint)]
+    pub stablecoin_mint: InterfaceAccount<'info, Mint>,
+    pub stablecoin_token_program: Interface<'info, TokenInterface>,
+}
+
+#[derive(Accounts)]
+pub struct SettleDebtAuction<'info> {
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, Config>,
+    #[account(
+        mut,
+        seeds = [b"vault", vault.creator.as_ref(), vault.collateral_mint.as_ref()],
+        bump = vault.bump,
+    )]
+    pub vault: Account<'info, Vault>,
+    #[account(
+        mut,
+        has_one = vault,
+        seeds = [b"debt_auction", vault.key().as_ref()],
+        bump = debt_auction.bump,
+    )]
+    pub debt_auction: Account<'info, DebtAuction>,
+    #[account(mut, seeds = [b"debt_auction_escrow", vault.key().as_ref()], bump)]
+    pub auction_escrow: InterfaceAccount<'info, TokenAccount>,
+    #[account(
+        mut,
+        token::mint = stablecoin_mint,
+        constraint = winner_stablecoin_account.owner == debt_auction.bidder
+            @ ErrorCode::Unauthorized
+    )]
+    pub winner_stablecoin_account: InterfaceAccount<'info, TokenAccount>,
+    #[account(
+        mut,
+        token::mint = governance_mint,
+        constraint = winner_governance_account.owner == debt_auction.bidder
+            @ ErrorCode::Unauthorized
+    )]
+    pub winner_governance_account: InterfaceAccount<'info, TokenAccount>,
+    #[account(mut, address = collateral_config.stablecoin_mint)]
+    pub stablecoin_mint: InterfaceAccount<'info, Mint>,
+    #[account(mut, address = config.governance_mint)]
+    pub governance_mint: InterfaceAccount<'info, Mint>,
+    #[account(
+        mut,
+        seeds = [b"collateral_config", vault.collateral_mint.as_ref()],
+        bump = collateral_config.bump,
+    )]
+    pub collateral_config: Account<'info, CollateralConfig>,
+    #[account(seeds = [b"rate_model"], bump = rate_model.bump)]
+    pub rate_model: Account<'info, InterestRateModel>,
+    #[account(
+        mut,
+        seeds = [b"issuer_exposure", collateral_config.issuer.as_ref()],
+        bump = issuer_exposure.bump,
+    )]
+    pub issuer_exposure: Account<'info, IssuerExposure>,
+    #[account(mut, seeds = [b"global_state"], bump = global_state.bump)]
+    pub global_state: Account<'info, GlobalState>,
+    #[account(mut, seeds = [b"sorted_vaults"], bump = sorted_vaults.bump)]
+    pub sorted_vaults: Account<'info, SortedVaults>,
+    #[account(mut, seeds = [b"supply_ledger"], bump = supply_ledger.bump)]
+    pub supply_ledger: Account<'info, SupplyLedger>,
+    pub stablecoin_token_program: Interface<'info, TokenInterface>,
+    pub governance_token_program: Interface<'info, TokenInterface>,
+}
+
+#[derive(Accounts)]
+pub struct DrawInsuranceFund<'info> {
+    pub risk_authority: Signer<'info>,
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump,
+        constraint = config.risk_authority == risk_authority.key() @ ErrorCode::Unauthorized
+    )]
+    pub config: Account<'info, Config>,
+    #[account(
+        mut,
+        seeds = [b"vault", vault.creator.as_ref(), vault.collateral_mint.as_ref()],
+        bump = vault.bump,
+    )]
+    pub vault: Account<'info, Vault>,
+    #[account(mut, seeds = [b"insurance_fund"], bump)]
+    pub insurance_fund: InterfaceAccount<'info, TokenAccount>,
+    #[account(mut, address = collateral_config.stablecoin_mint)]
+    pub stablecoin_mint: InterfaceAccount<'info, Mint>,
+    #[account(
+        mut,
+        seeds = [b"collateral_config", vault.collateral_mint.as_ref()],
+        bump = collateral_config.bump,
+    )]
+    pub collateral_config: Account<'info, CollateralConfig>,
+    #[account(seeds = [b"rate_model"], bump = rate_model.bump)]
+    pub rate_model: Account<'info, InterestRateModel>,
+    #[account(
+        mut,
+        seeds = [b"issuer_exposure", collateral_config.issuer.as_ref()],
+        bump = issuer_exposure.bump,
+    )]
+    pub issuer_exposure: Account<'info, IssuerExposure>,
+    #[account(mut, seeds = [b"global_state"], bump = global_state.bump)]
+    pub global_state: Account<'info, GlobalState>,
+    #[account(mut, seeds = [b"sorted_vaults"], bump = sorted_vaults.bump)]
+    pub sorted_vaults: Account<'info, SortedVaults>,
+    #[account(mut, seeds = [b"supply_ledger"], bump = supply_ledger.bump)]
+    pub supply_ledger: Account<'info, SupplyLedger>,
+    pub stablecoin_token_program: Interface<'info, TokenInterface>,
+}
+
+#[derive(Accounts)]
+pub struct SocializeBadDebt<'info> {
+    #[account(mut)]
+    pub risk_authority: Signer<'info>,
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump,
+        constraint = config.risk_authority == risk_authority.key() @ ErrorCode::Unauthorized
+    )]
+    pub config: Account<'info, Config>,
+    #[account(
+        mut,
+        seeds = [b"vault", vault.creator.as_ref(), vault.collateral_mint.as_ref()],
+        bump = vault.bump,
+    )]
+    pub vault: Account<'info, Vault>,
+    #[account(
+        init_if_needed,
+        payer = risk_authority,
+        space = 8 + 8 + 8 + 1,
+        seeds = [b"bad_debt"],
+        bump
+    )]
+    pub bad_debt: Account<'info, BadDebt>,
+    #[account(seeds = [b"reserve"], bump)]
+    pub reserve: InterfaceAccount<'info, TokenAccount>,
+    #[account(seeds = [b"insurance_fund"], bump)]
+    pub insurance_fund: InterfaceAccount<'info, TokenAccount>,
+    #[account(seeds = [b"savings"], bump = savings_state.bump)]
+    pub savings_state: Account<'info, SavingsState>,
+    #[account(mut, seeds = [b"staking_reward_vault"], bump)]
+    pub staking_reward_vault: InterfaceAccount<'info, TokenAccount>,
+    #[account(mut, address = collateral_config.stablecoin_mint)]
+    pub stablecoin_mint: InterfaceAccount<'info, Mint>,
+    #[account(
+        mut,
+        seeds = [b"collateral_config", vault.collateral_mint.as_ref()],
+        bump = collateral_config.bump,
+    )]
+    pub collateral_config: Account<'info, CollateralConfig>,
+    #[account(seeds = [b"rate_model"], bump = rate_model.bump)]
+    pub rate_model: Account<'info, InterestRateModel>,
+    #[account(
+        mut,
+        seeds = [b"issuer_exposure", collateral_config.issuer.as_ref()],
+        bump = issuer_exposure.bump,
+    )]
+    pub issuer_exposure: Account<'info, IssuerExposure>,
+    #[account(mut, seeds = [b"global_state"], bump = global_state.bump)]
+    pub global_state: Account<'info, GlobalState>,
+    #[account(mut, seeds = [b"sorted_vaults"], bump = sorted_vaults.bump)]
+    pub sorted_vaults: Account<'info, SortedVaults>,
+    #[account(mut, seeds = [b"supply_ledger"], bump = supply_ledger.bump)]
+    pub supply_ledger: Account<'info, SupplyLedger>,
+    pub stablecoin_token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct StartSurplusAuction<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, Config>,
+    #[account(seeds = [b"global_state"], bump = global_state.bump)]
+    pub global_state: Account<'info, GlobalState>,
+    #[account(
+        init_if_needed,
+        payer = payer,
+        space = 8 + 8 + 8 + 32 + 8 + 1 + 1,
+        seeds = [b"surplus_auction"],
+        bump
+    )]
+    pub surplus_auction: Account<'info, SurplusAuction>,
+    #[account(mut, seeds = [b"treasury"], bump)]
+    pub treasury: InterfaceAccount<'info, TokenAccount>,
+    #[account(
+        init_if_needed,
+        payer = payer,
+        seeds = [b"surplus_lot_escrow"],
+        bump,
+        token::mint = stablecoin_mint,
+        token::authority = surplus_auction,
+        token::token_program = stablecoin_token_program
+    )]
+    pub surplus_lot_escrow: InterfaceAccount<'info, TokenAccount>,
+    #[account(address = treasury.mint)]
+    pub stablecoin_mint: InterfaceAccount<'info, Mint>,
+    pub stablecoin_token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct BidSurplusAuction<'info> {
+    #[account(mut)]
+    pub bidder: Signer<'info>,
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, Config>,
+    #[account(mut, seeds = [b"surplus_auction"], bump = surplus_auction.bump)]
+    pub surplus_auction: Account<'info, SurplusAuction>,
+    #[account(
+        init_if_needed,
+        payer = bidder,
+        seeds = [b"surplus_bid_escrow"],
+        bump,
+        token::mint = governance_mint,
+        token::authority = surplus_auction,
+        token::token_program = governance_token_program
+    )]
+    pub surplus_bid_escrow: InterfaceAccount<'info, TokenAccount>,
+    #[account(mut, token::mint = governance_mint, token::authority = bidder)]
+    pub bidder_governance_account: InterfaceAccount<'info, TokenAccount>,
+    #[account(mut, token::mint = governance_mint)]
+    pub previous_bidder_governance_account: Option<InterfaceAccount<'info, TokenAccount>>,
+    #[account(address = config.governance_mint)]
+    pub governance_mint: InterfaceAccount<'info, Mint>,
+    pub governance_token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct SettleSurplusAuction<'info> {
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, Config>,
+    #[account(mut, seeds = [b"surplus_auction"], bump = surplus_auction.bump)]
+    pub surplus_auction: Account<'info, SurplusAuction>,
+    #[account(mut, seeds = [b"treasury"], bump)]
+    pub treasury: InterfaceAccount<'info, TokenAccount>,
+    #[account(mut, seeds = [b"surplus_lot_escrow"], bump)]
+    pub surplus_lot_escrow: InterfaceAccount<'info, TokenAccount>,
+    #[account(mut, seeds = [b"surplus_bid_escrow"], bump)]
+    pub surplus_bid_escrow: InterfaceAccount<'info, TokenAccount>,
+    #[account(mut, token::mint = stablecoin_mint)]
+    pub winner_stablecoin_account: Option<InterfaceAccount<'info, TokenAccount>>,
+    #[account(address = treasury.mint)]
+    pub stablecoin_mint: InterfaceAccount<'info, Mint>,
+    #[account(mut, address = config.governance_mint)]
+    pub governance_mint: InterfaceAccount<'info, Mint>,
+    pub stablecoin_token_program: Interface<'info, TokenInterface>,
+    pub governance_token_program: Interface<'info, TokenInterface>,
+}
+
+#[derive(Accounts)]
+pub struct TriggerSettlement<'info> {
+    #[account(mut)]
+    pub guardian: Signer<'info>,
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump,
+        constraint = config.guardian == guardian.key() @ ErrorCode::Unauthorized
+    )]
+    pub config: Account<'info, Config>,
+    #[account(
+        init,
+        payer = guardian,
+        space = 8 + 8 + 1 + 8 + 8 + 1 + 8 + 8,
+        seeds = [b"settlement"],
+        bump
+    )]
+    pub settlement: Account<'info, Settlement>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct FreezeCollateral<'info> {
+    #[account(mut)]
+    pub guardian: Signer<'info>,
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump,
+        constraint = config.guardian == guardian.key() @ ErrorCode::Unauthorized
+    )]
+    pub config: Account<'info, Config>,
+    #[account(mut, seeds = [b"settlement"], bump = settlement.bump)]
+    pub settlement: Account<'info, Settlement>,
+    pub collateral_mint: InterfaceAccount<'info, Mint>,
+    #[account(
+        seeds = [b"collateral_config", collateral_mint.key().as_ref()],
+        bump = collateral_config.bump,
+    )]
+    pub collateral_config: Account<'info, CollateralConfig>,
+    #[account(seeds = [b"price_cache", collateral_mint.key().as_ref()], bump = price_cache.bump)]
+    pub price_cache: Account<'info, PriceCache>,
+    #[account(
+        init,
+        payer = guardian,
+        space = 8 + 32 + 32 + 8 + 16 + 1 + 1 + 8,
+        seeds = [b"settlement_collateral", collateral_mint.key().as_ref()],
+        bump
+    )]
+    pub settlement_collateral: Account<'info, SettlementCollateral>,
+    #[account(
+        init,
+        payer = guardian,
+        seeds = [b"settlement_escrow", collateral_mint.key().as_ref()],
+        bump,
+        token::mint = collateral_mint,
+        token::authority = settlement
+    )]
+    pub settlement_escrow: InterfaceAccount<'info, TokenAccount>,
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct SettleVault<'info> {
+    pub keeper: Signer<'info>,
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, Config>,
+    #[account(seeds = [b"settlement"], bump = settlement.bump)]
+    pub settlement: Account<'info, Settlement>,
+    #[account(
+        seeds = [b"settlement_collateral", vault.collateral_mint.as_ref()],
+        bump = settlement_collateral.bump,
+    )]
+    pub settlement_collateral: Account<'info, SettlementCollateral>,
+    #[account(
+        mut,
+        seeds = [b"vault", vault.creator.as_ref(), vault.collateral_mint.as_ref()],
+        bump = vault.bump,
+    )]
+    pub vault: Account<'info, Vault>,
+    #[account(mut, seeds = [b"escrow", vault.key().as_ref()], bump)]
+    pub escrow: InterfaceAccount<'info, TokenAccount>,
+    #[account(mut, seeds = [b"settlement_escrow", vault.collateral_mint.as_ref()], bump)]
+    pub settlement_escrow: InterfaceAccount<'info, TokenAccount>,
+    #[account(
+        mut,
+        seeds = [b"collateral_config", vault.collateral_mint.as_ref()],
+        bump = collateral_config.bump,
+    )]
+    pub collateral_config: Account<'info, CollateralConfig>,
+    #[account(seeds = [b"rate_model"], bump = rate_model.bump)]
+    pub rate_model: Account<'info, InterestRateModel>,
+    #[account(
+        mut,
+        seeds = [b"issuer_exposure", collateral_config.issuer.as_ref()],
+        bump = issuer_exposure.bump,
+    )]
+    pub issuer_exposure: Account<'info, IssuerExposure>,
+    #[account(mut, seeds = [b"global_state"], bump = global_state.bump)]
+    pub global_state: Account<'info, GlobalState>,
+    #[account(mut, seeds = [b"sorted_vaults"], bump = sorted_vaults.bump)]
+    pub sorted_vaults: Account<'info, SortedVaults>,
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct SettleVaultLeg<'info> {
+    pub keeper: Signer<'info>,
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, Config>,
+    #[account(seeds = [b"settlement"], bump = settlement.bump)]
+    pub settlement: Account<'info, Settlement>,
+    #[account(
+        seeds = [b"settlement_collateral", leg.mint.as_ref()],
+        bump = settlement_collateral.bump,
+    )]
+    pub settlement_collateral: Account<'info, SettlementCollateral>,
+    #[account(
+        mut,
+        seeds = [b"vault", vault.creator.as_ref(), vault.collateral_mint.as_ref()],
+        bump = vault.bump,
+    )]
+    pub vault: Account<'info, Vault>,
+    #[account(
+        mut,
+        has_one = vault,
+        seeds = [b"leg", vault.key().as_ref(), leg.mint.as_ref()],
+        bump = leg.bump,
+    )]
+    pub leg: Account<'info, CollateralLeg>,
+    #[account(mut, address = leg.escrow)]
+    pub leg_escrow: InterfaceAccount<'info, TokenAccount>,
+    #[account(
+        seeds = [b"collateral_config", leg.mint.as_ref()],
+        bump = leg_collateral_config.bump,
+    )]
+    pub leg_collateral_config: Account<'info, CollateralConfig>,
+    #[account(mut, seeds = [b"settlement_escrow", leg.mint.as_ref()], bump)]
+    pub settlement_escrow: InterfaceAccount<'info, TokenAccount>,
+    #[account(
+        mut,
+        seeds = [b"collateral_config", vault.collateral_mint.as_ref()],
+        bump = collateral_config.bump,
+    )]
+    pub collateral_config: Account<'info, CollateralConfig>,
+    #[account(seeds = [b"rate_model"], bump = rate_model.bump)]
+    pub rate_model: Account<'info, InterestRateModel>,
+    #[account(
+        mut,
+        seeds = [b"issuer_exposure", collateral_config.issuer.as_ref()],
+        bump = issuer_exposure.bump,
+    )]
+    pub issuer_exposure: Account<'info, IssuerExposure>,
+    #[account(mut, seeds = [b"global_state"], bump = global_state.bump)]
+    pub global_state: Account<'info, GlobalState>,
+    #[account(mut, seeds = [b"sorted_vaults"], bump = sorted_vaults.bump)]
+    pub sorted_vaults: Account<'info, SortedVaults>,
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct ReclaimCollateral<'info> {
+    pub owner: Signer<'info>,
+    #[account(seeds = [b"settlement"], bump = settlement.bump)]
+    pub settlement: Account<'info, Settlement>,
+    #[account(
+        mut,
+        has_one = receipt_mint,
+        seeds = [b"vault", vault.creator.as_ref(), vault.collateral_mint.as_ref()],
+        bump = vault.bump,
+    )]
+    pub vault: Account<'info, Vault>,
+    #[account(
+        token::mint = vault.position_mint,
+        token::authority = owner,
+        constraint = position_account.amount == 1 @ ErrorCode::PositionRequired
+    )]
+    pub position_account: InterfaceAccount<'info, TokenAccount>,
+    #[account(mut)]
+    pub receipt_mint: InterfaceAccount<'info, Mint>,
+    #[account(
+        mut,
+        token::mint = receipt_mint,
+        token::authority = owner,
+        constraint = owner_receipt_account.amount >= vault.bond_amount @ ErrorCode::ReceiptRequired
+    )]
+    pub owner_receipt_account: InterfaceAccount<'info, TokenAccount>,
+    #[account(mut, token::mint = vault.collateral_mint)]
+    pub owner_bond_account: InterfaceAccount<'info, TokenAccount>,
+    #[account(mut, seeds = [b"escrow", vault.key().as_ref()], bump)]
+    pub escrow: InterfaceAccount<'info, TokenAccount>,
+    #[account(
+        mut,
+        seeds = [b"collateral_config", vault.collateral_mint.as_ref()],
+        bump = collateral_config.bump,
+    )]
+    pub collateral_config: Account<'info, CollateralConfig>,
+    #[account(mut, seeds = [b"sorted_vaults"], bump = sorted_vaults.bump)]
+    pub sorted_vaults: Account<'info, SortedVaults>,
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, Config>,
+    #[account(mut, seeds = [b"global_state"], bump = global_state.bump)]
+    pub global_state: Account<'info, GlobalState>,
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct FinalizeSettlement<'info> {
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, Config>,
+    #[account(mut, seeds = [b"settlement"], bump = settlement.bump)]
+    pub settlement: Account<'info, Settlement>,
+    #[account(address = config.stablecoin_mint)]
+    pub stablecoin_mint: InterfaceAccount<'info, Mint>,
+    #[account(seeds = [b"reserve"], bump)]
+    pub reserve: InterfaceAccount<'info, TokenAccount>,
+    #[account(seeds = [b"insurance_fund"], bump)]
+    pub insurance_fund: InterfaceAccount<'info, TokenAccount>,
+    #[account(seeds = [b"psm"], bump = psm_state.bump)]
+    pub psm_state: Account<'info, PsmState>,
+    #[account(seeds = [b"psm_reserve"], bump)]
+    pub psm_reserve: InterfaceAccount<'info, TokenAccount>,
+}
+
+#[derive(Accounts)]
+pub struct FixCollateral<'info> {
+    #[account(mut, seeds = [b"settlement"], bump = settlement.bump)]
+    pub settlement: Account<'info, Settlement>,
+    #[account(
+        mut,
+        seeds = [b"settlement_collateral", settlement_collateral.mint.as_ref()],
+        bump = settlement_collateral.bump,
+    )]
+    pub settlement_collateral: Account<'info, SettlementCollateral>,
+    #[account(seeds = [b"settlement_escrow", settlement_collateral.mint.as_ref()], bump)]
+    pub settlement_escrow: InterfaceAccount<'info, TokenAccount>,
+    #[account(seeds = [b"global_state"], bump = global_state.bump)]
+    pub global_state: Account<'info, GlobalState>,
+}
+
+#[derive(Accounts)]
+pub struct SettlementRedeem<'info> {
+    pub redeemer: Signer<'info>,
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, Config>,
+    #[account(seeds = [b"settlement"], bump = settlement.bump)]
+    pub settlement: Account<'info, Settlement>,
+    #[account(mut, seeds = [b"psm"], bump = psm_state.bump)]
+    pub psm_state: Account<'info, PsmState>,
+    #[account(mut, seeds = [b"psm_reserve"], bump)]
+    pub psm_reserve: InterfaceAccount<'info, TokenAccount>,
+    #[account(mut, token::mint = psm_state.usdc_mint)]
+    pub redeemer_usdc_account: InterfaceAccount<'info, TokenAccount>,
+    #[account(mut)]
+    pub stablecoin_mint: InterfaceAccount<'info, Mint>,
+    #[account(mut, token::mint = stablecoin_mint, token::authority = redeemer)]
+    pub redeemer_stablecoin_account: InterfaceAccount<'info, TokenAccount>,
+    /// CHECK: deny entry for the account owner; empty unless the owner is denied
+    #[account(
+        seeds = [b"deny", redeemer_stablecoin_account.owner.as_ref()],
+        seeds::program = stablecoin_transfer_hook::ID,
+        bump
+    )]
+    pub deny_entry: UncheckedAccount<'info>,
+    #[account(mut, seeds = [b"supply_ledger"], bump = supply_ledger.bump)]
+    pub supply_ledger: Account<'info, SupplyLedger>,
+    pub token_program: Program<'info, Token>,
+    pub stablecoin_token_program: Interface<'info, TokenInterface>,
+}
+
+#[derive(Accounts)]
+pub struct InitializePol<'info> {
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    #[account(seeds = [b"config"], bump = config.bump, has_one = admin)]
+    pub config: Account<'info, Config>,
+    #[account(
+        init,
+        payer = admin,
+        space = 8 + 32 + 32 + 32 + 8 + 8 + 1,
+        seeds = [b"pol"],
+        bump
+    )]
+    pub pol: Account<'info, ProtocolLiquidity>,
+    pub stablecoin_mint: InterfaceAccount<'info, Mint>,
+    #[account(address = psm_state.usdc_mint)]
+    pub usdc_mint: InterfaceAccount<'info, Mint>,
+    pub lp_mint: InterfaceAccount<'info, Mint>,
+    #[account(seeds = [b"psm"], bump = psm_state.bump)]
+    pub psm_state: Account<'info, PsmState>,
+    #[account(
+        init,
+        payer = admin,
+        seeds = [b"pol_stablecoin"],
+        bump,
+        token::mint = stablecoin_mint,
+        token::authority = pol,
+        token::token_program = stablecoin_token_program
+    )]
+    pub pol_stablecoin: InterfaceAccount<'info, TokenAccount>,
+    #[account(
+        init,
+        payer = admin,
+        seeds = [b"pol_usdc"],
+        bump,
+        token::mint = usdc_mint,
+        token::authority = pol
+    )]
+    pub pol_usdc: InterfaceAccount<'info, TokenAccount>,
+    #[account(
+        init,
+        payer = admin,
+        seeds = [b"pol_lp"],
+        bump,
+        token::mint = lp_mint,
+        token::authority = pol
+    )]
+    pub pol_lp: InterfaceAccount<'info, TokenAccount>,
+    pub token_program: Program<'info, Token>,
+    pub stablecoin_token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ManagePol<'info> {
+    pub admin: Signer<'info>,
+    #[account(seeds = [b"config"], bump = config.bump, has_one = admin)]
+    pub config: Account<'info, Config>,
+    #[account(mut, seeds = [b"pol"], bump = pol.bump)]
+    pub pol: Account<'info, ProtocolLiquidity>,
+    #[account(seeds = [b"global_state"], bump = global_state.bump)]
+    pub global_state: Account<'info, GlobalState>,
+    #[account(mut, seeds = [b"treasury"], bump)]
+    pub treasury: InterfaceAccount<'info, TokenAccount>,
+    #[account(mut, seeds = [b"pol_stablecoin"], bump)]
+    pub pol_stablecoin: InterfaceAccount<'info, TokenAccount>,
+    #[account(mut, seeds = [b"pol_usdc"], bump)]
+    pub pol_usdc: InterfaceAccount<'info, TokenAccount>,
+    #[account(mut, seeds = [b"pol_lp"], bump, token::mint = pol.lp_mint)]
+    pub pol_lp: InterfaceAccount<'info, TokenAccount>,
+    #[account(address = treasury.mint)]
+    pub stablecoin_mint: InterfaceAccount<'info, Mint>,
+    /// CHECK: AMM program designated when the pol account was created
+    #[account(address = pol.amm_program @ ErrorCode::InvalidSwapProgram)]
+    pub amm_program: UncheckedAccount<'info>,
+    pub stablecoin_token_program: Interface<'info, TokenInterface>,
+}
+
+#[derive(Accounts)]
+pub struct ClaimFees<'info> {
+    pub admin: Signer<'info>,
+    #[account(mut, seeds = [b"config"], bump = config.bump, has_one = admin)]
+    pub config: Account<'info, Config>,
+    #[account(seeds = [b"global_state"], bump = global_state.bump)]
+    pub global_state: Account<'info, GlobalState>,
+    #[account(mut, seeds = [b"treasury"], bump)]
+    pub treasury: InterfaceAccount<'info, TokenAccount>,
+    #[account(mut, seeds = [b"reserve"], bump)]
+    pub reserve: InterfaceAccount<'info, TokenAccount>,
+    #[account(mut, token::mint = treasury.mint)]
+    pub admin_stablecoin_account: InterfaceAccount<'info, TokenAccount>,
+    #[account(address = treasury.mint)]
+    pub stablecoin_mint: InterfaceAccount<'info, Mint>,
+    /// CHECK: settlement marker; empty until settlement is triggered
+    #[account(seeds = [b"settlement"], bump)]
+    pub settlement: UncheckedAccount<'info>,
+    pub stablecoin_token_program: Interface<'info, TokenInterface>,
+}
+
+#[derive(Accounts)]
+pub struct DistributeSurplus<'info> {
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, Config>,
+    #[account(seeds = [b"global_state"], bump = global_state.bump)]
+    pub global_state: Account<'info, GlobalState>,
+    #[account(mut, seeds = [b"reserve"], bump)]
+    pub reserve: InterfaceAccount<'info, TokenAccount>,
+    #[account(mut, seeds = [b"staking_reward_vault"], bump)]
+    pub staking_reward_vault: InterfaceAccount<'info, TokenAccount>,
+    #[account(address = reserve.mint)]
+    pub stablecoin_mint: InterfaceAccount<'info, Mint>,
+    /// CHECK: settlement marker; empty until settlement is triggered
+    #[account(seeds = [b"settlement"], bump)]
+    pub settlement: UncheckedAccount<'info>,
+    pub stablecoin_token_program: Interface<'info, TokenInterface>,
+}
+
+#[derive(Accounts)]
+pub struct ClaimSurplus<'info> {
+    pub owner: Signer<'info>,
+    #[account(
+        mut,
+        seeds = [b"vault", vault.creator.as_ref(), vault.collateral_mint.as_ref()],
+        bump = vault.bump,
+    )]
+    pub vault: Account<'info, Vault>,
+    #[account(
+        token::mint = vault.position_mint,
+        token::authority = owner,
+        constraint = position_account.amount == 1 @ ErrorCode::PositionRequired
+    )]
+    pub position_account: InterfaceAccount<'info, TokenAccount>,
+    #[account(seeds = [b"global_state"], bump = global_state.bump)]
+    pub global_state: Account<'info, GlobalState>,
+    #[account(mut, seeds = [b"surplus_vault"], bump)]
+    pub surplus_vault: InterfaceAccount<'info, TokenAccount>,
+    #[account(mut, token::mint = surplus_vault.mint)]
+    pub owner_stablecoin_account: InterfaceAccount<'info, TokenAccount>,
+    #[account(address = surplus_vault.mint)]
+    pub stablecoin_mint: InterfaceAccount<'info, Mint>,
+    pub stablecoin_token_program: Interface<'info, TokenInterface>,
+}
+
+#[derive(Accounts)]
+pub struct InitializeStabilityPool<'info> {
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    #[account(seeds = [b"config"], bump = config.bump, has_one = admin)]
+    pub config: Account<'info, Config>,
+    pub collateral_mint: InterfaceAccount<'info, Mint>,
+    pub stablecoin_mint: InterfaceAccount<'info, Mint>,
+    #[account(
+        init,
+        payer = admin,
+        space = 8 + 32 + 8 + 16 + 16 + 8 + 8 + 4 + (8 + 8 + 16) * MAX_SCALE_SUMS + 1,
+        seeds = [b"stability_pool", collateral_mint.key().as_ref()],
+        bump
+    )]
+    pub pool: Account<'info, StabilityPool>,
+    #[account(
+        init,
+        payer = admin,
+        seeds = [b"pool_stablecoin", pool.key().as_ref()],
+        bump,
+        token::mint = stablecoin_mint,
+        token::authority = pool,
+        token::token_program = stablecoin_token_program
+    )]
+    pub pool_stablecoin: InterfaceAccount<'info, TokenAccount>,
+    #[account(
+        init,
+        payer = admin,
+        seeds = [b"pool_collateral", pool.key().as_ref()],
+        bump,
+        token::mint = collateral_mint,
+        token::authority = pool
+    )]
+    pub pool_collateral: InterfaceAccount<'info, TokenAccount>,
+    pub token_program: Program<'info, Token>,
+    pub stablecoin_token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ProvideToStabilityPool<'info> {
+    #[account(mut)]
+    pub depositor: Signer<'info>,
+    #[account(
+        mut,
+        seeds = [b"stability_pool", pool.collateral_mint.as_ref()],
+        bump = pool.bump,
+    )]
+    pub pool: Account<'info, StabilityPool>,
+    #[account(
+        init_if_needed,
+        payer = depositor,
+        space = 8 + 32 + 32 + 8 + 16 + 16 + 8 + 8 + 1,
+        seeds = [b"stability_deposit", pool.key().as_ref(), depositor.key().as_ref()],
+        bump
+    )]
+    pub deposit: Account<'info, StabilityDeposit>,
+    #[account(mut, seeds = [b"pool_stablecoin", pool.key().as_ref()], bump)]
+    pub pool_stablecoin: InterfaceAccount<'info, TokenAccount>,
+    #[account(mut, seeds = [b"pool_collateral", pool.key().as_ref()], bump)]
+    pub pool_collateral: InterfaceAccount<'info, TokenAccount>,
+    #[account(mut, token::mint = pool_stablecoin.mint, token::authority = depositor)]
+    pub depositor_stablecoin_account: InterfaceAccount<'info, TokenAccount>,
+    #[account(mut, token::mint = pool.collateral_mint)]
+    pub depositor_bond_account: InterfaceAccount<'info, TokenAccount>,
+    pub token_program: Program<'info, Token>,
+    #[account(address = pool_stablecoin.mint)]
+    pub stablecoin_mint: InterfaceAccount<'info, Mint>,
+    pub stablecoin_token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct WithdrawFromStabilityPool<'info> {
+    pub owner: Signer<'info>,
+    #[account(
+        mut,
+        seeds = [b"stability_pool", pool.collateral_mint.as_ref()],
+        bump = pool.bump,
+    )]
+    pub pool: Account<'info, StabilityPool>,
+    #[account(
+        mut,
+        has_one = owner,
+        has_one = pool,
+        seeds = [b"stability_deposit", pool.key().as_ref(), owner.key().as_ref()],
+        bump = deposit.bump,
+    )]
+    pub deposit: Account<'info, StabilityDeposit>,
+    #[account(mut, seeds = [b"pool_stablecoin", pool.key().as_ref()], bump)]
+    pub pool_stablecoin: InterfaceAccount<'info, TokenAccount>,
+    #[account(mut, seeds = [b"pool_collateral", pool.key().as_ref()], bump)]
+    pub pool_collateral: InterfaceAccount<'info, TokenAccount>,
+    #[account(mut, token::mint = pool_stablecoin.mint)]
+    pub depositor_stablecoin_account: InterfaceAccount<'info, TokenAccount>,
+    #[account(mut, token::mint = pool.collateral_mint)]
+    pub depositor_bond_account: InterfaceAccount<'info, TokenAccount>,
+    pub token_program: Program<'info, Token>,
+    #[account(address = pool_stablecoin.mint)]
+    pub stablecoin_mint: InterfaceAccount<'info, Mint>,
+    pub stablecoin_token_program: Interface<'info, TokenInterface>,
+}
+
+#[derive(Accounts)]
+pub struct LiquidateViaStabilityPool<'info> {
+    pub keeper: Signer<'info>,
+    #[account(mut, token::authority = keeper)]
+    pub keeper_stablecoin_account: InterfaceAccount<'info, TokenAccount>,
+    #[account(
+        mut,
+        seeds = [b"vault", vault.creator.as_ref(), vault.collateral_mint.as_ref()],
+        bump = vault.bump,
+    )]
+    pub vault: Account<'info, Vault>,
+    #[account(mut, seeds = [b"sorted_vaults"], bump = sorted_vaults.bump)]
+    pub sorted_vaults: Account<'info, SortedVaults>,
+    #[account(mut, seeds = [b"escrow", vault.key().as_ref()], bump)]
+    pub escrow: InterfaceAccount<'info, TokenAccount>,
+    #[account(
+        mut,
+        seeds = [b"stability_pool", vault.collateral_mint.as_ref()],
+        bump = pool.bump,
+    )]
+    pub pool: Account<'info, StabilityPool>,
+    #[account(mut, seeds = [b"pool_stablecoin", pool.key().as_ref()], bump)]
+    pub pool_stablecoin: InterfaceAccount<'info, TokenAccount>,
+    #[account(mut, seeds = [b"pool_collateral", pool.key().as_ref()], bump)]
+    pub pool_collateral: InterfaceAccount<'info, TokenAccount>,
+    #[account(mut, address = collateral_config.stablecoin_mint)]
+    pub stablecoin_mint: InterfaceAccount<'info, Mint>,
+    #[account(mut, seeds = [b"staking_reward_vault"], bump)]
+    pub staking_reward_vault: InterfaceAccount<'info, TokenAccount>,
+    #[account(mut, seeds = [b"treasury"], bump)]
+    pub treasury: InterfaceAccount<'info, TokenAccount>,
+    #[account(mut, seeds = [b"reserve"], bump)]
+    pub reserve: InterfaceAccount<'info, TokenAccount>,
+    #[account(mut, seeds = [b"insurance_fund"], bump)]
+    pub insurance_fund: InterfaceAccount<'info, TokenAccount>,
+    /// CHECK: PDA authority
+    #[account(seeds = [b"mint_authority"], bump = config.mint_authority_bump)]
+    pub mint_authority: AccountInfo<'info>,
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, Config>,
+    #[account(seeds = [b"rate_model"], bump = rate_model.bump)]
+    pub rate_model: Account<'info, InterestRateModel>,
+    #[account(
+        mut,
+        seeds = [b"collateral_config", vault.collateral_mint.as_ref()],
+        bump = collateral_config.bump,
+    )]
+    pub collateral_config: Account<'info, CollateralConfig>,
+    #[account(seeds = [b"price_cache", vault.collateral_mint.as_ref()], bump = price_cache.bump)]
+    pub price_cache: Account<'info, PriceCache>,
+    #[account(
+        mut,
+        seeds = [b"issuer_exposure", collateral_config.issuer.as_ref()],
+        bump = issuer_exposure.bump,
+    )]
+    pub issuer_exposure: Account<'info, IssuerExposure>,
+    #[account(mut, seeds = [b"global_state"], bump = global_state.bump)]
+    pub global_state: Account<'info, GlobalState>,
+    pub token_program: Program<'info, Token>,
+    #[account(mut, seeds = [b"supply_ledger"], bump = supply_ledger.bump)]
+    pub supply_ledger: Account<'info, SupplyLedger>,
+    pub stablecoin_token_program: Interface<'info, TokenInterface>,
+}
+
+#[derive(Accounts)]
+pub struct InitializePsm<'info> {
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    #[account(seeds = [b"config"], bump = config.bump, has_one = admin)]
+    pub config: Account<'info, Config>,
+    pub usdc_mint: InterfaceAccount<'info, Mint>,
+    pub stablecoin_mint: InterfaceAccount<'info, Mint>,
+    #[account(
+        init,
+        payer = admin,
+        space = 8 + 32 + 8 + 8 + 8 + 8 + 1,
+        seeds = [b"psm"],
+        bump
+    )]
+    pub psm_state: Account<'info, PsmState>,
+    #[account(
+        init,
+        payer = admin,
+        seeds = [b"psm_reserve"],
+        bump,
+        token::mint = usdc_mint,
+        token::authority = psm_state
+    )]
+    pub psm_reserve: InterfaceAccount<'info, TokenAccount>,
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct SetPsmParams<'info> {
+    pub admin: Signer<'info>,
+    #[account(seeds = [b"config"], bump = config.bump, has_one = admin)]
+    pub config: Account<'info, Config>,
+    #[account(mut, seeds = [b"psm"], bump = psm_state.bump)]
+    pub psm_state: Account<'info, PsmState>,
+}
+
+#[derive(Accounts)]
+pub struct PsmMint<'info> {
+    pub user: Signer<'info>,
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, Config>,
+    #[account(mut, seeds = [b"psm"], bump = psm_state.bump)]
+    pub psm_state: Account<'info, PsmState>,
+    #[account(mut, seeds = [b"psm_reserve"], bump)]
+    pub psm_reserve: InterfaceAccount<'info, TokenAccount>,
+    #[account(mut, token::mint = psm_state.usdc_mint, token::authority = user)]
+    pub user_usdc_account: InterfaceAccount<'info, TokenAccount>,
+    #[account(mut, token::mint = stablecoin_mint)]
+    pub user_stablecoin_account: InterfaceAccount<'info, TokenAccount>,
+    /// CHECK: deny entry for the account owner; empty unless the owner is denied
+    #[account(
+        seeds = [b"deny", user_stablecoin_account.owner.as_ref()],
+        seeds::program = stablecoin_transfer_hook::ID,
+        bump
+    )]
+    pub deny_entry: UncheckedAccount<'info>,
+    #[account(mut)]
+    pub stablecoin_mint: InterfaceAccount<'info, Mint>,
+    #[account(mut, seeds = [b"treasury"], bump)]
+    pub treasury: InterfaceAccount<'info, TokenAccount>,
+    /// CHECK: PDA authority
+    #[account(seeds = [b"mint_authority"], bump = config.mint_authority_bump)]
+    pub mint_authority: AccountInfo<'info>,
+    pub token_program: Program<'info, Token>,
+    #[account(mut, seeds = [b"supply_ledger"], bump = supply_ledger.bump)]
+    pub supply_ledger: Account<'info, SupplyLedger>,
+    #[account(mut, seeds = [b"rate_limit"], bump = rate_limit.bump)]
+    pub rate_limit: Account<'info, RateLimit>,
+    /// CHECK: settlement marker; empty until settlement is triggered
+    #[account(seeds = [b"settlement"], bump)]
+    pub settlement: UncheckedAccount<'info>,
+    pub stablecoin_token_program: Interface<'info, TokenInterface>,
+}
+
+#[derive(Accounts)]
+pub struct PsmRedeem<'info> {
+    pub user: Signer<'info>,
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, Config>,
+    #[account(mut, seeds = [b"psm"], bump = psm_state.bump)]
+    pub psm_state: Account<'info, PsmState>,
+    #[account(mut, seeds = [b"psm_reserve"], bump)]
+    pub psm_reserve: InterfaceAccount<'info, TokenAccount>,
+    #[account(mut, token::mint = psm_state.usdc_mint)]
+    pub user_usdc_account: InterfaceAccount<'info, TokenAccount>,
+    #[account(mut, token::mint = stablecoin_mint, token::authority = user)]
+    pub user_stablecoin_account: InterfaceAccount<'info, TokenAccount>,
+    /// CHECK: deny entry for the account owner; empty unless the owner is denied
+    #[account(
+        seeds = [b"deny", user_stablecoin_account.owner.as_ref()],
+        seeds::program = stablecoin_transfer_hook::ID,
+        bump
+    )]
+    pub deny_entry: UncheckedAccount<'info>,
+    #[account(mut)]
+    pub stablecoin_mint: InterfaceAccount<'info, Mint>,
+    #[account(mut, seeds = [b"treasury"], bump)]
+    pub treasury: InterfaceAccount<'info, TokenAccount>,
+    pub token_program: Program<'info, Token>,
+    #[account(mut, seeds = [b"supply_ledger"], bump = supply_ledger.bump)]
+    pub supply_ledger: Account<'info, SupplyLedger>,
+    /// CHECK: settlement marker; empty until settlement is triggered
+    #[account(seeds = [b"settlement"], bump)]
+    pub settlement: UncheckedAccount<'info>,
+    pub stablecoin_token_program: Interface<'info, TokenInterface>,
+}
+
+#[derive(Accounts)]
+pub struct ArbPeg<'info> {
+    pub keeper: Signer<'info>,
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, Config>,
+    #[account(seeds = [b"rate_model"], bump = rate_model.bump)]
+    pub rate_model: Account<'info, InterestRateModel>,
+    #[account(mut, seeds = [b"psm"], bump = psm_state.bump)]
+    pub psm_state: Account<'info, PsmState>,
+    #[account(mut, seeds = [b"psm_reserve"], bump)]
+    pub psm_reserve: InterfaceAccount<'info, TokenAccount>,
+    #[account(mut, token::mint = psm_state.usdc_mint, token::authority = keeper)]
+    pub keeper_usdc_account: InterfaceAccount<'info, TokenAccount>,
+    #[account(mut, token::mint = stablecoin_mint, token::authority = keeper)]
+    pub keeper_stablecoin_account: InterfaceAccount<'info, TokenAccount>,
+    /// CHECK: deny entry for the account owner; empty unless the owner is denied
+    #[account(
+        seeds = [b"deny", keeper_stablecoin_account.owner.as_ref()],
+        seeds::program = stablecoin_transfer_hook::ID,
+        bump
+    )]
+    pub deny_entry: UncheckedAccount<'info>,
+    #[account(mut)]
+    pub stablecoin_mint: InterfaceAccount<'info, Mint>,
+    #[account(mut, seeds = [b"treasury"], bump)]
+    pub treasury: InterfaceAccount<'info, TokenAccount>,
+    /// CHECK: PDA authority
+    #[account(seeds = [b"mint_authority"], bump = config.mint_authority_bump)]
+    pub mint_authority: AccountInfo<'info>,
+    pub token_program: Program<'info, Token>,
+    #[account(mut, seeds = [b"supply_ledger"], bump = supply_ledger.bump)]
+    pub supply_ledger: Account<'info, SupplyLedger>,
+    #[account(mut, seeds = [b"rate_limit"], bump = rate_limit.bump)]
+    pub rate_limit: Account<'info, RateLimit>,
+    /// CHECK: settlement marker; empty until settlement is triggered
+    #[account(seeds = [b"settlement"], bump)]
+    pub settlement: UncheckedAccount<'info>,
+    pub stablecoin_token_program: Interface<'info, TokenInterface>,
+    /// CHECK: Swap route invoked with the remaining accounts
+    pub swap_program: UncheckedAccount<'info>,
+}
+
+#[derive(Accounts)]
+pub struct InitializeSavings<'info> {
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    #[account(seeds = [b"config"], bump = config.bump, has_one = admin)]
+    pub config: Account<'info, Config>,
+    pub stablecoin_mint: InterfaceAccount<'info, Mint>,
+    #[account(
+        init,
+        payer = admin,
+        space = 8 + 32 + 16 + 8 + 1,
+        seeds = [b"savings"],
+        bump
+    )]
+    pub savings_state: Account<'info, SavingsState>,
+    #[account(
+        init,
+        payer = admin,
+        seeds = [b"staking_reward_vault"],
+        bump,
+        token::mint = stablecoin_mint,
+        token::authority = savings_state,
+        token::token_program = stablecoin_token_program
+    )]
+    pub staking_reward_vault: InterfaceAccount<'info, TokenAccount>,
+    #[account(
+        init,
+        payer = admin,
+        seeds = [b"savings_share_mint"],
+        bump,
+        mint::decimals = stablecoin_mint.decimals,
+        mint::authority = savings_state,
+        mint::token_program = share_token_program
+    )]
+    pub share_mint: InterfaceAccount<'info, Mint>,
+    pub stablecoin_token_program: Interface<'info, TokenInterface>,
+    pub share_token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct SavingsDeposit<'info> {
+    pub user: Signer<'info>,
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, Config>,
+    #[account(seeds = [b"savings"], bump = savings_state.bump, has_one = share_mint)]
+    pub savings_state: Account<'info, SavingsState>,
+    #[account(mut, mint::token_program = share_token_program)]
+    pub share_mint: InterfaceAccount<'info, Mint>,
+    #[account(mut, seeds = [b"staking_reward_vault"], bump)]
+    pub staking_reward_vault: InterfaceAccount<'info, TokenAccount>,
+    #[account(mut, token::mint = staking_reward_vault.mint, token::authority = user)]
+    pub user_stablecoin_account: InterfaceAccount<'info, TokenAccount>,
+    #[account(
+        mut,
+        token::mint = share_mint,
+        token::token_program = share_token_program
+    )]
+    pub user_share_account: InterfaceAccount<'info, TokenAccount>,
+    #[account(address = staking_reward_vault.mint)]
+    pub stablecoin_mint: InterfaceAccount<'info, Mint>,
+    pub stablecoin_token_program: Interface<'info, TokenInterface>,
+    pub share_token_program: Interface<'info, TokenInterface>,
+}
+
+#[derive(Accounts)]
+pub struct SyncSavingsRate<'info> {
+    pub admin: Signer<'info>,
+    #[account(seeds = [b"config"], bump = config.bump, has_one = admin)]
+    pub config: Account<'info, Config>,
+    #[account(mut, seeds = [b"savings"], bump = savings_state.bump, has_one = share_mint)]
+    pub savings_state: Account<'info, SavingsState>,
+    pub share_mint: InterfaceAccount<'info, Mint>,
+    #[account(seeds = [b"staking_reward_vault"], bump)]
+    pub staking_reward_vault: InterfaceAccount<'info, TokenAccount>,
+    #[account(mut, address = staking_reward_vault.mint)]
+    pub stablecoin_mint: InterfaceAccount<'info, Mint>,
+    #[account(seeds = [b"global_state"], bump = global_state.bump)]
+    pub global_state: Account<'info, GlobalState>,
+    pub stablecoin_token_program: Interface<'info, TokenInterface>,
+}
+
+#[derive(Accounts)]
+pub struct SavingsWithdraw<'info> {
+    pub user: Signer<'info>,
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, Config>,
+    #[account(seeds = [b"savings"], bump = savings_state.bump, has_one = share_mint)]
+    pub savings_state: Account<'info, SavingsState>,
+    #[account(mut, mint::token_program = share_token_program)]
+    pub share_mint: InterfaceAccount<'info, Mint>,
+    #[account(mut, seeds = [b"staking_reward_vault"], bump)]
+    pub staking_reward_vault: InterfaceAccount<'info, TokenAccount>,
+    #[account(mut, token::mint = staking_reward_vault.mint)]
+    pub user_stablecoin_account: InterfaceAccount<'info, TokenAccount>,
+    #[account(
+        mut,
+        token::mint = share_mint,
+        token::authority = user,
+        token::token_program = share_token_program
+    )]
+    pub user_share_account: InterfaceAccount<'info, TokenAccount>,
+    #[account(address = staking_reward_vault.mint)]
+    pub stablecoin_mint: InterfaceAccount<'info, Mint>,
+    pub stablecoin_token_program: Interface<'info, TokenInterface>,
+    pub share_token_program: Interface<'info, TokenInterface>,
+}
+
+#[derive(Accounts)]
+pub struct InitializeInsuranceFund<'info> {
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    #[account(seeds = [b"config"], bump = config.bump, has_one = admin)]
+    pub config: Account<'info, Config>,
+    #[account(seeds = [b"global_state"], bump = global_state.bump)]
+    pub global_state: Account<'info, GlobalState>,
+    pub stablecoin_mint: InterfaceAccount<'info, Mint>,
+    #[account(
+        init,
+        payer = admin,
+        seeds = [b"insurance_fund"],
+        bump,
+        token::mint = stablecoin_mint,
+        token::authority = global_state,
+        token::token_program = stablecoin_token_program
+    )]
+    pub insurance_fund: InterfaceAccount<'info, TokenAccount>,
+    pub stablecoin_token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct InitializeFlashLock<'info> {
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    #[account(seeds = [b"config"], bump = config.bump, has_one = admin)]
+    pub config: Account<'info, Config>,
+    #[account(
+        init,
+        payer = admin,
+        space = 8 + 1 + 8 + 8 + 1,
+        seeds = [b"flash_lock"],
+        bump
+    )]
+    pub flash_lock: Account<'info, FlashLock>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct InitializeSupplyLedger<'info> {
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    #[account(seeds = [b"config"], bump = config.bump, has_one = admin)]
+    pub config: Account<'info, Config>,
+    #[account(
+        init,
+        payer = admin,
+        space = 8 + 8 + 8 + 8 + 8 + 8 + 1,
+        seeds = [b"supply_ledger"],
+        bump
+    )]
+    pub supply_ledger: Account<'info, SupplyLedger>,
+    pub stablecoin_mint: InterfaceAccount<'info, Mint>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct FlashMint<'info> {
+    pub borrower: Signer<'info>,
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, Config>,
+    #[account(mut, seeds = [b"flash_lock"], bump = flash_lock.bump)]
+    pub flash_lock: Account<'info, FlashLock>,
+    #[account(mut, token::mint = stablecoin_mint)]
+    pub borrower_stablecoin_account: InterfaceAccount<'info, TokenAccount>,
+    /// CHECK: deny entry for the account owner; empty unless the owner is denied
+    #[account(
+        seeds = [b"deny", borrower_stablecoin_account.owner.as_ref()],
+        seeds::program = stablecoin_transfer_hook::ID,
+        bump
+    )]
+    pub deny_entry: UncheckedAccount<'info>,
+    #[account(mut)]
+    pub stablecoin_mint: InterfaceAccount<'info, Mint>,
+    /// CHECK: PDA authority
+    #[account(seeds = [b"mint_authority"], bump = config.mint_authority_bump)]
+    pub mint_authority: AccountInfo<'info>,
+    /// CHECK: instructions sysvar, checked by address
+    #[account(address = anchor_lang::solana_program::sysvar::instructions::ID)]
+    pub instructions: AccountInfo<'info>,
+    #[account(mut, seeds = [b"supply_ledger"], bump = supply_ledger.bump)]
+    pub supply_ledger: Account<'info, SupplyLedger>,
+    #[account(mut, seeds = [b"rate_limit"], bump = rate_limit.bump)]
+    pub rate_limit: Account<'info, RateLimit>,
+    /// CHECK: settlement marker; empty until settlement is triggered
+    #[account(seeds = [b"settlement"], bump)]
+    pub settlement: UncheckedAccount<'info>,
+    pub stablecoin_token_program: Interface<'info, TokenInterface>,
+}
+
+#[derive(Accounts)]
+pub struct FlashBurn<'info> {
+    pub payer: Signer<'info>,
+    #[account(mut, seeds = [b"flash_lock"], bump = flash_lock.bump)]
+    pub flash_lock: Account<'info, FlashLock>,
+    #[account(mut, token::mint = stablecoin_mint, token::authority = payer)]
+    pub payer_stablecoin_account: InterfaceAccount<'info, TokenAccount>,
+    /// CHECK: deny entry for the account owner; empty unless the owner is denied
+    #[account(
+        seeds = [b"deny", payer_stablecoin_account.owner.as_ref()],
+        seeds::program = stablecoin_transfer_hook::ID,
+        bump
+    )]
+    pub deny_entry: UncheckedAccount<'info>,
+    #[account(mut)]
+    pub stablecoin_mint: InterfaceAccount<'info, Mint>,
+    #[account(mut, seeds = [b"treasury"], bump)]
+    pub treasury: InterfaceAccount<'info, TokenAccount>,
+    #[account(mut, seeds = [b"supply_ledger"], bump = supply_ledger.bump)]
+    pub supply_ledger: Account<'info, SupplyLedger>,
+    /// CHECK: settlement marker; empty until settlement is triggered
+    #[account(seeds = [b"settlement"], bump)]
+    pub settlement: UncheckedAccount<'info>,
+    pub stablecoin_token_program: Interface<'info, TokenInterface>,
+}
+
+#[derive(Accounts)]
+pub struct SetNttAuthority<'info> {
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    #[account(seeds = [b"config"], bump = config.bump, has_one = admin)]
+    pub config: Account<'info, Config>,
+    #[account(
+        init_if_needed,
+        payer = admin,
+        space = 8 + 32 + 8 + 1,
+        seeds = [b"bridge_config"],
+        bump
+    )]
+    pub bridge_config: Account<'info, BridgeConfig>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(chain_id: u16)]
+pub struct RegisterBridgePeer<'info> {
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    #[account(seeds = [b"config"], bump = config.bump, has_one = admin)]
+    pub config: Account<'info, Config>,
+    #[account(
+        init_if_needed,
+        payer = admin,
+        space = 8 + 2 + 32 + 1,
+        seeds = [b"bridge_peer", chain_id.to_le_bytes().as_ref()],
+        bump
+    )]
+    pub bridge_peer: Account<'info, BridgePeer>,
+    #[account(
+        init_if_needed,
+        payer = admin,
+        space = 8 + 8 + 8 + 8 + 8 + 1,
+        seeds = [b"bridge_outbound", chain_id.to_le_bytes().as_ref()],
+        bump
+    )]
+    pub outbound_limit: Account<'info, RateLimit>,
+    #[account(
+        init_if_needed,
+        payer = admin,
+        space = 8 + 8 + 8 + 8 + 8 + 1,
+        seeds = [b"bridge_inbound", chain_id.to_le_bytes().as_ref()],
+        bump
+    )]
+    pub inbound_limit: Account<'info, RateLimit>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct NttBurn<'info> {
+    pub ntt_authority: Signer<'info>,
+    pub sender: Signer<'info>,
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, Config>,
+    #[account(
+        mut,
+        seeds = [b"bridge_config"],
+        bump = bridge_config.bump,
+        has_one = ntt_authority @ ErrorCode::Unauthorized
+    )]
+    pub bridge_config: Account<'info, BridgeConfig>,
+    #[account(
+        seeds = [b"bridge_peer", bridge_peer.chain_id.to_le_bytes().as_ref()],
+        bump = bridge_peer.bump,
+    )]
+    pub bridge_peer: Account<'info, BridgePeer>,
+    #[account(
+        mut,
+        seeds = [b"bridge_outbound", bridge_peer.chain_id.to_le_bytes().as_ref()],
+        bump = outbound_limit.bump,
+    )]
+    pub outbound_limit: Account<'info, RateLimit>,
+    #[account(mut, token::mint = stablecoin_mint, token::authority = sender)]
+    pub sender_stablecoin_account: InterfaceAccount<'info, TokenAccount>,
+    /// CHECK: deny entry for the account owner; empty unless the owner is denied
+    #[account(
+        seeds = [b"deny", sender_stablecoin_account.owner.as_ref()],
+        seeds::program = stablecoin_transfer_hook::ID,
+        bump
+    )]
+    pub deny_entry: UncheckedAccount<'info>,
+    #[account(mut)]
+    pub stablecoin_mint: InterfaceAccount<'info, Mint>,
+    #[account(mut, seeds = [b"supply_ledger"], bump = supply_ledger.bump)]
+    pub supply_ledger: Account<'info, SupplyLedger>,
+    pub stablecoin_token_program: Interface<'info, TokenInterface>,
+}
+
+#[derive(Accounts)]
+pub struct NttMint<'info> {
+    pub ntt_authority: Signer<'info>,
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, Config>,
+    #[account(
+        mut,
+        seeds = [b"bridge_config"],
+        bump = bridge_config.bump,
+        has_one = ntt_authority @ ErrorCode::Unauthorized
+    )]
+    pub bridge_config: Account<'info, BridgeConfig>,
+    #[account(
+        seeds = [b"bridge_peer", bridge_peer.chain_id.to_le_bytes().as_ref()],
+        bump = bridge_peer.bump,
+    )]
+    pub bridge_peer: Account<'info, BridgePeer>,
+    #[account(
+        mut,
+        seeds = [b"bridge_inbound", bridge_peer.chain_id.to_le_bytes().as_ref()],
+        bump = inbound_limit.bump,
+    )]
+    pub inbound_limit: Account<'info, RateLimit>,
+    #[account(mut, token::mint = stablecoin_mint)]
+    pub recipient_stablecoin_account: InterfaceAccount<'info, TokenAccount>,
+    /// CHECK: deny entry for the account owner; empty unless the owner is denied
+    #[account(
+        seeds = [b"deny", recipient_stablecoin_account.owner.as_ref()],
+        seeds::program = stablecoin_transfer_hook::ID,
+        bump
+    )]
+    pub deny_entry: UncheckedAccount<'info>,
+    #[account(mut, address = config.stablecoin_mint)]
+    pub stablecoin_mint: InterfaceAccount<'info, Mint>,
+    /// CHECK: PDA authority
+    #[account(seeds = [b"mint_authority"], bump = config.mint_authority_bump)]
+    pub mint_authority: AccountInfo<'info>,
+    #[account(mut, seeds = [b"supply_ledger"], bump = supply_ledger.bump)]
+    pub supply_ledger: Account<'info, SupplyLedger>,
+    /// CHECK: settlement marker; empty until settlement is triggered
+    #[account(seeds = [b"settlement"], bump)]
+    pub settlement: UncheckedAccount<'info>,
+    pub stablecoin_token_program: Interface<'info, TokenInterface>,
+}
+
+#[derive(Accounts)]
+pub struct ConfigureConfidentialTransfers<'info> {
+    pub compliance_authority: Signer<'info>,
+    #[account(
+        seeds = [b"compliance"],
+        seeds::program = stablecoin_transfer_hook::ID,
+        bump = compliance.bump,
+        constraint = compliance.authority == compliance_authority.key() @ ErrorCode::Unauthorized
+    )]
+    pub compliance: Account<'info, Compliance>,
+    #[account(seeds = [b"global_state"], bump = global_state.bump)]
+    pub global_state: Account<'info, GlobalState>,
+    #[account(mut, mint::token_program = token_2022_program)]
+    pub stablecoin_mint: InterfaceAccount<'info, Mint>,
+    pub token_2022_program: Program<'info, Token2022>,
+}
+
+#[derive(Accounts)]
+pub struct ApproveConfidentialAccount<'info> {
+    pub compliance_authority: Signer<'info>,
+    #[account(
+        seeds = [b"compliance"],
+        seeds::program = stablecoin_transfer_hook::ID,
+        bump = compliance.bump,
+        constraint = compliance.authority == compliance_authority.key() @ ErrorCode::Unauthorized
+    )]
+    pub compliance: Account<'info, Compliance>,
+    #[account(seeds = [b"global_state"], bump = global_state.bump)]
+    pub global_state: Account<'info, GlobalState>,
+    #[account(mint::token_program = token_2022_program)]
+    pub stablecoin_mint: InterfaceAccount<'info, Mint>,
+    #[account(mut, token::mint = stablecoin_mint, token::token_program = token_2022_program)]
+    pub token_account: InterfaceAccount<'info, TokenAccount>,
+    pub token_2022_program: Program<'info, Token2022>,
+}
+
+#[derive(Accounts)]
+pub struct ReconcileSupply<'info> {
+    #[account(seeds = [b"supply_ledger"], bump = supply_ledger.bump)]
+    pub supply_ledger: Account<'info, SupplyLedger>,
+    pub stablecoin_mint: InterfaceAccount<'info, Mint>,
+}
+
+#[derive(Accounts)]
+pub struct Redeem<'info> {
+    pub redeemer: Signer<'info>,
+    #[account(mut, token::authority = redeemer)]
+    pub redeemer_stablecoin_account: InterfaceAccount<'info, TokenAccount>,
+    /// CHECK: deny entry for the account owner; empty unless the owner is denied
+    #[account(
+        seeds = [b"deny", redeemer_stablecoin_account.owner.as_ref()],
+        seeds::program = stablecoin_transfer_hook::ID,
+        bump
+    )]
+    pub deny_entry: UncheckedAccount<'info>,
+    #[account(mut)]
+    pub stablecoin_mint: InterfaceAccount<'info, Mint>,
+    #[account(mut, seeds = [b"staking_reward_vault"], bump)]
+    pub staking_reward_vault: InterfaceAccount<'info, TokenAccount>,
+    #[account(mut, seeds = [b"treasury"], bump)]
+    pub treasury: InterfaceAccount<'info, TokenAccount>,
+    #[account(mut, seeds = [b"reserve"], bump)]
+    pub reserve: InterfaceAccount<'info, TokenAccount>,
+    #[account(mut, seeds = [b"insurance_fund"], bump)]
+    pub insurance_fund: InterfaceAccount<'info, TokenAccount>,
+    /// CHECK: PDA authority
+    #[account(seeds = [b"mint_authority"], bump = config.mint_authority_bump)]
+    pub mint_authority: AccountInfo<'info>,
+    #[account(mut, seeds = [b"sorted_vaults"], bump = sorted_vaults.bump)]
+    pub sorted_vaults: Account<'info, SortedVaults>,
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, Config>,
+    #[account(seeds = [b"rate_model"], bump = rate_model.bump)]
+    pub rate_model: Account<'info, InterestRateModel>,
+    #[account(mut, seeds = [b"fee_state"], bump = fee_state.bump)]
+    pub fee_state: Account<'info, RedemptionFeeState>,
+    #[account(mut, seeds = [b"global_state"], bump = global_state.bump)]
+    pub global_state: Account<'info, GlobalState>,
+    pub token_program: Program<'info, Token>,
+    #[account(mut, seeds = [b"supply_ledger"], bump = supply_ledger.bump)]
+    pub supply_ledger: Account<'info, SupplyLedger>,
+    pub stablecoin_token_program: Interface<'info, TokenInterface>,
+}
+
+#[event]
+pub struct ConfigInitializedEvent {
+    pub config: Pubkey,
+    pub admin: Pubkey,
+    pub stablecoin_mint: Pubkey,
+}
+
+#[event]
+pub struct BorrowRateUpdatedEvent {
+    pub mint: Pubkey,
+    pub previous_rate_bps: u64,
+    pub borrow_rate_bps: u64,
+}
+
+#[event]
+pub struct BorrowRateScheduledEvent {
+    pub mint: Pubkey,
+    pub current_rate_bps: u64,
+    pub scheduled_rate_bps: u64,
+    pub effective_at: i64,
+}
+
+#[event]
+pub struct ParamChangeQueuedEvent {
+    pub id: u64,
+    pub param: RiskParam,
+    pub target: Pubkey,
+    pub value: u64,
+    pub eta: i64,
+}
+
+#[event]
+pub struct ParamChangeCancelledEvent {
+    pub id: u64,
+}
+
+#[event]
+pub struct ParamChangeExecutedEvent {
+    pub id: u64,
+    pub param: RiskParam,
+    pub target: Pubkey,
+    pub previous: u64,
+    pub value: u64,
+}
+
+#[event]
+pub struct PauseFlagsUpdatedEvent {
+    pub authority: Pubkey,
+    pub previous_flags: u64,
+    pub paused_flags: u64,
+}
+
+#[event]
+pub struct AdminProposedEvent {
+    pub admin: Pubkey,
+    pub pending_admin: Pubkey,
+}
+
+#[event]
+pub struct AdminTransferredEvent {
+    pub previous_admin: Pubkey,
+    pub admin: Pubkey,
+}
+
+#[event]
+pub struct AdminTransferCancelledEvent {
+    pub admin: Pubkey,
+    pub cancelled_admin: Pubkey,
+}
+
+#[event]
+pub struct StablecoinMetadataSetEvent {
+    pub name: String,
+    pub symbol: String,
+    pub uri: String,
+}
+
+#[event]
+pub struct CollateralRevaluedEvent {
+    pub mint: Pubkey,
+    pub unit_value: u64,
+    pub updated_at: i64,
+}
+
+#[event]
+pub struct IssuerCapSetEvent {
+    pub issuer: Pubkey,
+    pub debt_cap: u64,
+}
+
+#[event]
+pub struct CollateralApprovedEvent {
+    pub mint: Pubkey,
+    pub issuer: Pubkey,
+}
+
+#[event]
+pub struct CollateralRevokedEvent {
+    pub mint: Pubkey,
+    pub issuer: Pubkey,
+}
+
+#[event]
+pub struct DepositCollateralEvent {
+    pub vault: Pubkey,
+    pub owner: Pubkey,
+    pub bond_amount: u64,
+    pub total_amount: u64,
+}
+
+#[event]
+pub struct BorrowEvent {
+    pub vault: Pubkey,
+    pub owner: Pubkey,
+    pub amount: u64,
+    pub fee: u64,
+    pub interest: u64,
+    pub total_debt: u64,
+}
+
+#[event]
+pub struct LoopBorrowEvent {
+    pub vault: Pubkey,
+    pub owner: Pubkey,
+    pub rounds: u8,
+    pub borrowed: u64,
+    pub fee: u64,
+    pub collateral_acquired: u64,
+    pub total_amount: u64,
+    pub total_debt: u64,
+}
 
-        require!(ctx.accounts.user_nft_account.amount >= nft_count, ErrorCode::NotEnoughNFTs);
+#[event]
+pub struct RepayEvent {
+    pub vault: Pubkey,
+    pub owner: Pubkey,
+    pub amount: u64,
+    pub interest: u64,
+    pub remaining_debt: u64,
+}
 
-        vault.nft_count += nft_count;
-        vault.owner = ctx.accounts.user.key();
+#[event]
+pub struct RepayForEvent {
+    pub vault: Pubkey,
+    pub owner: Pubkey,
+    pub payer: Pubkey,
+    pub funded_by: Pubkey,
+    pub amount: u64,
+    pub interest: u64,
+    pub remaining_debt: u64,
+}
 
-        let total_value = BOND_UNIT_VALUE * nft_count;
-        let mintable = total_value * (100 - MARGIN_PERCENT) / 100;
-        vault.borrowed += mintable;
-        vault.last_borrow_timestamp = Clock::get()?.unix_timestamp;
+#[event]
+pub struct WithdrawCollateralEvent {
+    pub vault: Pubkey,
+    pub owner: Pubkey,
+    pub bond_amount: u64,
+    pub remaining_amount: u64,
+}
 
-        anchor_spl::token::mint_to(
-            CpiContext::new(
-                ctx.accounts.token_program.to_account_info(),
-                MintTo {
-                    mint: ctx.accounts.stablecoin_mint.to_account_info(),
-                    to: ctx.accounts.user_stablecoin_account.to_account_info(),
-                    authority: ctx.accounts.mint_authority.to_account_info(),
-                },
-            ),
-            mintable,
-        )?;
+#[event]
+pub struct CollateralLegEvent {
+    pub vault: Pubkey,
+    pub mint: Pubkey,
+    pub amount_delta: i64,
+    pub leg_amount: u64,
+    pub total_face_value: u64,
+}
 
-        Ok(())
-    }
+#[event]
+pub struct CollateralLegRevaluedEvent {
+    pub vault: Pubkey,
+    pub mint: Pubkey,
+    pub market_value: u64,
+    pub vault_leg_value: u64,
+}
 
-    pub fn accrue_interest(ctx: Context<AccrueInterest>) -> Result<()> {
-        let vault = &mut ctx.accounts.vault;
-        let config = &ctx.accounts.config;
+#[event]
+pub struct CollateralSubstitutedEvent {
+    pub vault: Pubkey,
+    pub owner: Pubkey,
+    pub from_mint: Pubkey,
+    pub to_mint: Pubkey,
+    pub withdrawn: u64,
+    pub deposited: u64,
+    pub total_face_value: u64,
+}
 
-        let now = Clock::get()?.unix_timestamp;
-        let elapsed = now - vault.last_borrow_timestamp;
+#[event]
+pub struct FixedTermStartedEvent {
+    pub vault: Pubkey,
+    pub owner: Pubkey,
+    pub maturity: i64,
+    pub rate_bps: u64,
+}
 
-        let interest = ((vault.borrowed as u128)
-            * (config.borrow_rate_bps as u128)
-            * (elapsed as u128))
-            / (10000 * 365 * 24 * 3600);
+#[event]
+pub struct RateModeSetEvent {
+    pub vault: Pubkey,
+    pub owner: Pubkey,
+    pub mode: RateMode,
+}
 
-        let interest_u64 = interest as u64;
+#[event]
+pub struct PegPriceUpdatedEvent {
+    pub price: u64,
+    pub twap_price: u64,
+}
 
-        anchor_spl::token::mint_to(
-            CpiContext::new(
-                ctx.accounts.token_program.to_account_info(),
-                MintTo {
-                    mint: ctx.accounts.stablecoin_mint.to_account_info(),
-                    to: ctx.accounts.staking_reward_vault.to_account_info(),
-                    authority: ctx.accounts.mint_authority.to_account_info(),
-                },
-            ),
-            interest_u64,
-        )?;
+#[event]
+pub struct WithdrawalRequestedEvent {
+    pub vault: Pubkey,
+    pub owner: Pubkey,
+    pub amount: u64,
+    pub ready_at: i64,
+}
 
-        vault.last_borrow_timestamp = now;
+#[event]
+pub struct WithdrawalCancelledEvent {
+    pub vault: Pubkey,
+    pub owner: Pubkey,
+    pub amount: u64,
+}
 
-        Ok(())
-    }
+#[event]
+pub struct CloseVaultEvent {
+    pub vault: Pubkey,
+    pub owner: Pubkey,
 }
 
-#[account]
-pub struct Vault {
+#[event]
+pub struct VaultOwnerProposedEvent {
+    pub vault: Pubkey,
     pub owner: Pubkey,
-    pub nft_count: u64,
-    pub borrowed: u64,
-    pub last_borrow_timestamp: i64,
+    pub pending_owner: Pubkey,
 }
 
-#[account]
-pub struct Config {
+#[event]
+pub struct VaultOwnerTransferredEvent {
+    pub vault: Pubkey,
+    pub previous_owner: Pubkey,
+    pub new_owner: Pubkey,
+}
+
+#[event]
+pub struct VaultOwnerTransferCancelledEvent {
+    pub vault: Pubkey,
+    pub owner: Pubkey,
+    pub cancelled_owner: Pubkey,
+}
+
+#[event]
+pub struct VaultDelegateSetEvent {
+    pub vault: Pubkey,
+    pub owner: Pubkey,
+    pub delegate: Pubkey,
+}
+
+#[event]
+pub struct FlagUnhealthyEvent {
+    pub vault: Pubkey,
+    pub owner: Pubkey,
+    pub flagged_at: i64,
+}
+
+#[event]
+pub struct SettlementFlaggedEvent {
+    pub vault: Pubkey,
+    pub owner: Pubkey,
+    pub bond_mint: Pubkey,
+    pub flagged_at: i64,
+}
+
+#[event]
+pub struct SettleMaturedEvent {
+    pub vault: Pubkey,
+    pub owner: Pubkey,
+    pub bond_mint: Pubkey,
+    pub collateral_redeemed: u64,
+    pub proceeds: u64,
+    pub debt_repaid: u64,
+    pub surplus: u64,
+}
+
+#[event]
+pub struct LiquidateEvent {
+    pub vault: Pubkey,
+    pub owner: Pubkey,
+    pub liquidator: Pubkey,
+    pub debt_repaid: u64,
+    pub collateral_seized: u64,
+    pub remaining_debt: u64,
+    pub surplus: u64,
+}
+
+#[event]
+pub struct StabilityDepositEvent {
+    pub pool: Pubkey,
+    pub depositor: Pubkey,
+    pub amount: u64,
+    pub collateral_gain: u64,
+    pub deposit: u64,
+}
+
+#[event]
+pub struct StabilityWithdrawEvent {
+    pub pool: Pubkey,
+    pub depositor: Pubkey,
+    pub amount: u64,
+    pub collateral_gain: u64,
+    pub deposit: u64,
+}
+
+#[event]
+pub struct StabilityPoolOffsetEvent {
+    pub pool: Pubkey,
+    pub vault: Pubkey,
+    pub debt_offset: u64,
+    pub collateral_seized: u64,
+    pub remaining_debt: u64,
+}
+
+#[event]
+pub struct VaultRedeemedEvent {
+    pub vault: Pubkey,
+    pub redeemer: Pubkey,
+    pub debt_redeemed: u64,
+    pub collateral_redeemed: u64,
+}
+
+#[event]
+pub struct OraclePriceUsedEvent {
+    pub mint: Pubkey,
+    pub sources_used: u8,
+    pub unit_value: u64,
+}
+
+#[event]
+pub struct PriceTwapUpdatedEvent {
+    pub mint: Pubkey,
+    pub spot_value: u64,
+    pub twap_value: u64,
+}
+
+#[event]
+pub struct NavAttestedEvent {
+    pub mint: Pubkey,
+    pub custodian: Pubkey,
+    pub nav: u64,
+    pub attested_at: i64,
+}
+
+#[event]
+pub struct YieldCurvePublishedEvent {
+    pub publisher: Pubkey,
+    pub tenors_secs: Vec<i64>,
+    pub rates_bps: Vec<u64>,
+    pub updated_at: i64,
+}
+
+#[event]
+pub struct FxFeedSetEvent {
+    pub currency: Currency,
+    pub feed_id: [u8; 32],
+    pub max_price_age_secs: u64,
+}
+
+#[event]
+pub struct CollateralCurrencySetEvent {
+    pub mint: Pubkey,
+    pub currency: Currency,
+}
+
+#[event]
+pub struct StablecoinMarketRegisteredEvent {
+    pub mint: Pubkey,
+    pub currency: Currency,
+    pub debt_ceiling: u64,
+}
+
+#[event]
+pub struct CollateralMarketSetEvent {
+    pub mint: Pubkey,
+    pub stablecoin_mint: Pubkey,
+    pub currency: Currency,
+}
+
+#[event]
+pub struct FeedRegistryUpdatedEvent {
+    pub mint: Pubkey,
+    pub action: RegistryAction,
+    pub feed_count: u8,
+    pub quorum: u8,
+}
+
+#[event]
+pub struct EmergencyPriceSetEvent {
+    pub mint: Pubkey,
+    pub authority: Pubkey,
+    pub unit_value: u64,
+    pub expires_at: i64,
+}
+
+#[event]
+pub struct ManualMarkSetEvent {
+    pub mint: Pubkey,
+    pub risk_authority: Pubkey,
+    pub unit_value: u64,
+    pub previous_unit_value: u64,
+    pub expires_at: i64,
+}
+
+#[event]
+pub struct ManualMarkAppliedEvent {
+    pub mint: Pubkey,
+    pub unit_value: u64,
+    pub expires_at: i64,
+}
+
+#[event]
+pub struct PriceBandBreachedEvent {
+    pub mint: Pubkey,
+    pub unit_value: u64,
+    pub rejected_value: u64,
+}
+
+#[event]
+pub struct PriceBandAcknowledgedEvent {
+    pub mint: Pubkey,
+    pub risk_authority: Pubkey,
+    pub unit_value: u64,
+}
+
+#[event]
+pub struct AccrueManyEvent {
+    pub caller: Pubkey,
+    pub vaults: u64,
+    pub total_interest: u64,
+    pub bounty: u64,
+}
+
+#[event]
+pub struct KeeperBountyPaidEvent {
+    pub keeper: Pubkey,
+    pub amount: u64,
+}
+
+#[event]
+pub struct RedeemEvent {
+    pub redeemer: Pubkey,
+    pub amount_redeemed: u64,
+    pub fee: u64,
+    pub base_rate: u64,
+}
+
+#[event]
+pub struct DeleverageEvent {
+    pub vault: Pubkey,
+    pub owner: Pubkey,
+    pub collateral_sold: u64,
+    pub debt_repaid: u64,
+    pub remaining_debt: u64,
+}
+
+#[event]
+pub struct BadDebtCoveredEvent {
+    pub vault: Pubkey,
+    pub owner: Pubkey,
+    pub covered: u64,
+    pub remaining_debt: u64,
+}
+
+#[event]
+pub struct DebtAuctionStartedEvent {
+    pub vault: Pubkey,
+    pub debt: u64,
+    pub lot: u64,
+    pub ends_at: i64,
+}
+
+#[event]
+pub struct DebtAuctionBidEvent {
+    pub vault: Pubkey,
+    pub bidder: Pubkey,
+    pub debt: u64,
+    pub lot: u64,
+}
+
+#[event]
+pub struct DebtAuctionSettledEvent {
+    pub vault: Pubkey,
+    pub winner: Pubkey,
+    pub covered: u64,
+    pub governance_minted: u64,
+    pub remaining_debt: u64,
+}
+
+#[event]
+pub struct BadDebtSocializedEvent {
+    pub vault: Pubkey,
+    pub shortfall: u64,
+    pub haircut: u64,
+    pub unbacked: u64,
+    pub unbacked_supply: u64,
+}
+
+#[event]
+pub struct SettlementTriggeredEvent {
+    pub guardian: Pubkey,
+    pub triggered_at: i64,
+}
+
+#[event]
+pub struct CollateralFrozenEvent {
+    pub mint: Pubkey,
+    pub unit_value: u64,
+}
+
+#[event]
+pub struct VaultSettledEvent {
+    pub vault: Pubkey,
+    pub debt: u64,
+    pub collateral_claimed: u64,
+    pub remaining_collateral: u64,
+}
+
+#[event]
+pub struct CollateralReclaimedEvent {
+    pub vault: Pubkey,
+    pub owner: Pubkey,
+    pub bond_amount: u64,
+}
+
+#[event]
+pub struct SettlementFinalizedEvent {
+    pub finalized_at: i64,
+    pub collateral_count: u64,
+}
+
+#[event]
+pub struct CollateralFixedEvent {
+    pub mint: Pubkey,
+    pub escrowed: u64,
+    pub supply: u64,
+    pub fix: u128,
+}
+
+#[event]
+pub struct SettlementRedeemedEvent {
+    pub redeemer: Pubkey,
+    pub collateral_mint: Pubkey,
+    pub amount: u64,
+    pub collateral_paid: u64,
+}
+
+#[event]
+pub struct InsuranceFundDrawnEvent {
+    pub vault: Pubkey,
+    pub risk_authority: Pubkey,
+    pub drawn: u64,
+    pub remaining_debt: u64,
+    pub fund_balance: u64,
+}
+
+#[event]
+pub struct SurplusAuctionStartedEvent {
+    pub lot: u64,
+    pub ends_at: i64,
+}
+
+#[event]
+pub struct SurplusAuctionBidEvent {
+    pub bidder: Pubkey,
+    pub lot: u64,
+    pub bid: u64,
+}
+
+#[event]
+pub struct SurplusAuctionSettledEvent {
+    pub winner: Pubkey,
+    pub lot: u64,
+    pub governance_burned: u64,
+}
+
+#[event]
+pub struct FeesClaimedEvent {
     pub admin: Pubkey,
-    pub borrow_rate_bps: u64,
+    pub source: FeeSource,
+    pub amount: u64,
+    pub total_fees_claimed: u64,
 }
 
-#[derive(Accounts)]
-pub struct SetBorrowRate<'info> {
-    #[account(mut, has_one = admin)]
-    pub config: Account<'info, Config>,
-    pub admin: Signer<'info>,
+#[event]
+pub struct PolDepositEvent {
+    pub pool: Pubkey,
+    pub stablecoin_amount: u64,
+    pub usdc_amount: u64,
+    pub lp_minted: u64,
 }
 
-#[derive(Accounts)]
-pub struct DepositBondAndMint<'info> {
-    #[account(mut)]
-    pub user: Signer<'info>,
-    #[account(mut)]
-    pub user_nft_account: Account<'info, TokenAccount>,
-    #[account(mut)]
-    pub user_stablecoin_account: Account<'info, TokenAccount>,
-    #[account(mut)]
-    pub stablecoin_mint: Account<'info, Mint>,
-    /// CHECK: PDA authority
-    pub mint_authority: AccountInfo<'info>,
-    #[account(init_if_needed, payer = user, space = 8 + 64)]
-    pub vault: Account<'info, Vault>,
-    pub config: Account<'info, Config>,
-    pub token_program: Program<'info, Token>,
-    pub system_program: Program<'info, System>,
+#[event]
+pub struct PolWithdrawEvent {
+    pub pool: Pubkey,
+    pub lp_burned: u64,
+    pub stablecoin_amount: u64,
+    pub usdc_amount: u64,
 }
 
-#[derive(Accounts)]
-pub struct AccrueInterest<'info> {
-    #[account(mut)]
-    pub vault: Account<'info, Vault>,
-    #[account(mut)]
-    pub stablecoin_mint: Account<'info, Mint>,
-    #[account(mut)]
-    pub staking_reward_vault: Account<'info, TokenAccount>,
-    /// CHECK: PDA authority
-    pub mint_authority: AccountInfo<'info>,
-    pub config: Account<'info, Config>,
-    pub token_program: Program<'info, Token>,
+#[event]
+pub struct PolHarvestEvent {
+    pub pool: Pubkey,
+    pub stablecoin_amount: u64,
+    pub usdc_amount: u64,
+}
+
+#[event]
+pub struct SurplusDistributedEvent {
+    pub amount: u64,
+    pub reserve_balance: u64,
+    pub reserve_target: u64,
+}
+
+#[event]
+pub struct ClaimSurplusEvent {
+    pub vault: Pubkey,
+    pub owner: Pubkey,
+    pub amount: u64,
+}
+
+#[event]
+pub struct PsmMintEvent {
+    pub user: Pubkey,
+    pub usdc_in: u64,
+    pub minted: u64,
+    pub fee: u64,
+    pub psm_supply: u64,
+}
+
+#[event]
+pub struct PsmRedeemEvent {
+    pub user: Pubkey,
+    pub amount: u64,
+    pub usdc_out: u64,
+    pub fee: u64,
+    pub psm_supply: u64,
+}
+
+#[event]
+pub struct PegArbitragedEvent {
+    pub keeper: Pubkey,
+    pub direction: ArbDirection,
+    pub price: u64,
+    pub psm_amount: u64,
+    pub fee: u64,
+    pub profit: u64,
+}
+
+#[event]
+pub struct FlashMintEvent {
+    pub borrower: Pubkey,
+    pub amount: u64,
+    pub fee: u64,
+}
+
+#[event]
+pub struct FlashBurnEvent {
+    pub payer: Pubkey,
+    pub amount: u64,
+    pub fee: u64,
+}
+
+#[event]
+pub struct BridgePeerRegisteredEvent {
+    pub chain_id: u16,
+    pub peer_address: [u8; 32],
+    pub outbound_limit: u64,
+    pub inbound_limit: u64,
+}
+
+#[event]
+pub struct BridgeBurnEvent {
+    pub chain_id: u16,
+    pub sender: Pubkey,
+    pub amount: u64,
+    pub bridged_out: u64,
+}
+
+#[event]
+pub struct BridgeMintEvent {
+    pub chain_id: u16,
+    pub recipient: Pubkey,
+    pub amount: u64,
+    pub bridged_out: u64,
+}
+
+#[event]
+pub struct ConfidentialTransfersConfiguredEvent {
+    pub mint: Pubkey,
+    pub auto_approve_new_accounts: bool,
+    pub auditor_elgamal_pubkey: Option<[u8; 32]>,
+}
+
+#[event]
+pub struct ConfidentialAccountApprovedEvent {
+    pub account: Pubkey,
+    pub owner: Pubkey,
+}
+
+#[event]
+pub struct SupplyReconciledEvent {
+    pub supply: u64,
+    pub cdp_debt: u64,
+    pub interest: u64,
+    pub psm: u64,
+    pub flash_mint: u64,
+    pub bridged_net: i64,
+    pub discrepancy: i128,
+}
+
+#[event]
+pub struct SavingsDepositEvent {
+    pub user: Pubkey,
+    pub amount: u64,
+    pub shares: u64,
+    pub share_price: u128,
+}
+
+#[event]
+pub struct SavingsRateSyncedEvent {
+    pub share_price: u128,
+    pub rate_bps: i16,
+}
+
+#[event]
+pub struct SavingsWithdrawEvent {
+    pub user: Pubkey,
+    pub amount: u64,
+    pub shares: u64,
+    pub share_price: u128,
+}
+
+#[error_code]
+pub enum ErrorCode {
+    #[msg("Not enough bond tokens in the source account.")]
+    NotEnoughBonds,
+    #[msg("Not enough stablecoin to cover the repayment.")]
+    InsufficientStablecoin,
+    #[msg("Amount must be greater than zero.")]
+    InvalidAmount,
+    #[msg("Repayment exceeds the outstanding debt.")]
+    RepayExceedsDebt,
+    #[msg("Remaining debt would fall below the minimum debt.")]
+    DebtBelowMinimum,
+    #[msg("Not enough collateral in the vault.")]
+    NotEnoughCollateral,
+    #[msg("Vault would fall below the required collateral ratio.")]
+    UndercollateralizedVault,
+    #[msg("Vault still has outstanding debt.")]
+    OutstandingDebt,
+    #[msg("Vault collateral must be withdrawn first.")]
+    CollateralNotWithdrawn,
+    #[msg("The vault receipt tokens must be presented.")]
+    ReceiptRequired,
+    #[msg("Vault has no debt to liquidate.")]
+    NothingToLiquidate,
+    #[msg("Vault is healthy and cannot be liquidated.")]
+    VaultHealthy,
+    #[msg("Repay amount exceeds the close factor.")]
+    ExceedsCloseFactor,
+    #[msg("Liquidation is too small to seize any collateral.")]
+    LiquidationTooSmall,
+    #[msg("Stability pool has no deposits to absorb the liquidation.")]
+    StabilityPoolEmpty,
+    #[msg("Withdrawal exceeds the stability pool deposit.")]
+    InsufficientStabilityDeposit,
+    #[msg("Nothing could be redeemed against the provided vaults.")]
+    NothingToRedeem,
+    #[msg("Vaults must be walked from the head of the sorted vault list.")]
+    RedemptionOrder,
+    #[msg("Escrow account does not belong to the vault.")]
+    InvalidEscrow,
+    #[msg("Unexpected remaining accounts layout.")]
+    InvalidRemainingAccounts,
+    #[msg("Sorted list hint does not describe a valid insert position.")]
+    InvalidListHint,
+    #[msg("A neighbouring vault required to update the sorted list is missing.")]
+    MissingListNode,
+    #[msg("Vault has no liquidation surplus to claim.")]
+    NoSurplus,
+    #[msg("Liquidation surplus must be claimed first.")]
+    UnclaimedSurplus,
+    #[msg("Swap proceeds do not cover the flash-minted repayment.")]
+    FlashRepayShortfall,
+    #[msg("Swap program cannot be this program.")]
+    InvalidSwapProgram,
+    #[msg("Vault has already been flagged as unhealthy.")]
+    AlreadyFlagged,
+    #[msg("Vault must be flagged as unhealthy before liquidation.")]
+    NotFlagged,
+    #[msg("Vault is still within its liquidation grace period.")]
+    GracePeriodActive,
+    #[msg("Vault has no debt to repay.")]
+    NothingToRepay,
+    #[msg("Swap proceeds are below the requested minimum.")]
+    SlippageExceeded,
+    #[msg("Signer does not hold the vault position NFT.")]
+    PositionRequired,
+    #[msg("Signer is neither the vault owner nor its delegate.")]
+    Unauthorized,
+    #[msg("Collateral debt ceiling exceeded.")]
+    DebtCeilingExceeded,
+    #[msg("Bond was not issued by the approved issuer.")]
+    UnknownIssuer,
+    #[msg("Bond is matured or defaulted.")]
+    BondNotEligible,
+    #[msg("Bond is still eligible collateral.")]
+    BondEligible,
+    #[msg("Vault is due for forced settlement.")]
+    SettlementDue,
+    #[msg("Bond has not reached maturity.")]
+    BondNotMatured,
+    #[msg("Substitute collateral is worth less than the collateral it replaces.")]
+    SubstituteValueTooLow,
+    #[msg("Global debt ceiling exceeded.")]
+    GlobalDebtCeilingExceeded,
+    #[msg("Vault debt cap exceeded.")]
+    VaultDebtCapExceeded,
+    #[msg("Issuer exposure cap exceeded.")]
+    IssuerCapExceeded,
+    #[msg("Stablecoin minted in the current window exceeds the rate limit.")]
+    MintRateLimited,
+    #[msg("Mint amount exceeds the per-transaction cap.")]
+    MintCapExceeded,
+    #[msg("Vault still holds collateral.")]
+    VaultNotInsolvent,
+    #[msg("Reserve holds no funds.")]
+    ReserveEmpty,
+    #[msg("Not enough fees accumulated to claim.")]
+    InsufficientFees,
+    #[msg("Withdrawal above the threshold has not been requested.")]
+    NoPendingWithdrawal,
+    #[msg("Withdrawal is still within its cooldown.")]
+    WithdrawalCooldownActive,
+    #[msg("Vault already has an active fixed term.")]
+    TermActive,
+    #[msg("Fixed loan term has ended.")]
+    TermExpired,
+    #[msg("Collateral is priced by an oracle feed; a fresh price update is required.")]
+    PriceFeedRequired,
+    #[msg("Oracle price is not usable.")]
+    InvalidPrice,
+    #[msg("Oracle account does not match the collateral's configured feed.")]
+    InvalidOracleAccount,
+    #[msg("Oracle price is older than the allowed maximum age.")]
+    StaleOracle,
+    #[msg("Oracle confidence interval is wider than the allowed bound.")]
+    WideConfidence,
+    #[msg("Borrowing against this collateral is paused pending a price band review.")]
+    BorrowingPaused,
+    #[msg("NAV attestation is missing, malformed or not signed by the custodian.")]
+    InvalidAttestation,
+    #[msg("Collateral already has a feed registry entry.")]
+    FeedAlreadyRegistered,
+    #[msg("Collateral has no feed registry entry.")]
+    FeedNotRegistered,
+    #[msg("Feed registry is full.")]
+    FeedRegistryFull,
+    #[msg("An FX feed is required to value collateral outside USD.")]
+    FxFeedRequired,
+    #[msg("Minting would exceed the PSM supply cap.")]
+    PsmCapExceeded,
+    #[msg("PSM reserve cannot cover the redemption.")]
+    PsmInsufficientReserve,
+    #[msg("A flash mint is already outstanding.")]
+    FlashLoanActive,
+    #[msg("No flash mint is outstanding.")]
+    FlashLoanInactive,
+    #[msg("Flash mint must be followed by a flash burn in the same transaction.")]
+    FlashBurnMissing,
+    #[msg("Bridge mint exceeds the supply bridged out.")]
+    BridgeSupplyExceeded,
+    #[msg("Stablecoin market debt ceiling exceeded.")]
+    MarketDebtCeilingExceeded,
+    #[msg("FX conversion requires one side of the pair to be USD.")]
+    UnsupportedCurrencyPair,
+    #[msg("Reserve excess is below the distribution threshold.")]
+    SurplusBelowThreshold,
+    #[msg("Reserve and insurance fund must be drained before debt is auctioned.")]
+    ReserveNotExhausted,
+    #[msg("Auction is still running.")]
+    AuctionActive,
+    #[msg("No auction is running.")]
+    AuctionInactive,
+    #[msg("Auction has ended.")]
+    AuctionEnded,
+    #[msg("Bid must lower the lot by at least the minimum decrement.")]
+    BidTooHigh,
+    #[msg("Auction closed without any bids.")]
+    NoBids,
+    #[msg("Bid must raise the current bid by at least the minimum increment.")]
+    BidTooLow,
+    #[msg("Insurance fund holds no funds.")]
+    InsuranceFundEmpty,
+    #[msg("Global settlement has been triggered.")]
+    SettlementTriggered,
+    #[msg("Settlement wait period has not elapsed.")]
+    SettlementPending,
+    #[msg("Settlement has already been finalized.")]
+    SettlementFinalized,
+    #[msg("Every registered collateral must be frozen before finalization.")]
+    CollateralNotFrozen,
+    #[msg("Every frozen collateral must be fixed before redemption.")]
+    CollateralNotFixed,
+    #[msg("Settlement collateral has already been fixed.")]
+    CollateralAlreadyFixed,
+    #[msg("Vault collateral legs must be withdrawn first.")]
+    LegsOutstanding,
+    #[msg("Stablecoin price is within the arbitrage threshold.")]
+    PegWithinThreshold,
+    #[msg("No admin transfer is pending.")]
+    NoPendingAdmin,
+    #[msg("Parameter is timelocked; queue the change instead.")]
+    TimelockActive,
+    #[msg("Pending parameter change queue is full.")]
+    PendingChangesFull,
+    #[msg("Pending parameter change not found.")]
+    ParamChangeNotFound,
+    #[msg("Parameter change delay has not elapsed.")]
+    ParamChangeNotReady,
+    #[msg("Account passed does not match the parameter change target.")]
+    ParamTargetMismatch,
+    #[msg("No borrow rate change is scheduled.")]
+    NoScheduledRate,
+    #[msg("Borrow rate exceeds the configured maximum.")]
+    BorrowRateTooHigh,
+    #[msg("Borrow rate change exceeds the maximum step.")]
+    RateStepTooLarge,
+    #[msg("This instruction is paused.")]
+    InstructionPaused,
+    #[msg("Signer is not the pending vault owner.")]
+    NotPendingOwner,
+    #[msg("No vault ownership transfer is pending.")]
+    NoPendingOwner,
+    #[msg("A vault ownership transfer is already pending.")]
+    OwnerTransferPending,
+    #[msg("Bond payment mint is not the PSM reserve mint.")]
+    InvalidPaymentMint,
+    #[msg("Burn exceeds the supply recorded for its source.")]
+    SupplyLedgerUnderflow,
+    #[msg("Every vault must be settled before collateral is fixed.")]
+    VaultsUnsettled,
+    #[msg("Stablecoin account owner is on the denylist.")]
+    AccountDenied,
+    #[msg("Stablecoin mint authority must be the program's mint authority PDA.")]
+    InvalidMintAuthority,
+    #[msg("Invalid parameter value.")]
+    InvalidParameter,
+    #[msg("Arithmetic overflow.")]
+    MathOverflow,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const NOW: i64 = 1_700_000_000;
+    const UNIT: u64 = 1_000_000;
+
+    // Every account type decodes from zeroed bytes, so tests start from an empty account and
+    // set only the fields they exercise.
+    fn zeroed<T: AnchorDeserialize>() -> T {
+        T::deserialize(&mut &[0u8; 1 << 16][..]).unwrap()
+    }
+
+    fn assert_error<T>(result: Result<T>, code: ErrorCode) {
+        match result {
+            Ok(_) => panic!("expected {:?}", code),
+            Err(err) => assert_eq!(err, anchor_lang::error::Error::from(code)),
+        }
+    }
+
+    // 100 bonds worth one stablecoin unit each, liquidatable below 85% LTV with a 5% penalty.
+    fn liquidation_setup(debt: u64) -> (Vault, CollateralConfig, InterestRateModel, Config) {
+        let mut vault: Vault = zeroed();
+        vault.bond_amount = 100;
+        vault.borrowed = debt;
+        vault.rate_mode = RateMode::Fixed;
+        vault.last_borrow_timestamp = NOW;
+        let mut collateral: CollateralConfig = zeroed();
+        collateral.unit_value = UNIT;
+        collateral.face_value = UNIT;
+        collateral.max_ltv_bps = 8000;
+        collateral.liquidation_ltv_bps = 8500;
+        collateral.liquidation_penalty_bps = 500;
+        (vault, collateral, zeroed(), zeroed())
+    }
+
+    fn plan(
+        vault: &Vault,
+        collateral: &CollateralConfig,
+        rate_model: &InterestRateModel,
+        config: &Config,
+        repay_amount: u64,
+    ) -> Result<LiquidationPlan> {
+        plan_liquidation(
+            vault,
+            collateral,
+            collateral,
+            rate_model,
+            config,
+            false,
+            NOW,
+            repay_amount,
+            vault.bond_amount,
+        )
+    }
+
+    #[test]
+    fn healthy_vaults_cannot_be_liquidated() {
+        let (vault, collateral, rate_model, config) = liquidation_setup(50 * UNIT);
+        assert_error(
+            plan(&vault, &collateral, &rate_model, &config, 10 * UNIT),
+            ErrorCode::VaultHealthy,
+        );
+    }
+
+    #[test]
+    fn partial_liquidation_seizes_repayment_plus_penalty() {
+        let (vault, collateral, rate_model, config) = liquidation_setup(90 * UNIT);
+        let plan = plan(&vault, &collateral, &rate_model, &config, 10 * UNIT).unwrap();
+        assert_eq!(plan.total_debt, 90 * UNIT);
+        assert_eq!(plan.seize_amount, 10);
+        assert_eq!(plan.surplus, 0);
+    }
+
+    #[test]
+    fn closing_liquidation_returns_surplus_to_owner() {
+        let (vault, collateral, rate_model, config) = liquidation_setup(90 * UNIT);
+        let plan = plan(&vault, &collateral, &rate_model, &config, 90 * UNIT).unwrap();
+        assert_eq!(plan.seize_amount, 100);
+        // The collateral sells at 100 / 1.05 and everything above the debt is surplus.
+        assert_eq!(plan.surplus, 100 * UNIT * 10000 / 10500 - 90 * UNIT);
+    }
+
+    #[test]
+    fn close_factor_caps_repayment_unless_remainder_is_dust() {
+        let (vault, collateral, rate_model, mut config) = liquidation_setup(90 * UNIT);
+        config.close_factor_bps = 5000;
+        assert_eq!(max_liquidation_repay(90 * UNIT, &config), 45 * UNIT);
+        assert_error(
+            plan(&vault, &collateral, &rate_model, &config, 50 * UNIT),
+            ErrorCode::ExceedsCloseFactor,
+        );
+
+        config.min_debt = 50 * UNIT;
+        assert_eq!(max_liquidation_repay(90 * UNIT, &config), 90 * UNIT);
+    }
+
+    #[test]
+    fn liquidation_waits_out_the_grace_period() {
+        let (mut vault, collateral, rate_model, mut config) = liquidation_setup(90 * UNIT);
+        config.grace_period_secs = 600;
+        assert_error(
+            plan(&vault, &collateral, &rate_model, &config, 10 * UNIT),
+            ErrorCode::NotFlagged,
+        );
+        vault.unhealthy_since = NOW - 100;
+        assert_error(
+            plan(&vault, &collateral, &rate_model, &config, 10 * UNIT),
+            ErrorCode::GracePeriodActive,
+        );
+        vault.unhealthy_since = NOW - 600;
+        assert!(plan(&vault, &collateral, &rate_model, &config, 10 * UNIT).is_ok());
+    }
+
+    fn vault_with_face_value(collateral_face_value: u64) -> Vault {
+        let mut vault: Vault = zeroed();
+        vault.collateral_face_value = collateral_face_value;
+        vault
+    }
+
+    #[test]
+    fn redemptions_walk_vaults_from_lowest_ratio() {
+        let low = vault_with_face_value(110 * UNIT);
+        let high = vault_with_face_value(200 * UNIT);
+        let last_cr = check_redemption_order(&low, 100 * UNIT, 0).unwrap();
+        let last_cr = check_redemption_order(&high, 100 * UNIT, last_cr).unwrap();
+        assert_error(
+            check_redemption_order(&low, 100 * UNIT, last_cr),
+            ErrorCode::RedemptionOrder,
+        );
+    }
+
+    #[test]
+    fn debt_free_vaults_do_not_move_the_redemption_walk() {
+        let low = vault_with_face_value(110 * UNIT);
+        let empty = vault_with_face_value(0);
+        let last_cr = check_redemption_order(&low, 100 * UNIT, 0).unwrap();
+        assert_eq!(check_redemption_order(&empty, 0, last_cr).unwrap(), last_cr);
+    }
+
+    #[test]
+    fn settlement_claims_are_capped_at_the_debt() {
+        assert_eq!(settled_debt(100 * UNIT, 40, UNIT), 40 * UNIT);
+        assert_eq!(settled_debt(100 * UNIT, 150, UNIT), 100 * UNIT);
+    }
+
+    #[test]
+    fn settlement_writes_off_debt_once_collateral_is_gone() {
+        let mut vault: Vault = zeroed();
+        vault.bond_amount = 10;
+        assert_eq!(
+            remaining_settlement_debt(&vault, 100 * UNIT, 40 * UNIT),
+            60 * UNIT
+        );
+        vault.bond_amount = 0;
+        assert_eq!(remaining_settlement_debt(&vault, 100 * UNIT, 40 * UNIT), 0);
+    }
+
+    #[test]
+    fn matured_proceeds_rescale_rounding_down() {
+        assert_eq!(scale_decimals(1_234_567, 6, 9).unwrap(), 1_234_567_000);
+        assert_eq!(scale_decimals(1_234_567_891, 9, 6).unwrap(), 1_234_567);
+        assert_eq!(scale_decimals(5, 0, 0).unwrap(), 5);
+    }
+
+    #[test]
+    fn psm_fees_round_down() {
+        assert_eq!(psm_fee(1_000_000, 30), 3_000);
+        assert_eq!(psm_fee(333, 30), 0);
+        assert_eq!(psm_fee(1_000_000, 10000), 1_000_000);
+    }
+
+    #[test]
+    fn psm_burns_cannot_exceed_psm_backed_supply() {
+        let mut ledger: SupplyLedger = zeroed();
+        record_mint(&mut ledger, SupplySource::Psm, 1_000).unwrap();
+        record_mint(&mut ledger, SupplySource::CdpDebt, 5_000).unwrap();
+        record_burn(&mut ledger, SupplySource::Psm, 600).unwrap();
+        assert_eq!(ledger.psm, 400);
+        assert_error(
+            record_burn(&mut ledger, SupplySource::Psm, 401),
+            ErrorCode::SupplyLedgerUnderflow,
+        );
+        assert_eq!(ledger_total(&ledger), 5_400);
+    }
+
+    #[test]
+    fn repayments_burn_principal_and_interest_from_their_buckets() {
+        let mut ledger: SupplyLedger = zeroed();
+        record_mint(&mut ledger, SupplySource::CdpDebt, 1_000).unwrap();
+        record_mint(&mut ledger, SupplySource::Interest, 50).unwrap();
+        record_repay_burn(&mut ledger, 300, 50).unwrap();
+        assert_eq!(ledger.cdp_debt, 750);
+        assert_eq!(ledger.interest, 0);
+    }
 }
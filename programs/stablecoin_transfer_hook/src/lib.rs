@@ -0,0 +1,225 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token_interface::{Mint, TokenAccount};
+use spl_tlv_account_resolution::account::ExtraAccountMeta;
+use spl_tlv_account_resolution::seeds::Seed;
+use spl_tlv_account_resolution::state::ExtraAccountMetaList;
+use spl_transfer_hook_interface::instruction::ExecuteInstruction;
+
+declare_id!("TransferHook11111111111111111111111111111111");
+
+#[program]
+pub mod stablecoin_transfer_hook {
+    use super::*;
+
+    pub fn initialize_compliance(
+        ctx: Context<InitializeCompliance>,
+        compliance_authority: Pubkey,
+    ) -> Result<()> {
+        let compliance = &mut ctx.accounts.compliance;
+        compliance.authority = compliance_authority;
+        compliance.bump = ctx.bumps.compliance;
+        Ok(())
+    }
+
+    pub fn set_compliance_authority(
+        ctx: Context<UpdateCompliance>,
+        compliance_authority: Pubkey,
+    ) -> Result<()> {
+        ctx.accounts.compliance.authority = compliance_authority;
+        Ok(())
+    }
+
+    // Token-2022 resolves the deny entries of the source and destination owners from the
+    // token accounts themselves (owner at offset 32), so the hook sees them on every transfer.
+    pub fn initialize_extra_account_meta_list(
+        ctx: Context<InitializeExtraAccountMetaList>,
+    ) -> Result<()> {
+        let extra_account_metas = extra_account_metas()?;
+        let mut data = ctx.accounts.extra_account_meta_list.try_borrow_mut_data()?;
+        ExtraAccountMetaList::init::<ExecuteInstruction>(&mut data, &extra_account_metas)?;
+        Ok(())
+    }
+
+    pub fn add_to_denylist(ctx: Context<AddToDenylist>, wallet: Pubkey) -> Result<()> {
+        let entry = &mut ctx.accounts.deny_entry;
+        entry.wallet = wallet;
+        entry.added_at = Clock::get()?.unix_timestamp;
+        entry.bump = ctx.bumps.deny_entry;
+
+        emit!(DenylistUpdatedEvent {
+            wallet,
+            denied: true,
+        });
+
+        Ok(())
+    }
+
+    pub fn remove_from_denylist(ctx: Context<RemoveFromDenylist>) -> Result<()> {
+        emit!(DenylistUpdatedEvent {
+            wallet: ctx.accounts.deny_entry.wallet,
+            denied: false,
+        });
+
+        Ok(())
+    }
+
+    #[interface(spl_transfer_hook_interface::execute)]
+    pub fn transfer_hook(ctx: Context<TransferHook>, _amount: u64) -> Result<()> {
+        require!(
+            ctx.accounts.source_deny_entry.data_is_empty(),
+            ErrorCode::SourceDenied
+        );
+        require!(
+            ctx.accounts.destination_deny_entry.data_is_empty(),
+            ErrorCode::DestinationDenied
+        );
+        Ok(())
+    }
+}
+
+fn extra_account_metas() -> Result<Vec<ExtraAccountMeta>> {
+    let deny_entry = |account_index: u8| {
+        ExtraAccountMeta::new_with_seeds(
+            &[
+                Seed::Literal {
+                    bytes: b"deny".to_vec(),
+                },
+                Seed::AccountData {
+                    account_index,
+                    data_index: 32,
+                    length: 32,
+                },
+            ],
+            false,
+            false,
+        )
+    };
+    Ok(vec![deny_entry(0)?, deny_entry(2)?])
+}
+
+#[derive(Accounts)]
+pub struct InitializeCompliance<'info> {
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    #[account(
+        init,
+        payer = admin,
+        space = 8 + 32 + 1,
+        seeds = [b"compliance"],
+        bump
+    )]
+    pub compliance: Account<'info, Compliance>,
+    #[account(constraint = program.programdata_address()? == Some(program_data.key()))]
+    pub program: Program<'info, crate::program::StablecoinTransferHook>,
+    #[account(constraint = program_data.upgrade_authority_address == Some(admin.key()))]
+    pub program_data: Account<'info, ProgramData>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateCompliance<'info> {
+    pub authority: Signer<'info>,
+    #[account(mut, seeds = [b"compliance"], bump = compliance.bump, has_one = authority)]
+    pub compliance: Account<'info, Compliance>,
+}
+
+#[derive(Accounts)]
+pub struct InitializeExtraAccountMetaList<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    #[account(seeds = [b"compliance"], bump = compliance.bump, has_one = authority)]
+    pub compliance: Account<'info, Compliance>,
+    /// CHECK: TLV account initialized by the transfer hook interface
+    #[account(
+        init,
+        payer = authority,
+        space = ExtraAccountMetaList::size_of(extra_account_metas()?.len())?,
+        seeds = [b"extra-account-metas", mint.key().as_ref()],
+        bump
+    )]
+    pub extra_account_meta_list: UncheckedAccount<'info>,
+    pub mint: InterfaceAccount<'info, Mint>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(wallet: Pubkey)]
+pub struct AddToDenylist<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    #[account(seeds = [b"compliance"], bump = compliance.bump, has_one = authority)]
+    pub compliance: Account<'info, Compliance>,
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + 32 + 8 + 1,
+        seeds = [b"deny", wallet.as_ref()],
+        bump
+    )]
+    pub deny_entry: Account<'info, DenyEntry>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct RemoveFromDenylist<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    #[account(seeds = [b"compliance"], bump = compliance.bump, has_one = authority)]
+    pub compliance: Account<'info, Compliance>,
+    #[account(
+        mut,
+        close = authority,
+        seeds = [b"deny", deny_entry.wallet.as_ref()],
+        bump = deny_entry.bump,
+    )]
+    pub deny_entry: Account<'info, DenyEntry>,
+}
+
+// Account order is fixed by the transfer hook interface: source, mint, destination,
+// owner, then the meta list and the extra accounts it declares.
+#[derive(Accounts)]
+pub struct TransferHook<'info> {
+    #[account(token::mint = mint)]
+    pub source_token: InterfaceAccount<'info, TokenAccount>,
+    pub mint: InterfaceAccount<'info, Mint>,
+    #[account(token::mint = mint)]
+    pub destination_token: InterfaceAccount<'info, TokenAccount>,
+    /// CHECK: source owner or delegate, already authorized by the token program
+    pub owner: UncheckedAccount<'info>,
+    /// CHECK: validated by seeds
+    #[account(seeds = [b"extra-account-metas", mint.key().as_ref()], bump)]
+    pub extra_account_meta_list: UncheckedAccount<'info>,
+    /// CHECK: deny entry for the source owner; empty unless the owner is denied
+    #[account(seeds = [b"deny", source_token.owner.as_ref()], bump)]
+    pub source_deny_entry: UncheckedAccount<'info>,
+    /// CHECK: deny entry for the destination owner; empty unless the owner is denied
+    #[account(seeds = [b"deny", destination_token.owner.as_ref()], bump)]
+    pub destination_deny_entry: UncheckedAccount<'info>,
+}
+
+#[account]
+pub struct Compliance {
+    pub authority: Pubkey,
+    pub bump: u8,
+}
+
+#[account]
+pub struct DenyEntry {
+    pub wallet: Pubkey,
+    pub added_at: i64,
+    pub bump: u8,
+}
+
+#[event]
+pub struct DenylistUpdatedEvent {
+    pub wallet: Pubkey,
+    pub denied: bool,
+}
+
+#[error_code]
+pub enum ErrorCode {
+    #[msg("Transfer source is on the denylist.")]
+    SourceDenied,
+    #[msg("Transfer destination is on the denylist.")]
+    DestinationDenied,
+}